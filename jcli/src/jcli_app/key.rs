@@ -8,6 +8,13 @@ use chain_crypto::{
 };
 use ed25519_bip32::{DerivationError, DerivationScheme};
 use hex::FromHexError;
+use jormungandr_lib::{
+    crypto::passphrase::{PassphraseError, PassphraseSource},
+    interfaces::{
+        EncryptedSecret, EncryptedSecretFile, NodeSecret, SecretDecryptionError,
+        DEFAULT_PBKDF2_ITERATIONS,
+    },
+};
 use rand::{rngs::OsRng, SeedableRng};
 use rand_chacha::ChaChaRng;
 use std::{
@@ -56,6 +63,18 @@ pub enum Error {
         public_hrp: String,
         private_hrp: String,
     },
+    #[error("could not read the secret file")]
+    SecretFileRead(#[from] io::ReadYamlError),
+    #[error("invalid secret file")]
+    SecretFileFormat(#[source] serde_yaml::Error),
+    #[error("the secret file is already encrypted")]
+    SecretFileEncrypted,
+    #[error("the secret file is not encrypted")]
+    SecretFileNotEncrypted,
+    #[error("could not read the passphrase")]
+    Passphrase(#[from] PassphraseError),
+    #[error("could not decrypt the secret file")]
+    SecretDecryption(#[from] SecretDecryptionError),
 }
 
 #[derive(StructOpt, Debug)]
@@ -75,6 +94,12 @@ pub enum Key {
     Verify(Verify),
     /// derive a child key from a ed25519bip32 parent key
     Derive(Derive),
+    /// encrypt a node secret file with a passphrase
+    EncryptSecret(EncryptSecret),
+    /// decrypt an encrypted node secret file
+    DecryptSecret(DecryptSecret),
+    /// change the passphrase of an encrypted node secret file
+    RekeySecret(RekeySecret),
 }
 
 #[derive(StructOpt, Debug)]
@@ -184,6 +209,80 @@ pub struct Derive {
     child_key: OutputFile,
 }
 
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct EncryptSecret {
+    /// the node secret file to encrypt. If not provided it will be read
+    /// from the standard input
+    #[structopt(name = "INPUT_FILE")]
+    input: Option<PathBuf>,
+
+    /// read the passphrase from the given environment variable instead of
+    /// asking it on the terminal
+    #[structopt(long, conflicts_with = "passphrase-fd")]
+    passphrase_env: Option<String>,
+
+    /// read the passphrase from the first line of the given file descriptor
+    #[structopt(long)]
+    passphrase_fd: Option<i32>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DecryptSecret {
+    /// the encrypted node secret file. If not provided it will be read from
+    /// the standard input
+    #[structopt(name = "INPUT_FILE")]
+    input: Option<PathBuf>,
+
+    /// read the passphrase from the given environment variable instead of
+    /// asking it on the terminal
+    #[structopt(long, conflicts_with = "passphrase-fd")]
+    passphrase_env: Option<String>,
+
+    /// read the passphrase from the first line of the given file descriptor
+    #[structopt(long)]
+    passphrase_fd: Option<i32>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct RekeySecret {
+    /// the encrypted node secret file. If not provided it will be read from
+    /// the standard input
+    #[structopt(name = "INPUT_FILE")]
+    input: Option<PathBuf>,
+
+    /// read the current passphrase from the given environment variable
+    /// instead of asking it on the terminal
+    #[structopt(long, conflicts_with = "passphrase-fd")]
+    passphrase_env: Option<String>,
+
+    /// read the current passphrase from the first line of the given file
+    /// descriptor
+    #[structopt(long)]
+    passphrase_fd: Option<i32>,
+
+    /// read the new passphrase from the given environment variable instead
+    /// of asking it on the terminal
+    #[structopt(long, conflicts_with = "new-passphrase-fd")]
+    new_passphrase_env: Option<String>,
+
+    /// read the new passphrase from the first line of the given file
+    /// descriptor
+    #[structopt(long)]
+    new_passphrase_fd: Option<i32>,
+
+    #[structopt(flatten)]
+    output_file: OutputFile,
+}
+
 arg_enum! {
     #[derive(StructOpt, Debug)]
     pub enum GenPrivKeyType {
@@ -205,10 +304,80 @@ impl Key {
             Key::Sign(args) => args.exec(),
             Key::Verify(args) => args.exec(),
             Key::Derive(args) => args.exec(),
+            Key::EncryptSecret(args) => args.exec(),
+            Key::DecryptSecret(args) => args.exec(),
+            Key::RekeySecret(args) => args.exec(),
         }
     }
 }
 
+impl EncryptSecret {
+    fn exec(self) -> Result<(), Error> {
+        let content: serde_yaml::Value = io::read_yaml(&self.input)?;
+        if content.get(EncryptedSecretFile::KEY).is_some() {
+            return Err(Error::SecretFileEncrypted);
+        }
+        // make sure the node will be able to load the secret once decrypted
+        serde_yaml::from_value::<NodeSecret>(content.clone()).map_err(Error::SecretFileFormat)?;
+        let plaintext = serde_yaml::to_vec(&content).map_err(Error::SecretFileFormat)?;
+
+        let passphrase = PassphraseSource::from_options(self.passphrase_env, self.passphrase_fd)
+            .read_new("New passphrase: ")?;
+        write_encrypted_secret(&self.output_file, &plaintext, &passphrase)
+    }
+}
+
+impl DecryptSecret {
+    fn exec(self) -> Result<(), Error> {
+        let secret = read_encrypted_secret(&self.input)?;
+        let passphrase = PassphraseSource::from_options(self.passphrase_env, self.passphrase_fd)
+            .read("Passphrase: ")?;
+        let plaintext = secret.decrypt(&passphrase)?;
+        self.output_file.open()?.write_all(&plaintext)?;
+        Ok(())
+    }
+}
+
+impl RekeySecret {
+    fn exec(self) -> Result<(), Error> {
+        let secret = read_encrypted_secret(&self.input)?;
+        let passphrase = PassphraseSource::from_options(self.passphrase_env, self.passphrase_fd)
+            .read("Current passphrase: ")?;
+        let plaintext = secret.decrypt(&passphrase)?;
+
+        let new_passphrase =
+            PassphraseSource::from_options(self.new_passphrase_env, self.new_passphrase_fd)
+                .read_new("New passphrase: ")?;
+        write_encrypted_secret(&self.output_file, &plaintext, &new_passphrase)
+    }
+}
+
+fn read_encrypted_secret(input: &Option<PathBuf>) -> Result<EncryptedSecret, Error> {
+    let content: serde_yaml::Value = io::read_yaml(input)?;
+    if content.get(EncryptedSecretFile::KEY).is_none() {
+        return Err(Error::SecretFileNotEncrypted);
+    }
+    let file: EncryptedSecretFile =
+        serde_yaml::from_value(content).map_err(Error::SecretFileFormat)?;
+    Ok(file.encrypted_secret)
+}
+
+fn write_encrypted_secret(
+    output_file: &OutputFile,
+    plaintext: &[u8],
+    passphrase: &str,
+) -> Result<(), Error> {
+    let file = EncryptedSecretFile {
+        encrypted_secret: EncryptedSecret::encrypt(
+            plaintext,
+            passphrase,
+            DEFAULT_PBKDF2_ITERATIONS,
+            &mut OsRng,
+        ),
+    };
+    serde_yaml::to_writer(output_file.open()?, &file).map_err(Error::SecretFileFormat)
+}
+
 impl Generate {
     fn exec(self) -> Result<(), Error> {
         let priv_key_bech32 = match self.key_type {
@@ -7,7 +7,7 @@ use std::path::PathBuf;
 use structopt::StructOpt;
 use thiserror::Error;
 
-#[derive(StructOpt)]
+#[derive(StructOpt, Clone)]
 pub struct RestArgs {
     /// node API address. Must always have `http://` or `https://` prefix.
     /// E.g. `-h http://127.0.0.1`, `--host https://node.com:8443/cardano/api`
@@ -21,6 +21,17 @@ pub struct RestArgs {
     /// certificate CA is not present within the webpki certificate bundle.
     #[structopt(long, name = "PATH", env = "JORMUNGANDR_TLS_CERT_PATH")]
     tls_cert_path: Option<PathBuf>,
+    /// A PEM-encoded TLS client certificate, sent to the nodes verifying
+    /// the certificates of their clients. Requires `--tls-client-key`.
+    #[structopt(long, env = "JORMUNGANDR_TLS_CLIENT_CERT_PATH")]
+    tls_client_cert: Option<PathBuf>,
+    /// The PEM-encoded private key of the TLS client certificate.
+    #[structopt(long, env = "JORMUNGANDR_TLS_CLIENT_KEY_PATH")]
+    tls_client_key: Option<PathBuf>,
+    /// A file holding the bearer token sent to the node, required by the
+    /// admin endpoints when the node is configured with one.
+    #[structopt(long, env = "JORMUNGANDR_API_TOKEN_FILE")]
+    api_token_file: Option<PathBuf>,
 }
 
 pub struct RestClient {
@@ -45,6 +56,16 @@ pub enum Error {
     CertIo(#[source] std::io::Error),
     #[error("expected a valid PEM-encoded certificate")]
     Pem(#[source] reqwest::Error),
+    #[error("the TLS client certificate and its private key must be given together")]
+    IncompleteClientIdentity,
+    #[error("could not read the TLS client certificate or its private key")]
+    ClientIdentityIo(#[source] std::io::Error),
+    #[error("expected a valid PEM-encoded TLS client certificate and private key")]
+    ClientIdentity(#[source] reqwest::Error),
+    #[error("could not read the API token file")]
+    TokenIo(#[source] std::io::Error),
+    #[error("the API token is not valid in an HTTP header")]
+    InvalidToken,
     #[error("failed to build an HTTP client")]
     Client(#[source] reqwest::Error),
     #[error("invalid request")]
@@ -57,6 +78,8 @@ pub enum Error {
     Text(#[source] reqwest::Error),
     #[error("connection with the node timed out")]
     Timeout,
+    #[error("node rejected request because of missing or invalid credentials")]
+    Unauthorized(#[source] reqwest::Error),
     #[error("node rejected request because of invalid parameters")]
     InvalidParams(#[source] reqwest::Error),
     #[error("node internal error")]
@@ -69,13 +92,20 @@ pub enum Error {
 
 impl RestArgs {
     pub fn client(self) -> Result<RestClient, Error> {
-        use reqwest::{blocking::ClientBuilder, Certificate};
+        use reqwest::{
+            blocking::ClientBuilder,
+            header::{HeaderMap, HeaderValue, AUTHORIZATION},
+            Certificate, Identity,
+        };
         use std::{fs::File, io::Read};
 
         let Self {
             tls_cert_path,
+            tls_client_cert,
+            tls_client_key,
             host,
             debug,
+            api_token_file,
         } = self;
 
         if host.cannot_be_a_base() {
@@ -97,6 +127,32 @@ impl RestArgs {
             client_builder
         };
 
+        // load the client certificate and its key
+        let client_builder = match (tls_client_cert, tls_client_key) {
+            (Some(cert_path), Some(key_path)) => {
+                let mut buf = std::fs::read(cert_path).map_err(Error::ClientIdentityIo)?;
+                buf.push(b'\n');
+                buf.extend(std::fs::read(key_path).map_err(Error::ClientIdentityIo)?);
+                let identity = Identity::from_pem(&buf).map_err(Error::ClientIdentity)?;
+                client_builder.use_rustls_tls().identity(identity)
+            }
+            (None, None) => client_builder,
+            _ => return Err(Error::IncompleteClientIdentity),
+        };
+
+        // send the bearer token with every request
+        let client_builder = if let Some(path) = api_token_file {
+            let token = std::fs::read_to_string(path).map_err(Error::TokenIo)?;
+            let mut value = HeaderValue::from_str(&format!("Bearer {}", token.trim()))
+                .map_err(|_| Error::InvalidToken)?;
+            value.set_sensitive(true);
+            let mut headers = HeaderMap::new();
+            headers.insert(AUTHORIZATION, value);
+            client_builder.default_headers(headers)
+        } else {
+            client_builder
+        };
+
         let client = client_builder.build().map_err(Error::Client)?;
 
         let rest_client = RestClient {
@@ -200,7 +256,9 @@ impl RestRequestBuilder {
                 if e.is_timeout() {
                     Error::Timeout
                 } else if let Some(status) = e.status() {
-                    if status.is_client_error() {
+                    if status == reqwest::StatusCode::UNAUTHORIZED {
+                        Error::Unauthorized(e)
+                    } else if status.is_client_error() {
                         Error::InvalidParams(e)
                     } else if status.is_server_error() {
                         Error::InternalError(e)
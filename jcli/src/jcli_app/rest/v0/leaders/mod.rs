@@ -33,6 +33,24 @@ pub enum Leaders {
 
     /// Leadership log operations
     Logs(GetLogs),
+
+    /// Get the counters of the produced, adopted, orphaned and missed
+    /// blocks of each leader
+    Stats {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+    },
+
+    /// Get the slots the leaders are elected for in the current epoch and,
+    /// if already known, in the next one
+    Schedule {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+    },
 }
 
 #[derive(StructOpt)]
@@ -60,6 +78,14 @@ impl Leaders {
                 args,
                 output_format,
             }) => get_logs(args, output_format),
+            Leaders::Stats {
+                args,
+                output_format,
+            } => get_stats(args, output_format),
+            Leaders::Schedule {
+                args,
+                output_format,
+            } => get_schedule(args, output_format),
         }
     }
 }
@@ -101,3 +127,25 @@ fn get_logs(args: RestArgs, output_format: OutputFormat) -> Result<(), Error> {
     println!("{}", formatted);
     Ok(())
 }
+
+fn get_stats(args: RestArgs, output_format: OutputFormat) -> Result<(), Error> {
+    let response = args
+        .client()?
+        .get(&["v0", "leaders", "stats"])
+        .execute()?
+        .json()?;
+    let formatted = output_format.format_json(response)?;
+    println!("{}", formatted);
+    Ok(())
+}
+
+fn get_schedule(args: RestArgs, output_format: OutputFormat) -> Result<(), Error> {
+    let response = args
+        .client()?
+        .get(&["v0", "leaders", "schedule"])
+        .execute()?
+        .json()?;
+    let formatted = output_format.format_json(response)?;
+    println!("{}", formatted);
+    Ok(())
+}
@@ -0,0 +1,80 @@
+use crate::jcli_app::rest::{Error, RestArgs};
+use crate::jcli_app::utils::OutputFormat;
+use jormungandr_lib::interfaces::LogTargetLevel;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Log {
+    /// Log filter operations
+    Filter(Filter),
+}
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum Filter {
+    /// Get the level of each log output and the levels set for the targets
+    Get {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+    },
+    /// Set the log level of a target in all the log outputs
+    Set {
+        #[structopt(flatten)]
+        args: RestArgs,
+        #[structopt(flatten)]
+        output_format: OutputFormat,
+        /// the target, e.g. `jormungandr::network`
+        target: String,
+        /// the new level of the target. If not provided, the target goes back
+        /// to the level of each log output.
+        level: Option<String>,
+    },
+}
+
+impl Log {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            Log::Filter(Filter::Get {
+                args,
+                output_format,
+            }) => get_filter(args, output_format),
+            Log::Filter(Filter::Set {
+                args,
+                output_format,
+                target,
+                level,
+            }) => set_filter(args, output_format, target, level),
+        }
+    }
+}
+
+fn get_filter(args: RestArgs, output_format: OutputFormat) -> Result<(), Error> {
+    let response = args
+        .client()?
+        .get(&["v0", "log", "filter"])
+        .execute()?
+        .json()?;
+    let formatted = output_format.format_json(response)?;
+    println!("{}", formatted);
+    Ok(())
+}
+
+fn set_filter(
+    args: RestArgs,
+    output_format: OutputFormat,
+    target: String,
+    level: Option<String>,
+) -> Result<(), Error> {
+    let response = args
+        .client()?
+        .post(&["v0", "log", "filter"])
+        .json(&LogTargetLevel { target, level })
+        .execute()?
+        .json()?;
+    let formatted = output_format.format_json(response)?;
+    println!("{}", formatted);
+    Ok(())
+}
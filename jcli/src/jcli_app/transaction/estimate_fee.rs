@@ -0,0 +1,92 @@
+use crate::jcli_app::{
+    rest::{self, RestArgs},
+    transaction::{common, Error},
+    utils::io,
+};
+use chain_impl_mockchain::fee::LinearFee;
+use jormungandr_lib::interfaces::{
+    Block0Configuration, FeeEstimate, SettingsDto, UnsignedTransaction,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum EstimateFee {
+    /// compute the fee with the fee settings found in a genesis file or in
+    /// the node settings (as returned by `jcli rest v0 settings get`)
+    Offline {
+        #[structopt(flatten)]
+        common: common::CommonTransaction,
+        /// the genesis file of the blockchain, in YAML
+        #[structopt(long, required_unless = "settings", conflicts_with = "settings")]
+        genesis: Option<PathBuf>,
+        /// the settings of a node, in YAML or JSON
+        #[structopt(long)]
+        settings: Option<PathBuf>,
+    },
+    /// ask a node for the fee, computed with the fee settings of its tip
+    Online {
+        #[structopt(flatten)]
+        common: common::CommonTransaction,
+        #[structopt(flatten)]
+        args: RestArgs,
+    },
+}
+
+impl EstimateFee {
+    pub fn exec(self) -> Result<(), Error> {
+        match self {
+            EstimateFee::Offline {
+                common,
+                genesis,
+                settings,
+            } => {
+                let staging = common.load()?;
+                let fees = match (genesis, settings) {
+                    (Some(genesis), _) => read_genesis_fees(genesis)?,
+                    (None, Some(settings)) => read_settings_fees(settings)?,
+                    (None, None) => unreachable!("structopt requires one of the fee settings"),
+                };
+                println!("{}", staging.fees(&fees).0);
+            }
+            EstimateFee::Online { common, args } => {
+                let transaction = common.load()?.unsigned_transaction();
+                let estimate = request_fee(args, &transaction)?;
+                println!("{}", estimate.fee);
+            }
+        }
+        Ok(())
+    }
+}
+
+fn read_genesis_fees(path: PathBuf) -> Result<LinearFee, Error> {
+    let genesis: Block0Configuration =
+        io::read_yaml(&Some(&path)).map_err(|source| Error::FeeSettingsReadFailed {
+            source,
+            path: path.clone(),
+        })?;
+    Ok(genesis.blockchain_configuration.linear_fees)
+}
+
+fn read_settings_fees(path: PathBuf) -> Result<LinearFee, Error> {
+    let settings: SettingsDto =
+        io::read_yaml(&Some(&path)).map_err(|source| Error::FeeSettingsReadFailed {
+            source,
+            path: path.clone(),
+        })?;
+    Ok(settings.fees)
+}
+
+fn request_fee(
+    args: RestArgs,
+    transaction: &UnsignedTransaction,
+) -> Result<FeeEstimate, rest::Error> {
+    let estimate = args
+        .client()?
+        .post(&["v1", "fees", "estimate"])
+        .json(transaction)
+        .execute()?
+        .json()?;
+    Ok(estimate)
+}
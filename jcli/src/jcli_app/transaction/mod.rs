@@ -5,20 +5,23 @@ mod add_output;
 mod add_witness;
 mod auth;
 mod common;
+mod estimate_fee;
 mod finalize;
 mod info;
 mod mk_witness;
 mod new;
 mod seal;
+mod send;
 mod staging;
 
 use self::staging::StagingKind;
 use crate::jcli_app::{
-    certificate,
-    utils::{key_parser, output_format},
+    certificate, rest,
+    utils::{io::ReadYamlError, key_parser, output_format},
 };
 use chain_core::property::Serialize as _;
 use chain_impl_mockchain as chain;
+use jormungandr_lib::interfaces;
 use std::path::PathBuf;
 use structopt::StructOpt;
 use thiserror::Error;
@@ -56,12 +59,19 @@ pub enum Transaction {
     DataForWitness(common::CommonTransaction),
     /// display the info regarding a given transaction
     Info(info::Info),
+    /// compute the fee of the transaction from the fee settings of a
+    /// blockchain
+    EstimateFee(estimate_fee::EstimateFee),
     /// create witnesses
     MakeWitness(mk_witness::MkWitness),
     /// make auth
     Auth(auth::Auth),
     /// get the message format out of a sealed transaction
     ToMessage(common::CommonTransaction),
+    /// build, sign and send a payment from the account of a secret key or
+    /// from the given UTxOs, querying the node for their value and the fee
+    /// settings
+    Send(send::Send),
 }
 
 type StaticStr = &'static str;
@@ -187,6 +197,34 @@ pub enum Error {
     TxWithOwnerStakeDelegationHasUtxoInput,
     #[error("transaction has owner stake delegation, but has outputs")]
     TxWithOwnerStakeDelegationHasOutputs,
+
+    #[error("could not read the fee settings from '{path}'")]
+    FeeSettingsReadFailed {
+        #[source]
+        source: ReadYamlError,
+        path: PathBuf,
+    },
+
+    #[error("request to the node failed")]
+    NodeRequestFailed(#[from] rest::Error),
+    #[error("the node returned a malformed block0 hash")]
+    Block0HashMalformed(#[source] chain_crypto::hash::Error),
+    #[error("not enough funds, {available} available but {needed} needed")]
+    NotEnoughFunds {
+        available: interfaces::Value,
+        needed: interfaces::Value,
+    },
+    #[error("paying the transaction needs more than the {max} inputs a transaction can have")]
+    TooManyInputs { max: usize },
+    #[error("UTxO {transaction_id}:{index} cannot be spent with the secret key")]
+    UtxoNotSpendable {
+        transaction_id: String,
+        index: chain::transaction::TransactionIndex,
+    },
+    #[error("the transaction was rejected: {reason}")]
+    FragmentRejected { reason: String },
+    #[error("the transaction {fragment_id} was not added in a block in time")]
+    FragmentWaitTimeout { fragment_id: String },
 }
 
 /*
@@ -212,9 +250,11 @@ impl Transaction {
             Transaction::Id(common) => display_id(common),
             Transaction::DataForWitness(common) => display_data_for_witness(common),
             Transaction::Info(info) => info.exec(),
+            Transaction::EstimateFee(estimate_fee) => estimate_fee.exec(),
             Transaction::MakeWitness(mk_witness) => mk_witness.exec(),
             Transaction::Auth(auth) => auth.exec(),
             Transaction::ToMessage(common) => display_message(common),
+            Transaction::Send(send) => send.exec(),
         }
     }
 }
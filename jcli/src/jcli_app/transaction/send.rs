@@ -0,0 +1,322 @@
+use crate::jcli_app::{
+    rest::{self, RestArgs},
+    transaction::{staging::Staging, Error},
+    utils::key_parser::read_ed25519_secret_key_from_file,
+};
+use chain_addr::{Address, Kind};
+use chain_core::property::Serialize as _;
+use chain_crypto::{Ed25519, PublicKey};
+use chain_impl_mockchain::{
+    account::SpendingCounter,
+    fee::{FeeAlgorithm, LinearFee},
+    fragment::FragmentId,
+    header::HeaderId,
+    key::EitherEd25519SecretKey,
+    transaction::{Output, OutputPolicy, TransactionIndex, UnspecifiedAccountIdentifier, Witness},
+    value::Value,
+};
+use jormungandr_lib::interfaces::{
+    self, AccountState, FragmentStatus, SettingsDto, TransactionOutput,
+};
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    str::FromStr,
+    thread,
+    time::{Duration, Instant},
+};
+use structopt::StructOpt;
+
+const STATUS_POLLING_INTERVAL: Duration = Duration::from_secs(1);
+
+/// the number of inputs of a transaction is encoded on a single byte
+const MAX_INPUTS: usize = u8::MAX as usize;
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub struct Send {
+    #[structopt(flatten)]
+    pub args: RestArgs,
+
+    /// the address to send the funds to
+    #[structopt(name = "ADDRESS")]
+    pub destination: interfaces::Address,
+
+    /// the value to send, the fee is paid on top of it
+    #[structopt(name = "VALUE")]
+    pub value: interfaces::Value,
+
+    /// a UTxO which can be spent, as `<TRANSACTION_ID>:<INDEX>`. Can be
+    /// given several times, the largest UTxOs are spent first and the change
+    /// is sent back to the address of the first one. The node cannot list the
+    /// UTxOs of an address, they have to be given here. If none is given the
+    /// funds are taken from the account of the secret key.
+    #[structopt(long = "utxo")]
+    pub utxos: Vec<UtxoPointer>,
+
+    /// wait until the transaction is added in a block or rejected
+    #[structopt(long)]
+    pub wait: bool,
+
+    /// how many seconds to wait for the transaction with `--wait`
+    #[structopt(long, default_value = "300")]
+    pub wait_timeout: u64,
+
+    /// the file path to the file to read the signing key from.
+    /// If omitted it will be read from the standard input.
+    #[structopt(long)]
+    pub secret: Option<PathBuf>,
+}
+
+/// The location of a UTxO: the transaction which created it and the index
+/// of the output in that transaction.
+pub struct UtxoPointer {
+    pub transaction_id: FragmentId,
+    pub index: TransactionIndex,
+}
+
+impl FromStr for UtxoPointer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = s.splitn(2, ':');
+        let (transaction_id, index) = match (parts.next(), parts.next()) {
+            (Some(transaction_id), Some(index)) => (transaction_id, index),
+            _ => return Err("expected `<TRANSACTION_ID>:<INDEX>'".to_owned()),
+        };
+        Ok(UtxoPointer {
+            transaction_id: transaction_id.parse().map_err(|e| format!("{}", e))?,
+            index: index.parse().map_err(|e| format!("{}", e))?,
+        })
+    }
+}
+
+impl Send {
+    pub fn exec(self) -> Result<(), Error> {
+        let secret_key = read_ed25519_secret_key_from_file(&self.secret)?;
+        let settings: SettingsDto = get(self.args.clone(), &["v0", "settings"])?;
+        let block0_hash =
+            HeaderId::from_str(&settings.block0_hash).map_err(Error::Block0HashMalformed)?;
+
+        let mut staging = Staging::new();
+        staging.add_output(Output {
+            address: self.destination.clone().into(),
+            value: self.value.into(),
+        })?;
+
+        if self.utxos.is_empty() {
+            self.spend_account(&mut staging, &secret_key, &settings.fees, &block0_hash)?;
+        } else {
+            self.spend_utxos(&mut staging, &secret_key, &settings.fees, &block0_hash)?;
+        }
+        staging.seal()?;
+
+        let fragment = staging.fragment()?;
+        let fragment_id = fragment.hash();
+        let bytes = fragment
+            .serialize_as_vec()
+            .map_err(Error::MessageSerializationFailed)?;
+        post_message(self.args.clone(), bytes)?;
+        println!("{}", fragment_id);
+
+        if self.wait {
+            let timeout = Duration::from_secs(self.wait_timeout);
+            wait_in_block(self.args, &fragment_id.to_string(), timeout)?;
+        }
+        Ok(())
+    }
+
+    fn spend_account(
+        &self,
+        staging: &mut Staging,
+        secret_key: &EitherEd25519SecretKey,
+        fees: &LinearFee,
+        block0_hash: &HeaderId,
+    ) -> Result<(), Error> {
+        let public_key = secret_key.to_public();
+        let account_id = hex::encode(public_key.as_ref());
+        let state: AccountState = get(self.args.clone(), &["v0", "account", &account_id])?;
+
+        let needed = (Value::from(self.value) + fees.calculate(None, 1, 1))?;
+        if Value::from(*state.value()) < needed {
+            return Err(Error::NotEnoughFunds {
+                available: *state.value(),
+                needed: needed.into(),
+            });
+        }
+
+        let account = UnspecifiedAccountIdentifier::from_single_account(public_key.into());
+        staging.add_input(interfaces::TransactionInput {
+            input: interfaces::TransactionInputType::Account(account.into()),
+            value: needed.into(),
+        })?;
+        staging.balance_inputs_outputs(fees, OutputPolicy::Forget)?;
+
+        let sign_data_hash = staging.transaction_sign_data_hash();
+        let counter = SpendingCounter::from(state.counter());
+        staging.add_witness(Witness::new_account(
+            block0_hash,
+            &sign_data_hash,
+            counter,
+            |d| secret_key.sign(d),
+        ))
+    }
+
+    fn spend_utxos(
+        &self,
+        staging: &mut Staging,
+        secret_key: &EitherEd25519SecretKey,
+        fees: &LinearFee,
+        block0_hash: &HeaderId,
+    ) -> Result<(), Error> {
+        let public_key = secret_key.to_public();
+        let mut candidates = Vec::with_capacity(self.utxos.len());
+        for utxo in &self.utxos {
+            let transaction_id = utxo.transaction_id.to_string();
+            let output: TransactionOutput = get(
+                self.args.clone(),
+                &["v0", "utxo", &transaction_id, &utxo.index.to_string()],
+            )?;
+            let address: Address = output.address().clone().into();
+            if !is_spendable_by(&address, &public_key) {
+                return Err(Error::UtxoNotSpendable {
+                    transaction_id,
+                    index: utxo.index,
+                });
+            }
+            candidates.push((utxo, address, Value::from(*output.value())));
+        }
+        candidates.sort_by(|(_, _, a), (_, _, b)| b.cmp(a));
+        let change_address = candidates[0].1.clone();
+
+        // take the largest UTxOs until they pay for the value, the fee and
+        // a change output
+        let value = Value::from(self.value);
+        let mut total = Value::zero();
+        let mut inputs = 0usize;
+        for (utxo, _, utxo_value) in candidates.iter().take(MAX_INPUTS) {
+            staging.add_input(interfaces::TransactionInput {
+                input: interfaces::TransactionInputType::Utxo(
+                    utxo.transaction_id.into(),
+                    utxo.index,
+                ),
+                value: (*utxo_value).into(),
+            })?;
+            total = (total + *utxo_value)?;
+            inputs += 1;
+            if total >= (value + fees.calculate(None, inputs as u8, 2))? {
+                break;
+            }
+        }
+        let needed = (value + fees.calculate(None, inputs as u8, 1))?;
+        if total < needed {
+            if candidates.len() > MAX_INPUTS {
+                return Err(Error::TooManyInputs { max: MAX_INPUTS });
+            }
+            return Err(Error::NotEnoughFunds {
+                available: total.into(),
+                needed: needed.into(),
+            });
+        }
+        staging.balance_inputs_outputs(fees, OutputPolicy::One(change_address))?;
+
+        let sign_data_hash = staging.transaction_sign_data_hash();
+        for _ in 0..inputs {
+            staging.add_witness(Witness::new_utxo(block0_hash, &sign_data_hash, |d| {
+                secret_key.sign(d)
+            }))?;
+        }
+        Ok(())
+    }
+}
+
+fn is_spendable_by(address: &Address, public_key: &PublicKey<Ed25519>) -> bool {
+    match address.kind() {
+        Kind::Single(key) | Kind::Group(key, _) => key == public_key,
+        Kind::Account(_) | Kind::Multisig(_) | Kind::Script(_) => false,
+    }
+}
+
+fn get<T: serde::de::DeserializeOwned>(args: RestArgs, path: &[&str]) -> Result<T, rest::Error> {
+    let response = args.client()?.get(path).execute()?.json()?;
+    Ok(response)
+}
+
+fn post_message(args: RestArgs, bytes: Vec<u8>) -> Result<(), rest::Error> {
+    args.client()?
+        .post(&["v0", "message"])
+        .body(bytes)
+        .execute()?;
+    Ok(())
+}
+
+fn wait_in_block(args: RestArgs, fragment_id: &str, timeout: Duration) -> Result<(), Error> {
+    let start = Instant::now();
+    loop {
+        match fragment_status(args.clone(), fragment_id)? {
+            Some(FragmentStatus::InABlock { date, block }) => {
+                println!("in block {} at {}", block, date);
+                return Ok(());
+            }
+            Some(FragmentStatus::Rejected { reason }) => {
+                return Err(Error::FragmentRejected { reason })
+            }
+            Some(FragmentStatus::Pending) | None => {}
+        }
+        if start.elapsed() >= timeout {
+            return Err(Error::FragmentWaitTimeout {
+                fragment_id: fragment_id.to_owned(),
+            });
+        }
+        thread::sleep(STATUS_POLLING_INTERVAL);
+    }
+}
+
+fn fragment_status(
+    args: RestArgs,
+    fragment_id: &str,
+) -> Result<Option<FragmentStatus>, rest::Error> {
+    let mut statuses: HashMap<String, FragmentStatus> = args
+        .client()?
+        .get(&["v1", "fragments", "statuses"])
+        .query(&[("fragment_ids", fragment_id)])
+        .execute()?
+        .json()?;
+    Ok(statuses.remove(fragment_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSACTION_ID: &str = "c355a02d3b5337ad0e5f5940582675229f25bc03e7feebc3aa929738e1fec35e";
+
+    #[test]
+    fn utxo_pointer_from_str() {
+        let utxo: UtxoPointer = format!("{}:3", TRANSACTION_ID).parse().unwrap();
+        assert_eq!(
+            utxo.transaction_id,
+            FragmentId::from_str(TRANSACTION_ID).unwrap()
+        );
+        assert_eq!(utxo.index, 3);
+    }
+
+    #[test]
+    fn utxo_pointer_from_str_rejects_malformed_pointers() {
+        for malformed in &[
+            TRANSACTION_ID.to_owned(),
+            format!("{}:", TRANSACTION_ID),
+            format!("{}:256", TRANSACTION_ID),
+            format!("{}:-1", TRANSACTION_ID),
+            format!("{}:1:2", TRANSACTION_ID),
+            "c355a02d:0".to_owned(),
+            ":0".to_owned(),
+        ] {
+            assert!(
+                malformed.parse::<UtxoPointer>().is_err(),
+                "`{}' should not be parsed",
+                malformed
+            );
+        }
+    }
+}
@@ -521,6 +521,20 @@ impl Staging {
         Value::sum(self.outputs().iter().map(|output| *output.value().as_ref()))
     }
 
+    /// The transaction without its witnesses, for the node to compute its fee.
+    pub fn unsigned_transaction(&self) -> interfaces::UnsignedTransaction {
+        let certificate = self
+            .extra_authed
+            .clone()
+            .map(|cert| cert.strip_auth())
+            .or_else(|| self.extra.clone());
+        interfaces::UnsignedTransaction {
+            inputs: self.inputs.clone(),
+            outputs: self.outputs.clone(),
+            certificate: certificate.as_ref().map(interfaces::CertificateKind::from),
+        }
+    }
+
     pub fn fees(&self, fee_algorithm: &impl FeeAlgorithm) -> Value {
         let cert_extra = self.extra_authed.clone().map(|cert| cert.strip_auth());
         let cert_payload = cert_extra
@@ -24,8 +24,14 @@
 //! It provides the same interfaces as for the identifier in the
 //! `key` module but limited to Account only.
 //!
+//! # Passphrase
+//!
+//! Where to read the passphrase of an encrypted secret file from: an
+//! environment variable, a file descriptor or the terminal.
+//!
 
 pub mod account;
 pub mod hash;
 pub mod key;
+pub mod passphrase;
 pub(crate) mod serde;
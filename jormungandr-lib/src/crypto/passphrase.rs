@@ -0,0 +1,128 @@
+use std::{env, io};
+use thiserror::Error;
+
+/// where to read the passphrase of an encrypted secret file from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PassphraseSource {
+    /// the value of the given environment variable
+    Env(String),
+    /// the first line read from the given file descriptor
+    Fd(i32),
+    /// ask the user on the terminal
+    Prompt,
+}
+
+#[derive(Debug, Error)]
+pub enum PassphraseError {
+    #[error("cannot read the passphrase from the environment variable '{0}'")]
+    Env(String),
+    #[error("cannot read the passphrase")]
+    Io(#[from] io::Error),
+    #[error("reading the passphrase from a file descriptor is only supported on Unix systems")]
+    FdNotSupported,
+    #[error("the passphrases do not match")]
+    Mismatch,
+    #[error("the passphrase is empty")]
+    Empty,
+}
+
+impl Default for PassphraseSource {
+    fn default() -> Self {
+        PassphraseSource::Prompt
+    }
+}
+
+impl PassphraseSource {
+    /// build the source from the optional environment variable and file
+    /// descriptor given on the command line, falling back to the prompt
+    pub fn from_options(env: Option<String>, fd: Option<i32>) -> Self {
+        match (env, fd) {
+            (Some(var), _) => PassphraseSource::Env(var),
+            (None, Some(fd)) => PassphraseSource::Fd(fd),
+            (None, None) => PassphraseSource::Prompt,
+        }
+    }
+
+    /// read the passphrase of an existing secret
+    pub fn read(&self, prompt: &str) -> Result<String, PassphraseError> {
+        match self {
+            PassphraseSource::Env(var) => {
+                env::var(var).map_err(|_| PassphraseError::Env(var.clone()))
+            }
+            PassphraseSource::Fd(fd) => read_fd(*fd),
+            PassphraseSource::Prompt => Ok(rpassword::read_password_from_tty(Some(prompt))?),
+        }
+    }
+
+    /// read the passphrase of a new secret, asking for it twice on the
+    /// terminal
+    pub fn read_new(&self, prompt: &str) -> Result<String, PassphraseError> {
+        let passphrase = self.read(prompt)?;
+        if passphrase.is_empty() {
+            return Err(PassphraseError::Empty);
+        }
+        if let PassphraseSource::Prompt = self {
+            let confirmation = rpassword::read_password_from_tty(Some("Confirm passphrase: "))?;
+            if confirmation != passphrase {
+                return Err(PassphraseError::Mismatch);
+            }
+        }
+        Ok(passphrase)
+    }
+}
+
+#[cfg(unix)]
+fn read_fd(fd: i32) -> Result<String, PassphraseError> {
+    use std::{fs::File, io::Read, mem::ManuallyDrop, os::unix::io::FromRawFd};
+
+    if fd < 0 {
+        return Err(io::Error::from(io::ErrorKind::InvalidInput).into());
+    }
+    // SAFETY: the descriptor is opened by the caller of the process and stays
+    // owned by it. The file is never dropped so the descriptor is not closed:
+    // reading the passphrase again cannot close it twice, nor close another
+    // file which reused the descriptor.
+    let file = ManuallyDrop::new(unsafe { File::from_raw_fd(fd) });
+
+    // read byte by byte so nothing after the first line is consumed
+    let mut line = Vec::new();
+    for byte in (&*file).bytes() {
+        match byte? {
+            b'\n' => break,
+            byte => line.push(byte),
+        }
+    }
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line).map_err(|_| io::Error::from(io::ErrorKind::InvalidData).into())
+}
+
+#[cfg(not(unix))]
+fn read_fd(_fd: i32) -> Result<String, PassphraseError> {
+    Err(PassphraseError::FdNotSupported)
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+    use std::{fs, io::Write, os::unix::io::AsRawFd};
+
+    #[test]
+    fn fd_is_read_line_by_line_and_left_open() {
+        let path = env::temp_dir().join(format!("jormungandr-passphrase-{}", std::process::id()));
+        fs::File::create(&path)
+            .unwrap()
+            .write_all(b"first passphrase\r\nsecond passphrase\n")
+            .unwrap();
+        let file = fs::File::open(&path).unwrap();
+        let source = PassphraseSource::Fd(file.as_raw_fd());
+
+        assert_eq!(source.read("").unwrap(), "first passphrase");
+        assert_eq!(source.read("").unwrap(), "second passphrase");
+        // the descriptor is still owned, and closed, by the file
+        assert!(file.metadata().is_ok());
+        drop(file);
+        fs::remove_file(&path).unwrap();
+    }
+}
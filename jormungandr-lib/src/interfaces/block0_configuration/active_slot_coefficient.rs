@@ -71,6 +71,12 @@ impl From<ActiveSlotCoefficient> for ConfigParam {
     }
 }
 
+impl From<ActiveSlotCoefficient> for Milli {
+    fn from(active_slot_coefficient: ActiveSlotCoefficient) -> Self {
+        active_slot_coefficient.0
+    }
+}
+
 impl fmt::Display for ActiveSlotCoefficient {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.0.fmt(f)
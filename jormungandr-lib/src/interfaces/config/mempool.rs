@@ -1,3 +1,4 @@
+use crate::time::Duration;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
@@ -6,6 +7,31 @@ pub struct PoolMaxEntries(usize);
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize, Serialize)]
 pub struct LogMaxEntries(usize);
 
+/// the algorithm used by the leaders to select the fragments of the pool
+/// to put in a new block
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SelectionAlgorithm {
+    /// the fragments are selected in the order they were received
+    OldestFirst,
+    /// the fragments paying the highest fee per byte are selected first,
+    /// the fragments spending from the same account are still selected in
+    /// the order they were received
+    HighestFeeFirst,
+}
+
+/// which fragments are evicted from the mempool to make room for new ones
+/// once it reached its maximum number of entries
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvictionStrategy {
+    /// evict the fragment received first
+    Oldest,
+    /// evict the fragment paying the lowest fee per byte, if a new fragment
+    /// pays less than all the fragments of the mempool it is not accepted
+    LowestFee,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(deny_unknown_fields)]
 pub struct Mempool {
@@ -15,6 +41,20 @@ pub struct Mempool {
     /// maximum number of entries in the fragment logs
     #[serde(default)]
     pub log_max_entries: LogMaxEntries,
+    /// algorithm used to select the fragments to put in a block
+    #[serde(default)]
+    pub selection_algorithm: SelectionAlgorithm,
+    /// keep a journal of the pending fragments in the node storage so they
+    /// are not lost on restart
+    #[serde(default)]
+    pub persistent: bool,
+    /// time after which a fragment still in the mempool is evicted,
+    /// fragments do not expire if not set
+    #[serde(default)]
+    pub fragment_ttl: Option<Duration>,
+    /// which fragments to evict once the mempool is full
+    #[serde(default)]
+    pub eviction_strategy: EvictionStrategy,
 }
 
 impl Default for PoolMaxEntries {
@@ -29,11 +69,27 @@ impl Default for LogMaxEntries {
     }
 }
 
+impl Default for SelectionAlgorithm {
+    fn default() -> Self {
+        SelectionAlgorithm::OldestFirst
+    }
+}
+
+impl Default for EvictionStrategy {
+    fn default() -> Self {
+        EvictionStrategy::Oldest
+    }
+}
+
 impl Default for Mempool {
     fn default() -> Self {
         Mempool {
             pool_max_entries: PoolMaxEntries::default(),
             log_max_entries: LogMaxEntries::default(),
+            selection_algorithm: SelectionAlgorithm::default(),
+            persistent: false,
+            fragment_ttl: None,
+            eviction_strategy: EvictionStrategy::default(),
         }
     }
 }
@@ -61,3 +117,47 @@ impl From<LogMaxEntries> for usize {
         s.0
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn selection_algorithm_defaults_to_oldest_first() {
+        let mempool: Mempool = serde_yaml::from_str("pool_max_entries: 100").unwrap();
+
+        assert_eq!(mempool.selection_algorithm, SelectionAlgorithm::OldestFirst);
+    }
+
+    #[test]
+    fn fragment_ttl_and_eviction_strategy() {
+        let mempool: Mempool =
+            serde_yaml::from_str("fragment_ttl: 30m\neviction_strategy: lowest_fee").unwrap();
+
+        assert_eq!(
+            mempool.fragment_ttl,
+            Some(std::time::Duration::from_secs(30 * 60).into())
+        );
+        assert_eq!(mempool.eviction_strategy, EvictionStrategy::LowestFee);
+
+        let mempool: Mempool = serde_yaml::from_str("{}").unwrap();
+        assert_eq!(mempool.fragment_ttl, None);
+        assert_eq!(mempool.eviction_strategy, EvictionStrategy::Oldest);
+    }
+
+    #[test]
+    fn selection_algorithm_serde_encode_decode() {
+        for selection_algorithm in &[
+            SelectionAlgorithm::OldestFirst,
+            SelectionAlgorithm::HighestFeeFirst,
+        ] {
+            let s = serde_yaml::to_string(selection_algorithm).unwrap();
+            let decoded: SelectionAlgorithm = serde_yaml::from_str(&s).unwrap();
+
+            assert_eq!(*selection_algorithm, decoded);
+        }
+
+        let decoded: SelectionAlgorithm = serde_yaml::from_str("highest_fee_first").unwrap();
+        assert_eq!(decoded, SelectionAlgorithm::HighestFeeFirst);
+    }
+}
@@ -4,9 +4,12 @@ mod node;
 mod secret;
 
 pub use log::{Log, LogEntry, LogOutput};
-pub use mempool::{LogMaxEntries, Mempool, PoolMaxEntries};
+pub use mempool::{EvictionStrategy, LogMaxEntries, Mempool, PoolMaxEntries, SelectionAlgorithm};
 pub use node::{
-    Cors, Explorer, LayersConfig, NodeConfig, P2p, Policy, PreferredListConfig, Rest, Tls,
-    TopicsOfInterest, TrustedPeer,
+    AdminAuth, Cors, Explorer, LayersConfig, NodeConfig, P2p, Policy, PreferredListConfig,
+    RateLimit, Rest, RestLimits, Tls, TopicsOfInterest, TrustedPeer,
+};
+pub use secret::{
+    Bft, EncryptedSecret, EncryptedSecretFile, GenesisPraos, Kdf, NodeSecret,
+    SecretDecryptionError, DEFAULT_PBKDF2_ITERATIONS, MINIMUM_PBKDF2_ITERATIONS,
 };
-pub use secret::{Bft, GenesisPraos, NodeSecret};
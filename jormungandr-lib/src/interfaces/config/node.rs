@@ -4,7 +4,7 @@ use crate::{
     time::Duration,
 };
 use serde::{de::Visitor, Deserialize, Deserializer, Serialize};
-use std::{fmt, net::SocketAddr, path::PathBuf, str::FromStr};
+use std::{fmt, net::SocketAddr, num::NonZeroU32, path::PathBuf, str::FromStr};
 const DEFAULT_PREFERRED_VIEW_MAX: usize = 20;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -17,6 +17,53 @@ pub struct Rest {
     /// Enables CORS if provided
     #[serde(skip_serializing_if = "Option::is_none")]
     pub cors: Option<Cors>,
+    /// Requires credentials for the admin endpoints if provided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub admin: Option<AdminAuth>,
+    /// Limits the requests submitting fragments if provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limits: Option<RestLimits>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RestLimits {
+    /// Maximum size in bytes of the body of the requests submitting fragments,
+    /// if none provided the size is not limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_body_size: Option<u64>,
+    /// Maximum number of fragments of a batch sent to `/api/v1/fragments` or
+    /// `/api/v1/fragments/validate`, if none provided the number is not limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_fragments_per_batch: Option<usize>,
+    /// Maximum rate of the requests submitting fragments from a single IPv4
+    /// address or IPv6 /64 prefix, if none provided the rate is not limited
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate_limit: Option<RateLimit>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct RateLimit {
+    /// Number of requests allowed per `period`, this is also the number of
+    /// requests a client can send at once after being idle for a `period`
+    pub requests: NonZeroU32,
+    /// e.g. `1s` or `1min`
+    pub period: Duration,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct AdminAuth {
+    /// Path to the file holding the token the requests to the admin endpoints
+    /// may carry in their `Authorization: Bearer <token>` header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearer_token_file: Option<PathBuf>,
+    /// Subjects of the client certificates allowed to use the admin endpoints,
+    /// e.g. `CN=admin, O=Example`. Requires the TLS client certificates to be
+    /// verified, see `Tls::client_ca_file`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub client_subjects: Vec<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
@@ -24,8 +71,12 @@ pub struct Rest {
 pub struct Tls {
     /// Path to server X.509 certificate chain file, must be PEM-encoded and contain at least 1 item
     pub cert_file: String,
-    /// Path to server private key file, must be PKCS8 with single PEM-encoded, unencrypted key
+    /// Path to server private key file, must be PKCS8 or RSA with single PEM-encoded, unencrypted key
     pub priv_key_file: String,
+    /// Path to the PEM-encoded X.509 certificates of the CAs the client certificates must be
+    /// signed by. The server requires and verifies the certificates of the clients if provided
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_ca_file: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
@@ -131,6 +182,8 @@ pub struct Policy {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Explorer {
     pub enabled: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub address_bech32_prefix: Option<String>,
 }
 
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
@@ -1,6 +1,21 @@
 use crate::crypto::{hash::Hash, key::SigningKey};
 use chain_crypto::{Curve25519_2HashDH, Ed25519, SumEd25519_12};
+use cryptoxide::{chacha20poly1305::ChaCha20Poly1305, hmac::Hmac, pbkdf2::pbkdf2, sha2::Sha512};
+use rand_core::{CryptoRng, RngCore};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// the number of PBKDF2 iterations used to encrypt new secret files
+pub const DEFAULT_PBKDF2_ITERATIONS: u32 = 210_000;
+
+/// the number of PBKDF2 iterations below which a secret file is refused, a
+/// key derived with fewer iterations is too cheap to brute force
+pub const MINIMUM_PBKDF2_ITERATIONS: u32 = 100_000;
+
+const SALT_SIZE: usize = 16;
+const NONCE_SIZE: usize = 12;
+const KEY_SIZE: usize = 32;
+const TAG_SIZE: usize = 16;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct NodeSecret {
@@ -24,3 +39,165 @@ pub struct GenesisPraos {
     pub sig_key: SigningKey<SumEd25519_12>,
     pub vrf_key: SigningKey<Curve25519_2HashDH>,
 }
+
+/// the content of an encrypted secret file. A secret file holding this
+/// top level `encrypted_secret` key is encrypted, any other secret file is a
+/// plain [`NodeSecret`].
+///
+/// [`NodeSecret`]: ./struct.NodeSecret.html
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedSecretFile {
+    pub encrypted_secret: EncryptedSecret,
+}
+
+impl EncryptedSecretFile {
+    /// the top level key identifying an encrypted secret file
+    pub const KEY: &'static str = "encrypted_secret";
+}
+
+/// a secret encrypted with ChaCha20-Poly1305, with a key derived from a
+/// passphrase
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct EncryptedSecret {
+    pub kdf: Kdf,
+    /// hex encoded nonce of the cipher
+    pub nonce: String,
+    /// hex encoded encrypted YAML of the secret, followed by the
+    /// authentication tag
+    pub ciphertext: String,
+}
+
+/// the function deriving the encryption key from the passphrase
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum Kdf {
+    Pbkdf2HmacSha512 {
+        iterations: u32,
+        /// hex encoded salt
+        salt: String,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum SecretDecryptionError {
+    #[error("invalid hexadecimal in the encrypted secret")]
+    Hex(#[from] hex::FromHexError),
+    #[error("invalid size of the nonce or of the ciphertext")]
+    InvalidSize,
+    #[error("wrong passphrase or corrupted secret")]
+    Authentication,
+    #[error(
+        "the key of the secret is derived with {0} iterations, at least {minimum} are required",
+        minimum = MINIMUM_PBKDF2_ITERATIONS
+    )]
+    TooFewIterations(u32),
+}
+
+impl EncryptedSecret {
+    pub fn encrypt<R>(plaintext: &[u8], passphrase: &str, iterations: u32, rng: &mut R) -> Self
+    where
+        R: RngCore + CryptoRng,
+    {
+        let mut salt = [0; SALT_SIZE];
+        let mut nonce = [0; NONCE_SIZE];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce);
+
+        let key = derive_key(passphrase, &salt, iterations);
+        let mut ciphertext = vec![0; plaintext.len() + TAG_SIZE];
+        let (encrypted, tag) = ciphertext.split_at_mut(plaintext.len());
+        ChaCha20Poly1305::new(&key, &nonce, &[]).encrypt(plaintext, encrypted, tag);
+
+        EncryptedSecret {
+            kdf: Kdf::Pbkdf2HmacSha512 {
+                iterations,
+                salt: hex::encode(salt),
+            },
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        }
+    }
+
+    pub fn decrypt(&self, passphrase: &str) -> Result<Vec<u8>, SecretDecryptionError> {
+        let key = match &self.kdf {
+            Kdf::Pbkdf2HmacSha512 { iterations, .. } if *iterations < MINIMUM_PBKDF2_ITERATIONS => {
+                return Err(SecretDecryptionError::TooFewIterations(*iterations))
+            }
+            Kdf::Pbkdf2HmacSha512 { iterations, salt } => {
+                derive_key(passphrase, &hex::decode(salt)?, *iterations)
+            }
+        };
+        let nonce = hex::decode(&self.nonce)?;
+        let ciphertext = hex::decode(&self.ciphertext)?;
+        if nonce.len() != NONCE_SIZE || ciphertext.len() < TAG_SIZE {
+            return Err(SecretDecryptionError::InvalidSize);
+        }
+
+        let (encrypted, tag) = ciphertext.split_at(ciphertext.len() - TAG_SIZE);
+        let mut plaintext = vec![0; encrypted.len()];
+        if ChaCha20Poly1305::new(&key, &nonce, &[]).decrypt(encrypted, &mut plaintext, tag) {
+            Ok(plaintext)
+        } else {
+            Err(SecretDecryptionError::Authentication)
+        }
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], iterations: u32) -> [u8; KEY_SIZE] {
+    let mut mac = Hmac::new(Sha512::new(), passphrase.as_bytes());
+    let mut key = [0; KEY_SIZE];
+    pbkdf2(&mut mac, salt, iterations, &mut key);
+    key
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rand_chacha::ChaChaRng;
+    use rand_core::SeedableRng;
+
+    const ITERATIONS: u32 = MINIMUM_PBKDF2_ITERATIONS;
+
+    #[test]
+    fn encrypted_secret_roundtrip() {
+        let mut rng = ChaChaRng::from_seed([0; 32]);
+        let plaintext = b"bft:\n  signing_key: ed25519_sk1...\n";
+        let encrypted = EncryptedSecret::encrypt(plaintext, "passphrase", ITERATIONS, &mut rng);
+
+        assert_eq!(encrypted.decrypt("passphrase").unwrap(), plaintext.to_vec());
+        assert!(matches!(
+            encrypted.decrypt("wrong passphrase"),
+            Err(SecretDecryptionError::Authentication)
+        ));
+    }
+
+    #[test]
+    fn encrypted_secret_file_serde() {
+        let mut rng = ChaChaRng::from_seed([0; 32]);
+        let file = EncryptedSecretFile {
+            encrypted_secret: EncryptedSecret::encrypt(b"{}", "passphrase", ITERATIONS, &mut rng),
+        };
+        let yaml = serde_yaml::to_string(&file).unwrap();
+        let value: serde_yaml::Value = serde_yaml::from_str(&yaml).unwrap();
+        assert!(value.get(EncryptedSecretFile::KEY).is_some());
+
+        let decoded: EncryptedSecretFile = serde_yaml::from_value(value).unwrap();
+        assert_eq!(decoded, file);
+        assert_eq!(
+            decoded.encrypted_secret.decrypt("passphrase").unwrap(),
+            b"{}"
+        );
+    }
+
+    #[test]
+    fn too_few_iterations_are_refused() {
+        let mut rng = ChaChaRng::from_seed([0; 32]);
+        let encrypted = EncryptedSecret::encrypt(b"{}", "passphrase", ITERATIONS - 1, &mut rng);
+        assert!(matches!(
+            encrypted.decrypt("passphrase"),
+            Err(SecretDecryptionError::TooFewIterations(iterations)) if iterations == ITERATIONS - 1
+        ));
+    }
+}
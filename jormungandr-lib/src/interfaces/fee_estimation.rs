@@ -0,0 +1,219 @@
+use crate::interfaces::{Certificate, TransactionInput, TransactionOutput, Value};
+use chain_impl_mockchain::{
+    certificate,
+    fee::{FeeAlgorithm, LinearFee},
+    value,
+};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+use thiserror::Error;
+
+/// A transaction before it is signed: everything its fee is computed from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UnsignedTransaction {
+    pub inputs: Vec<TransactionInput>,
+    pub outputs: Vec<TransactionOutput>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<CertificateKind>,
+}
+
+/// The kind of the certificate of a transaction, the fee of a certificate
+/// only depends on its kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CertificateKind {
+    StakeDelegation,
+    OwnerStakeDelegation,
+    PoolRegistration,
+    PoolRetirement,
+    PoolUpdate,
+    VotePlan,
+    VoteCast,
+    VoteTally,
+    EncryptedVoteTally,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FeeEstimate {
+    pub fee: Value,
+}
+
+#[derive(Debug, Error)]
+pub enum FeeEstimationError {
+    #[error("a transaction cannot have more than 255 inputs, it has {0}")]
+    TooManyInputs(usize),
+    #[error("a transaction cannot have more than 255 outputs, it has {0}")]
+    TooManyOutputs(usize),
+}
+
+impl UnsignedTransaction {
+    /// The fee the given fee settings charge for the transaction, the
+    /// witnesses do not change the fee.
+    pub fn fee(&self, fees: &LinearFee) -> Result<Value, FeeEstimationError> {
+        let inputs = u8::try_from(self.inputs.len())
+            .map_err(|_| FeeEstimationError::TooManyInputs(self.inputs.len()))?;
+        let outputs = u8::try_from(self.outputs.len())
+            .map_err(|_| FeeEstimationError::TooManyOutputs(self.outputs.len()))?;
+        let transaction = fees.calculate(None, inputs, outputs);
+        let certificate = self.certificate.map_or(0, |kind| kind.fee(fees));
+        Ok(value::Value(transaction.0 + certificate).into())
+    }
+}
+
+impl CertificateKind {
+    /// The fee charged for a certificate of this kind, the per certificate
+    /// fees fall back to the fee of any certificate when not set.
+    pub fn fee(self, fees: &LinearFee) -> u64 {
+        let specific = match self {
+            CertificateKind::PoolRegistration => {
+                fees.per_certificate_fees.certificate_pool_registration
+            }
+            CertificateKind::StakeDelegation => {
+                fees.per_certificate_fees.certificate_stake_delegation
+            }
+            CertificateKind::OwnerStakeDelegation => {
+                fees.per_certificate_fees.certificate_owner_stake_delegation
+            }
+            CertificateKind::VotePlan => fees.per_vote_certificate_fees.certificate_vote_plan,
+            CertificateKind::VoteCast => fees.per_vote_certificate_fees.certificate_vote_cast,
+            CertificateKind::PoolRetirement
+            | CertificateKind::PoolUpdate
+            | CertificateKind::VoteTally
+            | CertificateKind::EncryptedVoteTally => None,
+        };
+        specific.map_or(fees.certificate, |fee| fee.get())
+    }
+}
+
+impl<'a> From<&'a certificate::Certificate> for CertificateKind {
+    fn from(certificate: &'a certificate::Certificate) -> Self {
+        match certificate {
+            certificate::Certificate::StakeDelegation(_) => CertificateKind::StakeDelegation,
+            certificate::Certificate::OwnerStakeDelegation(_) => {
+                CertificateKind::OwnerStakeDelegation
+            }
+            certificate::Certificate::PoolRegistration(_) => CertificateKind::PoolRegistration,
+            certificate::Certificate::PoolRetirement(_) => CertificateKind::PoolRetirement,
+            certificate::Certificate::PoolUpdate(_) => CertificateKind::PoolUpdate,
+            certificate::Certificate::VotePlan(_) => CertificateKind::VotePlan,
+            certificate::Certificate::VoteCast(_) => CertificateKind::VoteCast,
+            certificate::Certificate::VoteTally(_) => CertificateKind::VoteTally,
+            certificate::Certificate::EncryptedVoteTally(_) => CertificateKind::EncryptedVoteTally,
+        }
+    }
+}
+
+impl<'a> From<&'a Certificate> for CertificateKind {
+    fn from(certificate: &'a Certificate) -> Self {
+        CertificateKind::from(&certificate.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::interfaces::TransactionInputType;
+    use chain_addr::{Discrimination, Kind};
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{
+        account::DelegationType, certificate::OwnerStakeDelegation, fee::PerCertificateFee,
+    };
+    use std::num::NonZeroU64;
+
+    fn linear_fee() -> LinearFee {
+        let mut fees = LinearFee::new(10, 2, 100);
+        fees.per_certificate_fees(PerCertificateFee::new(
+            NonZeroU64::new(500),
+            NonZeroU64::new(50),
+            NonZeroU64::new(30),
+        ));
+        fees
+    }
+
+    fn transaction(inputs: usize, outputs: usize) -> UnsignedTransaction {
+        let key = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let address = chain_addr::Address(Discrimination::Test, Kind::Account(key.to_public()));
+        UnsignedTransaction {
+            inputs: (0..inputs)
+                .map(|i| TransactionInput {
+                    input: TransactionInputType::Account([i as u8; 32]),
+                    value: Value::from(100),
+                })
+                .collect(),
+            outputs: (0..outputs)
+                .map(|_| TransactionOutput::new(address.clone().into(), Value::from(10)))
+                .collect(),
+            certificate: None,
+        }
+    }
+
+    fn owner_stake_delegation() -> Certificate {
+        Certificate(certificate::Certificate::OwnerStakeDelegation(
+            OwnerStakeDelegation {
+                delegation: DelegationType::NonDelegated,
+            },
+        ))
+    }
+
+    #[test]
+    fn fee_without_certificate() {
+        let fees = linear_fee();
+        assert_eq!(transaction(2, 3).fee(&fees).unwrap(), Value::from(20));
+        assert_eq!(transaction(0, 0).fee(&fees).unwrap(), Value::from(10));
+    }
+
+    #[test]
+    fn fee_with_certificate() {
+        let mut transaction = transaction(1, 0);
+        transaction.certificate = Some(CertificateKind::from(&owner_stake_delegation()));
+        assert_eq!(transaction.fee(&linear_fee()).unwrap(), Value::from(42));
+        // without specific fee, the fee of any certificate
+        assert_eq!(
+            transaction.fee(&LinearFee::new(10, 2, 100)).unwrap(),
+            Value::from(112)
+        );
+    }
+
+    #[test]
+    fn fee_per_certificate_kind() {
+        let fees = linear_fee();
+        let fee = |kind| {
+            let mut transaction = transaction(1, 1);
+            transaction.certificate = Some(kind);
+            transaction.fee(&fees).unwrap()
+        };
+        assert_eq!(fee(CertificateKind::PoolRegistration), Value::from(514));
+        assert_eq!(fee(CertificateKind::StakeDelegation), Value::from(64));
+        assert_eq!(fee(CertificateKind::OwnerStakeDelegation), Value::from(44));
+        // no specific fee for the vote certificates in these settings
+        assert_eq!(fee(CertificateKind::VotePlan), Value::from(114));
+        assert_eq!(fee(CertificateKind::PoolRetirement), Value::from(114));
+    }
+
+    #[test]
+    fn too_many_inputs_outputs() {
+        let fees = linear_fee();
+        assert!(matches!(
+            transaction(256, 0).fee(&fees),
+            Err(FeeEstimationError::TooManyInputs(256))
+        ));
+        assert!(matches!(
+            transaction(0, 300).fee(&fees),
+            Err(FeeEstimationError::TooManyOutputs(300))
+        ));
+    }
+
+    #[test]
+    fn unsigned_transaction_serde() {
+        let mut transaction = transaction(1, 2);
+        transaction.certificate = Some(CertificateKind::OwnerStakeDelegation);
+        let json = serde_json::to_string(&transaction).unwrap();
+        assert!(json.contains(r#""certificate":"owner_stake_delegation""#));
+        let decoded: UnsignedTransaction = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.inputs, transaction.inputs);
+        assert_eq!(decoded.outputs, transaction.outputs);
+        assert_eq!(serde_json::to_string(&decoded).unwrap(), json);
+    }
+}
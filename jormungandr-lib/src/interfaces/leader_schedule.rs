@@ -0,0 +1,20 @@
+use crate::{
+    interfaces::{BlockDate, EnclaveLeaderId},
+    time::SystemTime,
+};
+use serde::{Deserialize, Serialize};
+
+/// the slots the leaders of the enclave are elected for during an epoch
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochLeaderSchedule {
+    pub epoch: u32,
+    pub slots: Vec<LeaderSlot>,
+}
+
+/// a slot a leader of the enclave is elected for
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaderSlot {
+    pub enclave_leader_id: EnclaveLeaderId,
+    pub scheduled_at_date: BlockDate,
+    pub scheduled_at_time: SystemTime,
+}
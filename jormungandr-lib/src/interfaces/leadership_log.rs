@@ -27,6 +27,23 @@ pub enum LeadershipLogStatus {
     },
 }
 
+/// counters of the outcome of the scheduled events of a leader of the
+/// enclave, since the node started
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LeaderStats {
+    pub enclave_leader_id: EnclaveLeaderId,
+    /// the slots the leader was elected for and the node went through
+    pub scheduled: u64,
+    /// the blocks created and sent to the blockchain
+    pub produced: u64,
+    /// the produced blocks which are part of the tip chain
+    pub adopted: u64,
+    /// the produced blocks which did not make it to the tip chain
+    pub orphaned: u64,
+    /// the slots for which no block was produced
+    pub missed: u64,
+}
+
 /// provides information regarding events in the leadership schedule
 ///
 #[derive(Debug, Clone, Serialize, Deserialize)]
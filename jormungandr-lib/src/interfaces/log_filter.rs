@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// the log filter of a running node
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogFilter {
+    /// the level of each configured log output, in the order of the
+    /// configuration, for the targets without a level of their own
+    pub default_levels: Vec<String>,
+    /// the level set at runtime for each target, applying to all the outputs
+    pub targets: BTreeMap<String, String>,
+}
+
+/// change of the log level of a target, e.g. `jormungandr::network`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LogTargetLevel {
+    pub target: String,
+    /// the new level of the target, `None` to go back to the level of
+    /// each output
+    #[serde(default)]
+    pub level: Option<String>,
+}
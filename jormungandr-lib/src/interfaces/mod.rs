@@ -6,15 +6,19 @@ mod blockdate;
 mod certificate;
 mod committee;
 mod config;
+mod fee_estimation;
 mod fragment_log;
+mod leader_schedule;
 mod leadership_log;
 mod linear_fee;
+mod log_filter;
 mod old_address;
 mod peer_stats;
 mod ratio;
 mod reward_parameters;
 mod rewards_info;
 mod settings;
+mod signer;
 mod stake;
 mod stake_distribution;
 mod stake_pool_stats;
@@ -38,11 +42,16 @@ pub use self::certificate::{
 };
 pub use self::committee::CommitteeIdDef;
 pub use self::config::*;
+pub use self::fee_estimation::{
+    CertificateKind, FeeEstimate, FeeEstimationError, UnsignedTransaction,
+};
 pub use self::fragment_log::{FragmentLog, FragmentOrigin, FragmentStatus};
+pub use self::leader_schedule::{EpochLeaderSchedule, LeaderSlot};
 pub use self::leadership_log::{
-    EnclaveLeaderId, LeadershipLog, LeadershipLogId, LeadershipLogStatus,
+    EnclaveLeaderId, LeaderStats, LeadershipLog, LeadershipLogId, LeadershipLogStatus,
 };
 pub use self::linear_fee::LinearFeeDef;
+pub use self::log_filter::{LogFilter, LogTargetLevel};
 pub use self::old_address::OldAddress;
 pub use self::peer_stats::{
     Info, Logs, PeerRecord, PeerStats, Profile, Record, Strike, Subscription, When,
@@ -51,6 +60,10 @@ pub use self::ratio::{ParseRatioError, Ratio};
 pub use self::reward_parameters::RewardParams;
 pub use self::rewards_info::EpochRewardsInfo;
 pub use self::settings::{ParametersDef, RatioDef, SettingsDto, TaxTypeDef, TaxTypeSerde};
+pub use self::signer::{
+    serve_signer_request, SignerElection, SignerEpoch, SignerLeader, SignerPoolStake,
+    SignerRequest, SignerResponse,
+};
 pub use self::stake::{Stake, StakeDef};
 pub use self::stake_distribution::{StakeDistribution, StakeDistributionDto};
 pub use self::stake_pool_stats::{Rewards, StakePoolStats};
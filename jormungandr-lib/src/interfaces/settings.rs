@@ -1,5 +1,5 @@
 use crate::{
-    interfaces::{LinearFeeDef, ValueDef},
+    interfaces::{LinearFeeDef, SelectionAlgorithm, ValueDef},
     time::SystemTime,
 };
 use chain_impl_mockchain::block::Epoch;
@@ -26,6 +26,8 @@ pub struct SettingsDto {
     pub treasury_tax: TaxType,
     #[serde(with = "ParametersDef")]
     pub reward_params: Parameters,
+    #[serde(default)]
+    pub fragment_selection_algorithm: SelectionAlgorithm,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -93,5 +95,6 @@ impl PartialEq<SettingsDto> for SettingsDto {
             && self.slots_per_epoch == other.slots_per_epoch
             && self.treasury_tax == other.treasury_tax
             && self.reward_params == other.reward_params
+            && self.fragment_selection_algorithm == other.fragment_selection_algorithm
     }
 }
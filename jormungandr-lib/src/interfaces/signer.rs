@@ -0,0 +1,389 @@
+//! Protocol between the node and a remote signer holding the leader keys.
+//!
+//! The node connects to the Unix socket of the signer and sends one JSON
+//! encoded [`SignerRequest`] per line, the signer answers each of them with
+//! one JSON encoded [`SignerResponse`] per line.
+//!
+//! No secret key leaves the signer: the node only learns the public keys of
+//! the BFT leaders and the pool ids of the Genesis-Praos leaders. The
+//! Genesis-Praos leader schedule is evaluated by the signer, from the public
+//! parameters of the epoch sent by the node.
+//!
+//! [`SignerRequest`]: ./enum.SignerRequest.html
+//! [`SignerResponse`]: ./enum.SignerResponse.html
+
+use crate::{
+    crypto::{hash::Hash, key::Identifier},
+    interfaces::{ActiveSlotCoefficient, NodeSecret, Stake},
+};
+use chain_crypto::{Ed25519, SecretKey, SigningAlgorithm};
+use chain_impl_mockchain::{
+    leadership::genesis::{ActiveSlotsCoeff, Nonce, PercentStake, VrfEvaluator},
+    milli::Milli,
+};
+use serde::{Deserialize, Serialize};
+use std::convert::TryFrom;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "request", rename_all = "snake_case")]
+pub enum SignerRequest {
+    /// list the leaders held by the signer
+    Leaders,
+    /// sign the hex encoded authenticated data of a BFT block header
+    SignBft { leader: u32, data: String },
+    /// sign the hex encoded authenticated data of a Genesis-Praos block
+    /// header
+    SignGenesisPraos { leader: u32, data: String },
+    /// evaluate the Genesis-Praos leader schedule of an epoch
+    Schedule(SignerEpoch),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "response", rename_all = "snake_case")]
+pub enum SignerResponse {
+    Leaders {
+        leaders: Vec<SignerLeader>,
+    },
+    /// the hex encoded signature of the requested data
+    Signature {
+        signature: String,
+    },
+    /// the slots of the epoch the leaders of the signer are elected for
+    Schedule {
+        elections: Vec<SignerElection>,
+    },
+    /// the request could not be served, i.e. the leader is unknown or does
+    /// not hold the requested kind of key
+    Error {
+        reason: String,
+    },
+}
+
+/// the public part of a leader held by the signer
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerLeader {
+    /// identifier of the leader in the requests to the signer
+    pub id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bft: Option<Identifier<Ed25519>>,
+    /// the pool id of the leader, if it is a Genesis-Praos leader
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<Hash>,
+}
+
+/// the public parameters of an epoch the Genesis-Praos leaders are elected
+/// from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerEpoch {
+    pub epoch: u32,
+    pub slots_per_epoch: u32,
+    /// the hex encoded nonce of the epoch
+    pub nonce: String,
+    pub active_slots_coeff: ActiveSlotCoefficient,
+    /// the stake of the pools of the leaders of the signer
+    pub stakes: Vec<SignerPoolStake>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerPoolStake {
+    pub node_id: Hash,
+    pub stake: Stake,
+    /// the total stake of the pools in the epoch
+    pub total: Stake,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignerElection {
+    pub leader: u32,
+    pub slot: u32,
+    /// the hex encoded VRF proof of the election
+    pub proof: String,
+}
+
+/// Answer a request of the node with the keys of the given leaders, as the
+/// reference signer does. The leaders are identified by their index.
+pub fn serve_signer_request(secrets: &[NodeSecret], request: SignerRequest) -> SignerResponse {
+    match request {
+        SignerRequest::Leaders => SignerResponse::Leaders {
+            leaders: secrets
+                .iter()
+                .enumerate()
+                .map(|(id, secret)| SignerLeader {
+                    id: id as u32,
+                    bft: secret.bft.as_ref().map(|bft| bft.signing_key.identifier()),
+                    node_id: secret.genesis.as_ref().map(|genesis| genesis.node_id),
+                })
+                .collect(),
+        },
+        SignerRequest::SignBft { leader, data } => {
+            let key = secrets
+                .get(leader as usize)
+                .and_then(|secret| secret.bft.as_ref())
+                .map(|bft| bft.signing_key.as_ref());
+            sign(leader, key, &data)
+        }
+        SignerRequest::SignGenesisPraos { leader, data } => {
+            let key = secrets
+                .get(leader as usize)
+                .and_then(|secret| secret.genesis.as_ref())
+                .map(|genesis| genesis.sig_key.as_ref());
+            sign(leader, key, &data)
+        }
+        SignerRequest::Schedule(epoch) => schedule(secrets, &epoch),
+    }
+}
+
+fn sign<A>(leader: u32, key: Option<&SecretKey<A>>, data: &str) -> SignerResponse
+where
+    A: SigningAlgorithm,
+{
+    let key = match key {
+        Some(key) => key,
+        None => {
+            return SignerResponse::Error {
+                reason: format!("no such key for the leader {}", leader),
+            }
+        }
+    };
+    match hex::decode(data) {
+        Ok(data) => SignerResponse::Signature {
+            signature: hex::encode(key.sign_slice(&data).as_ref()),
+        },
+        Err(error) => SignerResponse::Error {
+            reason: format!("invalid data to sign: {}", error),
+        },
+    }
+}
+
+fn schedule(secrets: &[NodeSecret], epoch: &SignerEpoch) -> SignerResponse {
+    let nonce = match hex::decode(&epoch.nonce)
+        .ok()
+        .and_then(|nonce| <[u8; 32]>::try_from(nonce.as_slice()).ok())
+    {
+        Some(nonce) => Nonce::from(nonce),
+        None => {
+            return SignerResponse::Error {
+                reason: "invalid epoch nonce".to_owned(),
+            }
+        }
+    };
+    let active_slots_coeff = match ActiveSlotsCoeff::try_from(Milli::from(epoch.active_slots_coeff))
+    {
+        Ok(active_slots_coeff) => active_slots_coeff,
+        Err(error) => {
+            return SignerResponse::Error {
+                reason: format!("invalid active slot coefficient: {:?}", error),
+            }
+        }
+    };
+
+    let mut elections = Vec::new();
+    for (id, secret) in secrets.iter().enumerate() {
+        let genesis = match &secret.genesis {
+            Some(genesis) => genesis,
+            None => continue,
+        };
+        let pool = match epoch
+            .stakes
+            .iter()
+            .find(|pool| pool.node_id == genesis.node_id)
+        {
+            Some(pool) => pool,
+            None => continue,
+        };
+        for slot in 0..epoch.slots_per_epoch {
+            let evaluator = VrfEvaluator {
+                stake: PercentStake {
+                    stake: pool.stake.into(),
+                    total: pool.total.into(),
+                },
+                nonce: &nonce,
+                slot_id: slot,
+                active_slots_coeff,
+            };
+            match evaluator.evaluate(genesis.vrf_key.as_ref()) {
+                Ok(Some(witness)) => elections.push(SignerElection {
+                    leader: id as u32,
+                    slot,
+                    proof: hex::encode(witness.to_bytes()),
+                }),
+                Ok(None) => (),
+                Err(error) => {
+                    return SignerResponse::Error {
+                        reason: format!(
+                            "cannot evaluate the election of the leader {} at {}.{}: {:?}",
+                            id, epoch.epoch, slot, error
+                        ),
+                    }
+                }
+            }
+        }
+    }
+    SignerResponse::Schedule { elections }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        crypto::key::SigningKey,
+        interfaces::{Bft, GenesisPraos},
+    };
+    use chain_crypto::{Curve25519_2HashDH, SumEd25519_12};
+
+    fn bft_secret() -> NodeSecret {
+        NodeSecret {
+            bft: Some(Bft {
+                signing_key: SigningKey::generate(rand::thread_rng()),
+            }),
+            genesis: None,
+        }
+    }
+
+    fn genesis_secret(node_id: Hash) -> NodeSecret {
+        NodeSecret {
+            bft: None,
+            genesis: Some(GenesisPraos {
+                node_id,
+                sig_key: SigningKey::<SumEd25519_12>::generate(rand::thread_rng()),
+                vrf_key: SigningKey::<Curve25519_2HashDH>::generate(rand::thread_rng()),
+            }),
+        }
+    }
+
+    fn epoch(stakes: Vec<SignerPoolStake>) -> SignerEpoch {
+        SignerEpoch {
+            epoch: 3,
+            slots_per_epoch: 20,
+            nonce: hex::encode([7; 32]),
+            active_slots_coeff: ActiveSlotCoefficient::MAXIMUM,
+            stakes,
+        }
+    }
+
+    #[test]
+    fn request_serde() {
+        let request = SignerRequest::SignBft {
+            leader: 1,
+            data: "0102".to_owned(),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"request":"sign_bft","leader":1,"data":"0102"}"#);
+        let decoded: SignerRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, request);
+
+        let decoded: SignerRequest = serde_json::from_str(r#"{"request":"leaders"}"#).unwrap();
+        assert_eq!(decoded, SignerRequest::Leaders);
+
+        let request = SignerRequest::Schedule(epoch(vec![SignerPoolStake {
+            node_id: Hash::from([1; 32]),
+            stake: Stake::from(10),
+            total: Stake::from(100),
+        }]));
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.starts_with(r#"{"request":"schedule","epoch":3,"#));
+        let decoded: SignerRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, request);
+    }
+
+    #[test]
+    fn leaders_only_hold_public_data() {
+        let secrets = vec![bft_secret(), genesis_secret(Hash::from([1; 32]))];
+        let response = serve_signer_request(&secrets, SignerRequest::Leaders);
+        let json = serde_json::to_string(&response).unwrap();
+
+        let genesis = secrets[1].genesis.as_ref().unwrap();
+        for secret in &[
+            secrets[0].bft.as_ref().unwrap().signing_key.to_bech32_str(),
+            genesis.sig_key.to_bech32_str(),
+            genesis.vrf_key.to_bech32_str(),
+        ] {
+            assert!(!json.contains(secret.as_str()));
+        }
+
+        match response {
+            SignerResponse::Leaders { leaders } => assert_eq!(
+                leaders,
+                vec![
+                    SignerLeader {
+                        id: 0,
+                        bft: Some(secrets[0].bft.as_ref().unwrap().signing_key.identifier()),
+                        node_id: None,
+                    },
+                    SignerLeader {
+                        id: 1,
+                        bft: None,
+                        node_id: Some(genesis.node_id),
+                    },
+                ]
+            ),
+            response => panic!("unexpected response {:?}", response),
+        }
+    }
+
+    #[test]
+    fn unknown_keys_are_refused() {
+        let secrets = vec![bft_secret()];
+        for request in vec![
+            SignerRequest::SignBft {
+                leader: 1,
+                data: "00".to_owned(),
+            },
+            SignerRequest::SignGenesisPraos {
+                leader: 0,
+                data: "00".to_owned(),
+            },
+            SignerRequest::SignBft {
+                leader: 0,
+                data: "not hex".to_owned(),
+            },
+        ] {
+            assert!(matches!(
+                serve_signer_request(&secrets, request),
+                SignerResponse::Error { .. }
+            ));
+        }
+    }
+
+    #[test]
+    fn schedule_elects_the_pools_with_stake() {
+        let node_id = Hash::from([1; 32]);
+        let secrets = vec![
+            bft_secret(),
+            genesis_secret(node_id),
+            genesis_secret(Hash::from([2; 32])),
+        ];
+
+        // with the whole stake and an active slot coefficient of 1 the pool
+        // is elected at every slot, the pool without stake is never elected
+        let epoch = epoch(vec![SignerPoolStake {
+            node_id,
+            stake: Stake::from(100),
+            total: Stake::from(100),
+        }]);
+        let elections = match serve_signer_request(&secrets, SignerRequest::Schedule(epoch.clone()))
+        {
+            SignerResponse::Schedule { elections } => elections,
+            response => panic!("unexpected response {:?}", response),
+        };
+        assert_eq!(
+            elections
+                .iter()
+                .map(|election| (election.leader, election.slot))
+                .collect::<Vec<_>>(),
+            (0..epoch.slots_per_epoch)
+                .map(|slot| (1, slot))
+                .collect::<Vec<_>>()
+        );
+        assert!(elections
+            .iter()
+            .all(|election| hex::decode(&election.proof).is_ok()));
+
+        let mut invalid = epoch;
+        invalid.nonce = "00".to_owned();
+        assert!(matches!(
+            serve_signer_request(&secrets, SignerRequest::Schedule(invalid)),
+            SignerResponse::Error { .. }
+        ));
+    }
+}
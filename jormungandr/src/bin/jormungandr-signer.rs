@@ -0,0 +1,167 @@
+//! Reference remote signer for the node.
+//!
+//! It holds the leader keys of the given secret files and signs the block
+//! headers on behalf of the node, which connects to it through the Unix
+//! socket set in `leadership.remote_signer`. The protocol is described in
+//! `jormungandr_lib::interfaces::SignerRequest`.
+
+#[cfg(unix)]
+fn main() {
+    let arguments = signer::Arguments::from_args();
+    if let Err(error) = signer::run(arguments) {
+        eprintln!("{}", error);
+        std::process::exit(1);
+    }
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("the remote signer is only supported on Unix systems");
+    std::process::exit(1);
+}
+
+#[cfg(unix)]
+mod signer {
+    use jormungandr_lib::{
+        crypto::passphrase::PassphraseSource,
+        interfaces::{serve_signer_request, EncryptedSecretFile, NodeSecret, SignerResponse},
+    };
+    use std::{
+        fs::{self, File},
+        io::{self, BufRead, BufReader, Write},
+        os::unix::{
+            fs::{FileTypeExt, PermissionsExt},
+            net::{UnixListener, UnixStream},
+        },
+        path::{Path, PathBuf},
+        sync::Arc,
+        thread,
+    };
+    pub use structopt::StructOpt;
+
+    #[derive(StructOpt)]
+    #[structopt(name = "jormungandr-signer", rename_all = "kebab-case")]
+    pub struct Arguments {
+        /// the Unix socket to listen on for the node requests, only the user
+        /// running the signer can connect to it
+        #[structopt(long, parse(from_os_str))]
+        pub socket: PathBuf,
+
+        /// the secret file (in YAML format) of a leader. Can be given multiple
+        /// times.
+        #[structopt(long = "secret", parse(from_os_str))]
+        pub secrets: Vec<PathBuf>,
+
+        /// read the passphrase of the encrypted secret files from the given
+        /// environment variable instead of asking it on the terminal
+        #[structopt(long, conflicts_with = "secret-passphrase-fd")]
+        pub secret_passphrase_env: Option<String>,
+
+        /// read the passphrase of the encrypted secret files from the first
+        /// line of the given file descriptor
+        #[structopt(long)]
+        pub secret_passphrase_fd: Option<i32>,
+    }
+
+    pub fn run(arguments: Arguments) -> Result<(), String> {
+        let source = PassphraseSource::from_options(
+            arguments.secret_passphrase_env.clone(),
+            arguments.secret_passphrase_fd,
+        );
+        let mut passphrase = None;
+        let secrets = arguments
+            .secrets
+            .iter()
+            .map(|path| {
+                load_secret(path, &source, &mut passphrase)
+                    .map_err(|e| format!("cannot load secret file '{}': {}", path.display(), e))
+            })
+            .collect::<Result<Vec<NodeSecret>, String>>()?;
+        let secrets = Arc::new(secrets);
+
+        remove_stale_socket(&arguments.socket)?;
+        let listener = UnixListener::bind(&arguments.socket)
+            .map_err(|e| format!("cannot listen on '{}': {}", arguments.socket.display(), e))?;
+        fs::set_permissions(&arguments.socket, fs::Permissions::from_mode(0o600))
+            .map_err(|e| format!("cannot restrict '{}': {}", arguments.socket.display(), e))?;
+
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let secrets = Arc::clone(&secrets);
+                    thread::spawn(move || {
+                        if let Err(error) = serve(stream, &secrets) {
+                            eprintln!("connection closed: {}", error);
+                        }
+                    });
+                }
+                Err(error) => eprintln!("cannot accept connection: {}", error),
+            }
+        }
+        Ok(())
+    }
+
+    /// remove the socket left behind by a signer which did not stop cleanly,
+    /// a socket still accepting connections is left to its signer
+    fn remove_stale_socket(path: &Path) -> Result<(), String> {
+        let metadata = match fs::symlink_metadata(path) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => return Err(format!("cannot access '{}': {}", path.display(), error)),
+        };
+        if !metadata.file_type().is_socket() {
+            return Err(format!("'{}' exists and is not a socket", path.display()));
+        }
+        if UnixStream::connect(path).is_ok() {
+            return Err(format!(
+                "another signer is already listening on '{}'",
+                path.display()
+            ));
+        }
+        fs::remove_file(path)
+            .map_err(|e| format!("cannot remove the stale socket '{}': {}", path.display(), e))
+    }
+
+    fn load_secret(
+        path: &Path,
+        source: &PassphraseSource,
+        passphrase: &mut Option<String>,
+    ) -> Result<NodeSecret, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let content: serde_yaml::Value =
+            serde_yaml::from_reader(file).map_err(|e| e.to_string())?;
+        if content.get(EncryptedSecretFile::KEY).is_none() {
+            return serde_yaml::from_value(content).map_err(|e| e.to_string());
+        }
+
+        let file: EncryptedSecretFile =
+            serde_yaml::from_value(content).map_err(|e| e.to_string())?;
+        if passphrase.is_none() {
+            let read = source
+                .read("Passphrase of the secrets: ")
+                .map_err(|e| e.to_string())?;
+            *passphrase = Some(read);
+        }
+        let plaintext = file
+            .encrypted_secret
+            .decrypt(passphrase.as_deref().unwrap())
+            .map_err(|e| e.to_string())?;
+        serde_yaml::from_slice(&plaintext).map_err(|e| e.to_string())
+    }
+
+    fn serve(stream: UnixStream, secrets: &[NodeSecret]) -> io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        for line in BufReader::new(stream).lines() {
+            let response = match serde_json::from_str(&line?) {
+                Ok(request) => serve_signer_request(secrets, request),
+                Err(error) => SignerResponse::Error {
+                    reason: format!("invalid request: {}", error),
+                },
+            };
+            let mut message = serde_json::to_vec(&response)?;
+            message.push(b'\n');
+            writer.write_all(&message)?;
+        }
+        Ok(())
+    }
+}
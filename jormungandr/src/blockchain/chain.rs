@@ -49,7 +49,11 @@ See Internal documentation for more details: doc/internal_design.md
 [`Branch`]: ./struct.Branch.html
 */
 #![allow(clippy::large_enum_variant)]
-use super::{branch::Branches, reference_cache::RefCache};
+use super::{
+    branch::Branches,
+    reference_cache::RefCache,
+    snapshot::{LedgerSnapshot, LedgerSnapshots},
+};
 use crate::{
     blockcfg::{
         Block, Block0Error, BlockDate, ChainLength, Epoch, EpochRewardsInfo, Header, HeaderHash,
@@ -59,7 +63,11 @@ use crate::{
 };
 use chain_impl_mockchain::{leadership::Verification, ledger};
 use chain_time::TimeFrame;
-use std::sync::Arc;
+use lru::LruCache;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
 use tokio_stream::StreamExt;
 
 // derive
@@ -113,6 +121,11 @@ error_chain! {
         CannotApplyBlock {
             description("Block cannot be applied on top of the previous block's ledger state"),
         }
+
+        LedgerSnapshotBlockMissing (hash: HeaderHash) {
+            description("a block of the ledger snapshot is missing from the storage"),
+            display("Block '{}' of the ledger snapshot is missing from the storage", hash),
+        }
     }
 }
 
@@ -161,6 +174,9 @@ pub fn pre_verify_link(
     Ok(())
 }
 
+/// number of rejected forks remembered to avoid counting them twice
+const MAX_TRACKED_REJECTED_FORKS: usize = 256;
+
 /// blockchain object, can be safely shared across multiple threads. However it is better not
 /// to as some operations may require a mutex.
 ///
@@ -170,6 +186,9 @@ pub fn pre_verify_link(
 /// * `RefCache`: a cache of blocks headers and associated states;
 /// * `Multiverse`: of ledger. It is a cache of different ledger states.
 ///
+/// Periodic snapshots of the ledger states are persisted with the
+/// `LedgerSnapshots` to speed up the node restarts.
+///
 #[derive(Clone)]
 pub struct Blockchain {
     branches: Branches,
@@ -180,9 +199,19 @@ pub struct Blockchain {
 
     storage: Storage,
 
+    snapshots: LedgerSnapshots,
+
     block0: HeaderHash,
 
     rewards_report_all: bool,
+
+    /// number of longer candidate branches rejected because switching to
+    /// them would roll back beyond the epoch stability depth
+    rejected_forks: Arc<AtomicU64>,
+
+    /// first blocks of the rejected forks, so a fork is only counted once
+    /// however many times it is compared against the tip
+    rejected_fork_roots: Arc<Mutex<LruCache<HeaderHash, ()>>>,
 }
 
 pub enum PreCheckedHeader {
@@ -224,6 +253,7 @@ pub enum PreCheckedHeader {
 pub struct PostCheckedHeader {
     header: Header,
     epoch_leadership_schedule: Arc<Leadership>,
+    epoch_leadership_state: Arc<Ledger>,
     epoch_ledger_parameters: Arc<LedgerParameters>,
     parent_ledger_state: Arc<Ledger>,
     time_frame: Arc<TimeFrame>,
@@ -268,6 +298,7 @@ impl Blockchain {
     pub fn new(
         block0: HeaderHash,
         storage: Storage,
+        snapshots: LedgerSnapshots,
         cache_capacity: usize,
         rewards_report_all: bool,
     ) -> Self {
@@ -276,8 +307,11 @@ impl Blockchain {
             ref_cache: RefCache::new(cache_capacity),
             ledgers: Multiverse::new(),
             storage,
+            snapshots,
             block0,
             rewards_report_all,
+            rejected_forks: Arc::new(AtomicU64::new(0)),
+            rejected_fork_roots: Arc::new(Mutex::new(LruCache::new(MAX_TRACKED_REJECTED_FORKS))),
         }
     }
 
@@ -297,10 +331,35 @@ impl Blockchain {
         &mut self.branches
     }
 
+    /// record the fork starting at `fork_root` as rejected, returning `false`
+    /// if it was already rejected before
+    pub fn add_rejected_fork(&self, fork_root: HeaderHash) -> bool {
+        let newly_rejected = self
+            .rejected_fork_roots
+            .lock()
+            .unwrap()
+            .put(fork_root, ())
+            .is_none();
+        if newly_rejected {
+            self.rejected_forks.fetch_add(1, Ordering::Relaxed);
+        }
+        newly_rejected
+    }
+
+    /// number of forks rejected for rolling back beyond the epoch stability
+    /// depth since the node started
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    pub fn rejected_forks(&self) -> u64 {
+        self.rejected_forks.load(Ordering::Relaxed)
+    }
+
     pub async fn gc(&self, tip: Arc<Ref>) -> Result<()> {
         let depth = tip.epoch_ledger_parameters().epoch_stability_depth;
         self.ledgers.gc(depth).await;
         self.storage.gc(depth, tip.hash().as_ref())?;
+        if let Err(err) = self.snapshots.update(&self.storage, &tip, depth).await {
+            tracing::warn!(reason = %err, "cannot write the ledger snapshot");
+        }
         Ok(())
     }
 
@@ -313,6 +372,40 @@ impl Blockchain {
         ledger: Ledger,
         time_frame: Arc<TimeFrame>,
         leadership: Arc<Leadership>,
+        leadership_state: Arc<Ledger>,
+        epoch_rewards_info: Option<Arc<EpochRewardsInfo>>,
+        ledger_parameters: Arc<LedgerParameters>,
+        previous_epoch_state: Option<Arc<Ref>>,
+    ) -> Arc<Ref> {
+        let reference = self
+            .create_reference(
+                header_hash,
+                header,
+                ledger,
+                time_frame,
+                leadership,
+                leadership_state,
+                epoch_rewards_info,
+                ledger_parameters,
+                previous_epoch_state,
+            )
+            .await;
+        self.ref_cache
+            .insert(header_hash, Arc::clone(&reference))
+            .await;
+        reference
+    }
+
+    /// create a reference without adding it to the cache
+    #[allow(clippy::too_many_arguments)]
+    async fn create_reference(
+        &self,
+        header_hash: HeaderHash,
+        header: Header,
+        ledger: Ledger,
+        time_frame: Arc<TimeFrame>,
+        leadership: Arc<Leadership>,
+        leadership_state: Arc<Ledger>,
         epoch_rewards_info: Option<Arc<EpochRewardsInfo>>,
         ledger_parameters: Arc<LedgerParameters>,
         previous_epoch_state: Option<Arc<Ref>>,
@@ -320,21 +413,19 @@ impl Blockchain {
         let chain_length = header.chain_length();
 
         let multiverse = self.ledgers.clone();
-        let ref_cache = self.ref_cache.clone();
 
         let ledger_ref = multiverse.insert(chain_length, header_hash, ledger).await;
         let reference = Ref::new(
             ledger_ref,
             time_frame,
             leadership,
+            leadership_state,
             ledger_parameters,
             epoch_rewards_info,
             header,
             previous_epoch_state,
         );
-        let reference = Arc::new(reference);
-        ref_cache.insert(header_hash, Arc::clone(&reference)).await;
-        reference
+        Arc::new(reference)
     }
 
     /// get `Ref` of the given header hash
@@ -368,6 +459,85 @@ impl Blockchain {
         }
     }
 
+    /// get the `Ref` of the given header hash, recreating it if the block is
+    /// in the storage but no longer in the cache.
+    ///
+    /// The blocks are replayed from the closest ancestor among `ancestor`, the
+    /// cached blocks and block0. The recreated `Ref`s are not added to the
+    /// cache, so the ones of the recent blocks are not evicted; passing the
+    /// last recreated `Ref` as `ancestor` avoids replaying the same blocks
+    /// again when going through the chain.
+    ///
+    /// Returns `None` if the block is not in the storage.
+    pub async fn replay_ref(
+        &self,
+        header_hash: HeaderHash,
+        ancestor: Option<Arc<Ref>>,
+    ) -> Result<Option<Arc<Ref>>> {
+        if let Some(reference) = self.get_ref(header_hash).await? {
+            return Ok(Some(reference));
+        }
+
+        let is_ancestor = |hash: HeaderHash| {
+            ancestor
+                .as_ref()
+                .filter(|ancestor| ancestor.hash() == hash)
+                .map(Arc::clone)
+        };
+        if let Some(reference) = is_ancestor(header_hash) {
+            return Ok(Some(reference));
+        }
+
+        let mut hash = header_hash;
+        let mut reference = loop {
+            let block = match self
+                .storage
+                .get(hash)
+                .map_err(|e| Error::with_chain(e, "Cannot get a block to replay"))?
+            {
+                Some(block) => block,
+                None => return Ok(None),
+            };
+            if hash == self.block0 {
+                break self.create_block0_reference(&block).await?;
+            }
+            hash = block.header.block_parent_hash();
+            if let Some(reference) = is_ancestor(hash) {
+                break reference;
+            }
+            if let Some(reference) = self.get_ref(hash).await? {
+                break reference;
+            }
+        };
+
+        if reference.hash() == header_hash {
+            return Ok(Some(reference));
+        }
+
+        let mut block_stream = self
+            .storage
+            .stream_from_to(reference.hash(), header_hash)
+            .map(Box::pin)
+            .map_err(|e| Error::with_chain(e, "Cannot iterate the blocks to replay"))?;
+
+        while let Some(r) = block_stream.next().await {
+            let block =
+                r.map_err(|e| Error::with_chain(e, "Error while iterating the blocks to replay"))?;
+            let post_checked_header = self
+                .post_check_header(
+                    block.header.clone(),
+                    reference,
+                    CheckHeaderProof::SkipFromStorage,
+                )
+                .await?;
+            let new_ledger = self.apply_block_dry_run(&post_checked_header, &block)?;
+            reference = self
+                .create_block_reference(post_checked_header, new_ledger)
+                .await;
+        }
+        Ok(Some(reference))
+    }
+
     /// load the header's parent `Ref`.
     async fn load_header_parent(&self, header: Header, force: bool) -> Result<PreCheckedHeader> {
         let block_id = header.hash();
@@ -435,6 +605,7 @@ impl Blockchain {
         let EpochLeadership {
             state: parent_ledger_state,
             leadership: epoch_leadership_schedule,
+            leadership_state: epoch_leadership_state,
             ledger_parameters: epoch_ledger_parameters,
             rewards_info: epoch_rewards_info,
             time_frame,
@@ -453,6 +624,7 @@ impl Blockchain {
         Ok(PostCheckedHeader {
             header,
             epoch_leadership_schedule,
+            epoch_leadership_state,
             epoch_ledger_parameters,
             epoch_rewards_info,
             parent_ledger_state,
@@ -506,21 +678,37 @@ impl Blockchain {
         &self,
         post_checked_header: PostCheckedHeader,
         new_ledger: Ledger,
+    ) -> Arc<Ref> {
+        let reference = self
+            .create_block_reference(post_checked_header, new_ledger)
+            .await;
+        self.ref_cache
+            .insert(reference.hash(), Arc::clone(&reference))
+            .await;
+        reference
+    }
+
+    async fn create_block_reference(
+        &self,
+        post_checked_header: PostCheckedHeader,
+        new_ledger: Ledger,
     ) -> Arc<Ref> {
         let header = post_checked_header.header;
         let block_id = header.hash();
         let epoch_leadership_schedule = post_checked_header.epoch_leadership_schedule;
+        let epoch_leadership_state = post_checked_header.epoch_leadership_state;
         let epoch_rewards_info = post_checked_header.epoch_rewards_info;
         let epoch_ledger_parameters = post_checked_header.epoch_ledger_parameters;
         let time_frame = post_checked_header.time_frame;
         let previous_epoch_state = post_checked_header.previous_epoch_state;
 
-        self.create_and_store_reference(
+        self.create_reference(
             block_id,
             header,
             new_ledger,
             time_frame,
             epoch_leadership_schedule,
+            epoch_leadership_state,
             epoch_rewards_info,
             epoch_ledger_parameters,
             previous_epoch_state,
@@ -570,11 +758,20 @@ impl Blockchain {
     /// * the block0 does build an invalid `Ledger`: `ErrorKind::Block0InitialLedgerError`;
     ///
     async fn apply_block0(&self, block0: &Block) -> Result<Branch> {
+        let mut branches = self.branches.clone();
+
+        let b = self.create_block0_reference(block0).await?;
+        self.ref_cache.insert(b.hash(), Arc::clone(&b)).await;
+        let b = Branch::new(b);
+        branches.add(b.clone()).await;
+        Ok(b)
+    }
+
+    /// create the reference of block0 without adding it to the cache
+    async fn create_block0_reference(&self, block0: &Block) -> Result<Arc<Ref>> {
         let block0_id = block0.header.hash();
         let block0_date = block0.header.block_date();
 
-        let mut branches = self.branches.clone();
-
         let time_frame = {
             use crate::blockcfg::Block0DataSource as _;
 
@@ -599,21 +796,19 @@ impl Blockchain {
         let block0_leadership = Leadership::new(block0_date.epoch, &block0_ledger);
         let ledger_parameters = block0_leadership.ledger_parameters().clone();
 
-        let b = self
-            .create_and_store_reference(
+        Ok(self
+            .create_reference(
                 block0_id,
                 block0.header.clone(),
-                block0_ledger,
+                block0_ledger.clone(),
                 Arc::new(time_frame),
                 Arc::new(block0_leadership),
+                Arc::new(block0_ledger),
                 None, // block0 has no reward distribution
                 Arc::new(ledger_parameters),
                 None,
             )
-            .await;
-        let b = Branch::new(b);
-        branches.add(b.clone()).await;
-        Ok(b)
+            .await)
     }
 
     /// function to do the initial application of the block0 in the `Blockchain` and its
@@ -692,15 +887,35 @@ impl Blockchain {
             return Err(ErrorKind::NoTag(MAIN_BRANCH_TAG.to_owned()).into());
         };
 
-        let block0_branch = self.apply_block0(&block0).await?;
+        let mut branch = self.apply_block0(&block0).await?;
+
+        let mut replay_from = block0_id;
+        if let Some(snapshot) = self.snapshots.load(&self.storage, head_hash).await {
+            let block0_ref = branch.get_ref().await;
+            match self.restore_snapshot(block0_ref, snapshot).await {
+                Ok(snapshot_ref) => {
+                    tracing::info!(
+                        "resuming from the ledger snapshot at {}",
+                        snapshot_ref.header().description()
+                    );
+                    replay_from = snapshot_ref.hash();
+                    let _: Arc<Ref> = branch.update_ref(snapshot_ref).await;
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        reason = %err,
+                        "cannot restore the ledger snapshot, replaying the blockchain from block0"
+                    );
+                }
+            }
+        }
 
         let mut block_stream = self
             .storage
-            .stream_from_to(block0_id, head_hash)
+            .stream_from_to(replay_from, head_hash)
             .map(Box::pin)
             .map_err(|e| Error::with_chain(e, "Cannot iterate blocks from block0 to HEAD"))?;
 
-        let mut branch = block0_branch;
         let mut count = 0u64;
 
         let mut block_processing = std::time::Duration::from_secs(0);
@@ -774,6 +989,69 @@ impl Blockchain {
         Ok(branch)
     }
 
+    /// recreate the `Ref` of the block a ledger snapshot was taken at.
+    ///
+    /// The rewards info of the epoch are not part of the snapshot, the
+    /// restored `Ref` will not have any until the next epoch transition.
+    async fn restore_snapshot(
+        &self,
+        block0_ref: Arc<Ref>,
+        snapshot: LedgerSnapshot,
+    ) -> Result<Arc<Ref>> {
+        let time_frame = Arc::clone(block0_ref.time_frame());
+
+        let previous_epoch_state = match snapshot.previous_epoch {
+            Some((hash, ledger)) => {
+                let header = self.get_snapshot_header(hash)?;
+                // only the ledger state of the previous epoch's last block is
+                // used from now on, its leadership is approximated from it.
+                let leadership = Leadership::new(header.block_date().epoch, &ledger);
+                let ledger_parameters = Arc::new(leadership.ledger_parameters().clone());
+                let ledger_ref = self
+                    .ledgers
+                    .insert(header.chain_length(), hash, ledger.clone())
+                    .await;
+                Some(Arc::new(Ref::new(
+                    ledger_ref,
+                    Arc::clone(&time_frame),
+                    Arc::new(leadership),
+                    Arc::new(ledger),
+                    ledger_parameters,
+                    None,
+                    header,
+                    None,
+                )))
+            }
+            None => None,
+        };
+
+        let header = self.get_snapshot_header(snapshot.hash)?;
+        let leadership = Leadership::new(header.block_date().epoch, &snapshot.leadership_state);
+        let ledger_parameters = Arc::new(leadership.ledger_parameters().clone());
+
+        Ok(self
+            .create_and_store_reference(
+                snapshot.hash,
+                header,
+                snapshot.ledger,
+                time_frame,
+                Arc::new(leadership),
+                Arc::new(snapshot.leadership_state),
+                None,
+                ledger_parameters,
+                previous_epoch_state,
+            )
+            .await)
+    }
+
+    fn get_snapshot_header(&self, hash: HeaderHash) -> Result<Header> {
+        self.storage
+            .get(hash)
+            .map_err(|e| Error::with_chain(e, "Cannot get a ledger snapshot block"))?
+            .map(|block| block.header)
+            .ok_or_else(|| ErrorKind::LedgerSnapshotBlockMissing(hash).into())
+    }
+
     pub async fn get_checkpoints(&self, branch: &Branch) -> Checkpoints {
         Checkpoints::new_from(branch.get_ref().await)
     }
@@ -829,6 +1107,7 @@ fn write_reward_info(
 pub struct EpochLeadership {
     pub state: Arc<Ledger>,
     pub leadership: Arc<Leadership>,
+    pub leadership_state: Arc<Ledger>,
     pub ledger_parameters: Arc<LedgerParameters>,
     pub rewards_info: Option<Arc<EpochRewardsInfo>>,
     pub time_frame: Arc<TimeFrame>,
@@ -902,6 +1181,7 @@ pub fn new_epoch_leadership_from(
         EpochLeadership {
             state: transition_state,
             leadership,
+            leadership_state: epoch_state,
             ledger_parameters,
             rewards_info: epoch_rewards_info,
             time_frame: parent_time_frame,
@@ -911,6 +1191,7 @@ pub fn new_epoch_leadership_from(
         EpochLeadership {
             state: parent_ledger_state,
             leadership: parent_epoch_leadership_schedule,
+            leadership_state: parent.epoch_leadership_state().clone(),
             ledger_parameters: parent_epoch_ledger_parameters,
             rewards_info: parent_epoch_rewards_info,
             time_frame: parent_time_frame,
@@ -1,9 +1,15 @@
-use crate::blockchain::{Ref, Storage};
+use crate::{
+    blockcfg::HeaderHash,
+    blockchain::{Ref, Storage, StorageError},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Hash)]
 pub enum ComparisonResult {
     PreferCurrent,
     PreferCandidate,
+    /// the candidate is longer but switching to it would roll back more than
+    /// `epoch_stability_depth` blocks of the current branch
+    RejectCandidate,
 }
 
 /// chose which of the two Ref is the most interesting to keep as a branch
@@ -14,17 +20,18 @@ pub enum ComparisonResult {
 /// work to make a choice as to which of these two Ref is the right choice.
 ///
 pub fn compare_against(storage: &Storage, current: &Ref, candidate: &Ref) -> ComparisonResult {
-    let epoch_stability_depth = current.epoch_ledger_parameters().epoch_stability_depth;
-
-    let rollback_possible =
-        check_rollback_up_to(epoch_stability_depth, storage, current, candidate);
+    // the cheap checks go first, walking the storage is only needed for the
+    // candidates we would switch to
+    if current.chain_length() >= candidate.chain_length() || is_in_future(candidate) {
+        return ComparisonResult::PreferCurrent;
+    }
 
-    let not_in_future = !is_in_future(candidate);
+    let epoch_stability_depth = current.epoch_ledger_parameters().epoch_stability_depth;
 
-    if rollback_possible && not_in_future && current.chain_length() < candidate.chain_length() {
+    if check_rollback_up_to(epoch_stability_depth, storage, current, candidate) {
         ComparisonResult::PreferCandidate
     } else {
-        ComparisonResult::PreferCurrent
+        ComparisonResult::RejectCandidate
     }
 }
 
@@ -34,11 +41,204 @@ fn is_in_future(node: &Ref) -> bool {
     node.elapsed().is_err()
 }
 
+/// returns `true` if switching from the `current` tip to the `candidate`
+/// does not roll back more than `epoch_stability_depth` blocks of the
+/// current branch.
 fn check_rollback_up_to(
-    _epoch_stability_depth: u32,
-    _: &Storage,
-    _ref1: &Ref,
-    _ref2: &Ref,
+    epoch_stability_depth: u32,
+    storage: &Storage,
+    current: &Ref,
+    candidate: &Ref,
 ) -> bool {
-    true
+    check_rollback_between(
+        epoch_stability_depth,
+        storage,
+        current.hash(),
+        current.chain_length().into(),
+        candidate.hash(),
+    )
+}
+
+fn check_rollback_between(
+    epoch_stability_depth: u32,
+    storage: &Storage,
+    current: HeaderHash,
+    current_chain_length: u32,
+    candidate: HeaderHash,
+) -> bool {
+    if current_chain_length <= epoch_stability_depth {
+        // the whole current branch is within the stability depth
+        return true;
+    }
+
+    // the candidate is acceptable if the common ancestor with the current
+    // branch is not below the block at the stability depth, i.e. if that
+    // block is also an ancestor of the candidate.
+    let stable = match storage.get_nth_ancestor(current, epoch_stability_depth) {
+        Ok(stable) => stable,
+        Err(err) => {
+            tracing::warn!(
+                reason = %err,
+                "cannot find the stable ancestor of the current tip {}",
+                current
+            );
+            return false;
+        }
+    };
+
+    match storage.find_closest_ancestor(vec![stable], candidate) {
+        Ok(Some(_)) => true,
+        Ok(None) => false,
+        Err(err) => {
+            tracing::warn!(
+                reason = %err,
+                "cannot find the common ancestor of {} and {}",
+                current,
+                candidate
+            );
+            false
+        }
+    }
+}
+
+/// returns the first block of the `candidate` branch that is not on the
+/// `current` branch, i.e. the block right after their common ancestor.
+///
+/// The `candidate` must not be an ancestor of `current`.
+pub fn fork_root(
+    storage: &Storage,
+    current: &Ref,
+    candidate: &Ref,
+) -> Result<HeaderHash, StorageError> {
+    let current_length: u32 = current.chain_length().into();
+    let candidate_length: u32 = candidate.chain_length().into();
+    let candidate_block_at =
+        |length: u32| storage.get_nth_ancestor(candidate.hash(), candidate_length - length);
+
+    // block0 is on both branches while the block of the candidate at `hi` is
+    // either above the current tip or the candidate itself
+    let mut lo = 0;
+    let mut hi = std::cmp::min(current_length + 1, candidate_length);
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        let block = candidate_block_at(mid)?;
+        if storage
+            .find_closest_ancestor(vec![block], current.hash())?
+            .is_some()
+        {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+
+    candidate_block_at(hi)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockcfg::{Block, BlockDate, BlockVersion, ContentsBuilder, HeaderBuilderNew};
+    use chain_core::property::ChainLength as _;
+    use tracing::Span;
+
+    const EPOCH_STABILITY_DEPTH: u32 = 5;
+
+    fn block0() -> Block {
+        let contents = ContentsBuilder::new().into();
+        let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+            .set_genesis()
+            .set_date(BlockDate::first())
+            .into_unsigned_header()
+            .unwrap()
+            .generalize();
+        Block { header, contents }
+    }
+
+    /// store `length` blocks on top of `parent`, the `slot_offset` allows
+    /// to create different blocks at the same chain length for the forks.
+    fn extend(storage: &Storage, parent: &Block, length: u32, slot_offset: u32) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for _ in 0..length {
+            let parent = blocks.last().unwrap_or(parent);
+            let contents = ContentsBuilder::new().into();
+            let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+                .set_parent(&parent.header.hash(), parent.header.chain_length().next())
+                .set_date(BlockDate {
+                    epoch: 0,
+                    slot_id: parent.header.block_date().slot_id + 1 + slot_offset,
+                })
+                .into_unsigned_header()
+                .unwrap()
+                .generalize();
+            let block = Block { header, contents };
+            storage.put_block(&block).unwrap();
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    fn storage_with_block0() -> (Storage, Block) {
+        let storage = Storage::memory(Span::none()).unwrap();
+        let block0 = block0();
+        storage.put_block(&block0).unwrap();
+        (storage, block0)
+    }
+
+    fn check(storage: &Storage, current: &Block, candidate: &Block) -> bool {
+        check_rollback_between(
+            EPOCH_STABILITY_DEPTH,
+            storage,
+            current.header.hash(),
+            current.header.chain_length().into(),
+            candidate.header.hash(),
+        )
+    }
+
+    #[test]
+    fn continuation_of_the_current_branch_is_accepted() {
+        let (storage, block0) = storage_with_block0();
+        let main = extend(&storage, &block0, 10, 0);
+
+        assert!(check(&storage, &main[7], &main[9]));
+    }
+
+    #[test]
+    fn shallow_fork_is_accepted() {
+        let (storage, block0) = storage_with_block0();
+        let main = extend(&storage, &block0, 10, 0);
+        // fork from the 8th block, rolling back 2 blocks
+        let fork = extend(&storage, &main[7], 3, 100);
+
+        assert!(check(&storage, &main[9], fork.last().unwrap()));
+    }
+
+    #[test]
+    fn fork_at_the_stability_depth_is_accepted() {
+        let (storage, block0) = storage_with_block0();
+        let main = extend(&storage, &block0, 10, 0);
+        // fork from the 5th block, rolling back exactly 5 blocks
+        let fork = extend(&storage, &main[4], 6, 100);
+
+        assert!(check(&storage, &main[9], fork.last().unwrap()));
+    }
+
+    #[test]
+    fn deep_fork_is_rejected() {
+        let (storage, block0) = storage_with_block0();
+        let main = extend(&storage, &block0, 10, 0);
+        // fork from the 2nd block, rolling back 8 blocks
+        let fork = extend(&storage, &main[1], 10, 100);
+
+        assert!(!check(&storage, &main[9], fork.last().unwrap()));
+    }
+
+    #[test]
+    fn fork_of_a_branch_shorter_than_the_stability_depth_is_accepted() {
+        let (storage, block0) = storage_with_block0();
+        let main = extend(&storage, &block0, 3, 0);
+        let fork = extend(&storage, &block0, 4, 100);
+
+        assert!(check(&storage, &main[2], fork.last().unwrap()));
+    }
 }
@@ -7,6 +7,7 @@ mod multiverse;
 mod process;
 mod reference;
 mod reference_cache;
+mod snapshot;
 mod storage;
 mod tip;
 
@@ -35,6 +36,7 @@ pub use self::{
     multiverse::Multiverse,
     process::{process_new_ref, Process},
     reference::Ref,
+    snapshot::LedgerSnapshots,
     storage::{Error as StorageError, Storage},
     tip::Tip,
 };
@@ -9,6 +9,7 @@ use crate::{
     blockchain::Checkpoints,
     intercom::{self, BlockMsg, ExplorerMsg, NetworkMsg, PropagateMsg, TransactionMsg},
     network::p2p::Address,
+    notifier::{Event, Notifier},
     stats_counter::StatsCounter,
     utils::{
         async_msg::{self, MessageBox, MessageQueue},
@@ -25,7 +26,10 @@ use futures::prelude::*;
 use tracing::{span, Level};
 use tracing_futures::Instrument;
 
-use std::{sync::Arc, time::Duration};
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 type PullHeadersScheduler = FireForgetScheduler<HeaderHash, Address, Checkpoints>;
 type GetNextBlockScheduler = FireForgetScheduler<HeaderHash, Address, ()>;
@@ -58,6 +62,7 @@ pub struct Process {
     pub network_msgbox: MessageBox<NetworkMsg>,
     pub fragment_msgbox: MessageBox<TransactionMsg>,
     pub explorer_msgbox: Option<MessageBox<ExplorerMsg>>,
+    pub notifier: Option<Notifier>,
     pub garbage_collection_interval: Duration,
 }
 
@@ -92,6 +97,7 @@ impl Process {
         let blockchain_tip = self.blockchain_tip.clone();
         let network_msg_box = self.network_msgbox.clone();
         let explorer_msg_box = self.explorer_msgbox.clone();
+        let notifier = self.notifier.clone();
         let tx_msg_box = self.fragment_msgbox.clone();
         let stats_counter = self.stats_counter.clone();
 
@@ -117,6 +123,7 @@ impl Process {
                         tx_msg_box,
                         network_msg_box,
                         explorer_msg_box,
+                        notifier,
                         block,
                         stats_counter,
                     )
@@ -163,6 +170,7 @@ impl Process {
                         tx_msg_box,
                         network_msg_box,
                         explorer_msg_box,
+                        notifier,
                         get_next_block_scheduler,
                         handle,
                         stats_counter,
@@ -193,11 +201,19 @@ impl Process {
         let tip = self.blockchain_tip.clone();
         let blockchain = self.blockchain.clone();
         let explorer = self.explorer_msgbox.clone();
+        let notifier = self.notifier.clone();
 
         info.run_periodic_fallible(
             "branch reprocessing",
             BRANCH_REPROCESSING_INTERVAL,
-            move || reprocess_tip(blockchain.clone(), tip.clone(), explorer.clone()),
+            move || {
+                reprocess_tip(
+                    blockchain.clone(),
+                    tip.clone(),
+                    explorer.clone(),
+                    notifier.clone(),
+                )
+            },
         )
     }
 
@@ -282,6 +298,7 @@ async fn reprocess_tip(
     mut blockchain: Blockchain,
     tip: Tip,
     explorer_msg_box: Option<MessageBox<ExplorerMsg>>,
+    notifier: Option<Notifier>,
 ) -> Result<(), Error> {
     let branches: Vec<Arc<Ref>> = blockchain.branches().branches().await;
 
@@ -298,6 +315,7 @@ async fn reprocess_tip(
             tip.clone(),
             Arc::clone(other),
             explorer_msg_box.clone(),
+            notifier.clone(),
         )
         .await?
     }
@@ -314,17 +332,36 @@ async fn reprocess_tip(
 /// If the current tip is not the one being updated we will then trigger
 /// chain selection after updating that other branch as it may be possible that
 /// this branch just became more interesting for the current consensus algorithm.
+///
+/// The changes of the tip are reported to the `notifier`, if any.
 pub async fn process_new_ref(
     blockchain: &mut Blockchain,
     mut tip: Tip,
     candidate: Arc<Ref>,
     explorer_msg_box: Option<MessageBox<ExplorerMsg>>,
+    notifier: Option<Notifier>,
 ) -> Result<(), Error> {
     let candidate_hash = candidate.hash();
     let tip_ref = tip.get_ref().await;
 
-    match chain_selection::compare_against(blockchain.storage(), &tip_ref, &candidate) {
-        ComparisonResult::PreferCurrent => {
+    let comparison = chain_selection::compare_against(blockchain.storage(), &tip_ref, &candidate);
+    if comparison == ComparisonResult::RejectCandidate {
+        // the same fork is compared again on every reprocessing of the tip
+        // and on every new block extending it, only count it once
+        let fork_root = chain_selection::fork_root(blockchain.storage(), &tip_ref, &candidate)
+            .unwrap_or(candidate_hash);
+        if blockchain.add_rejected_fork(fork_root) {
+            tracing::warn!(
+                current = %tip_ref.hash(),
+                candidate = %candidate_hash,
+                fork_root = %fork_root,
+                "rejecting fork rolling back beyond the epoch stability depth"
+            );
+        }
+    }
+
+    match comparison {
+        ComparisonResult::PreferCurrent | ComparisonResult::RejectCandidate => {
             tracing::info!(
                 "create new branch with tip {} | current-tip {}",
                 candidate.header().description(),
@@ -332,6 +369,8 @@ pub async fn process_new_ref(
             );
         }
         ComparisonResult::PreferCandidate => {
+            let tip_event = notifier.as_ref().map(|_| Event::tip(candidate.header()));
+
             if tip_ref.hash() == candidate.block_parent_hash() {
                 tracing::info!(
                     "update current branch tip: {} -> {}",
@@ -357,6 +396,23 @@ pub async fn process_new_ref(
                     .put_tag(MAIN_BRANCH_TAG, candidate_hash)
                     .map_err(|e| Error::with_chain(e, "Cannot update the main storage's tip"))?;
 
+                if let Some(notifier) = &notifier {
+                    // switching to a branch built on top of the current tip
+                    // does not roll back any block
+                    let rolled_back = blockchain
+                        .storage()
+                        .find_closest_ancestor(vec![tip_ref.hash()], candidate_hash)
+                        .map_or(true, |ancestor| ancestor.is_none());
+                    if rolled_back {
+                        notifier.notify(Event::rollback(
+                            tip_ref.hash(),
+                            tip_ref.chain_length(),
+                            candidate_hash,
+                            candidate.chain_length(),
+                        ));
+                    }
+                }
+
                 let branch = blockchain.branches_mut().apply_or_create(candidate).await;
                 tip.swap(branch).await;
             }
@@ -369,6 +425,10 @@ pub async fn process_new_ref(
                         tracing::error!("cannot send new tip to explorer: {}", err)
                     });
             }
+
+            if let (Some(notifier), Some(tip_event)) = (notifier, tip_event) {
+                notifier.notify(tip_event);
+            }
         }
     }
 
@@ -381,11 +441,12 @@ async fn process_and_propagate_new_ref(
     new_block_ref: Arc<Ref>,
     mut network_msg_box: MessageBox<NetworkMsg>,
     explorer_msg_box: Option<MessageBox<ExplorerMsg>>,
+    notifier: Option<Notifier>,
 ) -> Result<(), Error> {
     let header = new_block_ref.header().clone();
     tracing::debug!("processing the new block and propagating");
 
-    process_new_ref(blockchain, tip, new_block_ref, explorer_msg_box).await?;
+    process_new_ref(blockchain, tip, new_block_ref, explorer_msg_box, notifier).await?;
 
     tracing::debug!("propagating block to the network");
     network_msg_box
@@ -402,10 +463,13 @@ async fn process_leadership_block(
     mut tx_msg_box: MessageBox<TransactionMsg>,
     network_msg_box: MessageBox<NetworkMsg>,
     explorer_msg_box: Option<MessageBox<ExplorerMsg>>,
+    notifier: Option<Notifier>,
     block: Block,
     stats_counter: StatsCounter,
 ) -> Result<(), Error> {
+    let processing_start = Instant::now();
     let new_block_ref = process_leadership_block_inner(&mut blockchain, block.clone()).await?;
+    stats_counter.add_block_processing_time(processing_start.elapsed());
 
     let fragments = block.fragments().map(|f| f.id()).collect();
 
@@ -419,6 +483,7 @@ async fn process_leadership_block(
         Arc::clone(&new_block_ref),
         network_msg_box,
         explorer_msg_box.clone(),
+        notifier,
     )
     .await?;
 
@@ -522,6 +587,7 @@ async fn process_network_blocks(
     mut tx_msg_box: MessageBox<TransactionMsg>,
     network_msg_box: MessageBox<NetworkMsg>,
     mut explorer_msg_box: Option<MessageBox<ExplorerMsg>>,
+    notifier: Option<Notifier>,
     mut get_next_block_scheduler: GetNextBlockScheduler,
     handle: intercom::RequestStreamHandle<Block, ()>,
     stats_counter: StatsCounter,
@@ -535,6 +601,7 @@ async fn process_network_blocks(
         match maybe_block {
             Some(block) => {
                 latest_block = Some(Arc::new(block.clone()));
+                let processing_start = Instant::now();
                 let res = process_network_block(
                     &blockchain,
                     block.clone(),
@@ -546,6 +613,7 @@ async fn process_network_blocks(
                 match res {
                     Ok(Some(r)) => {
                         stats_counter.add_block_recv_cnt(1);
+                        stats_counter.add_block_processing_time(processing_start.elapsed());
                         stream = stream_tail;
                         candidate = Some(r);
                     }
@@ -578,6 +646,7 @@ async fn process_network_blocks(
                 Arc::clone(&new_block_ref),
                 network_msg_box,
                 explorer_msg_box,
+                notifier,
             )
             .await?;
 
@@ -753,3 +822,143 @@ fn chain_header_error_into_reply(err: candidate::Error) -> intercom::Error {
         HeaderChainVerificationFailed(e) => intercom::Error::invalid_argument(e),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockcfg::{BlockDate, BlockVersion, ContentsBuilder, HeaderBuilderNew},
+        blockchain::{LedgerSnapshots, Storage},
+    };
+    use chain_core::property::ChainLength as _;
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{chaintypes::ConsensusVersion, fee::LinearFee, key::BftLeaderId};
+    use jormungandr_lib::interfaces::{
+        Block0Configuration, BlockchainConfiguration, ConsensusLeaderId,
+    };
+    use tracing::Span;
+
+    const EPOCH_STABILITY_DEPTH: u32 = 2;
+
+    fn block0() -> Block {
+        let leader = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let mut blockchain_configuration = BlockchainConfiguration::new(
+            chain_addr::Discrimination::Test,
+            ConsensusVersion::Bft,
+            LinearFee::new(1, 2, 3),
+        );
+        blockchain_configuration.consensus_leader_ids =
+            vec![ConsensusLeaderId(BftLeaderId::from(leader.to_public()))];
+        blockchain_configuration.epoch_stability_depth = EPOCH_STABILITY_DEPTH.into();
+        Block0Configuration {
+            blockchain_configuration,
+            initial: Vec::new(),
+        }
+        .to_block()
+    }
+
+    /// build `length` blocks on top of `parent`, the `slot_offset` allows
+    /// to create different blocks at the same chain length for the forks.
+    fn extend(parent: &Block, length: u32, slot_offset: u32) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for _ in 0..length {
+            let parent = blocks.last().unwrap_or(parent);
+            let contents = ContentsBuilder::new().into();
+            let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+                .set_parent(&parent.header.hash(), parent.header.chain_length().next())
+                .set_date(BlockDate {
+                    epoch: 0,
+                    slot_id: parent.header.block_date().slot_id + 1 + slot_offset,
+                })
+                .into_unsigned_header()
+                .unwrap()
+                .generalize();
+            blocks.push(Block { header, contents });
+        }
+        blocks
+    }
+
+    /// apply and store the `blocks`, returning the reference of the last one
+    async fn apply(blockchain: &Blockchain, blocks: &[Block]) -> Arc<Ref> {
+        let mut head = None;
+        for block in blocks {
+            let parent_ref = match blockchain
+                .pre_check_header(block.header.clone(), false)
+                .await
+                .unwrap()
+            {
+                PreCheckedHeader::HeaderWithCache { parent_ref, .. } => parent_ref,
+                _ => panic!("the parent block should be in the cache"),
+            };
+            let post_checked = blockchain
+                .post_check_header(
+                    block.header.clone(),
+                    parent_ref,
+                    CheckHeaderProof::SkipFromStorage,
+                )
+                .await
+                .unwrap();
+            head = Some(
+                blockchain
+                    .apply_and_store_block(post_checked, block.clone())
+                    .await
+                    .unwrap()
+                    .cached_ref(),
+            );
+        }
+        head.unwrap()
+    }
+
+    #[tokio::test]
+    async fn deep_fork_is_rejected_once() {
+        let block0 = block0();
+        let mut blockchain = Blockchain::new(
+            block0.header.hash(),
+            Storage::memory(Span::none()).unwrap(),
+            LedgerSnapshots::new(None, 0),
+            1024,
+            false,
+        );
+        let branch = blockchain.load_from_block0(block0.clone()).await.unwrap();
+        let tip = Tip::new(branch);
+
+        let main = extend(&block0, 5, 0);
+        let main_head = apply(&blockchain, &main).await;
+        process_new_ref(&mut blockchain, tip.clone(), main_head, None, None)
+            .await
+            .unwrap();
+
+        // forking from block0 rolls back the whole main branch
+        let fork = extend(&block0, 6, 100);
+        let fork_head = apply(&blockchain, &fork).await;
+        process_new_ref(
+            &mut blockchain,
+            tip.clone(),
+            Arc::clone(&fork_head),
+            None,
+            None,
+        )
+        .await
+        .unwrap();
+        assert_eq!(blockchain.rejected_forks(), 1);
+
+        blockchain.branches_mut().apply_or_create(fork_head).await;
+        reprocess_tip(blockchain.clone(), tip.clone(), None, None)
+            .await
+            .unwrap();
+        reprocess_tip(blockchain.clone(), tip.clone(), None, None)
+            .await
+            .unwrap();
+        assert_eq!(blockchain.rejected_forks(), 1);
+
+        // a new block on the rejected fork is still the same fork
+        let next = extend(fork.last().unwrap(), 1, 0);
+        let next_head = apply(&blockchain, &next).await;
+        process_new_ref(&mut blockchain, tip.clone(), next_head, None, None)
+            .await
+            .unwrap();
+        assert_eq!(blockchain.rejected_forks(), 1);
+
+        assert_eq!(tip.get_ref().await.hash(), main[4].header.hash());
+    }
+}
@@ -0,0 +1,683 @@
+//! Ledger snapshots
+//!
+//! Every `interval` blocks the state of the tip is kept aside as a snapshot
+//! candidate. Once the candidate is buried under `epoch_stability_depth`
+//! blocks of the main branch it is serialized in the snapshot directory
+//! (next to the block storage) and the [`LEDGER_SNAPSHOT_TAG`] is moved to
+//! it. When the node restarts it can restore the `Ref` of the snapshot
+//! block and only replay the blocks between the snapshot and the `HEAD`.
+//!
+//! A snapshot file contains:
+//!
+//! * the header hash and chain length of the block it was taken at;
+//! * the ledger state after that block;
+//! * the ledger state the epoch leadership schedule was computed from;
+//! * the hash and ledger state of the last block of the previous epoch
+//!   (needed to compute the leadership of the next epoch);
+//! * a Blake2b256 checksum of all the above.
+//!
+//! [`LEDGER_SNAPSHOT_TAG`]: ./constant.LEDGER_SNAPSHOT_TAG.html
+
+use crate::{
+    blockcfg::{HeaderHash, Ledger},
+    blockchain::{Ref, Storage, StorageError},
+};
+use chain_core::property::{Deserialize, Serialize};
+use chain_crypto::Blake2b256;
+use thiserror::Error;
+use tokio::sync::Mutex;
+
+use std::{
+    fs,
+    io::{self, Read},
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::Arc,
+};
+
+/// storage tag pointing to the block of the most recent ledger snapshot
+pub const LEDGER_SNAPSHOT_TAG: &str = "LEDGER_SNAPSHOT";
+
+const SNAPSHOT_MAGIC: &[u8; 8] = b"JORMLSNP";
+const SNAPSHOT_VERSION: u8 = 1;
+const SNAPSHOT_EXTENSION: &str = "snapshot";
+const CHECKSUM_SIZE: usize = 32;
+
+/// number of snapshot files to keep in the snapshot directory, so there is
+/// still something to fall back to if the most recent one is corrupted.
+const SNAPSHOTS_KEPT: usize = 2;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("I/O error while accessing the ledger snapshot")]
+    Io(#[from] io::Error),
+    #[error("error accessing storage")]
+    Storage(#[from] StorageError),
+    #[error("the file is not a ledger snapshot")]
+    InvalidMagic,
+    #[error("unsupported ledger snapshot version {0}")]
+    UnsupportedVersion(u8),
+    #[error("the ledger snapshot checksum does not match its content")]
+    ChecksumMismatch,
+    #[error(
+        "the ledger snapshot was expected to be for block {expected} but is for block {found}"
+    )]
+    BlockMismatch {
+        expected: HeaderHash,
+        found: HeaderHash,
+    },
+    #[error("the ledger snapshot block {0} is not an ancestor of the HEAD")]
+    NotInMainBranch(HeaderHash),
+    #[error("serialization error")]
+    Serialize(#[source] io::Error),
+    #[error("deserialization error")]
+    Deserialize(#[source] io::Error),
+    #[error("the snapshot writing task failed")]
+    Task(#[from] tokio::task::JoinError),
+}
+
+/// the content of a ledger snapshot loaded from the disk
+pub struct LedgerSnapshot {
+    pub hash: HeaderHash,
+    pub chain_length: u32,
+    pub ledger: Ledger,
+    pub leadership_state: Ledger,
+    pub previous_epoch: Option<(HeaderHash, Ledger)>,
+}
+
+/// handle to the ledger snapshots of the node, cheap to clone.
+///
+/// Snapshots are disabled if the node does not have a persistent storage
+/// or if the snapshot interval is `0`.
+#[derive(Clone)]
+pub struct LedgerSnapshots {
+    inner: Option<Arc<SnapshotsInner>>,
+}
+
+struct SnapshotsInner {
+    dir: PathBuf,
+    interval: u32,
+    state: Mutex<SnapshotsState>,
+}
+
+struct SnapshotsState {
+    /// chain length of the last `Ref` taken as a snapshot candidate
+    last_candidate: u32,
+    /// candidates not yet buried under `epoch_stability_depth` blocks
+    candidates: Vec<Arc<Ref>>,
+}
+
+impl LedgerSnapshots {
+    pub fn new(dir: Option<PathBuf>, interval: u32) -> Self {
+        let inner = dir.filter(|_| interval > 0).map(|dir| {
+            Arc::new(SnapshotsInner {
+                dir,
+                interval,
+                state: Mutex::new(SnapshotsState {
+                    last_candidate: 0,
+                    candidates: Vec::new(),
+                }),
+            })
+        });
+        LedgerSnapshots { inner }
+    }
+
+    /// notify the snapshots of the current tip, taking it as a new candidate
+    /// if needed and persisting the highest candidate that became stable
+    /// and is still part of the main branch.
+    pub async fn update(
+        &self,
+        storage: &Storage,
+        tip: &Arc<Ref>,
+        epoch_stability_depth: u32,
+    ) -> Result<(), Error> {
+        let inner = match &self.inner {
+            Some(inner) => inner,
+            None => return Ok(()),
+        };
+
+        let tip_length: u32 = tip.chain_length().into();
+        let stable = {
+            let mut state = inner.state.lock().await;
+
+            if tip_length >= state.last_candidate.saturating_add(inner.interval) {
+                state.candidates.push(Arc::clone(tip));
+                state.last_candidate = tip_length;
+            }
+
+            let (stable, pending): (Vec<_>, Vec<_>) =
+                state.candidates.drain(..).partition(|candidate| {
+                    let length: u32 = candidate.chain_length().into();
+                    length.saturating_add(epoch_stability_depth) <= tip_length
+                });
+            state.candidates = pending;
+            stable
+        };
+
+        // candidates are ordered by chain length, take the highest one that
+        // did not get rolled back
+        for candidate in stable.into_iter().rev() {
+            if storage
+                .find_closest_ancestor(vec![candidate.hash()], tip.hash())?
+                .is_none()
+            {
+                tracing::debug!(
+                    "ledger snapshot candidate {} is not in the main branch anymore",
+                    candidate.header().description()
+                );
+                continue;
+            }
+
+            inner.persist(storage, candidate).await?;
+            break;
+        }
+
+        Ok(())
+    }
+
+    /// load the most recent valid snapshot for the branch ending at `head`.
+    ///
+    /// Snapshots failing the integrity checks are skipped with a warning,
+    /// `None` is returned if no valid snapshot was found.
+    pub async fn load(&self, storage: &Storage, head: HeaderHash) -> Option<LedgerSnapshot> {
+        let inner = self.inner.as_ref()?;
+
+        let mut candidates = match inner.list() {
+            Ok(candidates) => candidates,
+            Err(err) => {
+                tracing::warn!(reason = %err, "cannot list the ledger snapshots");
+                return None;
+            }
+        };
+
+        // the tagged snapshot is the preferred one, whatever the file names say
+        if let Ok(Some(tagged)) = storage.get_tag(LEDGER_SNAPSHOT_TAG) {
+            if let Some(index) = candidates.iter().position(|(_, hash, _)| *hash == tagged) {
+                let tagged = candidates.remove(index);
+                candidates.insert(0, tagged);
+            }
+        }
+
+        for (_, hash, path) in candidates {
+            match load_and_check(storage, &path, hash, head) {
+                Ok(snapshot) => {
+                    let mut state = inner.state.lock().await;
+                    state.last_candidate = snapshot.chain_length;
+                    return Some(snapshot);
+                }
+                Err(err) => {
+                    tracing::warn!(
+                        reason = %err,
+                        "ignoring invalid ledger snapshot {}",
+                        path.display()
+                    );
+                }
+            }
+        }
+
+        None
+    }
+}
+
+impl SnapshotsInner {
+    async fn persist(&self, storage: &Storage, candidate: Arc<Ref>) -> Result<(), Error> {
+        let hash = candidate.hash();
+        let chain_length: u32 = candidate.chain_length().into();
+        let path = self.path(chain_length, hash);
+        let dir = self.dir.clone();
+
+        tracing::info!(
+            "writing ledger snapshot at {}",
+            candidate.header().description()
+        );
+
+        let ledger = candidate.ledger();
+        let leadership_state = Arc::clone(candidate.epoch_leadership_state());
+        let previous_epoch = candidate
+            .last_ref_previous_epoch()
+            .map(|previous| (previous.hash(), previous.ledger()));
+
+        // serializing the ledger is expensive, keep it out of the async runtime
+        tokio::task::spawn_blocking(move || {
+            let bytes = encode(
+                hash,
+                chain_length,
+                &ledger,
+                &leadership_state,
+                previous_epoch
+                    .as_ref()
+                    .map(|(hash, ledger)| (*hash, ledger.as_ref())),
+            )?;
+            fs::create_dir_all(&dir)?;
+            let tmp_path = path.with_extension("tmp");
+            fs::write(&tmp_path, bytes)?;
+            fs::rename(tmp_path, path)?;
+            Ok::<(), Error>(())
+        })
+        .await??;
+
+        storage.put_tag(LEDGER_SNAPSHOT_TAG, hash)?;
+
+        for (_, _, path) in self.list()?.into_iter().skip(SNAPSHOTS_KEPT) {
+            if let Err(err) = fs::remove_file(&path) {
+                tracing::warn!(
+                    reason = %err,
+                    "cannot remove old ledger snapshot {}",
+                    path.display()
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn path(&self, chain_length: u32, hash: HeaderHash) -> PathBuf {
+        self.dir.join(format!(
+            "{:010}-{}.{}",
+            chain_length, hash, SNAPSHOT_EXTENSION
+        ))
+    }
+
+    /// list the snapshot files of the directory, most recent first
+    fn list(&self) -> Result<Vec<(u32, HeaderHash, PathBuf)>, Error> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut snapshots = Vec::new();
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SNAPSHOT_EXTENSION) {
+                continue;
+            }
+            let parsed = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| {
+                    let mut split = stem.splitn(2, '-');
+                    let chain_length = split.next()?.parse::<u32>().ok()?;
+                    let hash = HeaderHash::from_str(split.next()?).ok()?;
+                    Some((chain_length, hash))
+                });
+            if let Some((chain_length, hash)) = parsed {
+                snapshots.push((chain_length, hash, path));
+            }
+        }
+        snapshots.sort_by(|a, b| b.0.cmp(&a.0));
+        Ok(snapshots)
+    }
+}
+
+fn load_and_check(
+    storage: &Storage,
+    path: &Path,
+    expected: HeaderHash,
+    head: HeaderHash,
+) -> Result<LedgerSnapshot, Error> {
+    let snapshot = decode(&fs::read(path)?)?;
+
+    if snapshot.hash != expected {
+        return Err(Error::BlockMismatch {
+            expected,
+            found: snapshot.hash,
+        });
+    }
+
+    if snapshot.hash != head
+        && storage
+            .find_closest_ancestor(vec![snapshot.hash], head)?
+            .is_none()
+    {
+        return Err(Error::NotInMainBranch(snapshot.hash));
+    }
+
+    Ok(snapshot)
+}
+
+fn encode(
+    hash: HeaderHash,
+    chain_length: u32,
+    ledger: &Ledger,
+    leadership_state: &Ledger,
+    previous_epoch: Option<(HeaderHash, &Ledger)>,
+) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(SNAPSHOT_MAGIC);
+    bytes.push(SNAPSHOT_VERSION);
+    bytes.extend_from_slice(hash.as_bytes());
+    bytes.extend_from_slice(&chain_length.to_be_bytes());
+    encode_ledger(&mut bytes, ledger)?;
+    encode_ledger(&mut bytes, leadership_state)?;
+    match previous_epoch {
+        None => bytes.push(0),
+        Some((hash, ledger)) => {
+            bytes.push(1);
+            bytes.extend_from_slice(hash.as_bytes());
+            encode_ledger(&mut bytes, ledger)?;
+        }
+    }
+
+    let checksum = Blake2b256::new(&bytes);
+    bytes.extend_from_slice(checksum.as_ref());
+    Ok(bytes)
+}
+
+fn encode_ledger(bytes: &mut Vec<u8>, ledger: &Ledger) -> Result<(), Error> {
+    let serialized = ledger.serialize_as_vec().map_err(Error::Serialize)?;
+    bytes.extend_from_slice(&(serialized.len() as u64).to_be_bytes());
+    bytes.extend_from_slice(&serialized);
+    Ok(())
+}
+
+fn decode(bytes: &[u8]) -> Result<LedgerSnapshot, Error> {
+    if bytes.len() < CHECKSUM_SIZE {
+        return Err(Error::ChecksumMismatch);
+    }
+    let (content, checksum) = bytes.split_at(bytes.len() - CHECKSUM_SIZE);
+    if Blake2b256::new(content).as_ref() != checksum {
+        return Err(Error::ChecksumMismatch);
+    }
+
+    let mut reader = content;
+
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != SNAPSHOT_MAGIC {
+        return Err(Error::InvalidMagic);
+    }
+    let mut version = [0; 1];
+    reader.read_exact(&mut version)?;
+    if version[0] != SNAPSHOT_VERSION {
+        return Err(Error::UnsupportedVersion(version[0]));
+    }
+
+    let hash = HeaderHash::deserialize(&mut reader).map_err(Error::Deserialize)?;
+    let mut chain_length = [0; 4];
+    reader.read_exact(&mut chain_length)?;
+    let ledger = decode_ledger(&mut reader)?;
+    let leadership_state = decode_ledger(&mut reader)?;
+
+    let mut has_previous_epoch = [0; 1];
+    reader.read_exact(&mut has_previous_epoch)?;
+    let previous_epoch = if has_previous_epoch[0] == 0 {
+        None
+    } else {
+        let hash = HeaderHash::deserialize(&mut reader).map_err(Error::Deserialize)?;
+        Some((hash, decode_ledger(&mut reader)?))
+    };
+
+    Ok(LedgerSnapshot {
+        hash,
+        chain_length: u32::from_be_bytes(chain_length),
+        ledger,
+        leadership_state,
+        previous_epoch,
+    })
+}
+
+fn decode_ledger<'a>(reader: &mut &'a [u8]) -> Result<Ledger, Error> {
+    let mut len = [0; 8];
+    reader.read_exact(&mut len)?;
+    let len = u64::from_be_bytes(len) as usize;
+    if reader.len() < len {
+        return Err(Error::Io(io::ErrorKind::UnexpectedEof.into()));
+    }
+    let remaining: &'a [u8] = *reader;
+    let (serialized, rest) = remaining.split_at(len);
+    *reader = rest;
+    Ledger::deserialize(serialized).map_err(Error::Deserialize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockcfg::{Block, BlockDate, BlockVersion, ContentsBuilder, HeaderBuilderNew},
+        blockchain::{Blockchain, CheckHeaderProof, PreCheckedHeader, MAIN_BRANCH_TAG},
+    };
+    use chain_core::property::ChainLength as _;
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{chaintypes::ConsensusVersion, fee::LinearFee, key::BftLeaderId};
+    use jormungandr_lib::interfaces::{
+        Block0Configuration, BlockchainConfiguration, ConsensusLeaderId,
+    };
+    use tracing::Span;
+
+    const CHAIN_LENGTH: u32 = 12;
+    const INTERVAL: u32 = 4;
+    const EPOCH_STABILITY_DEPTH: u32 = 3;
+
+    fn block0() -> Block {
+        let leader = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let mut blockchain_configuration = BlockchainConfiguration::new(
+            chain_addr::Discrimination::Test,
+            ConsensusVersion::Bft,
+            LinearFee::new(1, 2, 3),
+        );
+        blockchain_configuration.consensus_leader_ids =
+            vec![ConsensusLeaderId(BftLeaderId::from(leader.to_public()))];
+        Block0Configuration {
+            blockchain_configuration,
+            initial: Vec::new(),
+        }
+        .to_block()
+    }
+
+    fn extend(parent: &Block, length: u32) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for _ in 0..length {
+            let parent = blocks.last().unwrap_or(parent);
+            let contents = ContentsBuilder::new().into();
+            let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+                .set_parent(&parent.header.hash(), parent.header.chain_length().next())
+                .set_date(BlockDate {
+                    epoch: 0,
+                    slot_id: parent.header.block_date().slot_id + 1,
+                })
+                .into_unsigned_header()
+                .unwrap()
+                .generalize();
+            blocks.push(Block { header, contents });
+        }
+        blocks
+    }
+
+    fn snapshot_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "jormungandr-snapshot-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// store and apply a chain of `CHAIN_LENGTH` blocks, notifying the
+    /// snapshots of every new tip like the garbage collection does.
+    async fn build_chain(snapshots: LedgerSnapshots) -> (Storage, Block, Vec<Block>) {
+        let storage = Storage::memory(Span::none()).unwrap();
+        let block0 = block0();
+        let blockchain = Blockchain::new(
+            block0.header.hash(),
+            storage.clone(),
+            snapshots.clone(),
+            1024,
+            false,
+        );
+        blockchain.load_from_block0(block0.clone()).await.unwrap();
+
+        let blocks = extend(&block0, CHAIN_LENGTH);
+        for block in blocks.iter() {
+            let parent_ref = match blockchain
+                .pre_check_header(block.header.clone(), false)
+                .await
+                .unwrap()
+            {
+                PreCheckedHeader::HeaderWithCache { parent_ref, .. } => parent_ref,
+                _ => panic!("the parent block should be in the cache"),
+            };
+            let post_checked = blockchain
+                .post_check_header(
+                    block.header.clone(),
+                    parent_ref,
+                    CheckHeaderProof::SkipFromStorage,
+                )
+                .await
+                .unwrap();
+            let tip = blockchain
+                .apply_and_store_block(post_checked, block.clone())
+                .await
+                .unwrap()
+                .cached_ref();
+            storage.put_tag(MAIN_BRANCH_TAG, tip.hash()).unwrap();
+            snapshots
+                .update(&storage, &tip, EPOCH_STABILITY_DEPTH)
+                .await
+                .unwrap();
+        }
+
+        (storage, block0, blocks)
+    }
+
+    async fn tip_ledger(storage: &Storage, block0: &Block, snapshots: LedgerSnapshots) -> Ledger {
+        let blockchain = Blockchain::new(
+            block0.header.hash(),
+            storage.clone(),
+            snapshots,
+            1024,
+            false,
+        );
+        let branch = blockchain.load_from_storage(block0.clone()).await.unwrap();
+        let tip = branch.get_ref().await;
+        tip.ledger().as_ref().clone()
+    }
+
+    fn snapshot_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| {
+                path.extension().and_then(|ext| ext.to_str()) == Some(SNAPSHOT_EXTENSION)
+            })
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// append a valid checksum to `content`, to get past the checksum
+    /// verification with a modified snapshot.
+    fn with_checksum(mut content: Vec<u8>) -> Vec<u8> {
+        let checksum = Blake2b256::new(&content);
+        content.extend_from_slice(checksum.as_ref());
+        content
+    }
+
+    #[test]
+    fn encode_decode_round_trip() {
+        let block0 = block0();
+        let ledger = Ledger::new(block0.header.hash(), block0.contents.iter()).unwrap();
+        let hash = block0.header.hash();
+
+        let bytes = encode(hash, 42, &ledger, &ledger, Some((hash, &ledger))).unwrap();
+        let snapshot = decode(&bytes).unwrap();
+
+        assert_eq!(snapshot.hash, hash);
+        assert_eq!(snapshot.chain_length, 42);
+        assert!(snapshot.ledger == ledger);
+        assert!(snapshot.leadership_state == ledger);
+        let (previous_hash, previous_ledger) = snapshot.previous_epoch.unwrap();
+        assert_eq!(previous_hash, hash);
+        assert!(previous_ledger == ledger);
+
+        let bytes = encode(hash, 0, &ledger, &ledger, None).unwrap();
+        assert!(decode(&bytes).unwrap().previous_epoch.is_none());
+    }
+
+    #[test]
+    fn decode_detects_corruption() {
+        let block0 = block0();
+        let ledger = Ledger::new(block0.header.hash(), block0.contents.iter()).unwrap();
+        let bytes = encode(block0.header.hash(), 0, &ledger, &ledger, None).unwrap();
+
+        let mut corrupted = bytes.clone();
+        corrupted[SNAPSHOT_MAGIC.len() + 10] ^= 0xff;
+        assert!(matches!(decode(&corrupted), Err(Error::ChecksumMismatch)));
+        assert!(matches!(
+            decode(&bytes[..CHECKSUM_SIZE - 1]),
+            Err(Error::ChecksumMismatch)
+        ));
+
+        let mut content = bytes[..bytes.len() - CHECKSUM_SIZE].to_vec();
+        content[0] ^= 0xff;
+        assert!(matches!(
+            decode(&with_checksum(content)),
+            Err(Error::InvalidMagic)
+        ));
+
+        let mut content = bytes[..bytes.len() - CHECKSUM_SIZE].to_vec();
+        content[SNAPSHOT_MAGIC.len()] = SNAPSHOT_VERSION + 1;
+        assert!(matches!(
+            decode(&with_checksum(content)),
+            Err(Error::UnsupportedVersion(version)) if version == SNAPSHOT_VERSION + 1
+        ));
+    }
+
+    #[tokio::test]
+    async fn restore_gives_the_same_tip_as_a_replay() {
+        let dir = snapshot_dir("restore");
+        let snapshots = LedgerSnapshots::new(Some(dir.clone()), INTERVAL);
+        let (storage, block0, blocks) = build_chain(snapshots).await;
+
+        let tagged = storage.get_tag(LEDGER_SNAPSHOT_TAG).unwrap().unwrap();
+        assert!(blocks.iter().any(|block| block.header.hash() == tagged));
+        assert!(!snapshot_files(&dir).is_empty());
+
+        let snapshots = LedgerSnapshots::new(Some(dir.clone()), INTERVAL);
+        let head = storage.get_tag(MAIN_BRANCH_TAG).unwrap().unwrap();
+        assert!(snapshots.load(&storage, head).await.is_some());
+
+        let restored = tip_ledger(&storage, &block0, snapshots).await;
+        let replayed = tip_ledger(&storage, &block0, LedgerSnapshots::new(None, 0)).await;
+        assert!(restored == replayed);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn invalid_snapshots_fall_back_to_a_full_replay() {
+        let dir = snapshot_dir("fallback");
+        let snapshots = LedgerSnapshots::new(Some(dir.clone()), INTERVAL);
+        let (storage, block0, _) = build_chain(snapshots).await;
+        let head = storage.get_tag(MAIN_BRANCH_TAG).unwrap().unwrap();
+        let replayed = tip_ledger(&storage, &block0, LedgerSnapshots::new(None, 0)).await;
+
+        let files = snapshot_files(&dir);
+        assert_eq!(files.len(), SNAPSHOTS_KEPT);
+        let valid = fs::read(&files[0]).unwrap();
+        let content = valid[..valid.len() - CHECKSUM_SIZE].to_vec();
+
+        // checksum mismatch
+        let mut corrupted = content.clone();
+        corrupted.extend_from_slice(&[0; CHECKSUM_SIZE]);
+        fs::write(&files[0], &corrupted).unwrap();
+        // version mismatch
+        let mut newer = content.clone();
+        newer[SNAPSHOT_MAGIC.len()] = SNAPSHOT_VERSION + 1;
+        fs::write(&files[1], with_checksum(newer)).unwrap();
+
+        let snapshots = LedgerSnapshots::new(Some(dir.clone()), INTERVAL);
+        assert!(snapshots.load(&storage, head).await.is_none());
+        assert!(tip_ledger(&storage, &block0, snapshots).await == replayed);
+
+        // magic mismatch
+        let mut foreign = content;
+        foreign[..SNAPSHOT_MAGIC.len()].copy_from_slice(b"NOTASNAP");
+        fs::write(&files[0], with_checksum(foreign)).unwrap();
+
+        let snapshots = LedgerSnapshots::new(Some(dir.clone()), INTERVAL);
+        assert!(snapshots.load(&storage, head).await.is_none());
+        assert!(tip_ledger(&storage, &block0, snapshots).await == replayed);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -262,6 +262,19 @@ impl Storage {
         }))
     }
 
+    /// Return the hash of the ancestor of the given block `distance`
+    /// blocks below it.
+    pub fn get_nth_ancestor(
+        &self,
+        header_hash: HeaderHash,
+        distance: u32,
+    ) -> Result<HeaderHash, Error> {
+        let block_info = self
+            .storage
+            .get_nth_ancestor(header_hash.as_bytes(), distance)?;
+        HeaderHash::deserialize(block_info.id().as_ref()).map_err(Error::Deserialize)
+    }
+
     pub fn gc(&self, threshold_depth: u32, main_branch_tip: &[u8]) -> Result<(), Error> {
         let _enter = self.span.enter();
         let main_info = self.storage.get_block_info(main_branch_tip)?;
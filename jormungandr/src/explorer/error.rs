@@ -1,5 +1,8 @@
 use crate::blockcfg::HeaderHash;
-use crate::{blockchain::StorageError, intercom};
+use crate::{
+    blockchain::{self, StorageError},
+    intercom,
+};
 use thiserror::Error;
 
 #[derive(Debug, Error)]
@@ -20,6 +23,16 @@ pub enum ExplorerError {
     StorageError(#[from] StorageError),
     #[error("streaming error")]
     StreamingError(#[from] intercom::Error),
+    #[error("error accessing the explorer database")]
+    StableIndexError(#[from] sled::Error),
+    #[error("the explorer database is corrupted: {0}")]
+    CorruptedStableIndex(String),
+    #[error("blockchain error")]
+    BlockchainError(#[from] blockchain::Error),
+    #[error("the ledger of block {0} is not available to index its rewards")]
+    LedgerUnavailable(HeaderHash),
+    #[error("a balance of block {0} is out of bounds")]
+    BalanceOutOfBounds(HeaderHash),
 }
 
 pub type Result<T> = std::result::Result<T, ExplorerError>;
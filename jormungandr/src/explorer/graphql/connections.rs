@@ -1,10 +1,11 @@
 use super::error::ErrorKind;
 use super::scalars::{
-    BlockCount, IndexCursor, PoolCount, TransactionCount, VotePlanCount, VoteStatusCount,
+    BlockCount, IndexCursor, LedgerEntryCount, PoolCount, TransactionCount, VotePlanCount,
+    VoteStatusCount,
 };
-use super::{Block, Context, Pool, Transaction, VotePlanStatus, VoteStatus};
+use super::{Block, Context, LedgerEntry, Pool, Transaction, VotePlanStatus, VoteStatus};
 use crate::blockcfg::HeaderHash;
-use crate::explorer::indexing::ExplorerTransaction;
+use crate::explorer::indexing::{ExplorerLedgerEntry, ExplorerTransaction};
 use juniper::FieldResult;
 use std::convert::TryFrom;
 
@@ -57,6 +58,20 @@ impl TransactionEdge {
     }
 }
 
+#[juniper::graphql_object(
+    Context = Context
+)]
+impl LedgerEntryEdge {
+    pub fn node(&self) -> &LedgerEntry {
+        &self.node
+    }
+
+    /// A cursor for use in pagination
+    pub fn cursor(&self) -> &IndexCursor {
+        &self.cursor
+    }
+}
+
 #[juniper::graphql_object(
     Context = Context
 )]
@@ -135,6 +150,25 @@ impl TransactionConnection {
     }
 }
 
+#[juniper::graphql_object(
+    Context = Context,
+    name = "LedgerEntryConnection"
+)]
+impl LedgerEntryConnection {
+    pub fn page_info(&self) -> &PageInfo {
+        &self.page_info
+    }
+
+    pub fn edges(&self) -> &Vec<LedgerEntryEdge> {
+        &self.edges
+    }
+
+    /// A count of the total number of objects in this connection, ignoring pagination.
+    pub fn total_count(&self) -> &LedgerEntryCount {
+        &self.total_count
+    }
+}
+
 #[juniper::graphql_object(
     Context = Context,
     name = "PoolConnection"
@@ -215,6 +249,11 @@ pub struct BlockEdge {
     pub cursor: IndexCursor,
 }
 
+pub struct LedgerEntryEdge {
+    node: LedgerEntry,
+    cursor: IndexCursor,
+}
+
 pub struct PoolEdge {
     node: Pool,
     pub cursor: IndexCursor,
@@ -386,6 +425,7 @@ where
 
 pub type BlockConnection = Connection<BlockEdge, BlockCount>;
 pub type TransactionConnection = Connection<TransactionEdge, TransactionCount>;
+pub type LedgerEntryConnection = Connection<LedgerEntryEdge, LedgerEntryCount>;
 pub type PoolConnection = Connection<PoolEdge, PoolCount>;
 pub type VotePlanConnection = Connection<VotePlanEdge, VotePlanCount>;
 pub type VoteStatusConnection = Connection<VoteStatusEdge, VoteStatusCount>;
@@ -427,6 +467,20 @@ impl Edge for BlockEdge {
     }
 }
 
+impl Edge for LedgerEntryEdge {
+    type Node = ExplorerLedgerEntry;
+    fn new(node: Self::Node, cursor: IndexCursor) -> Self {
+        LedgerEntryEdge {
+            node: LedgerEntry(node),
+            cursor,
+        }
+    }
+
+    fn cursor(&self) -> &IndexCursor {
+        &self.cursor
+    }
+}
+
 impl Edge for PoolEdge {
     type Node = Pool;
     fn new(node: Self::Node, cursor: IndexCursor) -> Self {
@@ -4,32 +4,40 @@ mod error;
 mod scalars;
 
 use self::connections::{
-    BlockConnection, InclusivePaginationInterval, PaginationArguments, PaginationInterval,
-    PoolConnection, TransactionConnection, TransactionNodeFetchInfo, VotePlanConnection,
-    VoteStatusConnection,
+    BlockConnection, InclusivePaginationInterval, LedgerEntryConnection, PaginationArguments,
+    PaginationInterval, PoolConnection, TransactionConnection, TransactionNodeFetchInfo,
+    VotePlanConnection, VoteStatusConnection,
 };
 use self::error::ErrorKind;
 use self::scalars::{
-    BlockCount, ChainLength, EpochNumber, ExternalProposalId, IndexCursor, NonZero, PayloadType,
-    PoolId, PublicKey, Slot, Value, VoteOptionRange, VotePlanId, Weight,
+    BlockCount, ChainLength, Discrimination, EpochNumber, ExternalProposalId, IndexCursor, NonZero,
+    PayloadType, PoolId, PublicKey, Slot, SpendingCounter, Value, VoteOptionRange, VotePlanId,
+    Weight,
 };
 use super::indexing::{
-    BlockProducer, EpochData, ExplorerAddress, ExplorerBlock, ExplorerTransaction, StakePoolData,
+    BlockProducer, EpochData, ExplorerAddress, ExplorerAddressState, ExplorerBlock,
+    ExplorerLedgerEntry, ExplorerTransaction, StakePoolData,
 };
-use super::persistent_sequence::PersistentSequence;
+use super::stable::Sequence;
 use crate::blockcfg::{self, FragmentId, HeaderHash};
 use crate::explorer::indexing::ExplorerVote;
-use crate::explorer::{ExplorerDB, Settings};
+use crate::explorer::{ExplorerDB, ExplorerEvent, Settings};
 use cardano_legacy_address::Addr as OldAddress;
 use certificates::*;
+use chain_impl_mockchain::account::DelegationType;
 use chain_impl_mockchain::certificate;
 use chain_impl_mockchain::key::BftLeaderId;
 use chain_impl_mockchain::vote::{EncryptedVote, ProofOfCorrectVote};
+use futures::future;
+use futures::stream::{self, Stream, StreamExt as _};
 pub use juniper::http::GraphQLRequest;
-use juniper::{EmptyMutation, EmptySubscription, FieldResult, GraphQLUnion, RootNode};
+use juniper::{EmptyMutation, FieldError, FieldResult, GraphQLUnion, RootNode};
 use std::convert::{TryFrom, TryInto};
+use std::num::NonZeroU64;
+use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::Arc;
+use tokio::sync::broadcast;
 
 #[derive(Clone)]
 pub struct Block {
@@ -263,11 +271,8 @@ impl Transaction {
     async fn from_id(id: FragmentId, context: &Context) -> FieldResult<Transaction> {
         let block_hash = context
             .db
-            .get_main_tip()
-            .await
-            .1
-            .state()
             .find_block_hash_by_transaction(&id)
+            .await
             .ok_or_else(|| ErrorKind::NotFound(format!("transaction not found: {}", &id,)))?;
 
         Ok(Transaction {
@@ -298,11 +303,8 @@ impl Transaction {
             Some(block_id) => block_id,
             None => context
                 .db
-                .get_main_tip()
-                .await
-                .1
-                .state()
                 .find_block_hash_by_transaction(&self.id)
+                .await
                 .ok_or_else(|| {
                     ErrorKind::InternalError("Transaction's block was not found".to_owned())
                 })?,
@@ -428,14 +430,64 @@ struct Address {
 }
 
 impl Address {
-    fn from_bech32(bech32: &str) -> FieldResult<Address> {
-        let addr = chain_addr::AddressReadable::from_string_anyprefix(bech32)
-            .map(|adr| ExplorerAddress::New(adr.to_address()))
-            .or_else(|_| OldAddress::from_str(bech32).map(ExplorerAddress::Old))
-            .map_err(|_| ErrorKind::InvalidAddress(bech32.to_string()))?;
+    /// parse an address with any prefix and the discrimination of the
+    /// settings, or a legacy address
+    fn from_bech32(bech32: &str, settings: &Settings) -> FieldResult<Address> {
+        let addr = match chain_addr::AddressReadable::from_string_anyprefix(bech32) {
+            Ok(addr) => {
+                let addr = addr.to_address();
+                if addr.discrimination() != settings.discrimination {
+                    return Err(ErrorKind::InvalidAddress(format!(
+                        "{}, expected the {:?} discrimination",
+                        bech32, settings.discrimination
+                    ))
+                    .into());
+                }
+                ExplorerAddress::New(addr)
+            }
+            Err(_) => OldAddress::from_str(bech32)
+                .map(ExplorerAddress::Old)
+                .map_err(|_| ErrorKind::InvalidAddress(bech32.to_string()))?,
+        };
 
         Ok(Address { id: addr })
     }
+
+    async fn get_state(&self, context: &Context) -> Option<Arc<ExplorerAddressState>> {
+        context.db.get_address_state(&self.id).await
+    }
+
+    /// the state of the address after the last of its ledger entries matching
+    /// the predicate, which must hold for the entries up to some point and
+    /// not after it
+    async fn state_until(
+        &self,
+        context: &Context,
+        until: impl Fn(&ExplorerLedgerEntry) -> bool,
+    ) -> AddressState {
+        let entries = context.db.ledger_entries(&self.id).await;
+        // number of entries matching the predicate
+        let (mut low, mut high) = (0u64, entries.len());
+        while low < high {
+            let middle = low + (high - low) / 2;
+            if entries.get(middle).map_or(false, |entry| until(&entry)) {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+
+        match low.checked_sub(1).and_then(|last| entries.get(last)) {
+            Some(entry) => AddressState {
+                balance: entry.balance,
+                delegation: entry.delegation,
+            },
+            None => AddressState {
+                balance: blockcfg::Value::zero(),
+                delegation: None,
+            },
+        }
+    }
 }
 
 impl From<&ExplorerAddress> for Address {
@@ -460,8 +512,69 @@ impl Address {
         }
     }
 
-    fn delegation() -> FieldResult<Pool> {
-        Err(ErrorKind::Unimplemented.into())
+    /// The value of the utxos and of the account of the address
+    async fn balance(&self, context: &Context) -> Value {
+        let balance = self
+            .get_state(context)
+            .await
+            .map_or_else(blockcfg::Value::zero, |state| state.balance);
+        Value(format!("{}", balance))
+    }
+
+    /// The outputs sent to the address and not spent yet
+    async fn utxos(&self, context: &Context) -> Vec<Utxo> {
+        let state = match self.get_state(context).await {
+            Some(state) => state,
+            None => return Vec::new(),
+        };
+        let mut utxos: Vec<Utxo> = state
+            .utxos
+            .iter()
+            .map(|((transaction, index), value)| Utxo {
+                transaction: *transaction,
+                index: *index,
+                value: **value,
+            })
+            .collect();
+        utxos.sort_unstable_by_key(|utxo| (utxo.transaction.to_string(), utxo.index));
+        utxos
+    }
+
+    /// The stake pools the account delegates its stake to, empty if the
+    /// address is not an account or does not delegate its stake
+    async fn delegation(&self, context: &Context) -> Vec<PoolDelegation> {
+        let delegation = self
+            .get_state(context)
+            .await
+            .and_then(|state| state.delegation.clone());
+        pool_delegations(delegation)
+    }
+
+    /// The number of transactions spent from the account
+    async fn spending_counter(&self, context: &Context) -> SpendingCounter {
+        let counter = self
+            .get_state(context)
+            .await
+            .map_or(0, |state| state.spending_counter);
+        SpendingCounter(format!("{}", counter))
+    }
+
+    /// The total of the rewards received by the account
+    async fn rewards(&self, context: &Context) -> Value {
+        let rewards = self
+            .get_state(context)
+            .await
+            .map_or_else(blockcfg::Value::zero, |state| state.rewards);
+        Value(format!("{}", rewards))
+    }
+
+    /// The last rewards received by the account
+    async fn last_rewards(&self, context: &Context) -> Option<Rewards> {
+        let (epoch, value) = self.get_state(context).await?.last_rewards?;
+        Some(Rewards {
+            epoch: Epoch { id: epoch },
+            value: Value(format!("{}", value)),
+        })
     }
 
     async fn transactions(
@@ -472,16 +585,9 @@ impl Address {
         after: Option<IndexCursor>,
         context: &Context,
     ) -> FieldResult<TransactionConnection> {
-        let transactions = context
-            .db
-            .get_main_tip()
-            .await
-            .1
-            .state()
-            .transactions_by_address(&self.id)
-            .unwrap_or_else(PersistentSequence::<FragmentId>::new);
+        let transactions = context.db.transactions_by_address(&self.id).await;
 
-        let boundaries = if transactions.len() > 0 {
+        let boundaries = if !transactions.is_empty() {
             PaginationInterval::Inclusive(InclusivePaginationInterval {
                 lower_bound: 0u64,
                 upper_bound: transactions.len(),
@@ -507,12 +613,236 @@ impl Address {
                     .filter_map(|i| {
                         transactions
                             .get(i)
-                            .map(|h| (TransactionNodeFetchInfo::Id(*h.as_ref()), i))
+                            .map(|id| (TransactionNodeFetchInfo::Id(id), i))
                     })
                     .collect(),
             },
         )
     }
+
+    /// The changes of the balance and of the delegation of the address, in
+    /// the order of the chain
+    async fn ledger_entries(
+        &self,
+        first: Option<i32>,
+        last: Option<i32>,
+        before: Option<IndexCursor>,
+        after: Option<IndexCursor>,
+        context: &Context,
+    ) -> FieldResult<LedgerEntryConnection> {
+        let entries = context.db.ledger_entries(&self.id).await;
+
+        let boundaries = if !entries.is_empty() {
+            PaginationInterval::Inclusive(InclusivePaginationInterval {
+                lower_bound: 0u64,
+                upper_bound: entries.len().checked_sub(1).unwrap(),
+            })
+        } else {
+            PaginationInterval::Empty
+        };
+
+        let pagination_arguments = PaginationArguments {
+            first,
+            last,
+            before: before.map(u64::from),
+            after: after.map(u64::from),
+        }
+        .validate()?;
+
+        LedgerEntryConnection::new(
+            boundaries,
+            pagination_arguments,
+            |range: PaginationInterval<u64>| match range {
+                PaginationInterval::Empty => vec![],
+                PaginationInterval::Inclusive(range) => (range.lower_bound..=range.upper_bound)
+                    .filter_map(|i| entries.get(i).map(|entry| (entry, i)))
+                    .collect(),
+            },
+        )
+    }
+
+    /// The balance and the delegation of the address after the given block,
+    /// which must be on the main branch
+    async fn state_at_block(&self, block: String, context: &Context) -> FieldResult<AddressState> {
+        let block = Block::from_string_hash(block, &context.db)
+            .await?
+            .get_explorer_block(&context.db)
+            .await?;
+        let chain_length = block.chain_length();
+        if context.db.find_block_by_chain_length(chain_length).await != Some(block.id()) {
+            return Err(
+                ErrorKind::ArgumentError("the block is not on the main branch".to_owned()).into(),
+            );
+        }
+        Ok(self
+            .state_until(context, |entry| entry.chain_length <= chain_length)
+            .await)
+    }
+
+    /// The balance and the delegation of the address at the end of the given
+    /// epoch
+    async fn state_at_epoch(
+        &self,
+        epoch: EpochNumber,
+        context: &Context,
+    ) -> FieldResult<AddressState> {
+        let epoch: blockcfg::Epoch = epoch.try_into()?;
+        Ok(self
+            .state_until(context, |entry| entry.date.epoch <= epoch)
+            .await)
+    }
+}
+
+/// The balance and the delegation of an address at some point of the chain
+struct AddressState {
+    balance: blockcfg::Value,
+    delegation: Option<DelegationType>,
+}
+
+#[juniper::graphql_object(
+    Context = Context,
+)]
+impl AddressState {
+    pub fn balance(&self) -> Value {
+        Value(format!("{}", self.balance))
+    }
+
+    /// The stake pools the account delegates its stake to
+    pub fn delegation(&self) -> Vec<PoolDelegation> {
+        pool_delegations(self.delegation.clone())
+    }
+}
+
+pub struct LedgerEntry(ExplorerLedgerEntry);
+
+/// A change of the balance or of the delegation of an address, made by a
+/// transaction or by the rewards received at the start of an epoch
+#[juniper::graphql_object(
+    Context = Context,
+)]
+impl LedgerEntry {
+    pub fn block(&self) -> Block {
+        Block::from_valid_hash(self.0.block)
+    }
+
+    pub fn date(&self) -> BlockDate {
+        BlockDate::from(self.0.date)
+    }
+
+    /// The transaction of the entry, none for the rewards
+    pub fn transaction(&self) -> Option<Transaction> {
+        self.0.transaction.map(Transaction::from_valid_id)
+    }
+
+    /// The value received by the address
+    pub fn credit(&self) -> Value {
+        Value(format!("{}", self.0.credit))
+    }
+
+    /// The value spent from the address, fee included
+    pub fn debit(&self) -> Value {
+        Value(format!("{}", self.0.debit))
+    }
+
+    /// The fee of the transaction, if the address is one of its inputs
+    pub fn fee(&self) -> Value {
+        Value(format!("{}", self.0.fee))
+    }
+
+    /// The balance of the address after the entry
+    pub fn balance(&self) -> Value {
+        Value(format!("{}", self.0.balance))
+    }
+
+    /// The stake pools the account delegates its stake to after the entry
+    pub fn delegation(&self) -> Vec<PoolDelegation> {
+        pool_delegations(self.0.delegation.clone())
+    }
+}
+
+fn pool_delegations(delegation: Option<DelegationType>) -> Vec<PoolDelegation> {
+    match delegation {
+        None | Some(DelegationType::NonDelegated) => Vec::new(),
+        Some(DelegationType::Full(pool)) => vec![PoolDelegation {
+            pool,
+            parts: 1,
+            total_parts: 1,
+        }],
+        Some(DelegationType::Ratio(ratio)) => {
+            let total_parts = ratio
+                .pools()
+                .iter()
+                .map(|(_, parts)| u64::from(*parts))
+                .sum();
+            ratio
+                .pools()
+                .iter()
+                .map(|(pool, parts)| PoolDelegation {
+                    pool: pool.clone(),
+                    parts: u64::from(*parts),
+                    total_parts,
+                })
+                .collect()
+        }
+    }
+}
+
+struct Utxo {
+    transaction: FragmentId,
+    index: u8,
+    value: blockcfg::Value,
+}
+
+/// An output not spent yet
+#[juniper::graphql_object(
+    Context = Context,
+)]
+impl Utxo {
+    /// The transaction of the output
+    pub fn transaction(&self) -> Transaction {
+        Transaction::from_valid_id(self.transaction)
+    }
+
+    /// The index of the output in its transaction
+    pub fn index(&self) -> i32 {
+        i32::from(self.index)
+    }
+
+    pub fn value(&self) -> Value {
+        Value(format!("{}", self.value))
+    }
+}
+
+struct PoolDelegation {
+    pool: certificate::PoolId,
+    parts: u64,
+    total_parts: u64,
+}
+
+/// A stake pool and the share of the stake of the account delegated to it
+#[juniper::graphql_object(
+    Context = Context,
+)]
+impl PoolDelegation {
+    pub fn pool(&self) -> Pool {
+        Pool::from_valid_id(self.pool.clone())
+    }
+
+    /// The parts of the stake delegated to the pool over the total parts
+    pub fn ratio(&self) -> Ratio {
+        Ratio(chain_impl_mockchain::rewards::Ratio {
+            numerator: self.parts,
+            denominator: NonZeroU64::new(self.total_parts).unwrap(),
+        })
+    }
+}
+
+/// Rewards received by an account at the start of an epoch
+#[derive(juniper::GraphQLObject)]
+#[graphql(Context = Context)]
+struct Rewards {
+    epoch: Epoch,
+    value: Value,
 }
 
 struct TaxType(chain_impl_mockchain::rewards::TaxType);
@@ -575,7 +905,7 @@ impl Proposal {
 pub struct Pool {
     id: certificate::PoolId,
     data: Option<Arc<StakePoolData>>,
-    blocks: Option<Arc<PersistentSequence<HeaderHash>>>,
+    blocks: Option<Sequence<HeaderHash>>,
 }
 
 impl Pool {
@@ -642,7 +972,7 @@ impl Pool {
                 })?,
         };
 
-        let bounds = if blocks.len() > 0 {
+        let bounds = if !blocks.is_empty() {
             PaginationInterval::Inclusive(InclusivePaginationInterval {
                 lower_bound: 0u32,
                 upper_bound: blocks
@@ -667,7 +997,7 @@ impl Pool {
         BlockConnection::new(bounds, pagination_arguments, |range| match range {
             PaginationInterval::Empty => vec![],
             PaginationInterval::Inclusive(range) => (range.lower_bound..=range.upper_bound)
-                .filter_map(|i| blocks.get(i).map(|h| (*h.as_ref(), i)))
+                .filter_map(|i| blocks.get(i).map(|hash| (hash, i)))
                 .collect(),
         })
     }
@@ -716,6 +1046,16 @@ impl Status {
         latest_block(context).await.map(Block::from)
     }
 
+    /// The prefix of the bech32 representation of the addresses
+    pub fn address_bech32_prefix(&self, context: &Context) -> String {
+        context.settings.address_bech32_prefix.clone()
+    }
+
+    /// The discrimination of the addresses of the chain
+    pub fn discrimination(&self, context: &Context) -> Discrimination {
+        context.settings.discrimination.into()
+    }
+
     pub async fn epoch_stability_depth(&self, context: &Context) -> String {
         context
             .db
@@ -887,14 +1227,11 @@ impl Epoch {
                 PaginationInterval::Empty => unreachable!("No blocks found (not even genesis)"),
                 PaginationInterval::Inclusive(range) => context
                     .db
-                    .get_main_tip()
-                    .await
-                    .1
-                    .state()
                     .get_block_hash_range(
                         (range.lower_bound + epoch_lower_bound).into(),
                         (range.upper_bound + epoch_lower_bound + 1).into(),
                     )
+                    .await
                     .iter()
                     .map(|(hash, index)| (*hash, u32::from(*index) - epoch_lower_bound))
                     .collect(),
@@ -1266,11 +1603,8 @@ impl Query {
     ) -> FieldResult<Option<Block>> {
         Ok(context
             .db
-            .get_main_tip()
-            .await
-            .1
-            .state()
             .find_block_by_chain_length(length.try_into()?)
+            .await
             .map(Block::from_valid_hash))
     }
 
@@ -1308,11 +1642,8 @@ impl Query {
                     let b = range.upper_bound.checked_add(1).unwrap().into();
                     context
                         .db
-                        .get_main_tip()
-                        .await
-                        .1
-                        .state()
                         .get_block_hash_range(a, b)
+                        .await
                         .iter_mut()
                         .map(|(hash, chain_length)| (*hash, u32::from(*chain_length)))
                         .collect()
@@ -1332,8 +1663,8 @@ impl Query {
         Epoch::from_epoch_number(id)
     }
 
-    fn address(bech32: String) -> FieldResult<Address> {
-        Address::from_bech32(&bech32)
+    fn address(bech32: String, context: &Context) -> FieldResult<Address> {
+        Address::from_bech32(&bech32, &context.settings)
     }
 
     pub async fn stake_pool(id: PoolId, context: &Context) -> FieldResult<Pool> {
@@ -1463,17 +1794,151 @@ impl Query {
     }
 }
 
+type EventStream<T> = Pin<Box<dyn Stream<Item = Result<T, FieldError>> + Send>>;
+
+pub struct Subscription;
+
+#[juniper::graphql_subscription(
+    Context = Context,
+)]
+impl Subscription {
+    /// the new tips of the main branch
+    async fn tip(context: &Context) -> EventStream<Block> {
+        Box::pin(new_tips(context).map(|block| Ok(Block::from(block))))
+    }
+
+    /// the blocks as they are indexed, including the ones of the branches
+    /// which may not end up in the main branch
+    async fn new_block(context: &Context) -> EventStream<Block> {
+        Box::pin(new_blocks(context).map(|block| Ok(Block::from(block))))
+    }
+
+    /// the transactions of the new blocks with an input or an output with
+    /// the given address
+    async fn transactions_by_address(
+        address: String,
+        context: &Context,
+    ) -> FieldResult<EventStream<Transaction>> {
+        let address = Address::from_bech32(&address, &context.settings)?.id;
+        Ok(Box::pin(
+            new_transactions(context, move |tx| {
+                tx.inputs.iter().any(|input| input.address == address)
+                    || tx.outputs.iter().any(|output| output.address == address)
+            })
+            .map(Ok),
+        ))
+    }
+
+    /// the transactions of the new blocks casting a vote on a proposal of
+    /// the given vote plan
+    async fn vote_cast(
+        vote_plan_id: VotePlanId,
+        context: &Context,
+    ) -> FieldResult<EventStream<Transaction>> {
+        let vote_plan_id = certificate::VotePlanId::from_str(&vote_plan_id.0)?;
+        Ok(Box::pin(
+            new_transactions(context, move |tx| match &tx.certificate {
+                Some(certificate::Certificate::VoteCast(vote_cast)) => {
+                    vote_cast.vote_plan() == &vote_plan_id
+                }
+                _ => false,
+            })
+            .map(Ok),
+        ))
+    }
+
+    /// the epochs entered by the main branch, when its new tip is in a later
+    /// epoch than the previous one
+    async fn epoch_transition(context: &Context) -> EventStream<Epoch> {
+        let mut current_epoch = latest_block(context)
+            .await
+            .ok()
+            .map(|block| block.date.epoch);
+        Box::pin(new_tips(context).filter_map(move |block| {
+            let epoch = block.date.epoch;
+            let transition = current_epoch.map_or(false, |current| epoch > current);
+            current_epoch = Some(epoch);
+            future::ready(if transition {
+                Some(Ok(Epoch { id: epoch }))
+            } else {
+                None
+            })
+        }))
+    }
+}
+
+/// the events published after the subscription, skipping the ones missed
+/// because of a slow client
+fn events(context: &Context) -> impl Stream<Item = ExplorerEvent> + Send + 'static {
+    stream::unfold(context.events.subscribe(), |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::debug!(skipped, "explorer subscription lagging behind")
+                }
+                Err(broadcast::error::RecvError::Closed) => return None,
+            }
+        }
+    })
+}
+
+fn new_blocks(context: &Context) -> impl Stream<Item = Arc<ExplorerBlock>> + Send + 'static {
+    events(context).filter_map(|event| {
+        future::ready(match event {
+            ExplorerEvent::NewBlock(block) => Some(block),
+            ExplorerEvent::NewTip(_) => None,
+        })
+    })
+}
+
+fn new_tips(context: &Context) -> impl Stream<Item = Arc<ExplorerBlock>> + Send + 'static {
+    events(context).filter_map(|event| {
+        future::ready(match event {
+            ExplorerEvent::NewTip(block) => Some(block),
+            ExplorerEvent::NewBlock(_) => None,
+        })
+    })
+}
+
+fn new_transactions<F>(
+    context: &Context,
+    filter: F,
+) -> impl Stream<Item = Transaction> + Send + 'static
+where
+    F: Fn(&ExplorerTransaction) -> bool + Send + 'static,
+{
+    new_blocks(context).flat_map(move |block| {
+        let mut transactions: Vec<&ExplorerTransaction> = block
+            .transactions
+            .values()
+            .filter(|&tx| filter(tx))
+            .collect();
+        transactions.sort_unstable_by_key(|tx| tx.offset_in_block);
+        let transactions: Vec<Transaction> = transactions
+            .into_iter()
+            .map(|tx| Transaction {
+                id: tx.id,
+                block_hash: Some(block.id),
+                contents: Some(tx.clone()),
+            })
+            .collect();
+        stream::iter(transactions)
+    })
+}
+
 pub struct Context {
     pub db: ExplorerDB,
+    pub events: broadcast::Sender<ExplorerEvent>,
     pub settings: Settings,
 }
 
 impl juniper::Context for Context {}
 
-pub type Schema = RootNode<'static, Query, EmptyMutation<Context>, EmptySubscription<Context>>;
+pub type Schema = RootNode<'static, Query, EmptyMutation<Context>, Subscription>;
 
 pub fn create_schema() -> Schema {
-    Schema::new(Query {}, EmptyMutation::new(), EmptySubscription::new())
+    Schema::new(Query {}, EmptyMutation::new(), Subscription {})
 }
 
 async fn latest_block(context: &Context) -> FieldResult<Arc<ExplorerBlock>> {
@@ -1485,3 +1950,221 @@ async fn latest_block(context: &Context) -> FieldResult<Arc<ExplorerBlock>> {
     .ok_or_else(|| ErrorKind::InternalError("tip is not in explorer".to_owned()))
     .map_err(Into::into)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockchain::MAIN_BRANCH_TAG;
+    use crate::explorer::tests as explorer;
+    use chain_addr::{AddressReadable, Discrimination, Kind};
+    use chain_crypto::{Ed25519, KeyPair};
+
+    fn bech32(prefix: &str, discrimination: Discrimination) -> String {
+        let key_pair = KeyPair::<Ed25519>::generate(&mut rand::thread_rng());
+        let address =
+            chain_addr::Address(discrimination, Kind::Account(key_pair.public_key().clone()));
+        AddressReadable::from_address(prefix, &address).to_string()
+    }
+
+    #[test]
+    fn default_address_prefix() {
+        let production = Settings::new(Discrimination::Production, None);
+        assert_eq!(production.address_bech32_prefix, "addr");
+        let test = Settings::new(Discrimination::Test, None);
+        assert_eq!(test.address_bech32_prefix, "ta");
+        let custom = Settings::new(Discrimination::Test, Some("custom".to_owned()));
+        assert_eq!(custom.address_bech32_prefix, "custom");
+    }
+
+    #[test]
+    fn address_parsing() {
+        let production = Settings::new(Discrimination::Production, None);
+        let test = Settings::new(Discrimination::Test, None);
+
+        assert!(
+            Address::from_bech32(&bech32("addr", Discrimination::Production), &production).is_ok()
+        );
+        assert!(Address::from_bech32(&bech32("ta", Discrimination::Test), &test).is_ok());
+
+        // any prefix, the configured one is only used in the responses
+        assert!(
+            Address::from_bech32(&bech32("ta", Discrimination::Production), &production).is_ok()
+        );
+        assert!(Address::from_bech32(&bech32("custom", Discrimination::Test), &test).is_ok());
+        // the discrimination of the other chain
+        assert!(Address::from_bech32(&bech32("ta", Discrimination::Production), &test).is_err());
+        assert!(Address::from_bech32(&bech32("addr", Discrimination::Test), &production).is_err());
+        assert!(Address::from_bech32("not an address", &test).is_err());
+    }
+
+    /// the explorer of a chain with an empty block in each of the epochs 0 and
+    /// 1, followed by payments of 1 to 5 to the account in the epoch 1
+    async fn context() -> (Context, Vec<blockcfg::Block>) {
+        let block0 = explorer::block0();
+        let (blockchain, tip) = explorer::blockchain(&block0).await;
+        let storage = blockchain.storage().clone();
+        let mut blocks = vec![block0.clone()];
+        for &(epoch, slot_id) in [(0, 1), (1, 0), (1, 1), (1, 2), (1, 3), (1, 4), (1, 5)].iter() {
+            let date = blockcfg::BlockDate { epoch, slot_id };
+            // the ledgers of the blocks around the epoch boundary are replayed
+            // to read the rewards, the payments are only valid for the explorer
+            let fragments = if epoch == 1 && slot_id > 0 {
+                vec![explorer::payment(u64::from(slot_id))]
+            } else {
+                Vec::new()
+            };
+            let block = explorer::block_at(&storage, blocks.last().unwrap(), date, fragments);
+            blocks.push(block);
+        }
+        storage
+            .put_tag(MAIN_BRANCH_TAG, blocks.last().unwrap().header.hash())
+            .unwrap();
+
+        let db = ExplorerDB::bootstrap(block0, &blockchain, tip, None)
+            .await
+            .unwrap();
+        let (events, _) = broadcast::channel(1);
+        let context = Context {
+            db,
+            events,
+            settings: Settings::new(Discrimination::Test, None),
+        };
+        (context, blocks)
+    }
+
+    fn account_bech32() -> String {
+        match explorer::account() {
+            ExplorerAddress::New(address) => {
+                AddressReadable::from_address("ta", &address).to_string()
+            }
+            ExplorerAddress::Old(_) => unreachable!(),
+        }
+    }
+
+    async fn query(context: &Context, query: &str) -> serde_json::Value {
+        let (value, errors) = juniper::execute(
+            query,
+            None,
+            &create_schema(),
+            &juniper::Variables::new(),
+            context,
+        )
+        .await
+        .unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        serde_json::to_value(&value).unwrap()
+    }
+
+    /// the cursors and the balances of the page of ledger entries, and whether
+    /// there are pages after and before it
+    async fn ledger_entries(
+        context: &Context,
+        arguments: &str,
+    ) -> (Vec<(String, String)>, bool, bool) {
+        let value = query(
+            context,
+            &format!(
+                r#"{{ address(bech32: "{}") {{ ledgerEntries{} {{
+                    totalCount
+                    pageInfo {{ hasNextPage hasPreviousPage }}
+                    edges {{ cursor node {{ balance }} }}
+                }} }} }}"#,
+                account_bech32(),
+                arguments
+            ),
+        )
+        .await;
+        let connection = &value["address"]["ledgerEntries"];
+        assert_eq!(connection["totalCount"], "5");
+        let edges = connection["edges"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|edge| {
+                (
+                    edge["cursor"].as_str().unwrap().to_owned(),
+                    edge["node"]["balance"].as_str().unwrap().to_owned(),
+                )
+            })
+            .collect();
+        let page_info = &connection["pageInfo"];
+        (
+            edges,
+            page_info["hasNextPage"].as_bool().unwrap(),
+            page_info["hasPreviousPage"].as_bool().unwrap(),
+        )
+    }
+
+    /// the cursors and the balances of the ledger entries in the range
+    fn entries(range: std::ops::Range<usize>) -> Vec<(String, String)> {
+        const BALANCES: [u64; 5] = [1, 3, 6, 10, 15];
+        range
+            .map(|i| (i.to_string(), BALANCES[i].to_string()))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn ledger_entries_pagination() {
+        let (context, _) = context().await;
+
+        assert_eq!(
+            ledger_entries(&context, "").await,
+            (entries(0..5), false, false)
+        );
+        assert_eq!(
+            ledger_entries(&context, "(first: 2)").await,
+            (entries(0..2), true, false)
+        );
+        assert_eq!(
+            ledger_entries(&context, "(last: 2)").await,
+            (entries(3..5), false, true)
+        );
+        assert_eq!(
+            ledger_entries(&context, r#"(first: 2, after: "2")"#).await,
+            (entries(3..5), false, true)
+        );
+        assert_eq!(
+            ledger_entries(&context, r#"(last: 2, before: "2")"#).await,
+            (entries(0..2), true, false)
+        );
+    }
+
+    #[tokio::test]
+    async fn state_is_the_one_after_the_last_entry() {
+        let (context, blocks) = context().await;
+
+        // no entry up to the first payment, then one for each of them
+        let balances = [0, 0, 0, 1, 3, 6, 10, 15];
+        for (block, balance) in blocks.iter().zip(balances.iter()) {
+            let value = query(
+                &context,
+                &format!(
+                    r#"{{ address(bech32: "{}") {{ stateAtBlock(block: "{}") {{ balance }} }} }}"#,
+                    account_bech32(),
+                    block.header.hash()
+                ),
+            )
+            .await;
+            assert_eq!(
+                value["address"]["stateAtBlock"]["balance"],
+                balance.to_string()
+            );
+        }
+
+        for (epoch, balance) in [(0, 0), (1, 15), (2, 15)].iter() {
+            let value = query(
+                &context,
+                &format!(
+                    r#"{{ address(bech32: "{}") {{ stateAtEpoch(epoch: "{}") {{ balance }} }} }}"#,
+                    account_bech32(),
+                    epoch
+                ),
+            )
+            .await;
+            assert_eq!(
+                value["address"]["stateAtEpoch"]["balance"],
+                balance.to_string()
+            );
+        }
+    }
+}
@@ -55,12 +55,24 @@ pub enum PayloadType {
 #[derive(Clone, juniper::GraphQLScalarValue)]
 pub struct Weight(pub String);
 
+#[derive(Clone, juniper::GraphQLEnum)]
+pub enum Discrimination {
+    Production,
+    Test,
+}
+
 #[derive(juniper::GraphQLScalarValue)]
 pub struct VotePlanCount(pub String);
 
 #[derive(juniper::GraphQLScalarValue)]
 pub struct VoteStatusCount(pub String);
 
+#[derive(juniper::GraphQLScalarValue)]
+pub struct SpendingCounter(pub String);
+
+#[derive(juniper::GraphQLScalarValue)]
+pub struct LedgerEntryCount(pub String);
+
 /// Vote option range
 ///
 /// provide a range of available choices for a given proposal. Usual value would
@@ -173,6 +185,21 @@ impl From<u64> for TransactionCount {
     }
 }
 
+impl From<chain_addr::Discrimination> for Discrimination {
+    fn from(discrimination: chain_addr::Discrimination) -> Discrimination {
+        match discrimination {
+            chain_addr::Discrimination::Production => Discrimination::Production,
+            chain_addr::Discrimination::Test => Discrimination::Test,
+        }
+    }
+}
+
+impl From<u64> for LedgerEntryCount {
+    fn from(n: u64) -> LedgerEntryCount {
+        LedgerEntryCount(format!("{}", n))
+    }
+}
+
 impl From<u64> for PoolCount {
     fn from(n: u64) -> PoolCount {
         PoolCount(format!("{}", n))
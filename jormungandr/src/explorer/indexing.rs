@@ -1,4 +1,5 @@
 use super::persistent_sequence::PersistentSequence;
+use super::stable::{self, StableIndex};
 use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 
@@ -7,15 +8,16 @@ use cardano_legacy_address::Addr as OldAddress;
 use chain_addr::{Address, Discrimination};
 use chain_core::property::Block as _;
 use chain_core::property::Fragment as _;
+use chain_impl_mockchain::account::DelegationType;
 use chain_impl_mockchain::block::Proof;
 use chain_impl_mockchain::certificate::{
-    Certificate, ExternalProposalId, PoolId, PoolRegistration, PoolRetirement, VotePlanId,
+    Certificate, ExternalProposalId, PoolId, PoolRegistration, PoolRetirement, VotePlan, VotePlanId,
 };
 use chain_impl_mockchain::key::BftLeaderId;
 use chain_impl_mockchain::transaction::{InputEnum, TransactionSlice, Witness};
 use chain_impl_mockchain::value::Value;
 use chain_impl_mockchain::vote::{
-    Choice, EncryptedVote, Options, PayloadType, ProofOfCorrectVote, Weight,
+    Choice, EncryptedVote, Options, Payload, PayloadType, ProofOfCorrectVote, Weight,
 };
 use std::{convert::TryInto, sync::Arc};
 
@@ -26,6 +28,8 @@ pub type Blocks = Hamt<HeaderHash, ExplorerBlock>;
 pub type ChainLengths = Hamt<ChainLength, HeaderHash>;
 
 pub type Addresses = Hamt<ExplorerAddress, PersistentSequence<FragmentId>>;
+pub type AddressStates = Hamt<ExplorerAddress, ExplorerAddressState>;
+pub type AddressLedgerEntries = Hamt<ExplorerAddress, PersistentSequence<ExplorerLedgerEntry>>;
 pub type Epochs = Hamt<Epoch, EpochData>;
 
 pub type StakePoolBlocks = Hamt<PoolId, PersistentSequence<HeaderHash>>;
@@ -75,6 +79,8 @@ pub struct ExplorerTransaction {
 pub struct ExplorerInput {
     pub address: ExplorerAddress,
     pub value: Value,
+    /// The transaction and the index of the spent output, for the utxo inputs
+    pub utxo_pointer: Option<(FragmentId, u8)>,
 }
 
 #[derive(Clone)]
@@ -96,6 +102,56 @@ pub enum ExplorerAddress {
     Old(OldAddress),
 }
 
+/// Funds and stake of an address after a block. The utxos are the ones sent to
+/// the address, the other fields are only set for the account addresses
+#[derive(Clone)]
+pub struct ExplorerAddressState {
+    /// Value of the utxos and of the account
+    pub balance: Value,
+    pub utxos: Hamt<(FragmentId, u8), Value>,
+    /// `None` if the account never delegated its stake
+    pub delegation: Option<DelegationType>,
+    /// Number of account inputs spent from the account
+    pub spending_counter: u32,
+    /// Total of the rewards received by the account
+    pub rewards: Value,
+    /// The epoch at the start of which the account last received rewards,
+    /// and their value
+    pub last_rewards: Option<(Epoch, Value)>,
+}
+
+/// Change of the funds or of the delegation of an address, by a transaction or
+/// by the rewards received at the start of an epoch
+#[derive(Clone)]
+pub struct ExplorerLedgerEntry {
+    pub block: HeaderHash,
+    pub chain_length: ChainLength,
+    pub date: BlockDate,
+    /// `None` for the rewards
+    pub transaction: Option<FragmentId>,
+    pub credit: Value,
+    pub debit: Value,
+    /// Fee of the transaction, if the address is one of its inputs
+    pub fee: Value,
+    /// Balance of the address after the entry
+    pub balance: Value,
+    /// Delegation of the account after the entry
+    pub delegation: Option<DelegationType>,
+}
+
+impl Default for ExplorerAddressState {
+    fn default() -> Self {
+        ExplorerAddressState {
+            balance: Value::zero(),
+            utxos: Hamt::new(),
+            delegation: None,
+            spending_counter: 0,
+            rewards: Value::zero(),
+            last_rewards: None,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct ExplorerVotePlan {
     pub id: VotePlanId,
@@ -140,6 +196,9 @@ pub struct ExplorerBlockBuildingContext<'a> {
     pub discrimination: Discrimination,
     pub prev_transactions: &'a Transactions,
     pub prev_blocks: &'a Blocks,
+    /// Used to resolve the utxo inputs spending the outputs of the transactions
+    /// no longer in `prev_transactions` as their blocks were persisted
+    pub stable_index: &'a StableIndex,
 }
 
 impl ExplorerBlock {
@@ -380,7 +439,11 @@ impl ExplorerTransaction {
                             .into(),
                     );
                     let address = ExplorerAddress::New(Address(context.discrimination, kind));
-                    Some(ExplorerInput { address, value })
+                    Some(ExplorerInput {
+                        address,
+                        value,
+                        utxo_pointer: None,
+                    })
                 }
                 (InputEnum::AccountInput(id, value), Witness::Multisig(_)) => {
                     let kind = chain_addr::Kind::Multisig(
@@ -390,7 +453,11 @@ impl ExplorerTransaction {
                             .expect("multisig identifier size doesn't match address kind"),
                     );
                     let address = ExplorerAddress::New(Address(context.discrimination, kind));
-                    Some(ExplorerInput { address, value })
+                    Some(ExplorerInput {
+                        address,
+                        value,
+                        utxo_pointer: None,
+                    })
                 }
                 (InputEnum::UtxoInput(utxo_pointer), _witness) => {
                     let tx = utxo_pointer.transaction_id;
@@ -400,21 +467,24 @@ impl ExplorerTransaction {
                         .prev_transactions
                         .lookup(&tx)
                         .and_then(|block_id| {
-                            context
-                                .prev_blocks
-                                .lookup(&block_id)
-                                .map(|block| &block.transactions[&tx].outputs[index as usize])
+                            context.prev_blocks.lookup(&block_id).map(|block| {
+                                block.transactions[&tx].outputs[index as usize].clone()
+                            })
                         })
                         .or_else(|| {
                             transactions_in_current_block
                                 .get(&tx)
-                                .map(|fragment| &fragment.outputs[index as usize])
+                                .map(|fragment| fragment.outputs[index as usize].clone())
+                        })
+                        .or_else(|| {
+                            stable::logged(context.stable_index.transaction_output(&tx, index))
                         })
                         .expect("transaction not found for utxo input");
 
                     Some(ExplorerInput {
-                        address: output.address.clone(),
+                        address: output.address,
                         value: output.value,
+                        utxo_pointer: Some((tx, index)),
                     })
                 }
                 _ => None,
@@ -442,3 +512,40 @@ impl ExplorerTransaction {
         &self.outputs
     }
 }
+
+impl From<&VotePlan> for ExplorerVotePlan {
+    fn from(vote_plan: &VotePlan) -> Self {
+        ExplorerVotePlan {
+            id: vote_plan.to_id(),
+            vote_start: vote_plan.vote_start(),
+            vote_end: vote_plan.vote_end(),
+            committee_end: vote_plan.committee_end(),
+            payload_type: vote_plan.payload_type(),
+            proposals: vote_plan
+                .proposals()
+                .iter()
+                .map(|proposal| ExplorerVoteProposal {
+                    proposal_id: proposal.external_id().clone(),
+                    options: proposal.options().clone(),
+                    tally: None,
+                    votes: Default::default(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<&Payload> for ExplorerVote {
+    fn from(payload: &Payload) -> Self {
+        match payload {
+            Payload::Public { choice } => ExplorerVote::Public(*choice),
+            Payload::Private {
+                proof,
+                encrypted_vote,
+            } => ExplorerVote::Private {
+                proof: proof.clone(),
+                encrypted_vote: encrypted_vote.clone(),
+            },
+        }
+    }
+}
@@ -3,16 +3,18 @@ pub mod graphql;
 mod indexing;
 mod multiverse;
 mod persistent_sequence;
+mod stable;
 
 use self::error::{ExplorerError as Error, Result};
 pub use self::graphql::create_schema;
 use self::graphql::Context;
 use self::indexing::{
-    Addresses, Blocks, ChainLengths, EpochData, Epochs, ExplorerAddress, ExplorerBlock,
-    ExplorerVotePlan, ExplorerVoteProposal, ExplorerVoteTally, StakePool, StakePoolBlocks,
-    StakePoolData, Transactions, VotePlans,
+    AddressLedgerEntries, AddressStates, Addresses, Blocks, ChainLengths, EpochData, Epochs,
+    ExplorerAddress, ExplorerAddressState, ExplorerBlock, ExplorerLedgerEntry, ExplorerVotePlan,
+    ExplorerVoteTally, StakePool, StakePoolBlocks, StakePoolData, Transactions, VotePlans,
 };
 use self::persistent_sequence::PersistentSequence;
+use self::stable::{Sequence, StableIndex};
 use tracing::{span, Level};
 use tracing_futures::Instrument;
 
@@ -25,22 +27,37 @@ use crate::explorer::indexing::ExplorerVote;
 use crate::intercom::ExplorerMsg;
 use crate::utils::async_msg::MessageQueue;
 use crate::utils::task::TokioServiceInfo;
-use chain_addr::Discrimination;
+use chain_addr::{Address, Discrimination, Kind};
 use chain_core::property::Block as _;
 use chain_impl_mockchain::certificate::{Certificate, PoolId, VotePlanId};
 use chain_impl_mockchain::fee::LinearFee;
+use chain_impl_mockchain::value::Value;
 use futures::prelude::*;
 use multiverse::Multiverse;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::Infallible;
-use std::sync::{
-    atomic::{AtomicU32, Ordering},
-    Arc,
-};
-use tokio::sync::{Mutex, RwLock};
+use std::path::Path;
+use std::sync::{atomic::Ordering, Arc};
+use tokio::sync::{broadcast, Mutex, RwLock};
+
+/// number of events kept for the GraphQL subscriptions lagging behind, the
+/// older ones are skipped
+const EVENTS_CAPACITY: usize = 256;
 
 #[derive(Clone)]
 pub struct Explorer {
     pub db: ExplorerDB,
+    events: broadcast::Sender<ExplorerEvent>,
+    settings: Settings,
+}
+
+/// Events of the indexing, streamed to the GraphQL subscriptions
+#[derive(Clone)]
+pub enum ExplorerEvent {
+    /// A block was indexed, whether or not it is in the main branch
+    NewBlock(Arc<ExplorerBlock>),
+    /// The tip of the main branch changed to the given block
+    NewTip(Arc<ExplorerBlock>),
 }
 
 #[derive(Clone)]
@@ -59,12 +76,17 @@ pub struct ExplorerDB {
     pub blockchain_config: BlockchainConfig,
     blockchain: Blockchain,
     blockchain_tip: blockchain::Tip,
+    /// Indices of the confirmed blocks moved out of the multiverse, the states only
+    /// hold the blocks after the last of them
     stable_store: StableIndex,
-}
-
-#[derive(Clone)]
-pub struct StableIndex {
-    confirmed_block_chain_length: Arc<AtomicU32>,
+    /// Held for reading while a block is indexed or the indices are queried, and
+    /// for writing while the confirmed blocks are moved to the stable store, so
+    /// no block is seen both in the stable store and in the multiverse or in
+    /// neither of them
+    indices_lock: Arc<RwLock<()>>,
+    /// The last ledger state replayed by the blockchain to read the rewards of an
+    /// epoch, the next replay starts from it instead of an older cached block
+    replayed: Arc<Mutex<Option<Arc<blockchain::Ref>>>>,
 }
 
 #[derive(Clone)]
@@ -80,11 +102,17 @@ pub struct BlockchainConfig {
 /// Inmutable data structure used to represent the explorer's state at a given Block
 /// A new state can be obtained to from a Block and it's previous state, getting two
 /// independent states but with memory sharing to minimize resource utilization
+///
+/// The transactions, blocks, addresses, ledger entries, chain lengths and blocks of
+/// the stake pools only hold the blocks after the last one of the stable store, the
+/// other indices are complete.
 #[derive(Clone)]
 pub(self) struct State {
     pub transactions: Transactions,
     pub blocks: Blocks,
     addresses: Addresses,
+    address_states: AddressStates,
+    ledger_entries: AddressLedgerEntries,
     epochs: Epochs,
     chain_lengths: ChainLengths,
     stake_pool_data: StakePool,
@@ -99,20 +127,42 @@ pub struct Settings {
     /// also be used, but the `Address` struct doesn't have a deserialization method right
     /// now
     pub address_bech32_prefix: String,
+    /// The discrimination of the addresses of the chain, set in block0
+    pub discrimination: Discrimination,
+}
+
+impl Settings {
+    /// the settings of the chain with the given discrimination, the prefix
+    /// defaults to `addr` for the production discrimination and to `ta` for
+    /// the test one
+    pub fn new(discrimination: Discrimination, address_bech32_prefix: Option<String>) -> Self {
+        let address_bech32_prefix = address_bech32_prefix.unwrap_or_else(|| match discrimination {
+            Discrimination::Production => "addr".to_owned(),
+            Discrimination::Test => "ta".to_owned(),
+        });
+        Settings {
+            address_bech32_prefix,
+            discrimination,
+        }
+    }
 }
 
 impl Explorer {
-    pub fn new(db: ExplorerDB) -> Explorer {
-        Explorer { db }
+    pub fn new(db: ExplorerDB, address_bech32_prefix: Option<String>) -> Explorer {
+        let (events, _) = broadcast::channel(EVENTS_CAPACITY);
+        let settings = Settings::new(db.blockchain_config.discrimination, address_bech32_prefix);
+        Explorer {
+            db,
+            events,
+            settings,
+        }
     }
 
     pub fn context(&self) -> Context {
         Context {
             db: self.db.clone(),
-            settings: Settings {
-                // Hardcoded bech32 prefix
-                address_bech32_prefix: "addr".to_owned(),
-            },
+            events: self.events.clone(),
+            settings: self.settings.clone(),
         }
     }
 
@@ -122,18 +172,25 @@ impl Explorer {
         messages
             .for_each(|input| {
                 let explorer_db = self.db.clone();
+                let events = self.events.clone();
                 let tip_candidate = Arc::clone(&tip_candidate);
                 match input {
                     ExplorerMsg::NewBlock(block) => {
                         info.spawn_fallible::<_, Error>(
                             "apply block to explorer",
                             async move {
-                                let _state_ref = explorer_db.apply_block(block.clone()).await?;
+                                let state_ref = explorer_db.apply_block(block.clone()).await?;
+                                if let Some(block) = state_ref.state().blocks.lookup(&block.id()) {
+                                    // sending fails only when nobody is subscribed
+                                    let _ = events.send(ExplorerEvent::NewBlock(Arc::clone(block)));
+                                }
 
                                 let mut guard = tip_candidate.lock().await;
                                 if guard.map(|hash| hash == block.header.id()).unwrap_or(false) {
                                     let hash = guard.take().unwrap();
-                                    explorer_db.set_tip(hash).await;
+                                    if explorer_db.set_tip(hash).await? {
+                                        explorer_db.publish_tip(&events, &hash).await;
+                                    }
                                 }
 
                                 Ok(())
@@ -149,9 +206,11 @@ impl Explorer {
                         info.spawn_fallible::<_, Error>(
                             "apply tip to explorer",
                             async move {
-                                let successful = explorer_db.set_tip(hash).await;
+                                let successful = explorer_db.set_tip(hash).await?;
 
-                                if !successful {
+                                if successful {
+                                    explorer_db.publish_tip(&events, &hash).await;
+                                } else {
                                     let mut guard = tip_candidate.lock().await;
                                     guard.replace(hash);
                                 }
@@ -176,11 +235,15 @@ impl Explorer {
 impl ExplorerDB {
     /// Apply all the blocks in the [block0, MAIN_BRANCH_TAG], also extract the static
     /// Blockchain settings from the Block0 (Discrimination)
+    /// The blocks already in the stable store (kept in `store_dir`, or in a temporary
+    /// directory if `None`) are not applied again, the indexing resumes from the last
+    /// of them.
     /// This function is only called once on the node's bootstrap phase
     pub async fn bootstrap(
         block0: Block,
         blockchain: &Blockchain,
         blockchain_tip: blockchain::Tip,
+        store_dir: Option<&Path>,
     ) -> Result<Self> {
         let blockchain_config = BlockchainConfig::from_config_params(
             block0
@@ -194,43 +257,84 @@ impl ExplorerDB {
                 .expect("the Initial fragment to be present in the genesis block"),
         );
 
-        let block = ExplorerBlock::resolve_from(
-            &block0,
-            indexing::ExplorerBlockBuildingContext {
-                discrimination: blockchain_config.discrimination,
-                prev_transactions: &Transactions::new(),
-                prev_blocks: &Blocks::new(),
-            },
-        );
-
-        let blocks = apply_block_to_blocks(Blocks::new(), &block)?;
-        let epochs = apply_block_to_epochs(Epochs::new(), &block);
-        let chain_lengths = apply_block_to_chain_lengths(ChainLengths::new(), &block)?;
-        let transactions = apply_block_to_transactions(Transactions::new(), &block)?;
-        let addresses = apply_block_to_addresses(Addresses::new(), &block);
-        let (stake_pool_data, stake_pool_blocks) =
-            apply_block_to_stake_pools(StakePool::new(), StakePoolBlocks::new(), &block);
-        let vote_plans = apply_block_to_vote_plans(VotePlans::new(), &blockchain_tip, &block);
-
-        let initial_state = State {
-            transactions,
-            blocks,
-            epochs,
-            chain_lengths,
-            addresses,
-            stake_pool_data,
-            stake_pool_blocks,
-            vote_plans,
+        let stable_store = StableIndex::open(
+            store_dir,
+            blockchain.storage().clone(),
+            blockchain_config.discrimination,
+            block0.id(),
+        )?;
+
+        let (root_chain_length, root_id, initial_state) = match stable_store.tip()? {
+            Some((chain_length, hash)) => {
+                tracing::info!(
+                    "resuming the explorer indexing after block {} at chain length {}",
+                    hash,
+                    chain_length
+                );
+                let initial_state = State {
+                    transactions: Transactions::new(),
+                    blocks: Blocks::new(),
+                    epochs: stable_store.load_epochs()?,
+                    chain_lengths: ChainLengths::new(),
+                    addresses: Addresses::new(),
+                    address_states: stable_store.load_address_states()?,
+                    ledger_entries: AddressLedgerEntries::new(),
+                    stake_pool_data: stable_store.load_stake_pools()?,
+                    stake_pool_blocks: StakePoolBlocks::new(),
+                    vote_plans: stable_store.load_vote_plans()?,
+                };
+                (chain_length, hash, initial_state)
+            }
+            None => {
+                let block = ExplorerBlock::resolve_from(
+                    &block0,
+                    indexing::ExplorerBlockBuildingContext {
+                        discrimination: blockchain_config.discrimination,
+                        prev_transactions: &Transactions::new(),
+                        prev_blocks: &Blocks::new(),
+                        stable_index: &stable_store,
+                    },
+                );
+
+                let blocks = apply_block_to_blocks(Blocks::new(), &block)?;
+                let epochs = apply_block_to_epochs(Epochs::new(), &block);
+                let chain_lengths = apply_block_to_chain_lengths(ChainLengths::new(), &block)?;
+                let transactions = apply_block_to_transactions(Transactions::new(), &block)?;
+                let addresses = apply_block_to_addresses(Addresses::new(), &block);
+                let (address_states, ledger_entries) = apply_block_to_address_states(
+                    AddressStates::new(),
+                    AddressLedgerEntries::new(),
+                    &block,
+                    blockchain_config.discrimination,
+                    &[],
+                )?;
+                let stake_pool_data = apply_block_to_stake_pool_data(StakePool::new(), &block);
+                let stake_pool_blocks =
+                    apply_block_to_stake_pool_blocks(StakePoolBlocks::new(), &block);
+                let vote_plans =
+                    apply_block_to_vote_plans(VotePlans::new(), &blockchain_tip, &block);
+
+                let initial_state = State {
+                    transactions,
+                    blocks,
+                    epochs,
+                    chain_lengths,
+                    addresses,
+                    address_states,
+                    ledger_entries,
+                    stake_pool_data,
+                    stake_pool_blocks,
+                    vote_plans,
+                };
+                (block0.chain_length(), block0.id(), initial_state)
+            }
         };
 
-        let block0_id = block0.id();
-        let (_, multiverse) = Multiverse::new(block0.chain_length(), block0_id, initial_state);
-
-        let block0_id = block0.id();
+        let (_, multiverse) = Multiverse::new(root_chain_length, root_id, initial_state);
 
         let maybe_head = blockchain.storage().get_tag(MAIN_BRANCH_TAG)?;
         let (stream, hash) = match maybe_head {
-            Some(head) => (blockchain.storage().stream_from_to(block0_id, head)?, head),
+            Some(head) => (blockchain.storage().stream_from_to(root_id, head)?, head),
             None => {
                 return Err(Error::BootstrapError(
                     "Couldn't read the HEAD tag from storage".to_owned(),
@@ -244,29 +348,36 @@ impl ExplorerDB {
             blockchain_config,
             blockchain: blockchain.clone(),
             blockchain_tip,
-            stable_store: StableIndex {
-                confirmed_block_chain_length: Arc::new(AtomicU32::default()),
-            },
+            stable_store,
+            indices_lock: Arc::new(RwLock::new(())),
+            replayed: Arc::new(Mutex::new(None)),
         };
 
         let db = stream
             .map_err(Error::from)
             .try_fold(bootstraped_db, |db, block| async move {
-                db.apply_block(block).await?;
+                db.apply_block(block.clone()).await?;
+                db.persist_confirmed_blocks(block.id()).await?;
                 Ok(db)
             })
             .await?;
 
+        let stable_chain_length = db.stable_store.tip()?.map(|(chain_length, _)| chain_length);
         for branch in blockchain.branches().branches().await.iter() {
             let mut hash = branch.hash();
             let mut blocks = vec![];
             loop {
-                if db.get_block(&hash).await.is_some() {
+                if db.multiverse.get_ref(&hash).await.is_some() {
                     break;
                 }
                 let block = blockchain.storage().get(hash)?.ok_or_else(|| {
                     Error::BootstrapError(format!("couldn't get block {} from the storage", hash))
                 })?;
+                if stable_chain_length.map_or(false, |stable| block.chain_length() <= stable) {
+                    // the branch forked before the last persisted block
+                    blocks.clear();
+                    break;
+                }
                 hash = block.header.block_parent_hash();
                 blocks.push(block);
             }
@@ -284,6 +395,10 @@ impl ExplorerDB {
     /// This doesn't perform any validation on the given block and the previous state, it
     /// is assumed that the Block is valid
     async fn apply_block(&self, block: Block) -> Result<multiverse::Ref> {
+        let rewards = self.epoch_rewards(&block).await?;
+
+        let _indices = self.indices_lock.read().await;
+
         let previous_block = block.header.block_parent_hash();
         let chain_length = block.header.chain_length();
         let block_id = block.header.hash();
@@ -294,72 +409,133 @@ impl ExplorerDB {
             .get_ref(&previous_block)
             .await
             .ok_or_else(|| Error::AncestorNotFound(block.id()))?;
-        let State {
-            transactions,
-            blocks,
-            addresses,
-            epochs,
-            chain_lengths,
-            stake_pool_data,
-            stake_pool_blocks,
-            vote_plans,
-        } = previous_state.state().clone();
+        let previous_state = previous_state.state();
 
         let explorer_block = ExplorerBlock::resolve_from(
             &block,
             indexing::ExplorerBlockBuildingContext {
                 discrimination,
-                prev_transactions: &transactions,
-                prev_blocks: &blocks,
+                prev_transactions: &previous_state.transactions,
+                prev_blocks: &previous_state.blocks,
+                stable_index: &self.stable_store,
             },
         );
-        let (stake_pool_data, stake_pool_blocks) =
-            apply_block_to_stake_pools(stake_pool_data, stake_pool_blocks, &explorer_block);
+
+        let state = previous_state.with_block(
+            &explorer_block,
+            apply_block_to_epochs(previous_state.epochs.clone(), &explorer_block),
+            apply_block_to_stake_pool_data(previous_state.stake_pool_data.clone(), &explorer_block),
+            apply_block_to_vote_plans(
+                previous_state.vote_plans.clone(),
+                &self.blockchain_tip,
+                &explorer_block,
+            ),
+            apply_block_to_address_states(
+                previous_state.address_states.clone(),
+                previous_state.ledger_entries.clone(),
+                &explorer_block,
+                discrimination,
+                &rewards,
+            )?,
+        )?;
 
         let state_ref = multiverse
-            .insert(
-                chain_length,
-                block.parent_id(),
-                block_id,
-                State {
-                    transactions: apply_block_to_transactions(transactions, &explorer_block)?,
-                    blocks: apply_block_to_blocks(blocks, &explorer_block)?,
-                    addresses: apply_block_to_addresses(addresses, &explorer_block),
-                    epochs: apply_block_to_epochs(epochs, &explorer_block),
-                    chain_lengths: apply_block_to_chain_lengths(chain_lengths, &explorer_block)?,
-                    stake_pool_data,
-                    stake_pool_blocks,
-                    vote_plans: apply_block_to_vote_plans(
-                        vote_plans,
-                        &self.blockchain_tip,
-                        &explorer_block,
-                    ),
-                },
-            )
+            .insert(chain_length, block.parent_id(), block_id, state)
             .await;
 
         Ok(state_ref)
     }
 
+    /// The rewards received by the accounts at the start of the epoch of the given
+    /// block, if it is the first block of its epoch. They are not applied by the
+    /// fragments so they are read from the ledgers of the block and of its parent,
+    /// which are replayed from the storage if the blockchain no longer keeps them
+    /// in memory.
+    async fn epoch_rewards(&self, block: &Block) -> Result<Vec<(ExplorerAddress, Value)>> {
+        let parent_hash = block.header.block_parent_hash();
+        let parent_date = match self.blockchain.get_ref(parent_hash).await? {
+            Some(parent) => parent.block_date(),
+            None => self
+                .blockchain
+                .storage()
+                .get(parent_hash)?
+                .ok_or_else(|| Error::AncestorNotFound(block.id()))?
+                .header
+                .block_date(),
+        };
+        if parent_date.epoch == block.header.block_date().epoch {
+            return Ok(Vec::new());
+        }
+
+        let parent = self.replay_ref(parent_hash).await?;
+        let current = self.replay_ref(block.header.hash()).await?;
+
+        let (parent_ledger, ledger) = (parent.ledger(), current.ledger());
+        Ok(ledger
+            .accounts()
+            .iter()
+            .filter_map(|(id, account)| {
+                let rewards = &account.last_rewards;
+                let received = match parent_ledger.accounts().get_state(id) {
+                    Ok(previous) => {
+                        previous.last_rewards.epoch != rewards.epoch
+                            || previous.last_rewards.reward != rewards.reward
+                    }
+                    Err(_) => true,
+                };
+                if !received || rewards.reward == Value::zero() {
+                    return None;
+                }
+                let kind = Kind::Account(id.clone().into());
+                let address = Address(self.blockchain_config.discrimination, kind);
+                Some((ExplorerAddress::New(address), rewards.reward))
+            })
+            .collect())
+    }
+
+    /// the ledger state after the given block, replayed by the blockchain from
+    /// the last replayed one if it is no longer in memory
+    async fn replay_ref(&self, hash: HeaderHash) -> Result<Arc<blockchain::Ref>> {
+        let mut replayed = self.replayed.lock().await;
+        let state_ref = self
+            .blockchain
+            .replay_ref(hash, replayed.clone())
+            .await?
+            .ok_or(Error::LedgerUnavailable(hash))?;
+        *replayed = Some(Arc::clone(&state_ref));
+        Ok(state_ref)
+    }
+
     pub async fn get_block(&self, block_id: &HeaderHash) -> Option<Arc<ExplorerBlock>> {
+        let _indices = self.indices_lock.read().await;
+
         for (_hash, state_ref) in self.multiverse.tips().await.iter() {
             if let Some(b) = state_ref.state().blocks.lookup(&block_id) {
                 return Some(Arc::clone(b));
             }
         }
 
-        None
+        stable::logged(self.stable_store.get_block(block_id))
+    }
+
+    async fn publish_tip(&self, events: &broadcast::Sender<ExplorerEvent>, hash: &HeaderHash) {
+        if let Some(block) = self.get_block(hash).await {
+            let _ = events.send(ExplorerEvent::NewTip(block));
+        }
     }
 
-    pub(self) async fn set_tip(&self, hash: HeaderHash) -> bool {
+    pub(self) async fn set_tip(&self, hash: HeaderHash) -> Result<bool> {
         // the tip changes which means now a block is confirmed (at least after
         // the initial epoch_stability_depth blocks).
 
         let block = if let Some(state_ref) = self.multiverse.get_ref(&hash).await {
             let state = state_ref.state();
-            Arc::clone(state.blocks.lookup(&hash).unwrap())
+            match state.blocks.lookup(&hash) {
+                Some(block) => Arc::clone(block),
+                None => return Ok(false),
+            }
         } else {
-            return false;
+            return Ok(false);
         };
 
         if let Some(confirmed_block_chain_length) = block
@@ -378,6 +554,8 @@ impl ExplorerDB {
                 .confirmed_block_chain_length
                 .store(confirmed_block_chain_length.into(), Ordering::Release);
 
+            self.persist_confirmed_blocks(hash).await?;
+
             self.multiverse
                 .gc(self.blockchain_config.epoch_stability_depth)
                 .await;
@@ -387,7 +565,140 @@ impl ExplorerDB {
 
         *guard = hash;
 
-        true
+        Ok(true)
+    }
+
+    /// Move the blocks confirmed by the given tip to the stable store once there are
+    /// `epoch_stability_depth` of them (or at least one), then rebuild the multiverse
+    /// on top of the last of them with the blocks of the branches forking after it
+    async fn persist_confirmed_blocks(&self, tip: HeaderHash) -> Result<()> {
+        let depth = self.blockchain_config.epoch_stability_depth;
+        let tip_ref = match self.multiverse.get_ref(&tip).await {
+            Some(tip_ref) => tip_ref,
+            None => return Ok(()),
+        };
+        let confirmed = match tip_ref
+            .state()
+            .blocks
+            .lookup(&tip)
+            .and_then(|block| block.chain_length().nth_ancestor(depth))
+        {
+            Some(confirmed) => u32::from(confirmed),
+            None => return Ok(()),
+        };
+        let next = |stable_tip: Option<(ChainLength, HeaderHash)>| {
+            stable_tip.map_or(0, |(chain_length, _)| u32::from(chain_length) + 1)
+        };
+        if confirmed < next(self.stable_store.tip()?) + depth.max(1) - 1 {
+            return Ok(());
+        }
+
+        let _indices = self.indices_lock.write().await;
+
+        // checked again as another tip may have been processed in the meantime
+        let first = next(self.stable_store.tip()?);
+        if confirmed < first + depth.max(1) - 1 {
+            return Ok(());
+        }
+
+        let tip_state = tip_ref.state();
+        let blocks = (first..=confirmed)
+            .map(|chain_length| {
+                tip_state
+                    .chain_lengths
+                    .lookup(&chain_length.into())
+                    .and_then(|hash| tip_state.blocks.lookup(hash))
+                    .cloned()
+                    .ok_or_else(|| Error::BlockNotFound(tip))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let stable_block = Arc::clone(blocks.last().unwrap());
+        let stable_state = self
+            .multiverse
+            .get_ref(&stable_block.id())
+            .await
+            .ok_or_else(|| Error::BlockNotFound(stable_block.id()))?;
+
+        self.stable_store
+            .persist(
+                &blocks,
+                &stable_state.state().epochs,
+                &stable_state.state().vote_plans,
+                &stable_state.state().address_states,
+                &stable_state.state().ledger_entries,
+            )
+            .await?;
+
+        // the states of the blocks after the stable one, which still hold the
+        // indices of the blocks now in the stable store
+        let mut unstable = BTreeMap::new();
+        for (hash, _) in self.multiverse.tips().await {
+            let mut hash = hash;
+            let mut branch = Vec::new();
+            while let Some(state_ref) = self.multiverse.get_ref(&hash).await {
+                if hash == stable_block.id() {
+                    unstable.extend(branch);
+                    break;
+                }
+                let block = match state_ref.state().blocks.lookup(&hash) {
+                    Some(block) if block.chain_length() > stable_block.chain_length() => {
+                        Arc::clone(block)
+                    }
+                    // the branch forked before the stable block
+                    _ => break,
+                };
+                hash = block.parent_hash;
+                branch.push(((block.chain_length(), block.id()), (block, state_ref)));
+            }
+        }
+
+        let stable_state = stable_state.state();
+        self.multiverse
+            .reset(
+                stable_block.chain_length(),
+                stable_block.id(),
+                State {
+                    transactions: Transactions::new(),
+                    blocks: Blocks::new(),
+                    addresses: Addresses::new(),
+                    address_states: stable_state.address_states.clone(),
+                    ledger_entries: AddressLedgerEntries::new(),
+                    epochs: stable_state.epochs.clone(),
+                    chain_lengths: ChainLengths::new(),
+                    stake_pool_data: stable_state.stake_pool_data.clone(),
+                    stake_pool_blocks: StakePoolBlocks::new(),
+                    vote_plans: stable_state.vote_plans.clone(),
+                },
+            )
+            .await;
+
+        for ((chain_length, hash), (block, state_ref)) in unstable {
+            let previous_state = match self.multiverse.get_ref(&block.parent_hash).await {
+                Some(previous_state) => previous_state,
+                None => continue,
+            };
+            let state = state_ref.state();
+            let state = previous_state.state().with_block(
+                &block,
+                state.epochs.clone(),
+                state.stake_pool_data.clone(),
+                state.vote_plans.clone(),
+                (
+                    state.address_states.clone(),
+                    ledger_entries_after(&state.ledger_entries, stable_block.chain_length()),
+                ),
+            )?;
+            self.multiverse
+                .insert(chain_length, block.parent_hash, hash, state)
+                .await;
+        }
+
+        tracing::debug!(
+            "explorer indices persisted up to block {} at chain length {}",
+            stable_block.id(),
+            stable_block.chain_length()
+        );
+        Ok(())
     }
 
     pub async fn get_epoch(&self, epoch: Epoch) -> Option<EpochData> {
@@ -402,6 +713,8 @@ impl ExplorerDB {
     }
 
     pub async fn is_block_confirmed(&self, block_id: &HeaderHash) -> bool {
+        let _indices = self.indices_lock.read().await;
+
         let current_branch = self
             .multiverse
             .get_ref(&self.longest_chain_tip.get_block_id().await)
@@ -416,15 +729,15 @@ impl ExplorerDB {
                 .into();
             block.chain_length <= confirmed_block_chain_length
         } else {
-            false
+            // the blocks of the stable store are all confirmed
+            stable::logged(self.stable_store.block_chain_length(block_id)).is_some()
         }
     }
 
-    pub async fn get_stake_pool_blocks(
-        &self,
-        pool: &PoolId,
-    ) -> Option<Arc<PersistentSequence<HeaderHash>>> {
+    pub async fn get_stake_pool_blocks(&self, pool: &PoolId) -> Option<Sequence<HeaderHash>> {
+        let _indices = self.indices_lock.read().await;
         let pool = pool.clone();
+        let tips = self.multiverse.tips().await;
 
         // this is a tricky query, one option would be to take a hash and return
         // only the blocks from a particular branch, but it's not like a stake
@@ -434,13 +747,21 @@ impl ExplorerDB {
         // for the time being, this query uses the maximum, because the branch
         // that has more blocks from this particular stake pool has all the
         // blocks produced by it
-        self.multiverse
-            .tips()
-            .await
+        let unstable = tips
             .iter()
             .filter_map(|(_hash, state_ref)| state_ref.state().stake_pool_blocks.lookup(&pool))
             .max_by_key(|seq| seq.len())
-            .map(Arc::clone)
+            .map(Arc::clone);
+        let registered = tips
+            .iter()
+            .any(|(_hash, state_ref)| state_ref.state().stake_pool_data.lookup(&pool).is_some());
+
+        let blocks = self.stable_store.stake_pool_blocks(&pool, unstable);
+        if registered || !blocks.is_empty() {
+            Some(blocks)
+        } else {
+            None
+        }
     }
 
     pub async fn get_stake_pool_data(&self, pool: &PoolId) -> Option<Arc<StakePoolData>> {
@@ -468,6 +789,111 @@ impl ExplorerDB {
         None
     }
 
+    pub async fn transactions_by_address(&self, address: &ExplorerAddress) -> Sequence<FragmentId> {
+        let _indices = self.indices_lock.read().await;
+        let (_, state_ref) = self.get_main_tip().await;
+
+        let unstable = state_ref.state().addresses.lookup(address).map(Arc::clone);
+        self.stable_store.transactions_by_address(address, unstable)
+    }
+
+    /// the balance, utxos and delegation of the address in the main branch,
+    /// `None` if it never received or sent anything
+    pub async fn get_address_state(
+        &self,
+        address: &ExplorerAddress,
+    ) -> Option<Arc<ExplorerAddressState>> {
+        let (_, state_ref) = self.get_main_tip().await;
+        state_ref
+            .state()
+            .address_states
+            .lookup(address)
+            .map(Arc::clone)
+    }
+
+    /// the ledger entries of the address in the main branch, in the order of
+    /// the blocks
+    pub async fn ledger_entries(&self, address: &ExplorerAddress) -> Sequence<ExplorerLedgerEntry> {
+        let _indices = self.indices_lock.read().await;
+        let (_, state_ref) = self.get_main_tip().await;
+
+        let unstable = state_ref
+            .state()
+            .ledger_entries
+            .lookup(address)
+            .map(Arc::clone);
+        self.stable_store.ledger_entries(address, unstable)
+    }
+
+    // Get the hashes of all blocks in the range [from, to)
+    // the ChainLength is returned to for easy of use in the case where
+    // `to` is greater than the max
+    pub async fn get_block_hash_range(
+        &self,
+        from: ChainLength,
+        to: ChainLength,
+    ) -> Vec<(HeaderHash, ChainLength)> {
+        let _indices = self.indices_lock.read().await;
+        let (_, state_ref) = self.get_main_tip().await;
+        let from = u32::from(from);
+        let to = u32::from(to);
+
+        (from..to)
+            .filter_map(|i| {
+                self.find_block_in_branch(state_ref.state(), i.into())
+                    .map(|hash| (hash, i.into()))
+            })
+            .collect()
+    }
+
+    pub async fn find_block_by_chain_length(
+        &self,
+        chain_length: ChainLength,
+    ) -> Option<HeaderHash> {
+        let _indices = self.indices_lock.read().await;
+        let (_, state_ref) = self.get_main_tip().await;
+
+        self.find_block_in_branch(state_ref.state(), chain_length)
+    }
+
+    pub async fn find_block_hash_by_transaction(
+        &self,
+        transaction_id: &FragmentId,
+    ) -> Option<HeaderHash> {
+        let _indices = self.indices_lock.read().await;
+        let (_, state_ref) = self.get_main_tip().await;
+
+        state_ref
+            .state()
+            .transactions
+            .lookup(&transaction_id)
+            .map(|id| *id.as_ref())
+            .or_else(|| {
+                stable::logged(
+                    self.stable_store
+                        .find_block_hash_by_transaction(transaction_id),
+                )
+            })
+    }
+
+    fn find_block_in_branch(&self, state: &State, chain_length: ChainLength) -> Option<HeaderHash> {
+        state
+            .chain_lengths
+            .lookup(&chain_length)
+            .map(|b| *b.as_ref())
+            .or_else(|| stable::logged(self.stable_store.find_block_by_chain_length(chain_length)))
+    }
+
+    /// chain length of the latest block indexed by the explorer
+    #[cfg(feature = "prometheus")]
+    pub async fn tip_chain_length(&self) -> ChainLength {
+        let hash = self.longest_chain_tip.get_block_id().await;
+        self.get_block(&hash)
+            .await
+            .expect("the explorer tip to be indexed")
+            .chain_length()
+    }
+
     pub(self) async fn get_main_tip(&self) -> (HeaderHash, multiverse::Ref) {
         let hash = self.longest_chain_tip.get_block_id().await;
         (hash, self.multiverse.get_ref(&hash).await.unwrap())
@@ -531,6 +957,215 @@ fn apply_block_to_addresses(mut addresses: Addresses, block: &ExplorerBlock) ->
     addresses
 }
 
+/// Apply the rewards received at the start of the epoch, before the block, and
+/// then the transactions of the block in order to the states of their addresses,
+/// adding a ledger entry to the addresses for each of them. Fails if a balance
+/// goes below zero or overflows, which means the indices miss funds of the address
+fn apply_block_to_address_states(
+    mut address_states: AddressStates,
+    mut ledger_entries: AddressLedgerEntries,
+    block: &ExplorerBlock,
+    discrimination: Discrimination,
+    rewards: &[(ExplorerAddress, Value)],
+) -> Result<(AddressStates, AddressLedgerEntries)> {
+    let epoch = block.date().epoch;
+    let block_id = block.id();
+    let checked = |value: Option<u64>| value.map(Value).ok_or(Error::BalanceOutOfBounds(block_id));
+    let entry = |transaction, credit, debit, fee| ExplorerLedgerEntry {
+        block: block.id(),
+        chain_length: block.chain_length(),
+        date: block.date(),
+        transaction,
+        credit,
+        debit,
+        fee,
+        balance: Value::zero(),
+        delegation: None,
+    };
+
+    for (address, reward) in rewards {
+        address_states = update_address_state(address_states, address, |state| {
+            state.balance = checked(state.balance.0.checked_add(reward.0))?;
+            state.rewards = checked(state.rewards.0.checked_add(reward.0))?;
+            state.last_rewards = Some((epoch, *reward));
+            Ok(())
+        })?;
+        let entry = entry(None, *reward, Value::zero(), Value::zero());
+        ledger_entries = append_ledger_entry(ledger_entries, &address_states, address, entry);
+    }
+
+    let mut transactions: Vec<_> = block.transactions.values().collect();
+    transactions.sort_unstable_by_key(|tx| tx.offset_in_block);
+
+    for tx in transactions {
+        // the credit and the debit of each address of the transaction
+        let mut changes: HashMap<ExplorerAddress, (Value, Value)> = HashMap::new();
+
+        for input in tx.inputs() {
+            address_states = update_address_state(address_states, &input.address, |state| {
+                state.balance = checked(state.balance.0.checked_sub(input.value.0))?;
+                match &input.utxo_pointer {
+                    Some(utxo_pointer) => {
+                        if let Ok(utxos) = state.utxos.remove(utxo_pointer) {
+                            state.utxos = utxos;
+                        }
+                    }
+                    None => state.spending_counter += 1,
+                }
+                Ok(())
+            })?;
+            let (_, debit) = changes
+                .entry(input.address.clone())
+                .or_insert((Value::zero(), Value::zero()));
+            *debit = checked(debit.0.checked_add(input.value.0))?;
+        }
+
+        for (index, output) in tx.outputs().iter().enumerate() {
+            address_states = update_address_state(address_states, &output.address, |state| {
+                state.balance = checked(state.balance.0.checked_add(output.value.0))?;
+                if !is_account(&output.address) {
+                    let utxo_pointer = (tx.id(), index as u8);
+                    state.utxos = state.utxos.insert_or_update_simple(
+                        utxo_pointer,
+                        Arc::new(output.value),
+                        |_| None,
+                    );
+                }
+                Ok(())
+            })?;
+            let (credit, _) = changes
+                .entry(output.address.clone())
+                .or_insert((Value::zero(), Value::zero()));
+            *credit = checked(credit.0.checked_add(output.value.0))?;
+        }
+
+        let delegation = match &tx.certificate {
+            Some(Certificate::StakeDelegation(delegation)) => {
+                delegation.account_id.to_single_account().map(|account| {
+                    let address = Address(discrimination, Kind::Account(account.into()));
+                    (
+                        ExplorerAddress::New(address),
+                        delegation.get_delegation_type().clone(),
+                    )
+                })
+            }
+            Some(Certificate::OwnerStakeDelegation(delegation)) => {
+                tx.inputs().first().map(|input| {
+                    (
+                        input.address.clone(),
+                        delegation.get_delegation_type().clone(),
+                    )
+                })
+            }
+            _ => None,
+        };
+        if let Some((address, delegation)) = delegation {
+            address_states = update_address_state(address_states, &address, |state| {
+                state.delegation = Some(delegation);
+                Ok(())
+            })?;
+            changes
+                .entry(address)
+                .or_insert((Value::zero(), Value::zero()));
+        }
+
+        // the transactions without inputs, as the ones of block0, have no fee
+        let fee = if tx.inputs().is_empty() {
+            Value::zero()
+        } else {
+            let total_input = checked(
+                tx.inputs()
+                    .iter()
+                    .try_fold(0u64, |total, input| total.checked_add(input.value.0)),
+            )?;
+            let total_output = checked(
+                tx.outputs()
+                    .iter()
+                    .try_fold(0u64, |total, output| total.checked_add(output.value.0)),
+            )?;
+            checked(total_input.0.checked_sub(total_output.0))?
+        };
+        for (address, (credit, debit)) in changes {
+            let spent = tx.inputs().iter().any(|input| input.address == address);
+            let fee = if spent { fee } else { Value::zero() };
+            let entry = entry(Some(tx.id()), credit, debit, fee);
+            ledger_entries = append_ledger_entry(ledger_entries, &address_states, &address, entry);
+        }
+    }
+
+    Ok((address_states, ledger_entries))
+}
+
+/// append the entry to the ledger entries of the address, with the balance and
+/// the delegation of the given state of the address
+fn append_ledger_entry(
+    ledger_entries: AddressLedgerEntries,
+    address_states: &AddressStates,
+    address: &ExplorerAddress,
+    mut entry: ExplorerLedgerEntry,
+) -> AddressLedgerEntries {
+    if let Some(state) = address_states.lookup(address) {
+        entry.balance = state.balance;
+        entry.delegation = state.delegation.clone();
+    }
+    ledger_entries.insert_or_update_simple(
+        address.clone(),
+        Arc::new(PersistentSequence::new().append(entry.clone())),
+        |entries| Some(Arc::new(entries.append(entry))),
+    )
+}
+
+/// the ledger entries of the blocks after the given chain length
+fn ledger_entries_after(
+    ledger_entries: &AddressLedgerEntries,
+    chain_length: ChainLength,
+) -> AddressLedgerEntries {
+    let mut retained = AddressLedgerEntries::new();
+    for (address, entries) in ledger_entries.iter() {
+        let mut sequence = PersistentSequence::new();
+        for i in 0..entries.len() {
+            if let Some(entry) = entries
+                .get(i)
+                .filter(|entry| entry.chain_length > chain_length)
+            {
+                sequence = sequence.append(ExplorerLedgerEntry::clone(entry));
+            }
+        }
+        if sequence.len() > 0 {
+            retained = retained
+                .insert(address.clone(), Arc::new(sequence))
+                .unwrap();
+        }
+    }
+    retained
+}
+
+fn update_address_state(
+    address_states: AddressStates,
+    address: &ExplorerAddress,
+    update: impl FnOnce(&mut ExplorerAddressState) -> Result<()>,
+) -> Result<AddressStates> {
+    let mut state = address_states
+        .lookup(address)
+        .map(|state| ExplorerAddressState::clone(state))
+        .unwrap_or_default();
+    update(&mut state)?;
+    let state = Arc::new(state);
+    Ok(
+        address_states
+            .insert_or_update_simple(address.clone(), Arc::clone(&state), |_| Some(state)),
+    )
+}
+
+fn is_account(address: &ExplorerAddress) -> bool {
+    match address {
+        ExplorerAddress::New(address) => {
+            matches!(address.kind(), Kind::Account(_) | Kind::Multisig(_))
+        }
+        ExplorerAddress::Old(_) => false,
+    }
+}
+
 fn apply_block_to_epochs(epochs: Epochs, block: &ExplorerBlock) -> Epochs {
     let epoch_id = block.date().epoch;
     let block_id = block.id();
@@ -566,34 +1201,36 @@ fn apply_block_to_chain_lengths(
         })
 }
 
-fn apply_block_to_stake_pools(
-    data: StakePool,
+fn apply_block_to_stake_pool_blocks(
     blocks: StakePoolBlocks,
     block: &ExplorerBlock,
-) -> (StakePool, StakePoolBlocks) {
+) -> StakePoolBlocks {
     let mut blocks = match &block.producer() {
-        indexing::BlockProducer::StakePool(id) => blocks
-            .update(
-                &id,
-                |array: &Arc<PersistentSequence<HeaderHash>>| -> std::result::Result<_, Infallible> {
-                    Ok(Some(Arc::new(array.append(block.id()))))
-                },
-            )
-            .expect("block to be created by registered stake pool"),
+        indexing::BlockProducer::StakePool(id) => blocks.insert_or_update_simple(
+            id.clone(),
+            Arc::new(PersistentSequence::new().append(block.id())),
+            |array| Some(Arc::new(array.append(block.id()))),
+        ),
         indexing::BlockProducer::BftLeader(_) => blocks,
         indexing::BlockProducer::None => blocks,
     };
 
+    for tx in block.transactions.values() {
+        if let Some(Certificate::PoolRegistration(registration)) = &tx.certificate {
+            blocks = blocks
+                .insert(registration.to_id(), Arc::new(PersistentSequence::new()))
+                .expect("pool was registered more than once");
+        }
+    }
+
+    blocks
+}
+
+fn apply_block_to_stake_pool_data(data: StakePool, block: &ExplorerBlock) -> StakePool {
     let mut data = data;
 
     for tx in block.transactions.values() {
         if let Some(cert) = &tx.certificate {
-            blocks = match cert {
-                Certificate::PoolRegistration(registration) => blocks
-                    .insert(registration.to_id(), Arc::new(PersistentSequence::new()))
-                    .expect("pool was registered more than once"),
-                _ => blocks,
-            };
             data = match cert {
                 Certificate::PoolRegistration(registration) => data
                     .insert(
@@ -619,7 +1256,7 @@ fn apply_block_to_stake_pools(
         }
     }
 
-    (data, blocks)
+    data
 }
 
 fn apply_block_to_vote_plans(
@@ -633,23 +1270,7 @@ fn apply_block_to_vote_plans(
                 Certificate::VotePlan(vote_plan) => vote_plans
                     .insert(
                         vote_plan.to_id(),
-                        Arc::new(ExplorerVotePlan {
-                            id: vote_plan.to_id(),
-                            vote_start: vote_plan.vote_start(),
-                            vote_end: vote_plan.vote_end(),
-                            committee_end: vote_plan.committee_end(),
-                            payload_type: vote_plan.payload_type(),
-                            proposals: vote_plan
-                                .proposals()
-                                .iter()
-                                .map(|proposal| ExplorerVoteProposal {
-                                    proposal_id: proposal.external_id().clone(),
-                                    options: proposal.options().clone(),
-                                    tally: None,
-                                    votes: Default::default(),
-                                })
-                                .collect(),
-                        }),
+                        Arc::new(ExplorerVotePlan::from(vote_plan)),
                     )
                     .unwrap(),
                 Certificate::VoteCast(vote_cast) => {
@@ -824,6 +1445,34 @@ impl Tip {
 }
 
 impl State {
+    /// the state after the given block, with the epochs, stake pools, vote
+    /// plans, states and ledger entries of the addresses already updated with
+    /// the block
+    fn with_block(
+        &self,
+        block: &ExplorerBlock,
+        epochs: Epochs,
+        stake_pool_data: StakePool,
+        vote_plans: VotePlans,
+        (address_states, ledger_entries): (AddressStates, AddressLedgerEntries),
+    ) -> Result<State> {
+        Ok(State {
+            transactions: apply_block_to_transactions(self.transactions.clone(), block)?,
+            blocks: apply_block_to_blocks(self.blocks.clone(), block)?,
+            addresses: apply_block_to_addresses(self.addresses.clone(), block),
+            address_states,
+            ledger_entries,
+            epochs,
+            chain_lengths: apply_block_to_chain_lengths(self.chain_lengths.clone(), block)?,
+            stake_pool_data,
+            stake_pool_blocks: apply_block_to_stake_pool_blocks(
+                self.stake_pool_blocks.clone(),
+                block,
+            ),
+            vote_plans,
+        })
+    }
+
     pub fn get_vote_plans(&self) -> Vec<(VotePlanId, Arc<ExplorerVotePlan>)> {
         self.vote_plans
             .iter()
@@ -837,48 +1486,424 @@ impl State {
             .map(|(k, v)| (k.clone(), v.clone()))
             .collect()
     }
+}
 
-    pub fn transactions_by_address(
-        &self,
-        address: &ExplorerAddress,
-    ) -> Option<PersistentSequence<FragmentId>> {
-        self.addresses
-            .lookup(address)
-            .map(|txs| PersistentSequence::clone(txs))
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockcfg::{BlockDate, BlockVersion, ContentsBuilder, HeaderBuilderNew};
+    use crate::blockchain::{LedgerSnapshots, Storage};
+    use chain_core::property::{ChainLength as _, Fragment as _};
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{
+        account,
+        key::BftLeaderId,
+        transaction::{Input, Output, TxBuilder},
+    };
+    use jormungandr_lib::interfaces::{
+        Block0Configuration, BlockchainConfiguration, ConsensusLeaderId,
+    };
+    use std::path::PathBuf;
+    use tracing::Span;
+
+    const EPOCH_STABILITY_DEPTH: u32 = 2;
+
+    pub(super) fn block0() -> Block {
+        let leader = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let mut blockchain_configuration = BlockchainConfiguration::new(
+            Discrimination::Test,
+            ConsensusVersion::Bft,
+            LinearFee::new(1, 2, 3),
+        );
+        blockchain_configuration.epoch_stability_depth = EPOCH_STABILITY_DEPTH.into();
+        blockchain_configuration.consensus_leader_ids =
+            vec![ConsensusLeaderId(BftLeaderId::from(leader.to_public()))];
+        Block0Configuration {
+            blockchain_configuration,
+            initial: Vec::new(),
+        }
+        .to_block()
     }
 
-    // Get the hashes of all blocks in the range [from, to)
-    // the ChainLength is returned to for easy of use in the case where
-    // `to` is greater than the max
-    pub fn get_block_hash_range(
-        &self,
-        from: ChainLength,
-        to: ChainLength,
-    ) -> Vec<(HeaderHash, ChainLength)> {
-        let from = u32::from(from);
-        let to = u32::from(to);
+    pub(super) fn account() -> ExplorerAddress {
+        let key = SecretKey::<Ed25519>::from_binary(&[2; 32]).unwrap();
+        ExplorerAddress::New(Address(
+            Discrimination::Test,
+            Kind::Account(key.to_public()),
+        ))
+    }
 
-        (from..to)
-            .filter_map(|i| {
-                self.chain_lengths
-                    .lookup(&i.into())
-                    .map(|b| (*b.as_ref(), i.into()))
-            })
-            .collect()
+    /// a transaction spending from the account, without witness as the
+    /// explorer does not validate the blocks
+    fn spending(value: u64) -> Fragment {
+        let key = SecretKey::<Ed25519>::from_binary(&[2; 32]).unwrap();
+        let input =
+            Input::from_account_single(account::Identifier::from(key.to_public()), Value(value));
+        let tx = TxBuilder::new()
+            .set_nopayload()
+            .set_ios(&[input], &[])
+            .set_witnesses_unchecked(&[])
+            .set_payload_auth(&());
+        Fragment::Transaction(tx)
     }
 
-    pub fn find_block_by_chain_length(&self, chain_length: ChainLength) -> Option<HeaderHash> {
-        self.chain_lengths
-            .lookup(&chain_length)
-            .map(|b| *b.as_ref())
+    /// a transaction crediting the account, without inputs as the explorer
+    /// does not validate the blocks
+    pub(super) fn payment(value: u64) -> Fragment {
+        let address = match account() {
+            ExplorerAddress::New(address) => address,
+            ExplorerAddress::Old(_) => unreachable!(),
+        };
+        let tx = TxBuilder::new()
+            .set_nopayload()
+            .set_ios(&[], &[Output::from_address(address, Value(value))])
+            .set_witnesses(&[])
+            .set_payload_auth(&());
+        Fragment::Transaction(tx)
     }
 
-    pub fn find_block_hash_by_transaction(
-        &self,
-        transaction_id: &FragmentId,
-    ) -> Option<HeaderHash> {
-        self.transactions
-            .lookup(&transaction_id)
-            .map(|id| *id.as_ref())
+    /// the blocks on top of `parent`, each holding a payment of its slot to
+    /// the account. The `slot_offset` gives different blocks and payments at
+    /// the same chain length for the forks.
+    fn extend(storage: &Storage, parent: &Block, length: u32, slot_offset: u32) -> Vec<Block> {
+        let mut blocks: Vec<Block> = Vec::new();
+        for _ in 0..length {
+            let parent = blocks.last().unwrap_or(parent);
+            let slot_id = parent.header.block_date().slot_id + 1 + slot_offset;
+            let date = BlockDate { epoch: 0, slot_id };
+            let block = block_at(storage, parent, date, vec![payment(u64::from(slot_id))]);
+            blocks.push(block);
+        }
+        blocks
+    }
+
+    /// the block on top of `parent` at the given date, holding the fragments
+    pub(super) fn block_at(
+        storage: &Storage,
+        parent: &Block,
+        date: BlockDate,
+        fragments: Vec<Fragment>,
+    ) -> Block {
+        let mut contents = ContentsBuilder::new();
+        for fragment in fragments {
+            contents.push(fragment);
+        }
+        let contents = contents.into();
+        let header = HeaderBuilderNew::new(BlockVersion::Genesis, &contents)
+            .set_parent(&parent.header.hash(), parent.header.chain_length().next())
+            .set_date(date)
+            .into_unsigned_header()
+            .unwrap()
+            .generalize();
+        let block = Block { header, contents };
+        storage.put_block(&block).unwrap();
+        block
+    }
+
+    pub(super) async fn blockchain(block0: &Block) -> (Blockchain, blockchain::Tip) {
+        let blockchain = Blockchain::new(
+            block0.header.hash(),
+            Storage::memory(Span::none()).unwrap(),
+            LedgerSnapshots::new(None, 0),
+            1024,
+            false,
+        );
+        let branch = blockchain.load_from_block0(block0.clone()).await.unwrap();
+        (blockchain, blockchain::Tip::new(branch))
+    }
+
+    fn store_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "jormungandr-explorer-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    /// index the block and make it the tip, as the explorer task does
+    async fn apply_tip(db: &ExplorerDB, block: &Block) {
+        db.apply_block(block.clone()).await.unwrap();
+        assert!(db.set_tip(block.id()).await.unwrap());
+    }
+
+    fn stable_chain_length(db: &ExplorerDB) -> u32 {
+        let (chain_length, _) = db.stable_store.tip().unwrap().unwrap();
+        chain_length.into()
+    }
+
+    /// check the queries of the main branch against its blocks, whether they
+    /// are in the stable store or in the multiverse
+    async fn check_main_branch(db: &ExplorerDB, branch: &[&Block]) {
+        let hashes: Vec<HeaderHash> = branch.iter().map(|block| block.id()).collect();
+        let range = db
+            .get_block_hash_range(0.into(), (branch.len() as u32 + 5).into())
+            .await;
+        assert_eq!(
+            range.iter().map(|(hash, _)| *hash).collect::<Vec<_>>(),
+            hashes
+        );
+
+        let mut balance = 0;
+        let transactions = db.transactions_by_address(&account()).await;
+        let ledger_entries = db.ledger_entries(&account()).await;
+        assert_eq!(transactions.len(), branch.len() as u64 - 1);
+        assert_eq!(ledger_entries.len(), branch.len() as u64 - 1);
+
+        for (chain_length, block) in branch.iter().enumerate() {
+            let chain_length = ChainLength::from(chain_length as u32);
+            assert_eq!(
+                db.find_block_by_chain_length(chain_length).await,
+                Some(block.id())
+            );
+            let indexed = db.get_block(&block.id()).await.unwrap();
+            assert_eq!(indexed.chain_length(), chain_length);
+            if u32::from(chain_length) == 0 {
+                continue;
+            }
+
+            let i = u32::from(chain_length) - 1;
+            let fragment = block.contents.iter().next().unwrap();
+            assert_eq!(
+                db.find_block_hash_by_transaction(&fragment.id()).await,
+                Some(block.id())
+            );
+            assert_eq!(transactions.get(i), Some(fragment.id()));
+
+            balance += u64::from(block.header.block_date().slot_id);
+            let entry = ledger_entries.get(i).unwrap();
+            assert_eq!(entry.block, block.id());
+            assert_eq!(entry.transaction, Some(fragment.id()));
+            assert_eq!(
+                entry.credit,
+                Value(u64::from(block.header.block_date().slot_id))
+            );
+            assert_eq!(entry.balance, Value(balance));
+        }
+
+        let state = db.get_address_state(&account()).await.unwrap();
+        assert_eq!(state.balance, Value(balance));
+    }
+
+    #[tokio::test]
+    async fn indexed_blocks_are_persisted_and_resumed() {
+        let dir = store_dir("resume");
+        let block0 = block0();
+        let (blockchain, tip) = blockchain(&block0).await;
+        let storage = blockchain.storage().clone();
+        let blocks = extend(&storage, &block0, 9, 0);
+        storage
+            .put_tag(MAIN_BRANCH_TAG, blocks.last().unwrap().id())
+            .unwrap();
+
+        let db = ExplorerDB::bootstrap(block0.clone(), &blockchain, tip.clone(), Some(&dir))
+            .await
+            .unwrap();
+        // persisted by batches of `EPOCH_STABILITY_DEPTH` blocks, up to the
+        // block confirmed by the tip
+        assert_eq!(stable_chain_length(&db), 7);
+        let branch: Vec<&Block> = std::iter::once(&block0).chain(blocks.iter()).collect();
+        check_main_branch(&db, &branch).await;
+        drop(db);
+
+        let stable_store = StableIndex::open(
+            Some(&dir),
+            storage.clone(),
+            Discrimination::Test,
+            block0.id(),
+        )
+        .unwrap();
+        assert_eq!(
+            stable_store.tip().unwrap(),
+            Some((ChainLength::from(7), blocks[6].id()))
+        );
+        drop(stable_store);
+
+        // the node went on while the explorer was stopped
+        let more_blocks = extend(&storage, blocks.last().unwrap(), 3, 0);
+        storage
+            .put_tag(MAIN_BRANCH_TAG, more_blocks.last().unwrap().id())
+            .unwrap();
+
+        let resumed = ExplorerDB::bootstrap(block0.clone(), &blockchain, tip.clone(), Some(&dir))
+            .await
+            .unwrap();
+        assert_eq!(stable_chain_length(&resumed), 9);
+        let branch: Vec<&Block> = branch.into_iter().chain(more_blocks.iter()).collect();
+        check_main_branch(&resumed, &branch).await;
+
+        // the same as indexing everything again
+        let fresh = ExplorerDB::bootstrap(block0, &blockchain, tip, None)
+            .await
+            .unwrap();
+        assert_eq!(stable_chain_length(&fresh), 9);
+        check_main_branch(&fresh, &branch).await;
+
+        drop(resumed);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn queries_are_the_same_across_the_stable_boundary() {
+        let block0 = block0();
+        let (blockchain, tip) = blockchain(&block0).await;
+        let storage = blockchain.storage().clone();
+        let blocks = extend(&storage, &block0, 9, 0);
+        storage
+            .put_tag(MAIN_BRANCH_TAG, blocks.last().unwrap().id())
+            .unwrap();
+        let db = ExplorerDB::bootstrap(block0.clone(), &blockchain, tip, None)
+            .await
+            .unwrap();
+        assert_eq!(stable_chain_length(&db), 7);
+
+        // a fork from the current tip, which the main branch outgrows
+        let fork = extend(&storage, &blocks[8], 1, 100);
+        let main = extend(&storage, &blocks[8], 2, 0);
+        apply_tip(&db, &fork[0]).await;
+        for block in main.iter() {
+            apply_tip(&db, block).await;
+        }
+        assert_eq!(stable_chain_length(&db), 9);
+        assert!(db.is_block_confirmed(&blocks[8].id()).await);
+        assert!(!db.is_block_confirmed(&main[0].id()).await);
+
+        let main_branch: Vec<&Block> = std::iter::once(&block0)
+            .chain(blocks.iter())
+            .chain(main.iter())
+            .collect();
+        check_main_branch(&db, &main_branch).await;
+
+        // the fork after the stable block is kept and becomes the main branch
+        let fork = [fork, extend(&storage, &fork[0], 2, 100)].concat();
+        for block in fork.iter().skip(1) {
+            apply_tip(&db, block).await;
+        }
+        assert_eq!(stable_chain_length(&db), 9);
+        let fork_branch: Vec<&Block> = std::iter::once(&block0)
+            .chain(blocks.iter())
+            .chain(fork.iter())
+            .collect();
+        check_main_branch(&db, &fork_branch).await;
+        assert!(db
+            .find_block_hash_by_transaction(&main[0].contents.iter().next().unwrap().id())
+            .await
+            .is_none());
+    }
+
+    #[test]
+    fn epoch_rewards_are_applied_before_the_transactions() {
+        let block0 = block0();
+        let storage = Storage::memory(Span::none()).unwrap();
+        let stable_store =
+            StableIndex::open(None, storage.clone(), Discrimination::Test, block0.id()).unwrap();
+        let date = BlockDate {
+            epoch: 1,
+            slot_id: 0,
+        };
+        let block = block_at(&storage, &block0, date, vec![spending(15)]);
+        let block = ExplorerBlock::resolve_from(
+            &block,
+            indexing::ExplorerBlockBuildingContext {
+                discrimination: Discrimination::Test,
+                prev_transactions: &Transactions::new(),
+                prev_blocks: &Blocks::new(),
+                stable_index: &stable_store,
+            },
+        );
+
+        let (address_states, ledger_entries) = apply_block_to_address_states(
+            AddressStates::new(),
+            AddressLedgerEntries::new(),
+            &block,
+            Discrimination::Test,
+            &[(account(), Value(20))],
+        )
+        .unwrap();
+        let state = address_states.lookup(&account()).unwrap();
+        assert_eq!(state.balance, Value(5));
+        assert_eq!(state.rewards, Value(20));
+        assert_eq!(state.last_rewards, Some((1, Value(20))));
+        assert_eq!(state.spending_counter, 1);
+
+        let entries = ledger_entries.lookup(&account()).unwrap();
+        assert_eq!(entries.len(), 2);
+        let rewards = entries.get(0u64).unwrap();
+        assert_eq!(rewards.transaction, None);
+        assert_eq!(rewards.credit, Value(20));
+        assert_eq!(rewards.balance, Value(20));
+        let spent = entries.get(1u64).unwrap();
+        assert!(spent.transaction.is_some());
+        assert_eq!(spent.debit, Value(15));
+        assert_eq!(spent.fee, Value(15));
+        assert_eq!(spent.balance, Value(5));
+
+        // without the rewards the balance would go below zero
+        assert!(matches!(
+            apply_block_to_address_states(
+                AddressStates::new(),
+                AddressLedgerEntries::new(),
+                &block,
+                Discrimination::Test,
+                &[],
+            ),
+            Err(Error::BalanceOutOfBounds(hash)) if hash == block.id()
+        ));
+    }
+
+    #[tokio::test]
+    async fn ledgers_are_replayed_at_the_epoch_boundary() {
+        let block0 = block0();
+        let (blockchain, tip) = blockchain(&block0).await;
+        let storage = blockchain.storage().clone();
+        let last = block_at(
+            &storage,
+            &block0,
+            BlockDate {
+                epoch: 0,
+                slot_id: 1,
+            },
+            Vec::new(),
+        );
+        let first = block_at(
+            &storage,
+            &last,
+            BlockDate {
+                epoch: 1,
+                slot_id: 0,
+            },
+            Vec::new(),
+        );
+        storage.put_tag(MAIN_BRANCH_TAG, first.id()).unwrap();
+        // the blocks are only in the storage, as when indexing them again
+        assert!(blockchain.get_ref(first.id()).await.unwrap().is_none());
+
+        let db = ExplorerDB::bootstrap(block0.clone(), &blockchain, tip.clone(), None)
+            .await
+            .unwrap();
+        let replayed = db.replayed.lock().await.clone().unwrap();
+        assert_eq!(replayed.hash(), first.id());
+        assert_eq!(replayed.block_date(), first.header.block_date());
+        // the leader of a BFT chain does not receive rewards
+        assert!(db.get_address_state(&account()).await.is_none());
+        assert!(blockchain.get_ref(first.id()).await.unwrap().is_none());
+
+        // the indexing fails instead of dropping the rewards if the ledger
+        // cannot be replayed
+        let invalid = block_at(
+            &storage,
+            &last,
+            BlockDate {
+                epoch: 1,
+                slot_id: 1,
+            },
+            vec![payment(1)],
+        );
+        storage.put_tag(MAIN_BRANCH_TAG, invalid.id()).unwrap();
+        assert!(matches!(
+            ExplorerDB::bootstrap(block0, &blockchain, tip, None).await,
+            Err(Error::BlockchainError(_))
+        ));
     }
 }
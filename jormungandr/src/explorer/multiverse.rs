@@ -58,6 +58,20 @@ impl Multiverse {
         guard.multiverse.get_ref(&hash)
     }
 
+    /// drop all the states and start again from the given one, the states of the
+    /// blocks after it have to be inserted again
+    pub(super) async fn reset(&self, chain_length: ChainLength, hash: HeaderHash, value: State) {
+        let mut guard = self.inner.write().await;
+
+        let mut multiverse = MultiverseData::new();
+        multiverse.insert(chain_length, hash, value);
+
+        let mut tips = BTreeSet::new();
+        tips.insert((chain_length, hash));
+
+        *guard = Inner { multiverse, tips };
+    }
+
     /// run the garbage collection of the multiverse
     ///
     pub(super) async fn gc(&self, depth: u32) {
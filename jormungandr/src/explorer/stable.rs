@@ -0,0 +1,1052 @@
+//! On-disk index of the stable part of the chain.
+//!
+//! The blocks buried under `epoch_stability_depth` blocks of the main branch
+//! cannot be rolled back anymore, so once enough of them are confirmed their
+//! indices are moved out of the multiverse to a `sled` database stored next
+//! to the block storage, and the multiverse is rebuilt on top of the last of
+//! them. The explorer then only keeps the unstable tail of the chain in
+//! memory, and resumes indexing from the last persisted block on restart.
+//!
+//! The database does not hold the blocks themselves: it points to the block
+//! storage, from which the persisted blocks are resolved again when queried
+//! (the most recently used ones are cached). The epochs, stake pools, vote
+//! plans and states of the addresses stay whole in the states of the
+//! multiverse, they are persisted to restore the states on restart.
+//!
+//! All the indices share a single tree so a batch of blocks is persisted
+//! atomically, the first byte of the keys telling the index they belong to.
+//! The sequences (the transactions and the ledger entries of an address, the
+//! blocks of a stake pool) store their length under their key and their items
+//! under their key followed by the big-endian index of the item.
+
+use super::error::{ExplorerError as Error, Result};
+use super::indexing::{
+    AddressLedgerEntries, AddressStates, BlockProducer, Blocks, EpochData, Epochs, ExplorerAddress,
+    ExplorerAddressState, ExplorerBlock, ExplorerBlockBuildingContext, ExplorerLedgerEntry,
+    ExplorerOutput, ExplorerVote, ExplorerVotePlan, ExplorerVoteTally, Hamt, StakePool,
+    StakePoolData, Transactions, VotePlans,
+};
+use super::persistent_sequence::PersistentSequence;
+use crate::blockcfg::{BlockDate, ChainLength, Epoch, Fragment, FragmentId, HeaderHash};
+use crate::blockchain::Storage;
+use cardano_legacy_address::Addr as OldAddress;
+use chain_addr::{Address, Discrimination};
+use chain_core::property::{Deserialize, Fragment as _};
+use chain_impl_mockchain::account::{DelegationRatio, DelegationType};
+use chain_impl_mockchain::certificate::{Certificate, PoolId};
+use chain_impl_mockchain::transaction::Transaction;
+use chain_impl_mockchain::value::Value;
+use chain_impl_mockchain::vote::Weight;
+use jormungandr_lib::crypto::hash::Hash;
+use lru::LruCache;
+use std::{
+    collections::{HashMap, HashSet},
+    convert::TryInto,
+    path::Path,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc, Mutex,
+    },
+};
+
+// the first byte of the keys
+const TIP: u8 = 0;
+const BLOCK0: u8 = 1;
+const BLOCK: u8 = 2;
+const CHAIN_LENGTH: u8 = 3;
+const TRANSACTION: u8 = 4;
+const ADDRESS: u8 = 5;
+const STAKE_POOL_BLOCKS: u8 = 6;
+const EPOCH: u8 = 7;
+const STAKE_POOL: u8 = 8;
+const VOTE_PLAN: u8 = 9;
+const VOTE: u8 = 10;
+const TALLY: u8 = 11;
+const ADDRESS_STATE: u8 = 12;
+const LEDGER_ENTRIES: u8 = 13;
+
+const NEW_ADDRESS: u8 = 0;
+const OLD_ADDRESS: u8 = 1;
+
+/// number of resolved persisted blocks kept in memory
+const BLOCK_CACHE_SIZE: usize = 1024;
+
+#[derive(Clone)]
+pub struct StableIndex {
+    pub(super) confirmed_block_chain_length: Arc<AtomicU32>,
+    db: sled::Db,
+    storage: Storage,
+    discrimination: Discrimination,
+    blocks: Arc<Mutex<LruCache<HeaderHash, Arc<ExplorerBlock>>>>,
+}
+
+/// a sequence of which the first items are persisted and the next ones are
+/// in the state of a branch
+#[derive(Clone)]
+pub struct Sequence<T> {
+    index: StableIndex,
+    key: Vec<u8>,
+    stable_len: u64,
+    unstable: Option<Arc<PersistentSequence<T>>>,
+    decode: fn(&[u8]) -> Result<T>,
+}
+
+impl<T: Clone> Sequence<T> {
+    pub fn len(&self) -> u64 {
+        self.stable_len + self.unstable.as_ref().map_or(0, |unstable| unstable.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn get<I: Into<u64>>(&self, i: I) -> Option<T> {
+        let i = i.into();
+        if i < self.stable_len {
+            logged(self.index.sequence_item(&self.key, i, self.decode))
+        } else {
+            self.unstable
+                .as_ref()?
+                .get(i - self.stable_len)
+                .map(|item| T::clone(item))
+        }
+    }
+}
+
+impl StableIndex {
+    /// open the database in the given directory, or a temporary one. It is
+    /// cleared if it does not index the chain of the given block0, or if the
+    /// block storage does not have its blocks.
+    pub fn open(
+        dir: Option<&Path>,
+        storage: Storage,
+        discrimination: Discrimination,
+        block0: HeaderHash,
+    ) -> Result<Self> {
+        let config = match dir {
+            Some(dir) => sled::Config::new().path(dir),
+            None => sled::Config::new().temporary(true),
+        };
+        let index = StableIndex {
+            confirmed_block_chain_length: Default::default(),
+            db: config.open()?,
+            storage,
+            discrimination,
+            blocks: Arc::new(Mutex::new(LruCache::new(BLOCK_CACHE_SIZE))),
+        };
+
+        let valid = match (index.db.get([BLOCK0])?, index.tip()?) {
+            (None, tip) => tip.is_none(),
+            (Some(indexed_block0), None) => indexed_block0.as_ref() == block0.as_bytes(),
+            (Some(indexed_block0), Some((_, tip))) => {
+                indexed_block0.as_ref() == block0.as_bytes() && index.storage.get(tip)?.is_some()
+            }
+        };
+        if !valid {
+            tracing::warn!(
+                "the explorer database does not match the block storage, the blocks are indexed again"
+            );
+            index.db.clear()?;
+        }
+        index.db.insert([BLOCK0], block0.as_bytes())?;
+
+        if let Some((chain_length, _)) = index.tip()? {
+            index
+                .confirmed_block_chain_length
+                .store(chain_length.into(), Ordering::Release);
+        }
+        Ok(index)
+    }
+
+    /// the chain length and hash of the last persisted block
+    pub fn tip(&self) -> Result<Option<(ChainLength, HeaderHash)>> {
+        match self.db.get([TIP])? {
+            Some(tip) if tip.len() > 4 => {
+                Ok(Some((decode_u32(&tip[..4])?.into(), decode(&tip[4..])?)))
+            }
+            Some(_) => Err(corrupted("invalid tip")),
+            None => Ok(None),
+        }
+    }
+
+    /// the persisted block of the given hash, resolved again from the block
+    /// storage
+    pub fn get_block(&self, hash: &HeaderHash) -> Result<Option<Arc<ExplorerBlock>>> {
+        if let Some(block) = self.blocks.lock().unwrap().get(hash) {
+            return Ok(Some(Arc::clone(block)));
+        }
+        if self.db.get(key(BLOCK, hash.as_bytes()))?.is_none() {
+            return Ok(None);
+        }
+
+        let block = self
+            .storage
+            .get(*hash)?
+            .ok_or(Error::BlockNotFound(*hash))?;
+        let block = Arc::new(ExplorerBlock::resolve_from(
+            &block,
+            ExplorerBlockBuildingContext {
+                discrimination: self.discrimination,
+                prev_transactions: &Transactions::new(),
+                prev_blocks: &Blocks::new(),
+                stable_index: self,
+            },
+        ));
+        self.blocks.lock().unwrap().put(*hash, Arc::clone(&block));
+        Ok(Some(block))
+    }
+
+    pub fn block_chain_length(&self, hash: &HeaderHash) -> Result<Option<ChainLength>> {
+        self.db
+            .get(key(BLOCK, hash.as_bytes()))?
+            .map(|chain_length| decode_u32(&chain_length).map(ChainLength::from))
+            .transpose()
+    }
+
+    pub fn find_block_by_chain_length(
+        &self,
+        chain_length: ChainLength,
+    ) -> Result<Option<HeaderHash>> {
+        self.db
+            .get(key(CHAIN_LENGTH, &u32::from(chain_length).to_be_bytes()))?
+            .map(|hash| decode(&hash))
+            .transpose()
+    }
+
+    pub fn find_block_hash_by_transaction(&self, id: &FragmentId) -> Result<Option<HeaderHash>> {
+        self.db
+            .get(key(TRANSACTION, id.as_bytes()))?
+            .map(|hash| decode(&hash))
+            .transpose()
+    }
+
+    /// the output of a persisted transaction, read from the block storage
+    /// without resolving the inputs of its block as they are not needed
+    pub fn transaction_output(&self, id: &FragmentId, index: u8) -> Result<Option<ExplorerOutput>> {
+        let hash = match self.find_block_hash_by_transaction(id)? {
+            Some(hash) => hash,
+            None => return Ok(None),
+        };
+        if let Some(block) = self.blocks.lock().unwrap().get(&hash) {
+            return Ok(block
+                .transactions
+                .get(id)
+                .and_then(|tx| tx.outputs.get(usize::from(index)))
+                .cloned());
+        }
+
+        let block = self.storage.get(hash)?.ok_or(Error::BlockNotFound(hash))?;
+        Ok(block
+            .contents
+            .iter()
+            .find(|fragment| fragment.id() == *id)
+            .and_then(|fragment| {
+                fragment_outputs(fragment)
+                    .into_iter()
+                    .nth(usize::from(index))
+            }))
+    }
+
+    /// the transactions of the address, the persisted ones followed by the
+    /// given ones
+    pub fn transactions_by_address(
+        &self,
+        address: &ExplorerAddress,
+        unstable: Option<Arc<PersistentSequence<FragmentId>>>,
+    ) -> Sequence<FragmentId> {
+        self.sequence(address_key(address), unstable, decode)
+    }
+
+    /// the ledger entries of the address, the persisted ones followed by the
+    /// given ones
+    pub fn ledger_entries(
+        &self,
+        address: &ExplorerAddress,
+        unstable: Option<Arc<PersistentSequence<ExplorerLedgerEntry>>>,
+    ) -> Sequence<ExplorerLedgerEntry> {
+        self.sequence(
+            key(LEDGER_ENTRIES, &encode_address(address)),
+            unstable,
+            decode_ledger_entry,
+        )
+    }
+
+    /// the blocks of the stake pool, the persisted ones followed by the
+    /// given ones
+    pub fn stake_pool_blocks(
+        &self,
+        pool: &PoolId,
+        unstable: Option<Arc<PersistentSequence<HeaderHash>>>,
+    ) -> Sequence<HeaderHash> {
+        self.sequence(key(STAKE_POOL_BLOCKS, pool.as_ref()), unstable, decode)
+    }
+
+    fn sequence<T>(
+        &self,
+        key: Vec<u8>,
+        unstable: Option<Arc<PersistentSequence<T>>>,
+        decode: fn(&[u8]) -> Result<T>,
+    ) -> Sequence<T> {
+        let stable_len = logged(
+            self.db
+                .get(&key)
+                .map_err(Error::from)
+                .and_then(|len| len.map(|len| decode_u64(&len)).transpose()),
+        )
+        .unwrap_or(0);
+        Sequence {
+            index: self.clone(),
+            key,
+            stable_len,
+            unstable,
+            decode,
+        }
+    }
+
+    fn sequence_item<T>(
+        &self,
+        key: &[u8],
+        i: u64,
+        decode: fn(&[u8]) -> Result<T>,
+    ) -> Result<Option<T>> {
+        let mut item_key = key.to_vec();
+        item_key.extend_from_slice(&i.to_be_bytes());
+        self.db.get(item_key)?.map(|item| decode(&item)).transpose()
+    }
+
+    /// persist the indices of the given blocks, which follow the last
+    /// persisted block on the main branch. The epochs, the vote plans and the
+    /// states of the addresses are the ones of the state after the last of
+    /// the blocks, the ledger entries are the ones of the given blocks.
+    pub async fn persist(
+        &self,
+        blocks: &[Arc<ExplorerBlock>],
+        epochs: &Epochs,
+        vote_plans: &VotePlans,
+        address_states: &AddressStates,
+        ledger_entries: &AddressLedgerEntries,
+    ) -> Result<()> {
+        let last_block = match blocks.last() {
+            Some(block) => block,
+            None => return Ok(()),
+        };
+
+        let mut writer = Writer::new(&self.db);
+        let mut touched_epochs = HashSet::new();
+        let mut touched_addresses = HashSet::new();
+        let mut rewarded_epochs = HashSet::new();
+        let mut tallied_vote_plans = Vec::new();
+
+        for block in blocks {
+            let hash = block.id();
+            let chain_length = u32::from(block.chain_length()).to_be_bytes();
+            writer.insert(key(BLOCK, hash.as_bytes()), chain_length.to_vec());
+            writer.insert(key(CHAIN_LENGTH, &chain_length), hash.as_bytes().to_vec());
+            if let BlockProducer::StakePool(pool) = block.producer() {
+                writer.append(key(STAKE_POOL_BLOCKS, pool.as_ref()), hash.as_bytes())?;
+            }
+            touched_epochs.insert(block.date().epoch);
+            // the rewards of an epoch are received before its first block
+            if epochs
+                .lookup(&block.date().epoch)
+                .map_or(false, |data| data.first_block == hash)
+            {
+                rewarded_epochs.insert(block.date().epoch);
+            }
+
+            for tx in block.transactions.values() {
+                let id = tx.id();
+                writer.insert(key(TRANSACTION, id.as_bytes()), hash.as_bytes().to_vec());
+
+                // same as the addresses of the multiverse, an address both
+                // in the inputs and the outputs is only counted once
+                let addresses: HashSet<&ExplorerAddress> = tx
+                    .outputs()
+                    .iter()
+                    .map(|output| &output.address)
+                    .chain(tx.inputs().iter().map(|input| &input.address))
+                    .collect();
+                for address in addresses {
+                    writer.append(address_key(address), id.as_bytes())?;
+                    touched_addresses.insert(address.clone());
+                }
+
+                match &tx.certificate {
+                    Some(Certificate::PoolRegistration(registration)) => writer.insert(
+                        key(STAKE_POOL, registration.to_id().as_ref()),
+                        id.as_bytes().to_vec(),
+                    ),
+                    Some(Certificate::PoolRetirement(retirement)) => {
+                        let key = key(STAKE_POOL, retirement.pool_id.as_ref());
+                        let mut value = writer
+                            .get(&key)?
+                            .ok_or_else(|| corrupted("retirement of an unknown stake pool"))?;
+                        value.truncate(id.as_bytes().len());
+                        value.extend_from_slice(id.as_bytes());
+                        writer.insert(key, value);
+                    }
+                    Some(Certificate::VotePlan(vote_plan)) => writer.insert(
+                        key(VOTE_PLAN, vote_plan.to_id().as_ref()),
+                        id.as_bytes().to_vec(),
+                    ),
+                    Some(Certificate::VoteCast(vote_cast)) => {
+                        let mut key = key(VOTE, vote_cast.vote_plan().as_ref());
+                        key.push(vote_cast.proposal_index());
+                        key.extend(encode_address(&tx.inputs[0].address));
+                        writer.insert(key, id.as_bytes().to_vec());
+                    }
+                    Some(Certificate::VoteTally(vote_tally)) => {
+                        tallied_vote_plans.push(vote_tally.id().clone())
+                    }
+                    Some(Certificate::StakeDelegation(delegation)) => {
+                        if let Some(account) = delegation.account_id.to_single_account() {
+                            let kind = chain_addr::Kind::Account(account.into());
+                            touched_addresses
+                                .insert(ExplorerAddress::New(Address(self.discrimination, kind)));
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        for epoch in touched_epochs {
+            if let Some(data) = epochs.lookup(&epoch) {
+                writer.insert(key(EPOCH, &epoch.to_be_bytes()), encode_epoch(data));
+            }
+        }
+        if !rewarded_epochs.is_empty() {
+            touched_addresses.extend(
+                address_states
+                    .iter()
+                    .filter(|(_, state)| {
+                        state
+                            .last_rewards
+                            .map_or(false, |(epoch, _)| rewarded_epochs.contains(&epoch))
+                    })
+                    .map(|(address, _)| address.clone()),
+            );
+        }
+        for address in touched_addresses {
+            if let Some(state) = address_states.lookup(&address) {
+                writer.insert(
+                    key(ADDRESS_STATE, &encode_address(&address)),
+                    encode_address_state(state),
+                );
+            }
+        }
+        for (address, entries) in ledger_entries.iter() {
+            let key = key(LEDGER_ENTRIES, &encode_address(address));
+            for entry in (0..entries.len()).filter_map(|i| entries.get(i)) {
+                writer.append(key.clone(), &encode_ledger_entry(entry))?;
+            }
+        }
+        for id in tallied_vote_plans {
+            if let Some(vote_plan) = vote_plans.lookup(&id) {
+                writer.insert(key(TALLY, id.as_ref()), encode_tallies(vote_plan));
+            }
+        }
+
+        let mut tip = u32::from(last_block.chain_length()).to_be_bytes().to_vec();
+        tip.extend_from_slice(last_block.id().as_bytes());
+        writer.insert(vec![TIP], tip);
+
+        self.db.apply_batch(writer.into_batch())?;
+        self.db.flush_async().await?;
+        Ok(())
+    }
+
+    /// the epochs of the persisted blocks
+    pub fn load_epochs(&self) -> Result<Epochs> {
+        let mut epochs = Epochs::new();
+        for entry in self.db.scan_prefix([EPOCH]) {
+            let (key, value) = entry?;
+            let epoch: Epoch = decode_u32(&key[1..])?;
+            epochs = epochs
+                .insert(epoch, Arc::new(decode_epoch(&value)?))
+                .map_err(|_| corrupted("duplicated epoch"))?;
+        }
+        Ok(epochs)
+    }
+
+    /// the states of the addresses after the persisted blocks
+    pub fn load_address_states(&self) -> Result<AddressStates> {
+        let mut address_states = AddressStates::new();
+        for entry in self.db.scan_prefix([ADDRESS_STATE]) {
+            let (key, value) = entry?;
+            address_states = address_states
+                .insert(
+                    decode_address(&key[1..])?,
+                    Arc::new(decode_address_state(&value)?),
+                )
+                .map_err(|_| corrupted("duplicated address"))?;
+        }
+        Ok(address_states)
+    }
+
+    /// the stake pools registered in the persisted blocks
+    pub fn load_stake_pools(&self) -> Result<StakePool> {
+        let mut stake_pools = StakePool::new();
+        for entry in self.db.scan_prefix([STAKE_POOL]) {
+            let (_, value) = entry?;
+            let (registration, retirement) = value.split_at(value.len().min(32));
+
+            let registration = match self.certificate(&decode(registration)?)? {
+                Certificate::PoolRegistration(registration) => registration,
+                _ => return Err(corrupted("not a stake pool registration")),
+            };
+            let retirement = if retirement.is_empty() {
+                None
+            } else {
+                match self.certificate(&decode(retirement)?)? {
+                    Certificate::PoolRetirement(retirement) => Some(retirement),
+                    _ => return Err(corrupted("not a stake pool retirement")),
+                }
+            };
+
+            stake_pools = stake_pools
+                .insert(
+                    registration.to_id(),
+                    Arc::new(StakePoolData {
+                        registration,
+                        retirement,
+                    }),
+                )
+                .map_err(|_| corrupted("duplicated stake pool"))?;
+        }
+        Ok(stake_pools)
+    }
+
+    /// the vote plans created in the persisted blocks, with their votes and
+    /// their tallies
+    pub fn load_vote_plans(&self) -> Result<VotePlans> {
+        let mut vote_plans = VotePlans::new();
+        for entry in self.db.scan_prefix([VOTE_PLAN]) {
+            let (_, value) = entry?;
+            let mut vote_plan = match self.certificate(&decode(&value)?)? {
+                Certificate::VotePlan(vote_plan) => ExplorerVotePlan::from(&vote_plan),
+                _ => return Err(corrupted("not a vote plan")),
+            };
+
+            let votes_prefix = key(VOTE, vote_plan.id.as_ref());
+            for entry in self.db.scan_prefix(&votes_prefix) {
+                let (key, value) = entry?;
+                let (proposal_index, voter) = key[votes_prefix.len()..]
+                    .split_first()
+                    .ok_or_else(|| corrupted("invalid vote"))?;
+                let vote = match self.certificate(&decode(&value)?)? {
+                    Certificate::VoteCast(vote_cast) => ExplorerVote::from(vote_cast.payload()),
+                    _ => return Err(corrupted("not a vote cast")),
+                };
+                let proposal = vote_plan
+                    .proposals
+                    .get_mut(usize::from(*proposal_index))
+                    .ok_or_else(|| corrupted("vote for an unknown proposal"))?;
+                proposal.votes = proposal
+                    .votes
+                    .insert(decode_address(voter)?, Arc::new(vote))
+                    .map_err(|_| corrupted("duplicated vote"))?;
+            }
+
+            if let Some(tallies) = self.db.get(key(TALLY, vote_plan.id.as_ref()))? {
+                decode_tallies(&tallies, &mut vote_plan)?;
+            }
+
+            vote_plans = vote_plans
+                .insert(vote_plan.id.clone(), Arc::new(vote_plan))
+                .map_err(|_| corrupted("duplicated vote plan"))?;
+        }
+        Ok(vote_plans)
+    }
+
+    fn certificate(&self, id: &FragmentId) -> Result<Certificate> {
+        self.find_block_hash_by_transaction(id)?
+            .map(|hash| self.get_block(&hash))
+            .transpose()?
+            .flatten()
+            .and_then(|block| block.transactions.get(id)?.certificate.clone())
+            .ok_or_else(|| corrupted("certificate not found"))
+    }
+}
+
+/// the value of a query of the database, which are not expected to fail: the
+/// errors are logged and taken as missing data
+pub(super) fn logged<T>(result: Result<Option<T>>) -> Option<T> {
+    result.unwrap_or_else(|error| {
+        tracing::error!(%error, "cannot query the explorer database");
+        None
+    })
+}
+
+/// the writes of a batch, seen by the next reads of the same batch
+struct Writer<'a> {
+    db: &'a sled::Db,
+    pending: HashMap<Vec<u8>, Vec<u8>>,
+}
+
+impl<'a> Writer<'a> {
+    fn new(db: &'a sled::Db) -> Self {
+        Writer {
+            db,
+            pending: HashMap::new(),
+        }
+    }
+
+    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        match self.pending.get(key) {
+            Some(value) => Ok(Some(value.clone())),
+            None => Ok(self.db.get(key)?.map(|value| value.to_vec())),
+        }
+    }
+
+    fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        self.pending.insert(key, value);
+    }
+
+    fn append(&mut self, key: Vec<u8>, item: &[u8]) -> Result<()> {
+        let len = self
+            .get(&key)?
+            .map(|len| decode_u64(&len))
+            .transpose()?
+            .unwrap_or(0);
+        let mut item_key = key.clone();
+        item_key.extend_from_slice(&len.to_be_bytes());
+        self.insert(item_key, item.to_vec());
+        self.insert(key, (len + 1).to_be_bytes().to_vec());
+        Ok(())
+    }
+
+    fn into_batch(self) -> sled::Batch {
+        let mut batch = sled::Batch::default();
+        for (key, value) in self.pending {
+            batch.insert(key, value);
+        }
+        batch
+    }
+}
+
+/// the outputs of a fragment indexed as a transaction
+fn fragment_outputs(fragment: &Fragment) -> Vec<ExplorerOutput> {
+    fn outputs<P>(tx: &Transaction<P>) -> Vec<ExplorerOutput> {
+        tx.as_slice()
+            .outputs()
+            .iter()
+            .map(|output| ExplorerOutput {
+                address: ExplorerAddress::New(output.address.clone()),
+                value: output.value,
+            })
+            .collect()
+    }
+
+    match fragment {
+        Fragment::Transaction(tx) => outputs(tx),
+        Fragment::OwnerStakeDelegation(tx) => outputs(tx),
+        Fragment::StakeDelegation(tx) => outputs(tx),
+        Fragment::PoolRegistration(tx) => outputs(tx),
+        Fragment::PoolRetirement(tx) => outputs(tx),
+        Fragment::PoolUpdate(tx) => outputs(tx),
+        Fragment::VotePlan(tx) => outputs(tx),
+        Fragment::VoteCast(tx) => outputs(tx),
+        Fragment::VoteTally(tx) => outputs(tx),
+        Fragment::OldUtxoDeclaration(decl) => decl
+            .addrs
+            .iter()
+            .map(|(old_address, value)| ExplorerOutput {
+                address: ExplorerAddress::Old(old_address.clone()),
+                value: *value,
+            })
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn key(prefix: u8, id: &[u8]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(1 + id.len());
+    key.push(prefix);
+    key.extend_from_slice(id);
+    key
+}
+
+fn address_key(address: &ExplorerAddress) -> Vec<u8> {
+    key(ADDRESS, &encode_address(address))
+}
+
+/// the address prefixed by its kind and its length, so the keys of two
+/// addresses never prefix each other
+fn encode_address(address: &ExplorerAddress) -> Vec<u8> {
+    let (kind, bytes) = match address {
+        ExplorerAddress::New(address) => (NEW_ADDRESS, address.to_bytes()),
+        ExplorerAddress::Old(address) => (OLD_ADDRESS, address.to_string().into_bytes()),
+    };
+    let mut encoded = vec![kind];
+    encoded.extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    encoded.extend(bytes);
+    encoded
+}
+
+fn decode_address(mut bytes: &[u8]) -> Result<ExplorerAddress> {
+    let kind = take(&mut bytes, 1)?[0];
+    let len = u16::from_be_bytes(take(&mut bytes, 2)?.try_into().unwrap());
+    let address = take(&mut bytes, usize::from(len))?;
+    match kind {
+        NEW_ADDRESS => Address::from_bytes(address)
+            .map(ExplorerAddress::New)
+            .map_err(|_| corrupted("invalid address")),
+        OLD_ADDRESS => std::str::from_utf8(address)
+            .ok()
+            .and_then(|address| OldAddress::from_str(address).ok())
+            .map(ExplorerAddress::Old)
+            .ok_or_else(|| corrupted("invalid legacy address")),
+        _ => Err(corrupted("invalid address kind")),
+    }
+}
+
+fn encode_epoch(data: &EpochData) -> Vec<u8> {
+    let mut bytes = data.first_block.as_bytes().to_vec();
+    bytes.extend_from_slice(data.last_block.as_bytes());
+    bytes.extend_from_slice(&data.total_blocks.to_be_bytes());
+    bytes
+}
+
+fn decode_epoch(mut bytes: &[u8]) -> Result<EpochData> {
+    Ok(EpochData {
+        first_block: decode(take(&mut bytes, 32)?)?,
+        last_block: decode(take(&mut bytes, 32)?)?,
+        total_blocks: decode_u32(bytes)?,
+    })
+}
+
+/// the balance, the counter, the rewards, the delegation and the utxos of the
+/// address
+fn encode_address_state(state: &ExplorerAddressState) -> Vec<u8> {
+    let mut bytes = state.balance.0.to_be_bytes().to_vec();
+    bytes.extend_from_slice(&state.spending_counter.to_be_bytes());
+    bytes.extend_from_slice(&state.rewards.0.to_be_bytes());
+    let (rewards_epoch, last_rewards) = state
+        .last_rewards
+        .map_or((u32::MAX, 0), |(epoch, value)| (epoch, value.0));
+    bytes.extend_from_slice(&rewards_epoch.to_be_bytes());
+    bytes.extend_from_slice(&last_rewards.to_be_bytes());
+
+    encode_delegation(&mut bytes, state.delegation.as_ref());
+
+    let utxos: Vec<_> = state.utxos.iter().collect();
+    bytes.extend_from_slice(&(utxos.len() as u32).to_be_bytes());
+    for ((id, index), value) in utxos {
+        bytes.extend_from_slice(id.as_bytes());
+        bytes.push(*index);
+        bytes.extend_from_slice(&value.0.to_be_bytes());
+    }
+    bytes
+}
+
+fn decode_address_state(mut bytes: &[u8]) -> Result<ExplorerAddressState> {
+    let balance = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let spending_counter = decode_u32(take(&mut bytes, 4)?)?;
+    let rewards = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let rewards_epoch = decode_u32(take(&mut bytes, 4)?)?;
+    let last_rewards = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let last_rewards = if rewards_epoch == u32::MAX {
+        None
+    } else {
+        Some((rewards_epoch, last_rewards))
+    };
+
+    let delegation = decode_delegation(&mut bytes)?;
+
+    let mut utxos = Hamt::new();
+    for _ in 0..decode_u32(take(&mut bytes, 4)?)? {
+        let id: FragmentId = decode(take(&mut bytes, 32)?)?;
+        let index = take(&mut bytes, 1)?[0];
+        let value = Value(decode_u64(take(&mut bytes, 8)?)?);
+        utxos = utxos
+            .insert((id, index), Arc::new(value))
+            .map_err(|_| corrupted("duplicated utxo"))?;
+    }
+
+    Ok(ExplorerAddressState {
+        balance,
+        utxos,
+        delegation,
+        spending_counter,
+        rewards,
+        last_rewards,
+    })
+}
+
+/// `0` for none, `1` for not delegated, `2` for a pool, `3` for a ratio of
+/// pools, whose parts add up to the total
+fn encode_delegation(bytes: &mut Vec<u8>, delegation: Option<&DelegationType>) {
+    match delegation {
+        None => bytes.push(0),
+        Some(DelegationType::NonDelegated) => bytes.push(1),
+        Some(DelegationType::Full(pool)) => {
+            bytes.push(2);
+            bytes.extend_from_slice(pool.as_ref());
+        }
+        Some(DelegationType::Ratio(ratio)) => {
+            bytes.push(3);
+            bytes.push(ratio.pools().len() as u8);
+            for (pool, parts) in ratio.pools().iter() {
+                bytes.extend_from_slice(pool.as_ref());
+                bytes.push(*parts);
+            }
+        }
+    }
+}
+
+fn decode_delegation(bytes: &mut &[u8]) -> Result<Option<DelegationType>> {
+    let delegation = match take(bytes, 1)?[0] {
+        0 => None,
+        1 => Some(DelegationType::NonDelegated),
+        2 => Some(DelegationType::Full(decode_pool_id(take(bytes, 32)?)?)),
+        3 => {
+            let len = take(bytes, 1)?[0];
+            let pools = (0..len)
+                .map(|_| Ok((decode_pool_id(take(bytes, 32)?)?, take(bytes, 1)?[0])))
+                .collect::<Result<Vec<_>>>()?;
+            let parts = pools
+                .iter()
+                .map(|(_, parts)| u32::from(*parts))
+                .sum::<u32>();
+            let ratio = parts
+                .try_into()
+                .ok()
+                .and_then(|parts| DelegationRatio::new(parts, pools))
+                .ok_or_else(|| corrupted("invalid delegation ratio"))?;
+            Some(DelegationType::Ratio(ratio))
+        }
+        _ => return Err(corrupted("invalid delegation")),
+    };
+    Ok(delegation)
+}
+
+/// the block, its chain length and date, the transaction (all zeroes for the
+/// rewards), the credit, the debit, the fee, the balance and the delegation
+fn encode_ledger_entry(entry: &ExplorerLedgerEntry) -> Vec<u8> {
+    let mut bytes = entry.block.as_bytes().to_vec();
+    bytes.extend_from_slice(&u32::from(entry.chain_length).to_be_bytes());
+    bytes.extend_from_slice(&entry.date.epoch.to_be_bytes());
+    bytes.extend_from_slice(&entry.date.slot_id.to_be_bytes());
+    match &entry.transaction {
+        Some(id) => bytes.extend_from_slice(id.as_bytes()),
+        None => bytes.extend_from_slice(&[0; 32]),
+    }
+    for value in &[entry.credit, entry.debit, entry.fee, entry.balance] {
+        bytes.extend_from_slice(&value.0.to_be_bytes());
+    }
+    encode_delegation(&mut bytes, entry.delegation.as_ref());
+    bytes
+}
+
+fn decode_ledger_entry(mut bytes: &[u8]) -> Result<ExplorerLedgerEntry> {
+    let block = decode(take(&mut bytes, 32)?)?;
+    let chain_length = ChainLength::from(decode_u32(take(&mut bytes, 4)?)?);
+    let date = BlockDate {
+        epoch: decode_u32(take(&mut bytes, 4)?)?,
+        slot_id: decode_u32(take(&mut bytes, 4)?)?,
+    };
+    let transaction = take(&mut bytes, 32)?;
+    let transaction = if transaction == [0; 32] {
+        None
+    } else {
+        Some(decode(transaction)?)
+    };
+    let credit = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let debit = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let fee = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let balance = Value(decode_u64(take(&mut bytes, 8)?)?);
+    let delegation = decode_delegation(&mut bytes)?;
+
+    Ok(ExplorerLedgerEntry {
+        block,
+        chain_length,
+        date,
+        transaction,
+        credit,
+        debit,
+        fee,
+        balance,
+        delegation,
+    })
+}
+
+fn decode_pool_id(bytes: &[u8]) -> Result<PoolId> {
+    let bytes: [u8; 32] = bytes
+        .try_into()
+        .map_err(|_| corrupted("invalid stake pool id"))?;
+    Ok(Hash::from(bytes).into_digest_of())
+}
+
+/// the tallies of the proposals of the vote plan, their options are the ones
+/// of the proposals
+fn encode_tallies(vote_plan: &ExplorerVotePlan) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    for proposal in &vote_plan.proposals {
+        let results = match &proposal.tally {
+            None => {
+                bytes.push(0);
+                None
+            }
+            Some(ExplorerVoteTally::Public { results, .. }) => {
+                bytes.push(1);
+                Some(results)
+            }
+            Some(ExplorerVoteTally::Private { results: None, .. }) => {
+                bytes.push(2);
+                None
+            }
+            Some(ExplorerVoteTally::Private {
+                results: Some(results),
+                ..
+            }) => {
+                bytes.push(3);
+                Some(results)
+            }
+        };
+        if let Some(results) = results {
+            bytes.extend_from_slice(&(results.len() as u32).to_be_bytes());
+            for weight in results {
+                bytes.extend_from_slice(&u64::from(*weight).to_be_bytes());
+            }
+        }
+    }
+    bytes
+}
+
+fn decode_tallies(mut bytes: &[u8], vote_plan: &mut ExplorerVotePlan) -> Result<()> {
+    for proposal in vote_plan.proposals.iter_mut() {
+        let kind = take(&mut bytes, 1)?[0];
+        let results = if kind == 1 || kind == 3 {
+            let len = decode_u32(take(&mut bytes, 4)?)?;
+            let results = (0..len)
+                .map(|_| decode_u64(take(&mut bytes, 8)?).map(Weight::from))
+                .collect::<Result<Vec<_>>>()?;
+            Some(results)
+        } else {
+            None
+        };
+        let options = proposal.options.clone();
+        proposal.tally = match kind {
+            0 => None,
+            1 => Some(ExplorerVoteTally::Public {
+                results: results.unwrap_or_default(),
+                options,
+            }),
+            2 | 3 => Some(ExplorerVoteTally::Private { results, options }),
+            _ => return Err(corrupted("invalid tally")),
+        };
+    }
+    Ok(())
+}
+
+fn take<'a>(bytes: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if bytes.len() < len {
+        return Err(corrupted("truncated value"));
+    }
+    let (head, tail) = bytes.split_at(len);
+    *bytes = tail;
+    Ok(head)
+}
+
+fn decode<T: Deserialize>(bytes: &[u8]) -> Result<T> {
+    T::deserialize(bytes).map_err(|_| corrupted("invalid hash"))
+}
+
+fn decode_u32(bytes: &[u8]) -> Result<u32> {
+    bytes
+        .try_into()
+        .map(u32::from_be_bytes)
+        .map_err(|_| corrupted("invalid integer"))
+}
+
+fn decode_u64(bytes: &[u8]) -> Result<u64> {
+    bytes
+        .try_into()
+        .map(u64::from_be_bytes)
+        .map_err(|_| corrupted("invalid integer"))
+}
+
+fn corrupted(reason: &str) -> Error {
+    Error::CorruptedStableIndex(reason.to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_addr::Kind;
+    use chain_crypto::{Ed25519, KeyPair};
+
+    #[test]
+    fn address_encoding() {
+        let key_pair = KeyPair::<Ed25519>::generate(&mut rand::thread_rng());
+        let addresses = vec![
+            ExplorerAddress::New(Address(
+                Discrimination::Test,
+                Kind::Single(key_pair.public_key().clone()),
+            )),
+            ExplorerAddress::New(Address(
+                Discrimination::Production,
+                Kind::Account(key_pair.public_key().clone()),
+            )),
+        ];
+
+        for address in addresses {
+            assert!(decode_address(&encode_address(&address)).unwrap() == address);
+        }
+        assert!(decode_address(&[NEW_ADDRESS, 0, 33]).is_err());
+    }
+
+    #[test]
+    fn address_state_encoding() {
+        let pools = vec![
+            (Hash::from([1; 32]).into_digest_of(), 1),
+            (Hash::from([2; 32]).into_digest_of(), 3),
+        ];
+        let state = ExplorerAddressState {
+            balance: Value(100),
+            utxos: Hamt::new()
+                .insert((FragmentId::hash_bytes(b"tx"), 2), Arc::new(Value(60)))
+                .unwrap(),
+            delegation: Some(DelegationType::Ratio(
+                DelegationRatio::new(4, pools).unwrap(),
+            )),
+            spending_counter: 3,
+            rewards: Value(40),
+            last_rewards: Some((7, Value(10))),
+        };
+
+        let encoded = encode_address_state(&state);
+        let decoded = decode_address_state(&encoded).unwrap();
+        assert_eq!(encode_address_state(&decoded), encoded);
+        assert_eq!(decoded.spending_counter, 3);
+        assert!(decoded.delegation == state.delegation);
+        assert!(decode_address_state(&encoded[..encoded.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn ledger_entry_encoding() {
+        let entry = ExplorerLedgerEntry {
+            block: HeaderHash::hash_bytes(b"block"),
+            chain_length: ChainLength::from(12),
+            date: BlockDate {
+                epoch: 1,
+                slot_id: 5,
+            },
+            transaction: None,
+            credit: Value(20),
+            debit: Value::zero(),
+            fee: Value::zero(),
+            balance: Value(120),
+            delegation: Some(DelegationType::Full(Hash::from([1; 32]).into_digest_of())),
+        };
+
+        let encoded = encode_ledger_entry(&entry);
+        let decoded = decode_ledger_entry(&encoded).unwrap();
+        assert_eq!(encode_ledger_entry(&decoded), encoded);
+        assert!(decoded.transaction.is_none());
+        assert_eq!(decoded.balance, Value(120));
+        assert!(decoded.delegation == entry.delegation);
+    }
+}
@@ -2,8 +2,11 @@ use crate::{
     blockcfg::{Value, ValueError},
     fragment::{Fragment, FragmentId},
 };
+use chain_impl_mockchain::transaction::Transaction;
+use std::cmp::Ordering;
 use std::time::SystemTime;
 
+#[derive(Clone)]
 pub struct PoolEntry {
     // reference of the fragment stored in the pool
     fragment_ref: FragmentId,
@@ -30,20 +33,22 @@ pub struct PoolEntry {
 
 impl PoolEntry {
     pub fn new(fragment: &Fragment) -> Self {
+        Self::new_received_at(fragment, SystemTime::now())
+    }
+
+    /// create the entry of a fragment received at the given time, e.g. a
+    /// fragment restored from the mempool journal
+    pub fn new_received_at(fragment: &Fragment, received_at: SystemTime) -> Self {
         let raw = fragment.to_raw();
         let fragment_size = raw.size_bytes_plus_size();
         let fragment_ref = raw.id();
-        // TODO: the fragment fee is not yet computed. Yet we should
-        // have an explicit fee in the message. So we need to be able
-        // to extract this information without the need to compute the
-        // fee from the ledger's fee settings.
-        let fragment_fee = Value::zero();
+        let fragment_fee = fragment_fee(fragment);
 
         PoolEntry {
             fragment_ref,
             fragment_fee,
             fragment_size,
-            received_at: SystemTime::now(),
+            received_at,
 
             // when this entry is added in the pool, it has no
             // descendant
@@ -74,6 +79,10 @@ impl PoolEntry {
         &self.received_at
     }
     #[inline]
+    pub fn fee_rate(&self) -> FeeRate {
+        FeeRate::new(self.fragment_fee, self.fragment_size)
+    }
+    #[inline]
     pub fn with_descendants_fee(&self) -> Result<Value, ValueError> {
         self.descendants_fee + self.fragment_fee
     }
@@ -90,3 +99,67 @@ impl PoolEntry {
         self.ancestors_size + self.fragment_size
     }
 }
+
+/// Fee paid per byte of fragment, compared without loss of precision.
+#[derive(Clone, Copy)]
+pub struct FeeRate {
+    fee: u64,
+    size: u64,
+}
+
+impl FeeRate {
+    pub fn new(fee: Value, size: usize) -> Self {
+        FeeRate {
+            fee: fee.0,
+            size: size as u64,
+        }
+    }
+}
+
+impl PartialEq for FeeRate {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for FeeRate {}
+
+impl PartialOrd for FeeRate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for FeeRate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let lhs = self.fee as u128 * other.size.max(1) as u128;
+        let rhs = other.fee as u128 * self.size.max(1) as u128;
+        lhs.cmp(&rhs)
+    }
+}
+
+/// The fee paid by a fragment: the difference between its inputs and outputs.
+pub fn fragment_fee(fragment: &Fragment) -> Value {
+    fn fee<P>(tx: &Transaction<P>) -> Value {
+        tx.total_input()
+            .and_then(|input| tx.total_output().and_then(|output| input - output))
+            .unwrap_or_else(|_| Value::zero())
+    }
+
+    match fragment {
+        Fragment::Transaction(tx) => fee(tx),
+        Fragment::OwnerStakeDelegation(tx) => fee(tx),
+        Fragment::StakeDelegation(tx) => fee(tx),
+        Fragment::PoolRegistration(tx) => fee(tx),
+        Fragment::PoolRetirement(tx) => fee(tx),
+        Fragment::PoolUpdate(tx) => fee(tx),
+        Fragment::VotePlan(tx) => fee(tx),
+        Fragment::VoteCast(tx) => fee(tx),
+        Fragment::VoteTally(tx) => fee(tx),
+        Fragment::EncryptedVoteTally(tx) => fee(tx),
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Value::zero(),
+    }
+}
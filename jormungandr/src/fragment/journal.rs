@@ -0,0 +1,482 @@
+//! On-disk journal of the fragment pool.
+//!
+//! Every fragment accepted in the pool is appended to the journal together
+//! with its log, and every later change of the log status is appended as well.
+//! Replaying the journal at startup gives back the fragments that were still
+//! pending and the logs of the fragments that were already processed.
+//!
+//! The journal is a file of JSON entries, one per line. It is rewritten with
+//! only the live entries when it is opened and once enough entries have been
+//! appended since the last rewrite.
+//!
+//! The entries are encoded by the caller but written by a dedicated thread,
+//! so that the mempool task never waits on the disk.
+
+use crate::fragment::Fragment;
+use chain_core::property::{Deserialize as _, Fragment as _, Serialize as _};
+use jormungandr_lib::{crypto::hash::Hash, interfaces::FragmentLog};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+const JOURNAL_FILE: &str = "journal";
+const JOURNAL_TMP_FILE: &str = "journal.tmp";
+
+/// number of appended entries after which the journal is rewritten
+const COMPACTION_THRESHOLD: usize = 100_000;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("I/O error while accessing the mempool journal")]
+    Io(#[from] io::Error),
+    #[error("cannot encode a mempool journal entry")]
+    Encode(#[from] serde_json::Error),
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Entry {
+    Fragment { fragment: String, log: FragmentLog },
+    Log { log: FragmentLog },
+}
+
+enum Command {
+    Append(Vec<Entry>),
+    Rewrite(Vec<Entry>),
+}
+
+/// The content of the journal as it was found when opening it.
+pub struct Restored {
+    /// the fragments that were still pending, oldest first
+    pub fragments: Vec<Fragment>,
+    /// the logs of all the journaled fragments, oldest first
+    pub logs: Vec<FragmentLog>,
+}
+
+/// Handle to the journal writer thread.
+///
+/// Dropping the handle waits for the pending entries to be written.
+pub struct Journal {
+    commands: Option<mpsc::Sender<Command>>,
+    writer: Option<thread::JoinHandle<()>>,
+    appended: usize,
+}
+
+impl Journal {
+    /// Open the journal in the given directory, creating it if needed, and
+    /// return the state it recorded.
+    ///
+    /// This reads and rewrites the whole journal, it should not be called
+    /// from an async task.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<(Self, Restored), Error> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+
+        let restored = match File::open(dir.join(JOURNAL_FILE)) {
+            Ok(file) => replay(BufReader::new(file))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Restored {
+                fragments: Vec::new(),
+                logs: Vec::new(),
+            },
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut writer = Writer {
+            file: open_append(&dir)?,
+            dir,
+        };
+        writer.rewrite(live_entries(
+            restored.fragments.iter(),
+            restored.logs.iter(),
+        )?)?;
+
+        let (commands, receiver) = mpsc::channel();
+        let writer = thread::Builder::new()
+            .name("mempool-journal".to_owned())
+            .spawn(move || writer.run(receiver))?;
+
+        let journal = Journal {
+            commands: Some(commands),
+            writer: Some(writer),
+            appended: 0,
+        };
+        Ok((journal, restored))
+    }
+
+    /// Record fragments newly accepted in the pool.
+    pub fn append_fragments<'a>(
+        &mut self,
+        entries: impl IntoIterator<Item = (&'a Fragment, &'a FragmentLog)>,
+    ) -> Result<(), Error> {
+        let entries = entries
+            .into_iter()
+            .map(|(fragment, log)| fragment_entry(fragment, log))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.append(entries)
+    }
+
+    /// Record the current state of the given logs.
+    pub fn append_logs<'a>(
+        &mut self,
+        logs: impl IntoIterator<Item = &'a FragmentLog>,
+    ) -> Result<(), Error> {
+        let entries = logs
+            .into_iter()
+            .map(|log| Entry::Log { log: log.clone() })
+            .collect();
+        self.append(entries)
+    }
+
+    pub fn needs_compaction(&self) -> bool {
+        self.appended >= COMPACTION_THRESHOLD
+    }
+
+    /// Replace the journal with the given pending fragments and logs.
+    ///
+    /// Only the fragments whose log is found in `logs` are kept.
+    pub fn rewrite<'a>(
+        &mut self,
+        fragments: impl IntoIterator<Item = &'a Fragment>,
+        logs: impl IntoIterator<Item = &'a FragmentLog>,
+    ) -> Result<(), Error> {
+        let entries = live_entries(fragments, logs)?;
+        self.send(Command::Rewrite(entries))?;
+        self.appended = 0;
+        Ok(())
+    }
+
+    fn append(&mut self, entries: Vec<Entry>) -> Result<(), Error> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let count = entries.len();
+        self.send(Command::Append(entries))?;
+        self.appended += count;
+        Ok(())
+    }
+
+    fn send(&self, command: Command) -> Result<(), Error> {
+        self.commands
+            .as_ref()
+            .and_then(|commands| commands.send(command).ok())
+            .ok_or_else(|| {
+                Error::Io(io::Error::new(
+                    io::ErrorKind::BrokenPipe,
+                    "the mempool journal writer stopped",
+                ))
+            })
+    }
+}
+
+impl Drop for Journal {
+    fn drop(&mut self) {
+        // closing the channel stops the writer once it wrote everything
+        self.commands.take();
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                tracing::error!("the mempool journal writer panicked");
+            }
+        }
+    }
+}
+
+struct Writer {
+    dir: PathBuf,
+    file: BufWriter<File>,
+}
+
+impl Writer {
+    fn run(mut self, commands: mpsc::Receiver<Command>) {
+        for command in commands {
+            let result = match command {
+                Command::Append(entries) => self.append(entries),
+                Command::Rewrite(entries) => self.rewrite(entries),
+            };
+            if let Err(error) = result {
+                tracing::error!(%error, "cannot write to the mempool journal");
+            }
+        }
+    }
+
+    fn append(&mut self, entries: Vec<Entry>) -> Result<(), Error> {
+        for entry in entries {
+            write_entry(&mut self.file, &entry)?;
+        }
+        self.file.flush()?;
+        Ok(())
+    }
+
+    fn rewrite(&mut self, entries: Vec<Entry>) -> Result<(), Error> {
+        let tmp_path = self.dir.join(JOURNAL_TMP_FILE);
+        let mut tmp = BufWriter::new(File::create(&tmp_path)?);
+        for entry in entries {
+            write_entry(&mut tmp, &entry)?;
+        }
+        tmp.into_inner().map_err(io::Error::from)?.sync_all()?;
+        fs::rename(&tmp_path, self.dir.join(JOURNAL_FILE))?;
+
+        self.file = open_append(&self.dir)?;
+        Ok(())
+    }
+}
+
+/// The entries of the given logs, with the fragment of the ones found in
+/// `fragments`.
+fn live_entries<'a>(
+    fragments: impl IntoIterator<Item = &'a Fragment>,
+    logs: impl IntoIterator<Item = &'a FragmentLog>,
+) -> Result<Vec<Entry>, Error> {
+    let fragments: HashMap<Hash, &Fragment> = fragments
+        .into_iter()
+        .map(|fragment| (fragment.id().into(), fragment))
+        .collect();
+
+    logs.into_iter()
+        .map(|log| match fragments.get(log.fragment_id()) {
+            Some(fragment) => fragment_entry(fragment, log),
+            None => Ok(Entry::Log { log: log.clone() }),
+        })
+        .collect()
+}
+
+fn open_append(dir: &Path) -> Result<BufWriter<File>, Error> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(dir.join(JOURNAL_FILE))?;
+    Ok(BufWriter::new(file))
+}
+
+fn fragment_entry(fragment: &Fragment, log: &FragmentLog) -> Result<Entry, Error> {
+    Ok(Entry::Fragment {
+        fragment: hex::encode(fragment.serialize_as_vec()?),
+        log: log.clone(),
+    })
+}
+
+fn write_entry<W: Write>(writer: &mut W, entry: &Entry) -> Result<(), Error> {
+    serde_json::to_writer(&mut *writer, entry)?;
+    writer.write_all(b"\n")?;
+    Ok(())
+}
+
+fn replay<R: BufRead>(reader: R) -> Result<Restored, Error> {
+    let mut order = Vec::new();
+    let mut entries: HashMap<Hash, (Option<Fragment>, FragmentLog)> = HashMap::new();
+
+    for (line_number, line) in reader.lines().enumerate() {
+        let line = line?;
+        // the last line may be truncated if the node was stopped while
+        // writing it, skip whatever cannot be decoded
+        let entry = match serde_json::from_str::<Entry>(&line) {
+            Ok(entry) => entry,
+            Err(err) => {
+                tracing::warn!(line = line_number + 1, error = %err, "skipping invalid mempool journal entry");
+                continue;
+            }
+        };
+        match entry {
+            Entry::Fragment { fragment, log } => {
+                let fragment = match hex::decode(&fragment)
+                    .ok()
+                    .and_then(|bytes| Fragment::deserialize(bytes.as_slice()).ok())
+                {
+                    Some(fragment) => fragment,
+                    None => {
+                        tracing::warn!(
+                            line = line_number + 1,
+                            "skipping undecodable fragment in the mempool journal"
+                        );
+                        continue;
+                    }
+                };
+                let id = *log.fragment_id();
+                if !entries.contains_key(&id) {
+                    order.push(id);
+                }
+                entries.insert(id, (Some(fragment), log));
+            }
+            Entry::Log { log } => {
+                let id = *log.fragment_id();
+                match entries.get_mut(&id) {
+                    Some((fragment, entry_log)) => {
+                        if !log.is_pending() {
+                            *fragment = None;
+                        }
+                        *entry_log = log;
+                    }
+                    None => {
+                        order.push(id);
+                        entries.insert(id, (None, log));
+                    }
+                }
+            }
+        }
+    }
+
+    let mut fragments = Vec::new();
+    let mut logs = Vec::with_capacity(order.len());
+    for id in order {
+        let (fragment, log) = entries.remove(&id).expect("journal entry to be present");
+        if let Some(fragment) = fragment {
+            if log.is_pending() {
+                fragments.push(fragment);
+            }
+        }
+        logs.push(log);
+    }
+
+    Ok(Restored { fragments, logs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_impl_mockchain::{config::ConfigParam, fragment::ConfigParams};
+    use jormungandr_lib::interfaces::{FragmentOrigin, FragmentStatus};
+
+    fn journal_dir(test: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "jormungandr-journal-{}-{}",
+            test,
+            std::process::id()
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn fragment(n: u32) -> (Fragment, FragmentLog) {
+        let mut params = ConfigParams::new();
+        params.push(ConfigParam::SlotsPerEpoch(n));
+        let fragment = Fragment::Initial(params);
+        let log = FragmentLog::new(fragment.id(), FragmentOrigin::Rest);
+        (fragment, log)
+    }
+
+    fn rejected(log: &FragmentLog) -> FragmentLog {
+        let mut log = log.clone();
+        log.modify(FragmentStatus::Rejected {
+            reason: "test".to_owned(),
+        });
+        log
+    }
+
+    fn ids(fragments: &[Fragment]) -> Vec<Hash> {
+        fragments
+            .iter()
+            .map(|fragment| fragment.id().into())
+            .collect()
+    }
+
+    fn statuses(logs: &[FragmentLog]) -> Vec<(Hash, FragmentStatus)> {
+        logs.iter()
+            .map(|log| (*log.fragment_id(), log.status().clone()))
+            .collect()
+    }
+
+    /// append fragments `0..3` and reject the fragment `1`
+    fn write_journal(dir: &Path) -> Vec<(Fragment, FragmentLog)> {
+        let entries = (0..3).map(fragment).collect::<Vec<_>>();
+        let (mut journal, restored) = Journal::open(dir).unwrap();
+        assert!(restored.fragments.is_empty());
+        assert!(restored.logs.is_empty());
+
+        journal
+            .append_fragments(entries.iter().map(|(fragment, log)| (fragment, log)))
+            .unwrap();
+        journal.append_logs(vec![&rejected(&entries[1].1)]).unwrap();
+        entries
+    }
+
+    fn check_restored(restored: &Restored, entries: &[(Fragment, FragmentLog)]) {
+        assert_eq!(
+            ids(&restored.fragments),
+            ids(&[entries[0].0.clone(), entries[2].0.clone()])
+        );
+        assert_eq!(
+            statuses(&restored.logs),
+            statuses(&[
+                entries[0].1.clone(),
+                rejected(&entries[1].1),
+                entries[2].1.clone()
+            ])
+        );
+    }
+
+    #[test]
+    fn replay_gives_back_the_pending_fragments() {
+        let dir = journal_dir("replay");
+        let entries = write_journal(&dir);
+
+        let (_, restored) = Journal::open(&dir).unwrap();
+        check_restored(&restored, &entries);
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn truncated_last_line_is_skipped() {
+        let dir = journal_dir("truncated");
+        let entries = write_journal(&dir);
+
+        let (extra, extra_log) = fragment(3);
+        let mut line = Vec::new();
+        write_entry(&mut line, &fragment_entry(&extra, &extra_log).unwrap()).unwrap();
+        let mut file = open_append(&dir).unwrap();
+        file.write_all(&line[..line.len() / 2]).unwrap();
+        file.flush().unwrap();
+        drop(file);
+
+        let (journal, restored) = Journal::open(&dir).unwrap();
+        check_restored(&restored, &entries);
+        drop(journal);
+
+        // the truncated line was dropped when the journal was rewritten
+        let content = fs::read_to_string(dir.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(content.lines().count(), 3);
+        assert!(content
+            .lines()
+            .all(|line| serde_json::from_str::<Entry>(line).is_ok()));
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn compaction_keeps_only_the_live_entries() {
+        let dir = journal_dir("compaction");
+        let entries = (0..3).map(fragment).collect::<Vec<_>>();
+        let (mut journal, _) = Journal::open(&dir).unwrap();
+        journal
+            .append_fragments(entries.iter().map(|(fragment, log)| (fragment, log)))
+            .unwrap();
+        let rejected_log = rejected(&entries[1].1);
+        journal.append_logs(vec![&rejected_log]).unwrap();
+        assert_eq!(journal.appended, 4);
+
+        // the log of the first fragment was evicted from the logs
+        journal
+            .rewrite(vec![&entries[2].0], vec![&rejected_log, &entries[2].1])
+            .unwrap();
+        assert!(!journal.needs_compaction());
+        assert_eq!(journal.appended, 0);
+        drop(journal);
+
+        let content = fs::read_to_string(dir.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(content.lines().count(), 2);
+
+        let (_, restored) = Journal::open(&dir).unwrap();
+        assert_eq!(ids(&restored.fragments), ids(&[entries[2].0.clone()]));
+        assert_eq!(
+            statuses(&restored.logs),
+            statuses(&[rejected_log, entries[2].1.clone()])
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
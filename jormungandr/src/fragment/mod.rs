@@ -1,10 +1,11 @@
 mod entry;
+mod journal;
 mod logs;
 mod pool;
 mod process;
 pub mod selection;
 
-pub use self::entry::PoolEntry;
+pub use self::entry::{fragment_fee, PoolEntry};
 pub use self::logs::Logs;
 pub use self::pool::Pools;
 pub use self::process::Process;
@@ -1,23 +1,41 @@
 use crate::{
     blockcfg::{BlockDate, Ledger, LedgerParameters},
     fragment::{
-        selection::{FragmentSelectionAlgorithm, FragmentSelectionAlgorithmParams, OldestFirst},
-        Fragment, FragmentId, Logs,
+        journal::{Journal, Restored},
+        selection::{
+            self, FragmentSelectionAlgorithm, FragmentSelectionAlgorithmParams, HighestFeeFirst,
+            OldestFirst,
+        },
+        Fragment, FragmentId, Logs, PoolEntry,
     },
     intercom::{NetworkMsg, PropagateMsg},
+    notifier::{Event, Notifier},
     utils::async_msg::MessageBox,
 };
 use chain_core::property::Fragment as _;
 use chain_impl_mockchain::{fragment::Contents, transaction::Transaction};
 use futures::channel::mpsc::SendError;
 use futures::sink::SinkExt;
-use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin, FragmentStatus};
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{EvictionStrategy, FragmentLog, FragmentOrigin, FragmentStatus},
+};
 use thiserror::Error;
 
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
 pub struct Pools {
     logs: Logs,
     pools: Vec<internal::Pool>,
     network_msg_box: MessageBox<NetworkMsg>,
+    journal: Option<Journal>,
+    eviction_strategy: EvictionStrategy,
+    fragment_ttl: Option<Duration>,
+    notifier: Option<Notifier>,
+    /// accounts spent from by the fragments not yet in a final status, only
+    /// kept to be reported with the status events
+    fragment_accounts: HashMap<FragmentId, Vec<String>>,
 }
 
 #[derive(Debug, Error)]
@@ -29,24 +47,131 @@ pub enum Error {
 impl Pools {
     pub fn new(
         max_entries: usize,
+        eviction_strategy: EvictionStrategy,
+        fragment_ttl: Option<Duration>,
         n_pools: usize,
         logs: Logs,
         network_msg_box: MessageBox<NetworkMsg>,
+        notifier: Option<Notifier>,
     ) -> Self {
         let pools = (0..=n_pools)
-            .map(|_| internal::Pool::new(max_entries))
+            .map(|_| internal::Pool::new(max_entries, eviction_strategy))
             .collect();
         Pools {
             logs,
             pools,
             network_msg_box,
+            journal: None,
+            eviction_strategy,
+            fragment_ttl,
+            notifier,
+            fragment_accounts: HashMap::new(),
         }
     }
 
+    /// Put back the fragments found in the mempool journal and keep journaling
+    /// the changes of the pool.
+    ///
+    /// The restored fragments are checked against the given ledger, the ones
+    /// that are no longer valid are marked as rejected.
+    pub fn restore(
+        &mut self,
+        journal: Journal,
+        restored: Restored,
+        ledger: &Ledger,
+        ledger_params: &LedgerParameters,
+        block_date: BlockDate,
+    ) {
+        let received_at = restored
+            .logs
+            .iter()
+            .map(|log| (*log.fragment_id(), SystemTime::from(*log.received_at())))
+            .collect::<HashMap<_, _>>();
+        self.logs.insert_all(restored.logs);
+
+        let mut ledger_simulation = ledger.clone();
+        let mut valid_fragments = Vec::new();
+        let mut rejected_ids = Vec::new();
+        let now = SystemTime::now();
+
+        for fragment in restored.fragments {
+            let id = fragment.id();
+            let key: Hash = id.clone().into();
+            // keep the time the fragment was first received so that it does
+            // not stay in the mempool longer than the fragment ttl
+            let fragment_received_at = received_at.get(&key).copied().unwrap_or(now);
+            let expired = self.fragment_ttl.and_then(|ttl| {
+                let age = fragment_received_at.elapsed().ok()?;
+                Some(age >= ttl)
+            });
+            let result = if expired == Some(true) {
+                Err("the fragment expired".to_owned())
+            } else if is_fragment_valid(&fragment) {
+                ledger_simulation
+                    .apply_fragment(ledger_params, &fragment, block_date)
+                    .map_err(|error| selection::error_chain_message(&error))
+            } else {
+                Err("the fragment is not accepted in the mempool".to_owned())
+            };
+            match result {
+                Ok(ledger_new) => {
+                    ledger_simulation = ledger_new;
+                    valid_fragments.push((fragment, fragment_received_at));
+                }
+                Err(reason) => {
+                    tracing::debug!(%id, %reason, "rejecting fragment restored from the mempool journal");
+                    self.logs.modify(
+                        id,
+                        FragmentStatus::Rejected {
+                            reason: format!(
+                                "fragment no longer valid after the node restart: {}",
+                                reason
+                            ),
+                        },
+                    );
+                    rejected_ids.push(id);
+                }
+            }
+        }
+
+        tracing::info!(
+            "restored {} fragments in the mempool, {} were rejected",
+            valid_fragments.len(),
+            rejected_ids.len()
+        );
+
+        let mut evicted_ids = Vec::new();
+        for pool in &mut self.pools {
+            let (_, evicted) = pool.insert_restored(valid_fragments.iter().cloned());
+            evicted_ids.extend(evicted);
+        }
+        self.reject_evicted(&evicted_ids);
+        rejected_ids.extend(evicted_ids);
+
+        if self.notifier.is_some() {
+            for (fragment, _) in &valid_fragments {
+                self.fragment_accounts
+                    .insert(fragment.id(), fragment_accounts(fragment));
+            }
+        }
+
+        self.journal = Some(journal);
+        self.update_logs(rejected_ids);
+    }
+
     pub fn logs(&mut self) -> &mut Logs {
         &mut self.logs
     }
 
+    /// Returns the number of fragments in the largest pool
+    pub fn size(&self) -> usize {
+        self.pools
+            .iter()
+            .map(internal::Pool::len)
+            .max()
+            .unwrap_or(0)
+    }
+
     /// Returns number of registered fragments
     pub async fn insert_and_propagate_all(
         &mut self,
@@ -66,19 +191,21 @@ impl Pools {
             .into_iter()
             .zip(fragments_exist_in_logs)
             .filter(|(_, exists_in_logs)| !exists_in_logs)
-            .map(|(fragment, _)| fragment);
+            .map(|(fragment, _)| fragment)
+            .collect::<Vec<_>>();
 
         let mut max_added = 0;
+        let mut evicted_ids = Vec::new();
 
         for (i, pool) in self.pools.iter_mut().enumerate() {
-            let new_fragments = pool.insert_all(new_fragments.clone());
-            let count = new_fragments.len();
+            let (inserted, evicted) = pool.insert_all(new_fragments.iter().cloned());
+            let count = inserted.len();
             tracing::debug!(
                 "{} of the received fragments were added to the pool number {}",
                 count,
                 i
             );
-            let fragment_logs = new_fragments
+            let fragment_logs = inserted
                 .iter()
                 .map(move |fragment| FragmentLog::new(fragment.id(), origin))
                 .collect::<Vec<_>>();
@@ -86,9 +213,43 @@ impl Pools {
             if count > max_added {
                 max_added = count;
             }
+            for fragment_id in evicted {
+                if !evicted_ids.contains(&fragment_id) {
+                    evicted_ids.push(fragment_id);
+                }
+            }
+        }
+
+        // only the fragments still in one of the pools are reported, journaled
+        // and propagated, the others were refused or already evicted
+        let accepted = new_fragments
+            .into_iter()
+            .filter(|fragment| {
+                let fragment_id = fragment.id();
+                self.pools.iter().any(|pool| pool.contains(&fragment_id))
+            })
+            .collect::<Vec<_>>();
+
+        // a fragment refused by all the pools has no log yet
+        for fragment_id in &evicted_ids {
+            self.logs
+                .insert(FragmentLog::new(fragment_id.clone(), origin));
+        }
+        self.reject_evicted(&evicted_ids);
+
+        if self.notifier.is_some() {
+            for fragment in &accepted {
+                self.fragment_accounts
+                    .insert(fragment.id(), fragment_accounts(fragment));
+            }
+            self.notify_statuses(accepted.iter().map(Fragment::id));
+        }
+        if self.journal.is_some() {
+            self.journal_fragments(accepted.iter().cloned());
         }
+        self.update_logs(evicted_ids);
 
-        for fragment in new_fragments.into_iter() {
+        for fragment in accepted {
             let fragment_msg = NetworkMsg::Propagate(PropagateMsg::Fragment(fragment));
             network_msg_box
                 .send(fragment_msg)
@@ -99,11 +260,64 @@ impl Pools {
         Ok(max_added)
     }
 
+    /// Remove the fragments that stayed in the mempool longer than the
+    /// fragment time-to-live, returns the number of expired fragments.
+    pub fn remove_expired(&mut self) -> usize {
+        let ttl = match self.fragment_ttl {
+            Some(ttl) => ttl,
+            None => return 0,
+        };
+        let mut expired_ids = Vec::new();
+        for pool in &mut self.pools {
+            for fragment_id in pool.remove_expired(ttl) {
+                if !expired_ids.contains(&fragment_id) {
+                    expired_ids.push(fragment_id);
+                }
+            }
+        }
+        let reason = format!(
+            "fragment expired after staying {} in the mempool",
+            jormungandr_lib::time::Duration::from(ttl)
+        );
+        self.logs.modify_all(
+            expired_ids.iter().cloned(),
+            FragmentStatus::Rejected { reason },
+        );
+        let count = expired_ids.len();
+        self.update_logs(expired_ids);
+        count
+    }
+
+    /// Mark the evicted fragments that are no longer in any pool as rejected.
+    fn reject_evicted(&mut self, fragment_ids: &[FragmentId]) {
+        let reason = match self.eviction_strategy {
+            EvictionStrategy::Oldest => {
+                "fragment evicted from the full mempool to make room for newer fragments"
+            }
+            EvictionStrategy::LowestFee => {
+                "fragment evicted from the full mempool by fragments paying a higher fee"
+            }
+        };
+        for fragment_id in fragment_ids {
+            if self.pools.iter().any(|pool| pool.contains(fragment_id)) {
+                continue;
+            }
+            tracing::debug!(%fragment_id, "fragment evicted from the mempool");
+            self.logs.modify(
+                fragment_id.clone(),
+                FragmentStatus::Rejected {
+                    reason: reason.to_owned(),
+                },
+            );
+        }
+    }
+
     pub fn remove_added_to_block(&mut self, fragment_ids: Vec<FragmentId>, status: FragmentStatus) {
         for pool in &mut self.pools {
             pool.remove_all(fragment_ids.iter());
         }
-        self.logs.modify_all(fragment_ids, status);
+        self.logs.modify_all(fragment_ids.iter().cloned(), status);
+        self.update_logs(fragment_ids);
     }
 
     pub fn select(
@@ -116,16 +330,112 @@ impl Pools {
     ) -> Contents {
         let Pools { logs, pools, .. } = self;
         let pool = &mut pools[pool_idx];
-        match selection_alg {
+        let (contents, rejected_ids) = match selection_alg {
             FragmentSelectionAlgorithmParams::OldestFirst => {
                 let mut selection_alg = OldestFirst::new();
                 selection_alg.select(&ledger, &ledger_params, block_date, logs, pool);
-                selection_alg.finalize()
+                let rejected_ids = selection_alg.rejected().to_vec();
+                (selection_alg.finalize(), rejected_ids)
+            }
+            FragmentSelectionAlgorithmParams::HighestFeeFirst => {
+                let mut selection_alg = HighestFeeFirst::new();
+                selection_alg.select(&ledger, &ledger_params, block_date, logs, pool);
+                let rejected_ids = selection_alg.rejected().to_vec();
+                (selection_alg.finalize(), rejected_ids)
+            }
+        };
+        // the included fragments stay pending until the block is applied,
+        // only the rejected ones have a new status to report and journal
+        self.update_logs(rejected_ids);
+        contents
+    }
+
+    fn journal_fragments(&mut self, fragments: impl IntoIterator<Item = Fragment>) {
+        let Pools { logs, journal, .. } = self;
+        if let Some(journal) = journal {
+            let fragments = fragments.into_iter().collect::<Vec<_>>();
+            let fragment_logs = logs.logs_by_ids(fragments.iter().map(Fragment::id));
+            let entries = fragments.iter().filter_map(|fragment| {
+                fragment_logs
+                    .get(&fragment.id())
+                    .map(|log| (fragment, *log))
+            });
+            if let Err(error) = journal.append_fragments(entries) {
+                tracing::error!(%error, "cannot write fragments to the mempool journal");
+            }
+        }
+        self.compact_journal();
+    }
+
+    /// Record the changes of the logs of the given fragments.
+    fn update_logs(&mut self, fragment_ids: Vec<FragmentId>) {
+        self.notify_statuses(fragment_ids.iter().cloned());
+        self.journal_logs(fragment_ids);
+    }
+
+    fn notify_statuses(&mut self, fragment_ids: impl IntoIterator<Item = FragmentId>) {
+        let Pools {
+            logs,
+            notifier,
+            fragment_accounts,
+            ..
+        } = self;
+        if let Some(notifier) = notifier {
+            for (fragment_id, log) in logs.logs_by_ids(fragment_ids) {
+                let accounts = if log.is_pending() {
+                    fragment_accounts.get(&fragment_id).cloned()
+                } else {
+                    fragment_accounts.remove(&fragment_id)
+                };
+                notifier.notify(Event::fragment_status(
+                    fragment_id,
+                    log.status().clone(),
+                    accounts.unwrap_or_default(),
+                ));
+            }
+        }
+    }
+
+    fn journal_logs(&mut self, fragment_ids: impl IntoIterator<Item = FragmentId>) {
+        let Pools { logs, journal, .. } = self;
+        if let Some(journal) = journal {
+            let fragment_logs = logs.logs_by_ids(fragment_ids);
+            if let Err(error) = journal.append_logs(fragment_logs.values().copied()) {
+                tracing::error!(%error, "cannot write fragment logs to the mempool journal");
+            }
+        }
+        self.compact_journal();
+    }
+
+    fn compact_journal(&mut self) {
+        let Pools {
+            logs,
+            pools,
+            journal,
+            ..
+        } = self;
+        if let Some(journal) = journal {
+            if !journal.needs_compaction() {
+                return;
+            }
+            // oldest first, so the restored logs keep their eviction order
+            let fragment_logs = logs.logs().collect::<Vec<_>>();
+            let fragments = pools.iter().flat_map(|pool| pool.fragments());
+            if let Err(error) = journal.rewrite(fragments, fragment_logs.into_iter().rev()) {
+                tracing::error!(%error, "cannot compact the mempool journal");
             }
         }
     }
 }
 
+/// Hex encoded identifiers of the accounts spent from by the fragment.
+fn fragment_accounts(fragment: &Fragment) -> Vec<String> {
+    selection::fragment_accounts(fragment)
+        .iter()
+        .map(hex::encode)
+        .collect()
+}
+
 fn is_fragment_valid(fragment: &Fragment) -> bool {
     match fragment {
         // never valid in the pool, only acceptable in genesis
@@ -154,49 +464,359 @@ fn is_transaction_valid<E>(tx: &Transaction<E>) -> bool {
 
 pub(super) mod internal {
     use super::*;
+    use crate::fragment::entry::FeeRate;
     use lru::LruCache;
+    use std::collections::BTreeSet;
+
+    /// key of the fee index, the oldest entry comes first among the entries
+    /// paying the same fee rate
+    type FeeKey = (FeeRate, SystemTime, FragmentId);
 
     pub struct Pool {
-        entries: LruCache<FragmentId, Fragment>,
+        entries: LruCache<FragmentId, (Fragment, PoolEntry)>,
+        /// the entries ordered by fee rate, only maintained for the
+        /// `LowestFee` eviction strategy
+        by_fee: BTreeSet<FeeKey>,
+        max_entries: usize,
+        eviction_strategy: EvictionStrategy,
     }
 
     impl Pool {
-        pub fn new(max_entries: usize) -> Self {
+        pub fn new(max_entries: usize, eviction_strategy: EvictionStrategy) -> Self {
             Pool {
-                entries: LruCache::new(max_entries),
+                entries: LruCache::unbounded(),
+                by_fee: BTreeSet::new(),
+                max_entries,
+                eviction_strategy,
             }
         }
 
-        /// Returns clone of fragment if it was registered
-        pub fn insert(&mut self, fragment: Fragment) -> Option<Fragment> {
-            let fragment_id = fragment.id();
-            if self.entries.contains(&fragment_id) {
-                None
-            } else {
-                self.entries.put(fragment_id, fragment.clone());
-                Some(fragment)
-            }
-        }
-
-        /// Returns clones of registered fragments
+        /// Returns clones of registered fragments and the identifiers of the
+        /// fragments evicted to make room for them. A fragment refused because
+        /// the pool is full is counted as evicted.
         pub fn insert_all(
             &mut self,
             fragments: impl IntoIterator<Item = Fragment>,
-        ) -> Vec<Fragment> {
-            fragments
-                .into_iter()
-                .filter_map(|fragment| self.insert(fragment))
-                .collect()
+        ) -> (Vec<Fragment>, Vec<FragmentId>) {
+            let now = SystemTime::now();
+            self.insert_restored(fragments.into_iter().map(|fragment| (fragment, now)))
+        }
+
+        /// Same as `insert_all` for fragments received at the given times,
+        /// e.g. the fragments restored from the mempool journal.
+        pub fn insert_restored(
+            &mut self,
+            fragments: impl IntoIterator<Item = (Fragment, SystemTime)>,
+        ) -> (Vec<Fragment>, Vec<FragmentId>) {
+            let mut inserted = Vec::new();
+            let mut evicted = Vec::new();
+            for (fragment, received_at) in fragments {
+                let fragment_id = fragment.id();
+                if self.entries.contains(&fragment_id) {
+                    continue;
+                }
+                let entry = PoolEntry::new_received_at(&fragment, received_at);
+                if self.entries.len() >= self.max_entries {
+                    match self.evict_for(&entry) {
+                        Some(evicted_id) => evicted.push(evicted_id),
+                        None => {
+                            evicted.push(fragment_id);
+                            continue;
+                        }
+                    }
+                }
+                self.put(fragment.clone(), entry);
+                inserted.push(fragment);
+            }
+            (inserted, evicted)
+        }
+
+        /// Put back fragments previously taken out of the pool with
+        /// `remove_oldest`.
+        pub fn return_all(&mut self, entries: impl IntoIterator<Item = (Fragment, PoolEntry)>) {
+            for (fragment, entry) in entries {
+                self.put(fragment, entry);
+            }
+        }
+
+        /// Remove an entry to make room for the given one, returns `None` if
+        /// the given entry should not be accepted instead.
+        fn evict_for(&mut self, entry: &PoolEntry) -> Option<FragmentId> {
+            match self.eviction_strategy {
+                EvictionStrategy::Oldest => self.entries.pop_lru().map(|(id, _)| id),
+                EvictionStrategy::LowestFee => {
+                    let (lowest_fee_rate, _, lowest_id) = self.by_fee.iter().next()?.clone();
+                    if lowest_fee_rate >= entry.fee_rate() {
+                        return None;
+                    }
+                    self.pop(&lowest_id);
+                    Some(lowest_id)
+                }
+            }
+        }
+
+        /// Remove the fragments received more than `ttl` ago.
+        pub fn remove_expired(&mut self, ttl: Duration) -> Vec<FragmentId> {
+            let expired = self
+                .entries
+                .iter()
+                .filter(|(_, (_, entry))| {
+                    entry
+                        .received_at()
+                        .elapsed()
+                        .map_or(false, |age| age >= ttl)
+                })
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>();
+            self.remove_all(expired.iter());
+            expired
         }
 
         pub fn remove_all<'a>(&mut self, fragment_ids: impl IntoIterator<Item = &'a FragmentId>) {
             for fragment_id in fragment_ids {
-                self.entries.pop(fragment_id);
+                self.pop(fragment_id);
+            }
+        }
+
+        pub fn remove_oldest(&mut self) -> Option<(Fragment, PoolEntry)> {
+            let (fragment_id, value) = self.entries.pop_lru()?;
+            self.unindex(&fragment_id, &value.1);
+            Some(value)
+        }
+
+        pub fn len(&self) -> usize {
+            self.entries.len()
+        }
+
+        pub fn contains(&self, fragment_id: &FragmentId) -> bool {
+            self.entries.contains(fragment_id)
+        }
+
+        pub fn fragments(&self) -> impl Iterator<Item = &Fragment> {
+            self.entries.iter().map(|(_, (fragment, _))| fragment)
+        }
+
+        fn put(&mut self, fragment: Fragment, entry: PoolEntry) {
+            let fragment_id = fragment.id();
+            let key = fee_key(&fragment_id, &entry);
+            if let Some((_, replaced)) = self.entries.put(fragment_id.clone(), (fragment, entry)) {
+                self.unindex(&fragment_id, &replaced);
+            }
+            if self.eviction_strategy == EvictionStrategy::LowestFee {
+                self.by_fee.insert(key);
+            }
+        }
+
+        fn pop(&mut self, fragment_id: &FragmentId) -> Option<(Fragment, PoolEntry)> {
+            let value = self.entries.pop(fragment_id)?;
+            self.unindex(fragment_id, &value.1);
+            Some(value)
+        }
+
+        fn unindex(&mut self, fragment_id: &FragmentId, entry: &PoolEntry) {
+            if self.eviction_strategy == EvictionStrategy::LowestFee {
+                self.by_fee.remove(&fee_key(fragment_id, entry));
+            }
+        }
+    }
+
+    fn fee_key(fragment_id: &FragmentId, entry: &PoolEntry) -> FeeKey {
+        (entry.fee_rate(), *entry.received_at(), fragment_id.clone())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::fragment::pool::tests::fragment;
+
+        /// insert the fragments one by one so that they are received at
+        /// different times
+        fn insert(pool: &mut Pool, fragments: &[Fragment]) -> Vec<FragmentId> {
+            let mut evicted = Vec::new();
+            for fragment in fragments {
+                std::thread::sleep(Duration::from_millis(1));
+                evicted.extend(pool.insert_all(vec![fragment.clone()]).1);
             }
+            evicted
+        }
+
+        fn ids(fragments: &[&Fragment]) -> Vec<FragmentId> {
+            fragments.iter().map(|fragment| fragment.id()).collect()
+        }
+
+        #[test]
+        fn lowest_fee_is_evicted_first() {
+            let mut pool = Pool::new(3, EvictionStrategy::LowestFee);
+            let high = fragment(50, 1);
+            let low_old = fragment(30, 2);
+            let low_new = fragment(30, 3);
+            assert!(
+                insert(&mut pool, &[high.clone(), low_old.clone(), low_new.clone()]).is_empty()
+            );
+
+            // the oldest of the lowest fee entries makes room
+            let medium = fragment(40, 4);
+            assert_eq!(insert(&mut pool, &[medium.clone()]), ids(&[&low_old]));
+            assert_eq!(insert(&mut pool, &[fragment(45, 5)]), ids(&[&low_new]));
+
+            // not paying more than the lowest fee entry, refused
+            let lowest = fragment(40, 6);
+            assert_eq!(insert(&mut pool, &[lowest.clone()]), ids(&[&lowest]));
+            assert!(!pool.contains(&lowest.id()));
+            assert!(pool.contains(&medium.id()));
+            assert!(pool.contains(&high.id()));
+            assert_eq!(pool.len(), 3);
+        }
+
+        #[test]
+        fn evicted_entries_leave_the_fee_index() {
+            let mut pool = Pool::new(2, EvictionStrategy::LowestFee);
+            let low = fragment(10, 1);
+            let high = fragment(20, 2);
+            insert(&mut pool, &[low.clone(), high.clone()]);
+
+            // the removed entries must not be evicted again
+            pool.remove_all(vec![low.id()].iter());
+            assert!(insert(&mut pool, &[fragment(15, 3)]).is_empty());
+            let (oldest, _) = pool.remove_oldest().unwrap();
+            assert_eq!(oldest.id(), high.id());
+            assert!(insert(&mut pool, &[fragment(12, 4)]).is_empty());
+            assert_eq!(pool.by_fee.len(), pool.len());
+        }
+
+        #[test]
+        fn oldest_is_evicted_first() {
+            let mut pool = Pool::new(2, EvictionStrategy::Oldest);
+            let oldest = fragment(50, 1);
+            let newer = fragment(10, 2);
+            insert(&mut pool, &[oldest.clone(), newer.clone()]);
+
+            let newest = fragment(1, 3);
+            assert_eq!(insert(&mut pool, &[newest.clone()]), ids(&[&oldest]));
+            assert!(pool.contains(&newer.id()));
+            assert!(pool.contains(&newest.id()));
+            assert!(pool.by_fee.is_empty());
+        }
+
+        #[test]
+        fn expired_entries_are_removed() {
+            let mut pool = Pool::new(10, EvictionStrategy::LowestFee);
+            let first = fragment(10, 1);
+            let second = fragment(20, 2);
+            insert(&mut pool, &[first.clone(), second.clone()]);
+
+            assert!(pool.remove_expired(Duration::from_secs(3600)).is_empty());
+            assert_eq!(pool.len(), 2);
+
+            let mut expired = pool.remove_expired(Duration::from_secs(0));
+            expired.sort();
+            let mut expected = ids(&[&first, &second]);
+            expected.sort();
+            assert_eq!(expired, expected);
+            assert_eq!(pool.len(), 0);
+            assert!(pool.by_fee.is_empty());
         }
 
-        pub fn remove_oldest(&mut self) -> Option<Fragment> {
-            self.entries.pop_lru().map(|(_, value)| value)
+        #[test]
+        fn restored_entries_expire_from_their_reception() {
+            let ttl = Duration::from_secs(3600);
+            let mut pool = Pool::new(10, EvictionStrategy::LowestFee);
+            let restored = fragment(10, 1);
+            let received_at = SystemTime::now() - ttl + Duration::from_millis(200);
+            pool.insert_restored(vec![(restored.clone(), received_at)]);
+            let fresh = fragment(20, 2);
+            insert(&mut pool, &[fresh.clone()]);
+
+            assert!(pool.remove_expired(ttl).is_empty());
+            std::thread::sleep(Duration::from_millis(300));
+            assert_eq!(pool.remove_expired(ttl), ids(&[&restored]));
+            assert!(pool.contains(&fresh.id()));
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockcfg::{HeaderId, Value},
+        utils::async_msg,
+    };
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::transaction::{Input, TxBuilder, Witness, INPUT_PTR_SIZE};
+    use futures::stream::StreamExt;
+
+    /// a transaction spending `fee` from a single input without outputs,
+    /// all of them have the same size
+    pub(super) fn fragment(fee: u64, n: u8) -> Fragment {
+        let key = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let input = Input::new(0, Value(fee), [n; INPUT_PTR_SIZE]);
+        let builder = TxBuilder::new().set_nopayload().set_ios(&[input], &[]);
+        let sign_data_hash = builder.get_auth_data_for_witness().hash();
+        let witness =
+            Witness::new_utxo(&HeaderId::hash_bytes(&[]), &sign_data_hash, |d| key.sign(d));
+        Fragment::Transaction(builder.set_witnesses(&[witness]).set_payload_auth(&()))
+    }
+
+    #[tokio::test]
+    async fn refused_fragments_are_neither_journaled_nor_propagated() {
+        let journal_dir =
+            std::env::temp_dir().join(format!("jormungandr-pool-refused-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&journal_dir);
+        let (journal, _) = Journal::open(&journal_dir).unwrap();
+
+        let (network_msg_box, network_msgs) = async_msg::channel(16);
+        // two full pools of a single entry
+        let mut pools = Pools::new(
+            1,
+            EvictionStrategy::LowestFee,
+            None,
+            1,
+            Logs::new(16),
+            network_msg_box,
+            None,
+        );
+        pools.journal = Some(journal);
+
+        let accepted = fragment(20, 1);
+        let refused = fragment(10, 2);
+        let added = pools
+            .insert_and_propagate_all(FragmentOrigin::Rest, vec![accepted.clone()])
+            .await
+            .unwrap();
+        assert_eq!(added, 1);
+        let added = pools
+            .insert_and_propagate_all(FragmentOrigin::Rest, vec![refused.clone()])
+            .await
+            .unwrap();
+        assert_eq!(added, 0);
+
+        let logs = pools.logs().logs_by_ids(vec![accepted.id(), refused.id()]);
+        assert!(logs[&accepted.id()].is_pending());
+        assert!(logs[&refused.id()].is_rejected());
+        drop(pools);
+
+        let propagated = network_msgs
+            .map(|msg| match msg {
+                NetworkMsg::Propagate(PropagateMsg::Fragment(fragment)) => fragment.id(),
+                _ => panic!("unexpected network message"),
+            })
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(propagated, vec![accepted.id()]);
+
+        let (_, restored) = Journal::open(&journal_dir).unwrap();
+        let journaled = restored
+            .fragments
+            .iter()
+            .map(Fragment::id)
+            .collect::<Vec<_>>();
+        assert_eq!(journaled, vec![accepted.id()]);
+        assert!(restored
+            .logs
+            .iter()
+            .any(|log| *log.fragment_id() == Hash::from(refused.id()) && log.is_rejected()));
+
+        std::fs::remove_dir_all(journal_dir).unwrap();
+    }
+}
@@ -1,6 +1,8 @@
 use crate::{
-    fragment::{Logs, Pools},
+    blockchain::Tip,
+    fragment::{journal::Journal, Logs, Pools},
     intercom::{NetworkMsg, TransactionMsg},
+    notifier::Notifier,
     stats_counter::StatsCounter,
     utils::{
         async_msg::{MessageBox, MessageQueue},
@@ -8,36 +10,60 @@ use crate::{
     },
 };
 
+use jormungandr_lib::interfaces::EvictionStrategy;
+
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 
+use futures::FutureExt;
 use thiserror::Error;
 use tokio_stream::StreamExt;
 use tracing::{span, Level};
 use tracing_futures::Instrument;
 
+/// how often the pool is checked for expired fragments
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 pub struct Process {
     pool_max_entries: usize,
+    eviction_strategy: EvictionStrategy,
+    fragment_ttl: Option<Duration>,
     logs: Logs,
+    journal_dir: Option<PathBuf>,
     network_msg_box: MessageBox<NetworkMsg>,
+    notifier: Option<Notifier>,
 }
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("transaction pool error")]
     Pool(#[from] crate::fragment::pool::Error),
+    #[error("cannot open the mempool journal")]
+    Journal(#[from] crate::fragment::journal::Error),
+    #[error("the mempool journal opening task failed")]
+    JournalTask(#[from] tokio::task::JoinError),
 }
 
 impl Process {
     pub fn new(
         pool_max_entries: usize,
+        eviction_strategy: EvictionStrategy,
+        fragment_ttl: Option<Duration>,
         logs_max_entries: usize,
+        journal_dir: Option<PathBuf>,
         network_msg_box: MessageBox<NetworkMsg>,
+        notifier: Option<Notifier>,
     ) -> Self {
         let logs = Logs::new(logs_max_entries);
         Process {
             pool_max_entries,
+            eviction_strategy,
+            fragment_ttl,
             logs,
+            journal_dir,
             network_msg_box,
+            notifier,
         }
     }
 
@@ -46,70 +72,119 @@ impl Process {
         n_pools: usize,
         service_info: TokioServiceInfo,
         stats_counter: StatsCounter,
+        tip: Tip,
         mut input: MessageQueue<TransactionMsg>,
     ) -> Result<(), Error> {
         let mut pool = Pools::new(
             self.pool_max_entries,
+            self.eviction_strategy,
+            self.fragment_ttl,
             n_pools,
             self.logs,
             self.network_msg_box,
+            self.notifier,
         );
 
+        if let Some(journal_dir) = self.journal_dir {
+            let (journal, restored) = {
+                let journal_dir = journal_dir.clone();
+                tokio::task::spawn_blocking(move || Journal::open(journal_dir)).await??
+            };
+            let tip_ref = tip.get_ref().await;
+            tracing::info!(
+                "restoring {} pending fragments from the mempool journal in '{:?}'",
+                restored.fragments.len(),
+                journal_dir
+            );
+            pool.restore(
+                journal,
+                restored,
+                &tip_ref.ledger(),
+                tip_ref.epoch_ledger_parameters(),
+                tip_ref.block_date(),
+            );
+        }
+
         async move {
-            while let Some(input_result) = input.next().await {
-                match input_result {
-                    TransactionMsg::SendTransaction(origin, txs) => {
-                        // Note that we cannot use apply_block here, since we don't have a valid context to which to apply
-                        // those blocks. one valid tx in a given context, could be invalid in another. for example
-                        // fee calculations, existence utxo / account solvency.
+            let mut expiry_check = tokio::time::interval(EXPIRY_CHECK_INTERVAL);
+            loop {
+                futures::select! {
+                    _ = expiry_check.tick().fuse() => {
+                        let expired = pool.remove_expired();
+                        if expired > 0 {
+                            tracing::debug!(
+                                "removed {} expired fragments from the mempool",
+                                expired
+                            );
+                        }
+                    },
+                    input_result = input.next().fuse() => {
+                        let input_result = match input_result {
+                            Some(input_result) => input_result,
+                            None => break,
+                        };
+                        match input_result {
+                            TransactionMsg::SendTransaction(origin, txs) => {
+                                // Note that we cannot use apply_block here, since we don't have a valid context to which to apply
+                                // those blocks. one valid tx in a given context, could be invalid in another. for example
+                                // fee calculations, existence utxo / account solvency.
 
-                        // FIXME/TODO check that the txs are valid within themselves with basic requirements (e.g. inputs >= outputs).
-                        // we also want to keep a basic capability to filter away repetitive queries or definitely discarded txid.
+                                // FIXME/TODO check that the txs are valid within themselves with basic requirements (e.g. inputs >= outputs).
+                                // we also want to keep a basic capability to filter away repetitive queries or definitely discarded txid.
 
-                        // This interface only makes sense for messages coming from arbitrary users (like transaction, certificates),
-                        // for other message we don't want to receive them through this interface, and possibly
-                        // put them in another pool.
+                                // This interface only makes sense for messages coming from arbitrary users (like transaction, certificates),
+                                // for other message we don't want to receive them through this interface, and possibly
+                                // put them in another pool.
 
-                        let stats_counter = stats_counter.clone();
+                                let stats_counter = stats_counter.clone();
 
-                        pool.insert_and_propagate_all(origin, txs)
-                            .await
-                            .map(move |count| stats_counter.add_tx_recv_cnt(count))?;
-                    }
-                    TransactionMsg::RemoveTransactions(fragment_ids, status) => {
-                        tracing::debug!(
-                            "removing fragments added to block {:?}: {:?}",
-                            status,
-                            fragment_ids
-                        );
-                        pool.remove_added_to_block(fragment_ids, status);
-                    }
-                    TransactionMsg::GetLogs(reply_handle) => {
-                        let logs = pool.logs().logs().cloned().collect();
-                        reply_handle.reply_ok(logs);
-                    }
-                    TransactionMsg::GetStatuses(fragment_ids, reply_handle) => {
-                        let mut statuses = HashMap::new();
-                        pool.logs().logs_by_ids(fragment_ids).into_iter().for_each(
-                            |(fragment_id, log)| {
-                                statuses.insert(fragment_id, log.status().clone());
-                            },
-                        );
-                        reply_handle.reply_ok(statuses);
-                    }
-                    TransactionMsg::SelectTransactions {
-                        pool_idx,
-                        ledger,
-                        block_date,
-                        ledger_params,
-                        selection_alg,
-                        reply_handle,
-                    } => {
-                        let contents =
-                            pool.select(pool_idx, ledger, block_date, ledger_params, selection_alg);
-                        reply_handle.reply_ok(contents);
-                    }
+                                pool.insert_and_propagate_all(origin, txs)
+                                    .await
+                                    .map(move |count| stats_counter.add_tx_recv_cnt(count))?;
+                            }
+                            TransactionMsg::RemoveTransactions(fragment_ids, status) => {
+                                tracing::debug!(
+                                    "removing fragments added to block {:?}: {:?}",
+                                    status,
+                                    fragment_ids
+                                );
+                                pool.remove_added_to_block(fragment_ids, status);
+                            }
+                            TransactionMsg::GetLogs(reply_handle) => {
+                                let logs = pool.logs().logs().cloned().collect();
+                                reply_handle.reply_ok(logs);
+                            }
+                            TransactionMsg::GetStatuses(fragment_ids, reply_handle) => {
+                                let mut statuses = HashMap::new();
+                                pool.logs()
+                                    .logs_by_ids(fragment_ids)
+                                    .into_iter()
+                                    .for_each(|(fragment_id, log)| {
+                                        statuses.insert(fragment_id, log.status().clone());
+                                    });
+                                reply_handle.reply_ok(statuses);
+                            }
+                            TransactionMsg::SelectTransactions {
+                                pool_idx,
+                                ledger,
+                                block_date,
+                                ledger_params,
+                                selection_alg,
+                                reply_handle,
+                            } => {
+                                let contents = pool.select(
+                                    pool_idx,
+                                    ledger,
+                                    block_date,
+                                    ledger_params,
+                                    selection_alg,
+                                );
+                                reply_handle.reply_ok(contents);
+                            }
+                        }
+                    },
                 }
+                stats_counter.set_mempool_usage(pool.size());
             }
             Ok(())
         }
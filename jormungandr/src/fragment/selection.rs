@@ -2,14 +2,18 @@ use super::logs::Logs;
 use super::pool::internal::Pool;
 use crate::{
     blockcfg::{BlockDate, Contents, ContentsBuilder, Ledger, LedgerParameters},
-    fragment::FragmentId,
+    fragment::{entry::FeeRate, Fragment, FragmentId},
 };
 use chain_core::property::Fragment as _;
-use jormungandr_lib::interfaces::FragmentStatus;
+use chain_impl_mockchain::transaction::{InputEnum, Transaction, UnspecifiedAccountIdentifier};
+use jormungandr_lib::interfaces::{FragmentStatus, SelectionAlgorithm};
 
 use tracing::{span, Level};
 
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::error::Error;
+use std::hash::Hash;
 use std::iter;
 
 pub enum SelectionOutput {
@@ -29,17 +33,34 @@ pub trait FragmentSelectionAlgorithm {
         pool: &mut Pool,
     );
 
+    /// the fragments rejected by the selection, they are no longer in the
+    /// pool
+    fn rejected(&self) -> &[FragmentId];
+
     fn finalize(self) -> Contents;
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub enum FragmentSelectionAlgorithmParams {
     OldestFirst,
+    HighestFeeFirst,
+}
+
+impl From<SelectionAlgorithm> for FragmentSelectionAlgorithmParams {
+    fn from(selection_algorithm: SelectionAlgorithm) -> Self {
+        match selection_algorithm {
+            SelectionAlgorithm::OldestFirst => FragmentSelectionAlgorithmParams::OldestFirst,
+            SelectionAlgorithm::HighestFeeFirst => {
+                FragmentSelectionAlgorithmParams::HighestFeeFirst
+            }
+        }
+    }
 }
 
 pub struct OldestFirst {
     builder: ContentsBuilder,
     current_total_size: u32,
+    rejected: Vec<FragmentId>,
 }
 
 impl OldestFirst {
@@ -47,6 +68,7 @@ impl OldestFirst {
         OldestFirst {
             builder: ContentsBuilder::new(),
             current_total_size: 0,
+            rejected: Vec::new(),
         }
     }
 }
@@ -58,6 +80,10 @@ impl Default for OldestFirst {
 }
 
 impl FragmentSelectionAlgorithm for OldestFirst {
+    fn rejected(&self) -> &[FragmentId] {
+        &self.rejected
+    }
+
     fn finalize(self) -> Contents {
         self.builder.into()
     }
@@ -74,10 +100,9 @@ impl FragmentSelectionAlgorithm for OldestFirst {
 
         let mut return_to_pool = Vec::new();
 
-        while let Some(fragment) = pool.remove_oldest() {
+        while let Some((fragment, entry)) = pool.remove_oldest() {
             let id = fragment.id();
-            let fragment_raw = fragment.to_raw(); // TODO: replace everything to FragmentRaw in the node
-            let fragment_size = fragment_raw.size_bytes_plus_size() as u32;
+            let fragment_size = *entry.fragment_size() as u32;
 
             let span = span!(Level::TRACE, "fragment_selection_algorithm", kind="older_first", hash=%id.to_string());
             let _enter = span.enter();
@@ -87,7 +112,8 @@ impl FragmentSelectionAlgorithm for OldestFirst {
                     fragment_size, ledger_params.block_content_max_size
                 );
                 tracing::debug!("{}", reason);
-                logs.modify(id, FragmentStatus::Rejected { reason });
+                logs.modify(id.clone(), FragmentStatus::Rejected { reason });
+                self.rejected.push(id);
                 continue;
             }
 
@@ -102,13 +128,14 @@ impl FragmentSelectionAlgorithm for OldestFirst {
                         tracing::debug!("successfully applied and committed the fragment");
                     }
                     Err(error) => {
-                        let mut msg = error.to_string();
-                        for e in iter::successors(error.source(), |&e| e.source()) {
-                            msg.push_str(": ");
-                            msg.push_str(&e.to_string());
-                        }
                         tracing::debug!(?error, "fragment is rejected");
-                        logs.modify(id, FragmentStatus::Rejected { reason: msg })
+                        logs.modify(
+                            id.clone(),
+                            FragmentStatus::Rejected {
+                                reason: error_chain_message(&error),
+                            },
+                        );
+                        self.rejected.push(id);
                     }
                 }
 
@@ -119,11 +146,322 @@ impl FragmentSelectionAlgorithm for OldestFirst {
                 }
             } else {
                 // return a fragment to the pool later if does not fit the contents size limit
-                return_to_pool.push(fragment);
+                return_to_pool.push((fragment, entry));
             }
             drop(_enter);
         }
 
-        pool.insert_all(return_to_pool);
+        pool.return_all(return_to_pool);
+    }
+}
+
+/// Selects the fragments paying the highest fee per byte first.
+///
+/// Fragments spending from the same account are kept in their arrival order
+/// so that the account counters they were signed with stay valid: a fragment
+/// only becomes a candidate once all the earlier fragments spending from the
+/// same accounts have been committed or rejected.
+pub struct HighestFeeFirst {
+    builder: ContentsBuilder,
+    current_total_size: u32,
+    rejected: Vec<FragmentId>,
+}
+
+impl HighestFeeFirst {
+    pub fn new() -> Self {
+        HighestFeeFirst {
+            builder: ContentsBuilder::new(),
+            current_total_size: 0,
+            rejected: Vec::new(),
+        }
+    }
+}
+
+impl Default for HighestFeeFirst {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FragmentSelectionAlgorithm for HighestFeeFirst {
+    fn rejected(&self) -> &[FragmentId] {
+        &self.rejected
+    }
+
+    fn finalize(self) -> Contents {
+        self.builder.into()
+    }
+
+    fn select(
+        &mut self,
+        ledger: &Ledger,
+        ledger_params: &LedgerParameters,
+        block_date: BlockDate,
+        logs: &mut Logs,
+        pool: &mut Pool,
+    ) {
+        let mut ledger_simulation = ledger.clone();
+
+        let mut candidates = Vec::new();
+        while let Some(candidate) = pool.remove_oldest() {
+            candidates.push(Some(candidate));
+        }
+
+        let mut queue = ReadyQueue::new(candidates.iter().map(|candidate| {
+            let (fragment, entry) = candidate.as_ref().expect("all candidates are present");
+            (entry.fee_rate(), fragment_accounts(fragment))
+        }));
+
+        let mut return_to_pool = Vec::new();
+
+        while let Some(index) = queue.pop() {
+            let (fragment, entry) = candidates[index]
+                .take()
+                .expect("fragment is selected only once");
+            let id = fragment.id();
+            let fragment_size = *entry.fragment_size() as u32;
+
+            let span = span!(Level::TRACE, "fragment_selection_algorithm", kind="highest_fee_first", hash=%id.to_string());
+            let _enter = span.enter();
+
+            if fragment_size > ledger_params.block_content_max_size {
+                let reason = format!(
+                    "fragment size {} exceeds maximum block content size {}",
+                    fragment_size, ledger_params.block_content_max_size
+                );
+                tracing::debug!("{}", reason);
+                logs.modify(id.clone(), FragmentStatus::Rejected { reason });
+                self.rejected.push(id);
+            } else if self.current_total_size + fragment_size > ledger_params.block_content_max_size
+            {
+                // the fragment goes back to the pool and keeps blocking the
+                // later fragments spending from the same accounts
+                return_to_pool.push((index, fragment, entry));
+                continue;
+            } else {
+                tracing::debug!("applying fragment in simulation");
+                match ledger_simulation.apply_fragment(ledger_params, &fragment, block_date) {
+                    Ok(ledger_new) => {
+                        self.builder.push(fragment);
+                        ledger_simulation = ledger_new;
+                        tracing::debug!("successfully applied and committed the fragment");
+                    }
+                    Err(error) => {
+                        tracing::debug!(?error, "fragment is rejected");
+                        logs.modify(
+                            id.clone(),
+                            FragmentStatus::Rejected {
+                                reason: error_chain_message(&error),
+                            },
+                        );
+                        self.rejected.push(id);
+                    }
+                }
+
+                self.current_total_size += fragment_size;
+            }
+            drop(_enter);
+
+            if self.current_total_size == ledger_params.block_content_max_size {
+                break;
+            }
+
+            queue.release(index);
+        }
+
+        // keep the arrival order of the fragments that were not selected
+        return_to_pool.extend(candidates.into_iter().enumerate().filter_map(
+            |(index, candidate)| candidate.map(|(fragment, entry)| (index, fragment, entry)),
+        ));
+        return_to_pool.sort_by_key(|(index, _, _)| *index);
+
+        pool.return_all(
+            return_to_pool
+                .into_iter()
+                .map(|(_, fragment, entry)| (fragment, entry)),
+        );
+    }
+}
+
+/// Orders candidates by decreasing fee rate, the earliest received first
+/// among the candidates paying the same fee rate.
+///
+/// A candidate is ready once it is at the front of the queues of all the
+/// accounts it spends from, i.e. once all the candidates received before it
+/// and spending from the same accounts have been released.
+struct ReadyQueue<A> {
+    fee_rates: Vec<FeeRate>,
+    accounts: Vec<Vec<A>>,
+    account_queues: HashMap<A, VecDeque<usize>>,
+    ready: BinaryHeap<(FeeRate, Reverse<usize>)>,
+}
+
+impl<A: Clone + Eq + Hash> ReadyQueue<A> {
+    /// the candidates are given in their arrival order
+    fn new(candidates: impl IntoIterator<Item = (FeeRate, Vec<A>)>) -> Self {
+        let mut fee_rates = Vec::new();
+        let mut accounts = Vec::new();
+        let mut account_queues: HashMap<A, VecDeque<usize>> = HashMap::new();
+
+        for (index, (fee_rate, candidate_accounts)) in candidates.into_iter().enumerate() {
+            for account in &candidate_accounts {
+                account_queues
+                    .entry(account.clone())
+                    .or_default()
+                    .push_back(index);
+            }
+            fee_rates.push(fee_rate);
+            accounts.push(candidate_accounts);
+        }
+
+        let mut queue = ReadyQueue {
+            fee_rates,
+            accounts,
+            account_queues,
+            ready: BinaryHeap::new(),
+        };
+        queue.ready = (0..queue.fee_rates.len())
+            .filter(|&index| queue.is_ready(index))
+            .map(|index| (queue.fee_rates[index], Reverse(index)))
+            .collect();
+        queue
+    }
+
+    /// the next candidate to process
+    fn pop(&mut self) -> Option<usize> {
+        self.ready.pop().map(|(_, Reverse(index))| index)
+    }
+
+    /// the candidate is processed, the next candidates waiting on the same
+    /// accounts can be processed.
+    fn release(&mut self, index: usize) {
+        for account in self.accounts[index].clone() {
+            let queue = self
+                .account_queues
+                .get_mut(&account)
+                .expect("account queue exists");
+            queue.pop_front();
+            if let Some(&next) = queue.front() {
+                if self.is_ready(next) {
+                    self.ready.push((self.fee_rates[next], Reverse(next)));
+                }
+            }
+        }
+    }
+
+    fn is_ready(&self, index: usize) -> bool {
+        self.accounts[index]
+            .iter()
+            .all(|account| self.account_queues[account].front() == Some(&index))
+    }
+}
+
+pub(super) fn fragment_accounts(fragment: &Fragment) -> Vec<UnspecifiedAccountIdentifier> {
+    fn accounts<P>(tx: &Transaction<P>) -> Vec<UnspecifiedAccountIdentifier> {
+        let mut accounts = Vec::new();
+        for input in tx.as_slice().inputs().iter() {
+            if let InputEnum::AccountInput(id, _) = input.to_enum() {
+                if !accounts.contains(&id) {
+                    accounts.push(id);
+                }
+            }
+        }
+        accounts
+    }
+
+    match fragment {
+        Fragment::Transaction(tx) => accounts(tx),
+        Fragment::OwnerStakeDelegation(tx) => accounts(tx),
+        Fragment::StakeDelegation(tx) => accounts(tx),
+        Fragment::PoolRegistration(tx) => accounts(tx),
+        Fragment::PoolRetirement(tx) => accounts(tx),
+        Fragment::PoolUpdate(tx) => accounts(tx),
+        Fragment::VotePlan(tx) => accounts(tx),
+        Fragment::VoteCast(tx) => accounts(tx),
+        Fragment::VoteTally(tx) => accounts(tx),
+        Fragment::EncryptedVoteTally(tx) => accounts(tx),
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Vec::new(),
+    }
+}
+
+/// Format an error followed by the chain of its sources.
+pub fn error_chain_message(error: &dyn Error) -> String {
+    let mut msg = error.to_string();
+    for e in iter::successors(error.source(), |&e| e.source()) {
+        msg.push_str(": ");
+        msg.push_str(&e.to_string());
+    }
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blockcfg::Value;
+
+    fn fee_rate(fee: u64, size: usize) -> FeeRate {
+        FeeRate::new(Value(fee), size)
+    }
+
+    fn order(queue: &mut ReadyQueue<char>) -> Vec<usize> {
+        let mut order = Vec::new();
+        while let Some(index) = queue.pop() {
+            queue.release(index);
+            order.push(index);
+        }
+        order
+    }
+
+    #[test]
+    fn fee_rates_are_compared_per_byte() {
+        assert!(fee_rate(10, 100) < fee_rate(11, 100));
+        assert!(fee_rate(10, 100) > fee_rate(10, 101));
+        assert!(fee_rate(20, 200) == fee_rate(10, 100));
+        // would be equal once rounded to an integer fee per byte
+        assert!(fee_rate(199, 100) < fee_rate(101, 50));
+        assert!(fee_rate(0, 0) < fee_rate(1, 0));
+    }
+
+    #[test]
+    fn highest_fee_rate_first() {
+        let mut queue = ReadyQueue::new(vec![
+            (fee_rate(1, 100), vec!['a']),
+            (fee_rate(3, 100), vec!['b']),
+            (fee_rate(2, 100), vec!['c']),
+            (fee_rate(6, 200), vec!['d']),
+            (fee_rate(0, 100), vec![]),
+        ]);
+
+        // the earliest received first among the same fee rates
+        assert_eq!(order(&mut queue), vec![1, 3, 2, 0, 4]);
+    }
+
+    #[test]
+    fn same_account_keeps_the_arrival_order() {
+        let mut queue = ReadyQueue::new(vec![
+            (fee_rate(1, 100), vec!['a']),
+            (fee_rate(5, 100), vec!['a']),
+            (fee_rate(3, 100), vec!['b']),
+            (fee_rate(4, 100), vec!['a', 'b']),
+        ]);
+
+        // the 2nd candidate waits on the 1st, the 4th on the 2nd and the 3rd
+        assert_eq!(order(&mut queue), vec![2, 0, 1, 3]);
+    }
+
+    #[test]
+    fn candidates_are_only_ready_once_released() {
+        let mut queue = ReadyQueue::new(vec![
+            (fee_rate(1, 100), vec!['a']),
+            (fee_rate(5, 100), vec!['a']),
+        ]);
+
+        assert_eq!(queue.pop(), Some(0));
+        // the fragment did not fit and went back to the pool, the later
+        // fragments of the account must not be selected
+        assert_eq!(queue.pop(), None);
     }
 }
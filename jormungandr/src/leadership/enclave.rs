@@ -16,6 +16,8 @@ pub use crate::secure::enclave::Schedule;
 pub enum EnclaveError {
     #[error("This leader {id} is not in the enclave")]
     NotInEnclave { id: LeaderId },
+    #[error("The enclave failed to sign for the leader {id}: {reason}")]
+    SigningFailed { id: LeaderId, reason: String },
 }
 
 /// represent the client side of an enclave. From there we will query the
@@ -23,16 +25,13 @@ pub enum EnclaveError {
 ///
 #[derive(Clone)]
 pub struct Enclave {
-    /// TODO: we will need to remove this to instead query data to an
-    /// external service (running in the secure enclave). For now we
-    /// hold on the `SecureEnclave` . But it will need to be separated
-    /// when we have the necessary crypto done.
+    /// the keys are either held in memory or by a remote signer, depending
+    /// on the backend of the `SecureEnclave`
     inner: Arc<SecureEnclave>,
 }
 
 impl Enclave {
-    /// create a new enclave structure. This will need to change in the future
-    /// with a parameter to contact the secure enclave instead of the dummy type.
+    /// create a new enclave structure.
     pub fn new(secure_enclave: SecureEnclave) -> Self {
         Enclave {
             inner: Arc::new(secure_enclave),
@@ -68,10 +67,13 @@ impl Enclave {
         block_builder: HeaderBftBuilder<HeaderSetConsensusSignature>,
         id: LeaderId,
     ) -> Result<HeaderBft, EnclaveError> {
-        if let Some(block) = self.inner.create_header_bft(block_builder, id).await {
-            Ok(block)
-        } else {
-            Err(EnclaveError::NotInEnclave { id })
+        match self.inner.create_header_bft(block_builder, id).await {
+            Ok(Some(block)) => Ok(block),
+            Ok(None) => Err(EnclaveError::NotInEnclave { id }),
+            Err(error) => Err(EnclaveError::SigningFailed {
+                id,
+                reason: error.to_string(),
+            }),
         }
     }
 
@@ -85,14 +87,17 @@ impl Enclave {
         block_builder: HeaderGenesisPraosBuilder<HeaderSetConsensusSignature>,
         id: LeaderId,
     ) -> Result<HeaderGenesisPraos, EnclaveError> {
-        if let Some(block) = self
+        match self
             .inner
             .create_header_genesis_praos(block_builder, id)
             .await
         {
-            Ok(block)
-        } else {
-            Err(EnclaveError::NotInEnclave { id })
+            Ok(Some(block)) => Ok(block),
+            Ok(None) => Err(EnclaveError::NotInEnclave { id }),
+            Err(error) => Err(EnclaveError::SigningFailed {
+                id,
+                reason: error.to_string(),
+            }),
         }
     }
 }
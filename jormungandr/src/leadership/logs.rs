@@ -49,6 +49,14 @@ impl LeadershipLogHandle {
     pub async fn mark_finished(&self) {
         self.logs.mark_finished(self.internal_id).await
     }
+
+    /// the current status of the log, `None` if the log was already removed
+    /// from the [`Logs`]
+    ///
+    /// [`Logs`]: ./struct.Logs.html
+    pub async fn status(&self) -> Option<LeadershipLogStatus> {
+        self.logs.status(self.internal_id).await
+    }
 }
 
 impl Logs {
@@ -83,6 +91,12 @@ impl Logs {
         inner.write().await.mark_finished(&leadership_log_id);
     }
 
+    async fn status(&self, leadership_log_id: LeadershipLogId) -> Option<LeadershipLogStatus> {
+        let inner = self.0.clone();
+        let guard = inner.read().await;
+        guard.status(&leadership_log_id).cloned()
+    }
+
     pub async fn logs(&self) -> Vec<LeadershipLog> {
         let inner = self.0.clone();
         let guard = inner.read().await;
@@ -134,6 +148,10 @@ pub(super) mod internal {
             }
         }
 
+        pub fn status(&self, leadership_log_id: &LeadershipLogId) -> Option<&LeadershipLogStatus> {
+            self.entries.peek(leadership_log_id).map(|log| log.status())
+        }
+
         pub fn logs(&self) -> impl Iterator<Item = &LeadershipLog> {
             self.entries.iter().map(|(_, v)| v)
         }
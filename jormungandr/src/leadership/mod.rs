@@ -21,10 +21,12 @@
 //!       executed (and what time);
 //!     * optional: have a way for the blockchain task to update
 //!       the schedule to know if the scheduled block as been accepted in the
-//!       branch;
+//!       branch; this is followed up by the `Tracker`, which counts the
+//!       produced, adopted, orphaned and missed blocks of each leader;
 //!
-//! The enclave is not yet implemented, but we will need to separate the crypto
-//! from the representation here.
+//! The enclave keeps the keys either in the memory of the node or in a remote
+//! signer process (see `secure::remote`), the crypto is separated from the
+//! representation here.
 //!
 //! ## workflow
 //!
@@ -60,7 +62,9 @@
 mod enclave;
 mod logs;
 mod process;
+mod tracker;
 
 pub use self::enclave::{Enclave, EnclaveError, LeaderEvent};
 pub use self::logs::{LeadershipLogHandle, Logs};
 pub use self::process::Module;
+pub use self::tracker::{check_produced_blocks, Tracker};
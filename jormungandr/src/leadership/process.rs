@@ -5,10 +5,11 @@ use crate::{
         Ledger, LedgerParameters,
     },
     blockchain::{new_epoch_leadership_from, Ref, Tip},
+    fragment::selection::FragmentSelectionAlgorithmParams,
     intercom::{unary_reply, BlockMsg, Error as IntercomError, TransactionMsg},
     leadership::{
         enclave::{Enclave, EnclaveError, LeaderEvent, Schedule},
-        LeadershipLogHandle, Logs,
+        LeadershipLogHandle, Logs, Tracker,
     },
     utils::{async_msg::MessageBox, task::TokioServiceInfo},
 };
@@ -64,21 +65,25 @@ pub struct Module {
     schedule: Option<Schedule>,
     service_info: TokioServiceInfo,
     logs: Logs,
+    tracker: Tracker,
     tip_ref: Arc<Ref>,
     tip: Tip,
     pool: MessageBox<TransactionMsg>,
     enclave: Enclave,
     block_message: MessageBox<BlockMsg>,
+    selection_alg: FragmentSelectionAlgorithmParams,
 }
 
 impl Module {
     pub async fn new(
         service_info: TokioServiceInfo,
         logs: Logs,
+        tracker: Tracker,
         tip: Tip,
         pool: MessageBox<TransactionMsg>,
         enclave: Enclave,
         block_message: MessageBox<BlockMsg>,
+        selection_alg: FragmentSelectionAlgorithmParams,
     ) -> Result<Self, LeadershipError> {
         let tip_ref = tip.get_ref().await;
 
@@ -86,11 +91,13 @@ impl Module {
             schedule: None,
             service_info,
             logs,
+            tracker,
             tip_ref,
             tip,
             pool,
             enclave,
             block_message,
+            selection_alg,
         })
     }
 
@@ -295,16 +302,33 @@ impl Module {
         let scheduled_at_time = module.slot_time(epoch, slot);
         let log = LeadershipLog::new(event.id, event.date.into(), scheduled_at_time);
 
+        let (leader_id, date) = (event.id, event.date);
         let entry = match module.logs.insert(log).await {
             Ok(log) => Entry { event, log },
             Err(()) => return Err(LeadershipError::CannotUpdateLogs),
         };
 
+        // the module is consumed by the entry, the outcome is recorded even
+        // if the entry failed
+        let tracker = module.tracker.clone();
         let end_log = entry.log.clone();
         entry.log.mark_wake().await;
-        let module = module.action_run_entry(entry).await?;
-        end_log.mark_finished().await;
-        Ok(module)
+        match module.action_run_entry(entry).await {
+            Ok(module) => {
+                end_log.mark_finished().await;
+                tracker
+                    .record(leader_id, date, end_log.status().await)
+                    .await;
+                Ok(module)
+            }
+            Err(error) => {
+                let status = LeadershipLogStatus::Rejected {
+                    reason: error.to_string(),
+                };
+                tracker.record(leader_id, date, Some(status)).await;
+                Err(error)
+            }
+        }
     }
 
     async fn action_run_entry(self, entry: Entry) -> Result<Self, LeadershipError> {
@@ -451,7 +475,15 @@ impl Module {
             return Ok(());
         };
 
-        let contents = prepare_block(pool, event.id, event.date, ledger, ledger_parameters).await?;
+        let contents = prepare_block(
+            pool,
+            event.id,
+            event.date,
+            ledger,
+            ledger_parameters,
+            self.selection_alg,
+        )
+        .await?;
 
         let event_logs_error = event_logs.clone();
         let signing = {
@@ -647,9 +679,8 @@ async fn prepare_block(
     block_date: BlockDate,
     ledger: Arc<Ledger>,
     epoch_parameters: Arc<LedgerParameters>,
+    selection_alg: FragmentSelectionAlgorithmParams,
 ) -> Result<Contents, LeadershipError> {
-    use crate::fragment::selection::FragmentSelectionAlgorithmParams;
-
     let (reply_handle, reply_future) = unary_reply();
 
     let pool_idx: u32 = leader_id.into();
@@ -659,7 +690,7 @@ async fn prepare_block(
         ledger: ledger.as_ref().clone(),
         block_date,
         ledger_params: epoch_parameters.as_ref().clone(),
-        selection_alg: FragmentSelectionAlgorithmParams::OldestFirst,
+        selection_alg,
         reply_handle,
     };
 
@@ -0,0 +1,303 @@
+//! follow up on the outcome of the scheduled leader events
+//!
+//! The leadership process records the outcome of each event: either a block
+//! was produced and sent to the blockchain, or the slot was missed. The
+//! produced blocks are then checked against the tip chain once it grew
+//! [`CONFIRMATION_DEPTH`] blocks above them, to find the blocks which were
+//! orphaned (i.e. lost against a competing block or rejected by the
+//! blockchain).
+//!
+//! [`CONFIRMATION_DEPTH`]: ./constant.CONFIRMATION_DEPTH.html
+
+use crate::blockchain::{Storage, Tip};
+use chain_impl_mockchain::block::BlockDate;
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{EnclaveLeaderId, LeaderStats, LeadershipLogStatus},
+};
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::sync::RwLock;
+
+/// number of blocks the tip chain needs above a produced block before
+/// checking whether the block is part of it
+pub const CONFIRMATION_DEPTH: u32 = 3;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// the outcome of the scheduled events of each leader of the enclave
+#[derive(Clone, Default)]
+pub struct Tracker(Arc<RwLock<Inner>>);
+
+#[derive(Default)]
+struct Inner {
+    stats: BTreeMap<EnclaveLeaderId, LeaderStats>,
+    produced: Vec<ProducedBlock>,
+}
+
+struct ProducedBlock {
+    leader_id: EnclaveLeaderId,
+    date: BlockDate,
+    block: Hash,
+    chain_length: u32,
+}
+
+impl Inner {
+    fn stats_mut(&mut self, leader_id: EnclaveLeaderId) -> &mut LeaderStats {
+        self.stats.entry(leader_id).or_insert_with(|| LeaderStats {
+            enclave_leader_id: leader_id,
+            ..LeaderStats::default()
+        })
+    }
+}
+
+impl Tracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// record the final status of the leadership log of a scheduled event.
+    /// `None` if the log is not available anymore, the event is then only
+    /// counted as scheduled.
+    pub async fn record(
+        &self,
+        leader_id: EnclaveLeaderId,
+        date: BlockDate,
+        status: Option<LeadershipLogStatus>,
+    ) {
+        let mut inner = self.0.write().await;
+        inner.stats_mut(leader_id).scheduled += 1;
+
+        let cause = match status {
+            None => return,
+            Some(LeadershipLogStatus::Block {
+                block,
+                chain_length,
+                ..
+            }) => {
+                inner.stats_mut(leader_id).produced += 1;
+                inner.produced.push(ProducedBlock {
+                    leader_id,
+                    date,
+                    block,
+                    chain_length,
+                });
+                return;
+            }
+            Some(LeadershipLogStatus::Rejected { reason }) => reason,
+            Some(LeadershipLogStatus::Pending) => "no block was produced".to_owned(),
+        };
+
+        inner.stats_mut(leader_id).missed += 1;
+        tracing::warn!(
+            leader_id = %leader_id,
+            date = %date,
+            cause = %cause,
+            "missed a scheduled slot"
+        );
+    }
+
+    /// check the produced blocks which are deep enough in the tip chain
+    pub async fn check_produced(&self, tip: &Tip, storage: &Storage) {
+        let tip = tip.get_ref().await;
+        let tip_hash = tip.hash();
+        self.check_against(tip.chain_length().into(), |distance| {
+            storage.get_nth_ancestor(tip_hash, distance).map(Hash::from)
+        })
+        .await
+    }
+
+    /// check the produced blocks against the tip chain of the given length,
+    /// `nth_ancestor` giving the block of the tip chain at a distance from
+    /// the tip. A block whose ancestor cannot be found is checked again the
+    /// next time.
+    async fn check_against<F, E>(&self, tip_chain_length: u32, nth_ancestor: F)
+    where
+        F: Fn(u32) -> Result<Hash, E>,
+        E: std::fmt::Display,
+    {
+        let mut inner = self.0.write().await;
+        let produced = std::mem::take(&mut inner.produced);
+        for block in produced {
+            if tip_chain_length < block.chain_length + CONFIRMATION_DEPTH {
+                inner.produced.push(block);
+                continue;
+            }
+
+            let distance = tip_chain_length - block.chain_length;
+            let adopted = match nth_ancestor(distance) {
+                Ok(ancestor) => ancestor == block.block,
+                Err(error) => {
+                    tracing::warn!(
+                        leader_id = %block.leader_id,
+                        block = %block.block,
+                        %error,
+                        "cannot check whether the produced block is in the tip chain, checking it again later"
+                    );
+                    inner.produced.push(block);
+                    continue;
+                }
+            };
+
+            let stats = inner.stats_mut(block.leader_id);
+            if adopted {
+                stats.adopted += 1;
+            } else {
+                stats.orphaned += 1;
+                tracing::warn!(
+                    leader_id = %block.leader_id,
+                    date = %block.date,
+                    block = %block.block,
+                    chain_length = block.chain_length,
+                    cause = "the tip chain holds another block at its chain length",
+                    "produced block was orphaned"
+                );
+            }
+        }
+    }
+
+    pub async fn stats(&self) -> Vec<LeaderStats> {
+        self.0.read().await.stats.values().cloned().collect()
+    }
+}
+
+/// periodically check the produced blocks against the tip chain
+pub async fn check_produced_blocks(tracker: Tracker, tip: Tip, storage: Storage) {
+    let mut interval = tokio::time::interval(CHECK_INTERVAL);
+    loop {
+        interval.tick().await;
+        tracker.check_produced(&tip, &storage).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block_status(block: u8, chain_length: u32) -> Option<LeadershipLogStatus> {
+        Some(LeadershipLogStatus::Block {
+            block: Hash::from([block; 32]),
+            parent: Hash::from([0; 32]),
+            chain_length,
+        })
+    }
+
+    fn date(slot_id: u32) -> BlockDate {
+        BlockDate { epoch: 0, slot_id }
+    }
+
+    async fn stats_of(tracker: &Tracker, leader_id: EnclaveLeaderId) -> LeaderStats {
+        tracker
+            .stats()
+            .await
+            .into_iter()
+            .find(|stats| stats.enclave_leader_id == leader_id)
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn record_counts_the_outcome_of_the_events() {
+        let tracker = Tracker::new();
+        let (fst, snd) = (
+            EnclaveLeaderId::new().next(),
+            EnclaveLeaderId::new().next().next(),
+        );
+
+        tracker.record(fst, date(1), block_status(1, 1)).await;
+        tracker
+            .record(
+                fst,
+                date(2),
+                Some(LeadershipLogStatus::Rejected {
+                    reason: "too late".to_owned(),
+                }),
+            )
+            .await;
+        tracker
+            .record(snd, date(3), Some(LeadershipLogStatus::Pending))
+            .await;
+        tracker.record(snd, date(4), None).await;
+
+        assert_eq!(
+            tracker.stats().await,
+            vec![
+                LeaderStats {
+                    enclave_leader_id: fst,
+                    scheduled: 2,
+                    produced: 1,
+                    adopted: 0,
+                    orphaned: 0,
+                    missed: 1,
+                },
+                LeaderStats {
+                    enclave_leader_id: snd,
+                    scheduled: 2,
+                    produced: 0,
+                    adopted: 0,
+                    orphaned: 0,
+                    missed: 1,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn produced_blocks_are_checked_once_deep_enough() {
+        let tracker = Tracker::new();
+        let leader_id = EnclaveLeaderId::new().next();
+        tracker.record(leader_id, date(1), block_status(1, 1)).await;
+        tracker.record(leader_id, date(2), block_status(2, 2)).await;
+
+        // the tip chain holds the first block but another block at the chain
+        // length of the second one
+        let tip_chain = |chain_length: u32| {
+            move |distance: u32| -> Result<Hash, String> {
+                match chain_length - distance {
+                    1 => Ok(Hash::from([1; 32])),
+                    _ => Ok(Hash::from([9; 32])),
+                }
+            }
+        };
+
+        tracker
+            .check_against(CONFIRMATION_DEPTH, tip_chain(CONFIRMATION_DEPTH))
+            .await;
+        let stats = stats_of(&tracker, leader_id).await;
+        assert_eq!((stats.adopted, stats.orphaned), (0, 0));
+
+        tracker
+            .check_against(CONFIRMATION_DEPTH + 1, tip_chain(CONFIRMATION_DEPTH + 1))
+            .await;
+        let stats = stats_of(&tracker, leader_id).await;
+        assert_eq!((stats.adopted, stats.orphaned), (1, 0));
+
+        tracker
+            .check_against(CONFIRMATION_DEPTH + 2, tip_chain(CONFIRMATION_DEPTH + 2))
+            .await;
+        let stats = stats_of(&tracker, leader_id).await;
+        assert_eq!((stats.adopted, stats.orphaned), (1, 1));
+        assert!(tracker.0.read().await.produced.is_empty());
+    }
+
+    #[tokio::test]
+    async fn produced_blocks_are_checked_again_after_a_storage_error() {
+        let tracker = Tracker::new();
+        let leader_id = EnclaveLeaderId::new().next();
+        tracker.record(leader_id, date(1), block_status(1, 1)).await;
+
+        let tip_chain_length = CONFIRMATION_DEPTH + 1;
+        tracker
+            .check_against(tip_chain_length, |_| {
+                Err::<Hash, _>("the ancestor is not in the storage")
+            })
+            .await;
+        let stats = stats_of(&tracker, leader_id).await;
+        assert_eq!((stats.adopted, stats.orphaned), (0, 0));
+        assert_eq!(tracker.0.read().await.produced.len(), 1);
+
+        tracker
+            .check_against(tip_chain_length, |_| Ok::<_, String>(Hash::from([1; 32])))
+            .await;
+        let stats = stats_of(&tracker, leader_id).await;
+        assert_eq!((stats.adopted, stats.orphaned), (1, 0));
+    }
+}
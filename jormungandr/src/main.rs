@@ -10,7 +10,7 @@ extern crate lazy_static;
 extern crate serde_derive;
 
 use crate::{
-    blockcfg::{HeaderHash, Leader},
+    blockcfg::HeaderHash,
     blockchain::Blockchain,
     diagnostic::Diagnostic,
     secure::enclave::Enclave,
@@ -38,6 +38,7 @@ pub mod intercom;
 pub mod leadership;
 pub mod log;
 pub mod network;
+pub mod notifier;
 pub mod rest;
 pub mod secure;
 pub mod settings;
@@ -49,7 +50,6 @@ pub mod utils;
 
 use stats_counter::StatsCounter;
 use tokio_compat_02::FutureExt;
-use tracing_appender::non_blocking::WorkerGuard;
 use tracing_futures::Instrument;
 
 fn start() -> Result<(), start_up::Error> {
@@ -68,7 +68,7 @@ pub struct BootstrappedNode {
     explorer_db: Option<explorer::ExplorerDB>,
     rest_context: Option<rest::ContextLock>,
     services: Services,
-    _logger_guards: Vec<WorkerGuard>,
+    _logger_guards: Vec<settings::logging::LogGuard>,
 }
 
 const BLOCK_TASK_QUEUE_LEN: usize = 32;
@@ -99,16 +99,29 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
     let blockchain = bootstrapped_node.blockchain;
     let leadership_logs =
         leadership::Logs::new(bootstrapped_node.settings.leadership.logs_capacity);
+    let leadership_tracker = leadership::Tracker::new();
 
     let stats_counter = StatsCounter::default();
 
+    // the events are only consumed by the REST subscribers
+    let notifier = bootstrapped_node
+        .rest_context
+        .as_ref()
+        .map(|_| notifier::Notifier::new());
+
     let explorer = {
         if bootstrapped_node.settings.explorer {
             let explorer_db = bootstrapped_node
                 .explorer_db
                 .expect("explorer db to be bootstrapped");
 
-            let explorer = explorer::Explorer::new(explorer_db);
+            let explorer = explorer::Explorer::new(
+                explorer_db,
+                bootstrapped_node
+                    .settings
+                    .explorer_address_bech32_prefix
+                    .clone(),
+            );
 
             // Context to give to the rest api
             let context = explorer.clone();
@@ -130,6 +143,7 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         let network_msgbox = network_msgbox.clone();
         let fragment_msgbox = fragment_msgbox.clone();
         let explorer_msgbox = explorer.as_ref().map(|(msg_box, _context)| msg_box.clone());
+        let notifier = notifier.clone();
         // TODO: we should get this value from the configuration
         let block_cache_ttl: Duration = Duration::from_secs(120);
         let stats_counter = stats_counter.clone();
@@ -141,6 +155,7 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
                 network_msgbox,
                 fragment_msgbox,
                 explorer_msgbox,
+                notifier,
                 garbage_collection_interval: block_cache_ttl,
             };
             process.start(info, block_queue)
@@ -186,24 +201,24 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
         });
     }
 
-    let leader_secrets: Result<Vec<Leader>, start_up::Error> = bootstrapped_node
-        .settings
-        .secrets
-        .iter()
-        .map(|secret_path| {
-            let secret = secure::NodeSecret::load_from_file(secret_path.as_path())?;
-            Ok(Leader {
-                bft_leader: secret.bft(),
-                genesis_leader: secret.genesis(),
-            })
-        })
-        .collect();
-    let leader_secrets = leader_secrets?;
-    let n_pools = leader_secrets.len();
-    let enclave = block_on(Enclave::from_vec(leader_secrets));
+    let enclave = match &bootstrapped_node.settings.leadership.remote_signer {
+        Some(socket_path) => {
+            services.block_on_task("remote_signer", |_info| connect_remote_signer(socket_path))?
+        }
+        None => Enclave::new(),
+    };
+    let mut secret_files = secure::reload::SecretFiles::new(secure::Passphrase::new(
+        bootstrapped_node.settings.secret_passphrase.clone(),
+    ));
+    for secret_path in &bootstrapped_node.settings.secrets {
+        block_on(secret_files.add(secret_path.clone(), &enclave))?;
+    }
+    let n_pools = block_on(enclave.get_leader_ids()).len();
+    let selection_algorithm = bootstrapped_node.settings.mempool.selection_algorithm;
 
     {
         let leadership_logs = leadership_logs.clone();
+        let leadership_tracker = leadership_tracker.clone();
         let block_msgbox = block_msgbox;
         let blockchain_tip = blockchain_tip.clone();
         let enclave = leadership::Enclave::new(enclave.clone());
@@ -213,25 +228,55 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
             leadership::Module::new(
                 info,
                 leadership_logs,
+                leadership_tracker,
                 blockchain_tip,
                 fragment_msgbox,
                 enclave,
                 block_msgbox,
+                selection_algorithm.into(),
             )
             .and_then(|module| module.run())
         });
     }
 
+    {
+        let leadership_tracker = leadership_tracker.clone();
+        let blockchain_tip = blockchain_tip.clone();
+        let storage = blockchain.storage().clone();
+
+        services.spawn_future("leadership_tracker", move |_| {
+            leadership::check_produced_blocks(leadership_tracker, blockchain_tip, storage)
+        });
+    }
+
+    if !secret_files.is_empty() {
+        let enclave = enclave.clone();
+        let blockchain_tip = blockchain_tip.clone();
+
+        services.spawn_future("secret_reload", move |_| {
+            secure::reload::watch(secret_files, enclave, blockchain_tip)
+        });
+    }
+
     {
         let stats_counter = stats_counter.clone();
+        let blockchain_tip = blockchain_tip.clone();
         let process = fragment::Process::new(
             bootstrapped_node.settings.mempool.pool_max_entries.into(),
+            bootstrapped_node.settings.mempool.eviction_strategy,
+            bootstrapped_node
+                .settings
+                .mempool
+                .fragment_ttl
+                .map(Into::into),
             bootstrapped_node.settings.mempool.log_max_entries.into(),
+            start_up::prepare_mempool_journal(&bootstrapped_node.settings),
             network_msgbox.clone(),
+            notifier.clone(),
         );
 
         services.spawn_try_future("fragment", move |info| {
-            process.start(n_pools, info, stats_counter, fragment_queue)
+            process.start(n_pools, info, stats_counter, blockchain_tip, fragment_queue)
         });
     };
 
@@ -241,9 +286,12 @@ fn start_services(bootstrapped_node: BootstrappedNode) -> Result<(), start_up::E
             network_task: network_msgbox,
             transaction_task: fragment_msgbox,
             leadership_logs,
+            leadership_tracker,
             enclave,
             network_state,
             explorer: explorer.as_ref().map(|(_msg_box, context)| context.clone()),
+            fragment_selection_algorithm: selection_algorithm,
+            notifier: notifier.expect("notifier to be created with the REST context"),
         };
         block_on(async {
             let mut rest_context = rest_context.write().await;
@@ -366,9 +414,16 @@ async fn bootstrap_internal(
 
     let cache_capacity = 102_400;
 
-    let (blockchain, blockchain_tip) =
-        start_up::load_blockchain(block0, storage, cache_capacity, settings.rewards_report_all)
-            .await?;
+    let ledger_snapshots = start_up::prepare_ledger_snapshots(&settings);
+
+    let (blockchain, blockchain_tip) = start_up::load_blockchain(
+        block0,
+        storage,
+        ledger_snapshots,
+        cache_capacity,
+        settings.rewards_report_all,
+    )
+    .await?;
 
     if let Some(context) = &rest_context {
         let mut context = context.write().await;
@@ -417,8 +472,9 @@ async fn bootstrap_internal(
     }
 
     let explorer_db = if settings.explorer {
+        let explorer_store = start_up::prepare_explorer_store(&settings);
         futures::select! {
-            explorer_result = explorer::ExplorerDB::bootstrap(block0_explorer, &blockchain, blockchain_tip.clone()).fuse() => {
+            explorer_result = explorer::ExplorerDB::bootstrap(block0_explorer, &blockchain, blockchain_tip.clone(), explorer_store.as_deref()).fuse() => {
                 Some(explorer_result?)
             },
             _ = cancellation_token.cancelled().fuse() => return Err(start_up::Error::Interrupted),
@@ -449,7 +505,20 @@ pub struct InitializedNode {
     pub rest_context: Option<rest::ContextLock>,
     pub services: Services,
     pub cancellation_token: CancellationToken,
-    pub _logger_guards: Vec<WorkerGuard>,
+    pub _logger_guards: Vec<settings::logging::LogGuard>,
+}
+
+#[cfg(unix)]
+async fn connect_remote_signer(socket_path: &std::path::Path) -> Result<Enclave, start_up::Error> {
+    let remote = secure::remote::RemoteEnclave::connect(socket_path)
+        .await
+        .map_err(start_up::Error::Enclave)?;
+    Ok(Enclave::with_backend(remote))
+}
+
+#[cfg(not(unix))]
+async fn connect_remote_signer(_socket_path: &std::path::Path) -> Result<Enclave, start_up::Error> {
+    Err(start_up::Error::RemoteSignerNotSupported)
 }
 
 #[cfg(unix)]
@@ -518,7 +587,7 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
     let raw_settings = RawSettings::load(command_line)?;
 
     let log_settings = raw_settings.log_settings();
-    let (_logger_guards, log_info_msg) = log_settings.init_log()?;
+    let (_logger_guards, log_filter, log_info_msg) = log_settings.init_log()?;
 
     let init_span = span!(Level::TRACE, "task", kind = "init");
     let async_span = init_span.clone();
@@ -560,12 +629,31 @@ fn initialize_node() -> Result<InitializedNode, start_up::Error> {
 
             let mut context = rest::Context::new();
             context.set_diagnostic_data(diagnostic);
+            context.set_log_filter(log_filter);
             context.set_node_state(NodeState::PreparingStorage);
             let context = Arc::new(RwLock::new(context));
 
             let service_context = context.clone();
             let explorer = settings.explorer;
-            let server_handler = rest::start_rest_server(rest, explorer, context.clone()).compat();
+            let prometheus = settings.prometheus;
+            let client_tls = match &rest.tls {
+                Some(tls) => rest::tls::server_config(tls)?,
+                None => None,
+            };
+            let admin_auth = rest
+                .admin
+                .as_ref()
+                .map(|admin| rest::AdminAuth::load(admin, client_tls.is_some()))
+                .transpose()?;
+            let server_handler = rest::start_rest_server(
+                rest,
+                client_tls,
+                admin_auth,
+                explorer,
+                prometheus,
+                context.clone(),
+            )
+            .compat();
             services.spawn_future("rest", move |info| async move {
                 service_context.write().await.set_span(info.span().clone());
                 server_handler.await
@@ -262,6 +262,7 @@ where
                         branch.clone(),
                         parent_tip.clone(),
                         None,
+                        None,
                     )
                     .await
                     {
@@ -274,7 +275,7 @@ where
     }
 
     if let Some(parent_tip) = maybe_parent_tip {
-        blockchain::process_new_ref(&mut blockchain, branch, parent_tip, None)
+        blockchain::process_new_ref(&mut blockchain, branch, parent_tip, None, None)
             .await
             .map_err(Error::ChainSelectionFailed)
     } else {
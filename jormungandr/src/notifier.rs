@@ -0,0 +1,116 @@
+//! Broadcast of the node events to the REST subscribers.
+//!
+//! The blockchain task reports the changes of the tip and the fragment task
+//! reports the changes of the fragment statuses. Events are dropped when
+//! nobody is subscribed, and a subscriber too slow to keep up with the last
+//! `EVENT_QUEUE_LEN` events misses the oldest ones.
+
+use crate::blockcfg::{ChainLength, FragmentId, Header, HeaderHash};
+use chain_core::property::Header as _;
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{BlockDate, FragmentStatus},
+};
+use tokio::sync::broadcast;
+
+const EVENT_QUEUE_LEN: usize = 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    /// the tip moved to a block
+    Tip {
+        hash: Hash,
+        parent: Hash,
+        chain_length: u32,
+        date: BlockDate,
+    },
+    /// the tip moved to a block which is not a descendant of the previous
+    /// tip, it is followed by the `Tip` event of the new tip
+    Rollback {
+        from: Hash,
+        from_chain_length: u32,
+        to: Hash,
+        to_chain_length: u32,
+    },
+    /// the status of a fragment received by the node changed
+    FragmentStatus {
+        fragment_id: Hash,
+        status: FragmentStatus,
+        /// hex encoded identifiers of the accounts spent from by the fragment
+        accounts: Vec<String>,
+    },
+}
+
+impl Event {
+    pub fn tip(header: &Header) -> Self {
+        Event::Tip {
+            hash: header.hash().into(),
+            parent: header.parent_id().into(),
+            chain_length: header.chain_length().into(),
+            date: header.block_date().into(),
+        }
+    }
+
+    pub fn rollback(
+        from: HeaderHash,
+        from_chain_length: ChainLength,
+        to: HeaderHash,
+        to_chain_length: ChainLength,
+    ) -> Self {
+        Event::Rollback {
+            from: from.into(),
+            from_chain_length: from_chain_length.into(),
+            to: to.into(),
+            to_chain_length: to_chain_length.into(),
+        }
+    }
+
+    pub fn fragment_status(
+        fragment_id: FragmentId,
+        status: FragmentStatus,
+        accounts: Vec<String>,
+    ) -> Self {
+        Event::FragmentStatus {
+            fragment_id: fragment_id.into(),
+            status,
+            accounts,
+        }
+    }
+
+    /// name of the event as sent to the subscribers
+    pub fn name(&self) -> &'static str {
+        match self {
+            Event::Tip { .. } => "tip",
+            Event::Rollback { .. } => "rollback",
+            Event::FragmentStatus { .. } => "fragment_status",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Notifier {
+    sender: broadcast::Sender<Event>,
+}
+
+impl Notifier {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_QUEUE_LEN);
+        Notifier { sender }
+    }
+
+    pub fn notify(&self, event: Event) {
+        // an error only means there is no subscriber at the moment
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+impl Default for Notifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,159 @@
+//! Restriction of the admin endpoints of the REST API to the clients holding
+//! the credentials set in the `admin` section of the REST configuration.
+
+use super::tls::{self, ClientSubject};
+use jormungandr_lib::interfaces::AdminAuth as AdminAuthConfig;
+use std::{fs, io, path::PathBuf, sync::Arc};
+use warp::{http::StatusCode, reject::Reject, Filter, Rejection, Reply};
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cannot read the admin bearer token file `{}`", .path.display())]
+    TokenFile {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("the admin bearer token file `{}` is empty", .0.display())]
+    EmptyToken(PathBuf),
+    #[error("the admin section sets neither a bearer token file nor client subjects")]
+    NoCredentials,
+    #[error("the admin client subjects require the client certificates to be verified, set `client_ca_file` in the TLS configuration")]
+    ClientCertificatesNotVerified,
+}
+
+/// the credentials required by the admin endpoints
+#[derive(Clone)]
+pub struct AdminAuth {
+    bearer_token: Option<Arc<str>>,
+    client_subjects: Arc<[String]>,
+}
+
+/// rejection of a request to an admin endpoint without valid credentials
+#[derive(Debug)]
+pub struct Unauthorized;
+
+impl Reject for Unauthorized {}
+
+impl AdminAuth {
+    /// load the credentials of the configuration. `client_certificates_verified`
+    /// tells whether the REST server verifies the certificates of the clients,
+    /// without which their subjects cannot be trusted.
+    pub fn load(
+        config: &AdminAuthConfig,
+        client_certificates_verified: bool,
+    ) -> Result<Self, Error> {
+        if config.bearer_token_file.is_none() && config.client_subjects.is_empty() {
+            return Err(Error::NoCredentials);
+        }
+        if !config.client_subjects.is_empty() && !client_certificates_verified {
+            return Err(Error::ClientCertificatesNotVerified);
+        }
+
+        let bearer_token = match &config.bearer_token_file {
+            Some(path) => {
+                let token = fs::read_to_string(path).map_err(|source| Error::TokenFile {
+                    path: path.clone(),
+                    source,
+                })?;
+                let token = token.trim();
+                if token.is_empty() {
+                    return Err(Error::EmptyToken(path.clone()));
+                }
+                Some(token.into())
+            }
+            None => None,
+        };
+        Ok(AdminAuth {
+            bearer_token,
+            client_subjects: config.client_subjects.clone().into(),
+        })
+    }
+
+    /// the request is authorized by either of the configured credentials
+    fn authorizes(&self, authorization: Option<&str>, subject: Option<&ClientSubject>) -> bool {
+        let by_token = match (&self.bearer_token, authorization) {
+            (Some(bearer_token), Some(authorization)) => authorization
+                .strip_prefix("Bearer ")
+                .map(|token| constant_time_eq(token.trim().as_bytes(), bearer_token.as_bytes()))
+                .unwrap_or(false),
+            _ => false,
+        };
+        let by_subject = subject.map_or(false, |subject| {
+            self.client_subjects
+                .iter()
+                .any(|allowed| allowed == subject.as_str())
+        });
+        by_token || by_subject
+    }
+}
+
+/// compare the tokens without giving away through the timing how much of
+/// the given token is right
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// let through the requests carrying the admin credentials, or all the
+/// requests if no credentials are configured
+pub fn admin(auth: Option<AdminAuth>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    warp::header::optional::<String>("authorization")
+        .and(tls::client_subject())
+        .and_then(
+            move |authorization: Option<String>, subject: Option<ClientSubject>| {
+                let authorized = auth.as_ref().map_or(true, |auth| {
+                    auth.authorizes(authorization.as_deref(), subject.as_ref())
+                });
+                async move {
+                    if authorized {
+                        Ok(())
+                    } else {
+                        Err(warp::reject::custom(Unauthorized))
+                    }
+                }
+            },
+        )
+        .untuple_one()
+}
+
+pub fn unauthorized_reply() -> impl Reply {
+    warp::reply::with_header(
+        warp::reply::with_status(
+            "missing or invalid admin credentials",
+            StatusCode::UNAUTHORIZED,
+        ),
+        "www-authenticate",
+        "Bearer",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bearer_token() {
+        let auth = AdminAuth {
+            bearer_token: Some("s3cr3t".into()),
+            client_subjects: Arc::new([]),
+        };
+        assert!(auth.authorizes(Some("Bearer s3cr3t"), None));
+        assert!(!auth.authorizes(Some("Bearer s3cr3"), None));
+        assert!(!auth.authorizes(Some("Bearer s3cr3t2"), None));
+        assert!(!auth.authorizes(Some("Basic s3cr3t"), None));
+        assert!(!auth.authorizes(None, None));
+    }
+
+    #[test]
+    fn client_subject() {
+        let auth = AdminAuth {
+            bearer_token: None,
+            client_subjects: vec!["CN=admin, O=Example".to_owned()].into(),
+        };
+        let subject = |subject: &str| ClientSubject(subject.to_owned());
+        assert!(auth.authorizes(None, Some(&subject("CN=admin, O=Example"))));
+        assert!(!auth.authorizes(None, Some(&subject("CN=admin"))));
+        assert!(!auth.authorizes(Some("Bearer "), Some(&subject("CN=guest, O=Example"))));
+        assert!(!auth.authorizes(None, None));
+    }
+}
@@ -4,14 +4,16 @@ use crate::{
     blockchain::{Blockchain, Tip},
     diagnostic::Diagnostic,
     intercom::{NetworkMsg, TransactionMsg},
-    leadership::Logs as LeadershipLogs,
+    leadership::{Logs as LeadershipLogs, Tracker as LeadershipTracker},
     network::GlobalStateR as NetworkStateR,
+    notifier::Notifier,
     rest::ServerStopper,
     secure::enclave::Enclave,
+    settings::logging::LogFilterHandle,
     stats_counter::StatsCounter,
     utils::async_msg::MessageBox,
 };
-use jormungandr_lib::interfaces::NodeState;
+use jormungandr_lib::interfaces::{NodeState, SelectionAlgorithm};
 
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
@@ -25,6 +27,7 @@ pub struct Context {
     node_state: NodeState,
     span: Option<Span>,
     diagnostic: Option<Diagnostic>,
+    log_filter: Option<LogFilterHandle>,
     blockchain: Option<Blockchain>,
     blockchain_tip: Option<Tip>,
     bootstrap_stopper: Option<CancellationToken>,
@@ -44,6 +47,8 @@ pub enum Error {
     BlockchainTip,
     #[error("Diagnostic data not set in REST context")]
     Diagnostic,
+    #[error("Log filter not set in REST context")]
+    LogFilter,
 }
 
 impl Default for Context {
@@ -60,6 +65,7 @@ impl Context {
             node_state: NodeState::StartingRestServer,
             span: Default::default(),
             diagnostic: Default::default(),
+            log_filter: Default::default(),
             blockchain: Default::default(),
             blockchain_tip: Default::default(),
             bootstrap_stopper: Default::default(),
@@ -106,6 +112,14 @@ impl Context {
         self.diagnostic.as_ref().ok_or(Error::Diagnostic)
     }
 
+    pub fn set_log_filter(&mut self, log_filter: LogFilterHandle) {
+        self.log_filter = Some(log_filter);
+    }
+
+    pub fn log_filter(&self) -> Result<&LogFilterHandle, Error> {
+        self.log_filter.as_ref().ok_or(Error::LogFilter)
+    }
+
     pub fn set_blockchain(&mut self, blockchain: Blockchain) {
         self.blockchain = Some(blockchain)
     }
@@ -142,7 +156,10 @@ pub struct FullContext {
     pub network_task: MessageBox<NetworkMsg>,
     pub transaction_task: MessageBox<TransactionMsg>,
     pub leadership_logs: LeadershipLogs,
+    pub leadership_tracker: LeadershipTracker,
     pub enclave: Enclave,
     pub network_state: NetworkStateR,
     pub explorer: Option<crate::explorer::Explorer>,
+    pub fragment_selection_algorithm: SelectionAlgorithm,
+    pub notifier: Notifier,
 }
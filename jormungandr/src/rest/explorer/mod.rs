@@ -1,4 +1,7 @@
+use crate::explorer::graphql::Context;
 use crate::rest::{context, display_internal_server_error, ContextLock};
+use juniper_graphql_ws::ConnectionConfig;
+use std::sync::Arc;
 use thiserror::Error;
 use warp::reject::Reject;
 use warp::{http::StatusCode, Filter, Rejection, Reply};
@@ -30,12 +33,32 @@ pub fn filter(
         })
         .boxed();
 
+    let schema = Arc::new(crate::explorer::create_schema());
+    let subscriptions =
+        warp::ws()
+            .and(context_extractor.clone())
+            .map(move |ws: warp::ws::Ws, context: Context| {
+                let schema = Arc::clone(&schema);
+                ws.on_upgrade(move |websocket| async move {
+                    let config = ConnectionConfig::new(context);
+                    if let Err(error) =
+                        juniper_warp::subscriptions::serve_graphql_ws(websocket, schema, config)
+                            .await
+                    {
+                        tracing::debug!(%error, "explorer subscriptions connection failed");
+                    }
+                })
+            });
+
     let graphql_filter =
         juniper_warp::make_graphql_filter(crate::explorer::create_schema(), context_extractor);
 
-    let graphql = warp::path!("graphql").and(graphql_filter).boxed();
+    let graphql = warp::path!("graphql")
+        .and(subscriptions.or(graphql_filter))
+        .boxed();
 
-    let graphiql_filter = juniper_warp::graphiql_filter("/explorer/graphql", None);
+    let graphiql_filter =
+        juniper_warp::graphiql_filter("/explorer/graphql", Some("/explorer/graphql"));
 
     let graphiql = warp::path!("graphiql").and(graphiql_filter).boxed();
 
@@ -0,0 +1,461 @@
+//! Limits of the requests submitting fragments, set in the `limits` section
+//! of the REST configuration, so a single client cannot flood the mempool.
+
+use super::tls::RemoteAddr;
+use futures::prelude::*;
+use jormungandr_lib::interfaces::{RateLimit, RestLimits};
+use lru::LruCache;
+use std::{
+    convert::Infallible,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use warp::{
+    filters::BoxedFilter,
+    http::StatusCode,
+    hyper::body::{Buf, Bytes},
+    reject::Reject,
+    Filter, Rejection, Reply,
+};
+
+/// number of clients tracked by the rate limiter, the least recently seen
+/// one is forgotten when a new client comes
+const MAX_TRACKED_CLIENTS: usize = 4096;
+
+/// rejection of a request of a client which exceeded its rate limit
+#[derive(Debug)]
+pub struct TooManyRequests {
+    retry_after: Duration,
+}
+
+/// rejection of a request with a body larger than the limit
+#[derive(Debug)]
+pub struct PayloadTooLarge {
+    limit: u64,
+}
+
+/// rejection of a request body which cannot be read or parsed
+#[derive(Debug)]
+pub struct InvalidBody(String);
+
+/// rejection of a batch with more fragments than the limit
+#[derive(Debug)]
+pub struct TooManyFragments {
+    count: usize,
+    limit: usize,
+}
+
+impl Reject for TooManyRequests {}
+impl Reject for PayloadTooLarge {}
+impl Reject for InvalidBody {}
+impl Reject for TooManyFragments {}
+
+/// the limits of the requests submitting fragments
+#[derive(Clone, Default)]
+pub struct Limits {
+    max_body_size: Option<u64>,
+    max_fragments_per_batch: Option<usize>,
+    rate_limiter: Option<RateLimiter>,
+}
+
+impl Limits {
+    pub fn new(config: &RestLimits) -> Self {
+        Limits {
+            max_body_size: config.max_body_size,
+            max_fragments_per_batch: config.max_fragments_per_batch,
+            rate_limiter: config.rate_limit.as_ref().and_then(RateLimiter::new),
+        }
+    }
+
+    /// let through the requests of the clients within their rate limit
+    pub fn fragment_submission(&self) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+        let rate_limiter = self.rate_limiter.clone();
+        client_ip()
+            .and_then(move |ip: Option<IpAddr>| {
+                let result = match (&rate_limiter, ip) {
+                    (Some(rate_limiter), Some(ip)) => rate_limiter
+                        .acquire(ip, Instant::now())
+                        .map_err(|retry_after| {
+                            warp::reject::custom(TooManyRequests { retry_after })
+                        }),
+                    _ => Ok(()),
+                };
+                async move { result }
+            })
+            .untuple_one()
+    }
+
+    /// extract the body of a request submitting fragments, if it is within
+    /// the maximum size
+    pub fn fragment_body(&self) -> BoxedFilter<(Bytes,)> {
+        let limit = match self.max_body_size {
+            Some(limit) => limit,
+            None => return warp::body::bytes().boxed(),
+        };
+
+        // the requests declaring the size of their body are rejected before
+        // reading it, the others once they went past the limit
+        let sized = warp::body::content_length_limit(limit)
+            .or_else(move |rejection: Rejection| async move {
+                let rejection = if rejection.find::<warp::reject::PayloadTooLarge>().is_some() {
+                    warp::reject::custom(PayloadTooLarge { limit })
+                } else {
+                    rejection
+                };
+                Err::<(), _>(rejection)
+            })
+            .and(warp::body::bytes());
+        let chunked = warp::header::optional::<u64>("content-length")
+            .and_then(|length: Option<u64>| async move {
+                match length {
+                    Some(_) => Err(warp::reject::not_found()),
+                    None => Ok(()),
+                }
+            })
+            .untuple_one()
+            .and(warp::body::stream())
+            .and_then(move |body| read_body(body, limit));
+
+        sized.or(chunked).unify().boxed()
+    }
+
+    /// extract the hex-encoded fragments of a batch, if there are no more of
+    /// them than the limit
+    pub fn fragment_batch(
+        &self,
+    ) -> impl Filter<Extract = (Vec<String>,), Error = Rejection> + Clone {
+        let max_fragments_per_batch = self.max_fragments_per_batch;
+        self.fragment_body()
+            .and_then(move |body: Bytes| async move {
+                let fragments: Vec<String> = serde_json::from_slice(&body)
+                    .map_err(|error| warp::reject::custom(InvalidBody(error.to_string())))?;
+                match max_fragments_per_batch {
+                    Some(limit) if fragments.len() > limit => {
+                        Err(warp::reject::custom(TooManyFragments {
+                            count: fragments.len(),
+                            limit,
+                        }))
+                    }
+                    _ => Ok(fragments),
+                }
+            })
+    }
+}
+
+/// read the body until its end or until it goes past the limit
+async fn read_body<S, B>(body: S, limit: u64) -> Result<Bytes, Rejection>
+where
+    S: Stream<Item = Result<B, warp::Error>>,
+    B: Buf,
+{
+    futures::pin_mut!(body);
+    let mut bytes = Vec::new();
+    while let Some(mut chunk) = body
+        .try_next()
+        .await
+        .map_err(|error| warp::reject::custom(InvalidBody(error.to_string())))?
+    {
+        if (bytes.len() + chunk.remaining()) as u64 > limit {
+            return Err(warp::reject::custom(PayloadTooLarge { limit }));
+        }
+        while chunk.has_remaining() {
+            let slice = chunk.bytes();
+            let len = slice.len();
+            bytes.extend_from_slice(slice);
+            chunk.advance(len);
+        }
+    }
+    Ok(Bytes::from(bytes))
+}
+
+/// the IP address of the client, whether the connection was accepted by warp
+/// or by the server verifying the client certificates
+fn client_ip() -> impl Filter<Extract = (Option<IpAddr>,), Error = Infallible> + Clone {
+    warp::addr::remote()
+        .and(warp::ext::optional::<RemoteAddr>())
+        .map(|addr: Option<SocketAddr>, tls_addr: Option<RemoteAddr>| {
+            addr.or_else(|| tls_addr.map(|RemoteAddr(addr)| addr))
+                .map(|addr| addr.ip())
+        })
+}
+
+/// the reply to the rejections of the limits, `None` for the other rejections
+pub fn rejection_reply(err: &Rejection) -> Option<warp::reply::Response> {
+    if let Some(TooManyRequests { retry_after }) = err.find::<TooManyRequests>() {
+        // round up so the client does not retry too early
+        let retry_after = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+        let body = format!(
+            "too many requests from this client, retry in {} seconds",
+            retry_after
+        );
+        return Some(
+            warp::reply::with_header(
+                warp::reply::with_status(body, StatusCode::TOO_MANY_REQUESTS),
+                "retry-after",
+                retry_after.to_string(),
+            )
+            .into_response(),
+        );
+    }
+
+    let (body, code) = if let Some(PayloadTooLarge { limit }) = err.find::<PayloadTooLarge>() {
+        (
+            format!(
+                "the request body is larger than the limit of {} bytes",
+                limit
+            ),
+            StatusCode::PAYLOAD_TOO_LARGE,
+        )
+    } else if let Some(InvalidBody(reason)) = err.find::<InvalidBody>() {
+        (
+            format!("invalid request body: {}", reason),
+            StatusCode::BAD_REQUEST,
+        )
+    } else if let Some(TooManyFragments { count, limit }) = err.find::<TooManyFragments>() {
+        (
+            format!(
+                "the batch holds {} fragments, more than the limit of {}",
+                count, limit
+            ),
+            StatusCode::PAYLOAD_TOO_LARGE,
+        )
+    } else {
+        return None;
+    };
+    Some(warp::reply::with_status(body, code).into_response())
+}
+
+/// token bucket of each client: the bucket holds up to `capacity` requests
+/// and is refilled of one request every `interval`
+#[derive(Clone)]
+struct RateLimiter {
+    capacity: f64,
+    interval: Duration,
+    clients: Arc<Mutex<LruCache<IpAddr, Bucket>>>,
+}
+
+/// the address the client is told apart by: its IPv4 address, or the /64
+/// prefix of its IPv6 address as a single host is commonly given a whole /64
+fn client_key(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V4(_) => ip,
+        IpAddr::V6(ip) => {
+            let octets = ip.octets();
+            // the IPv4 clients of a dual-stack socket
+            if octets[..12] == [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff] {
+                IpAddr::V4(Ipv4Addr::new(
+                    octets[12], octets[13], octets[14], octets[15],
+                ))
+            } else {
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & (!0 << 64)))
+            }
+        }
+    }
+}
+
+struct Bucket {
+    requests: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant, capacity: f64, interval: Duration) {
+        let elapsed = now.saturating_duration_since(self.updated);
+        self.requests =
+            (self.requests + elapsed.as_secs_f64() / interval.as_secs_f64()).min(capacity);
+        self.updated = now;
+    }
+}
+
+impl RateLimiter {
+    /// `None` if the period is zero, which does not limit anything
+    fn new(config: &RateLimit) -> Option<Self> {
+        let period: &Duration = config.period.as_ref();
+        if *period == Duration::from_secs(0) {
+            return None;
+        }
+        Some(RateLimiter {
+            capacity: f64::from(config.requests.get()),
+            interval: *period / config.requests.get(),
+            clients: Arc::new(Mutex::new(LruCache::new(MAX_TRACKED_CLIENTS))),
+        })
+    }
+
+    /// take a request from the bucket of the client, or give the time until
+    /// the bucket holds one
+    fn acquire(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let key = client_key(ip);
+        let mut clients = self.clients.lock().unwrap();
+        if !clients.contains(&key) {
+            clients.put(
+                key,
+                Bucket {
+                    requests: self.capacity,
+                    updated: now,
+                },
+            );
+        }
+        let bucket = clients.get_mut(&key).unwrap();
+        bucket.refill(now, self.capacity, self.interval);
+        if bucket.requests >= 1.0 {
+            bucket.requests -= 1.0;
+            Ok(())
+        } else {
+            Err(self.interval.mul_f64(1.0 - bucket.requests))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::num::NonZeroU32;
+
+    #[test]
+    fn rate_limit() {
+        let rate_limiter = RateLimiter::new(&RateLimit {
+            requests: NonZeroU32::new(2).unwrap(),
+            period: jormungandr_lib::time::Duration::new(1, 0),
+        })
+        .unwrap();
+        let client = IpAddr::from([127, 0, 0, 1]);
+        let other_client = IpAddr::from([127, 0, 0, 2]);
+        let start = Instant::now();
+
+        assert!(rate_limiter.acquire(client, start).is_ok());
+        assert!(rate_limiter.acquire(client, start).is_ok());
+        assert_eq!(
+            rate_limiter.acquire(client, start),
+            Err(Duration::from_millis(500))
+        );
+        assert!(rate_limiter.acquire(other_client, start).is_ok());
+
+        let later = start + Duration::from_millis(500);
+        assert!(rate_limiter.acquire(client, later).is_ok());
+        assert!(rate_limiter.acquire(client, later).is_err());
+    }
+
+    #[test]
+    fn ipv6_clients_are_limited_by_prefix() {
+        let rate_limiter = RateLimiter::new(&RateLimit {
+            requests: NonZeroU32::new(1).unwrap(),
+            period: jormungandr_lib::time::Duration::new(1, 0),
+        })
+        .unwrap();
+        let start = Instant::now();
+
+        let client: IpAddr = "2001:db8:0:1::1".parse().unwrap();
+        let same_prefix: IpAddr = "2001:db8:0:1:ffff::2".parse().unwrap();
+        let other_prefix: IpAddr = "2001:db8:0:2::1".parse().unwrap();
+        assert!(rate_limiter.acquire(client, start).is_ok());
+        assert!(rate_limiter.acquire(same_prefix, start).is_err());
+        assert!(rate_limiter.acquire(other_prefix, start).is_ok());
+
+        let ipv4_client: IpAddr = "::ffff:192.0.2.1".parse().unwrap();
+        let other_ipv4_client: IpAddr = "::ffff:192.0.2.2".parse().unwrap();
+        assert!(rate_limiter.acquire(ipv4_client, start).is_ok());
+        assert!(rate_limiter.acquire(other_ipv4_client, start).is_ok());
+        assert!(rate_limiter
+            .acquire(IpAddr::from([192, 0, 2, 1]), start)
+            .is_err());
+    }
+
+    #[test]
+    fn tracked_clients_are_capped() {
+        let rate_limiter = RateLimiter::new(&RateLimit {
+            requests: NonZeroU32::new(1).unwrap(),
+            period: jormungandr_lib::time::Duration::new(60, 0),
+        })
+        .unwrap();
+        let start = Instant::now();
+        let first_client = IpAddr::from([10, 0, 0, 0]);
+
+        assert!(rate_limiter.acquire(first_client, start).is_ok());
+        assert!(rate_limiter.acquire(first_client, start).is_err());
+        for n in 1..=MAX_TRACKED_CLIENTS as u32 {
+            let client = IpAddr::V4(Ipv4Addr::from(0x0a00_0000 + n));
+            assert!(rate_limiter.acquire(client, start).is_ok());
+        }
+        assert_eq!(
+            rate_limiter.clients.lock().unwrap().len(),
+            MAX_TRACKED_CLIENTS
+        );
+        // the least recently seen client was forgotten
+        assert!(rate_limiter.acquire(first_client, start).is_ok());
+    }
+
+    fn limits(max_body_size: u64) -> Limits {
+        Limits::new(&RestLimits {
+            max_body_size: Some(max_body_size),
+            max_fragments_per_batch: Some(2),
+            rate_limit: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn sized_bodies_above_the_limit_are_rejected() {
+        let filter = limits(8).fragment_body();
+
+        let body = warp::test::request()
+            .header("content-length", "8")
+            .body("01234567")
+            .filter(&filter)
+            .await
+            .unwrap();
+        assert_eq!(&body[..], b"01234567");
+
+        let rejection = warp::test::request()
+            .header("content-length", "9")
+            .body("012345678")
+            .filter(&filter)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<PayloadTooLarge>().is_some());
+    }
+
+    #[tokio::test]
+    async fn unsized_bodies_are_read_up_to_the_limit() {
+        let chunks = |chunks: Vec<&'static str>| {
+            stream::iter(
+                chunks
+                    .into_iter()
+                    .map(|chunk| Ok::<_, warp::Error>(Bytes::from(chunk))),
+            )
+        };
+
+        let body = read_body(chunks(vec!["0123", "4567"]), 8).await.unwrap();
+        assert_eq!(&body[..], b"01234567");
+
+        let rejection = read_body(chunks(vec!["0123", "4567", "8"]), 8)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<PayloadTooLarge>().is_some());
+    }
+
+    #[tokio::test]
+    async fn batches_are_limited() {
+        let filter = limits(1024).fragment_batch();
+
+        let fragments = warp::test::request()
+            .json(&vec!["00", "01"])
+            .filter(&filter)
+            .await
+            .unwrap();
+        assert_eq!(fragments, vec!["00", "01"]);
+
+        let rejection = warp::test::request()
+            .json(&vec!["00", "01", "02"])
+            .filter(&filter)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<TooManyFragments>().is_some());
+
+        let rejection = warp::test::request()
+            .body("not json")
+            .filter(&filter)
+            .await
+            .unwrap_err();
+        assert!(rejection.find::<InvalidBody>().is_some());
+    }
+}
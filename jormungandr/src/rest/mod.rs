@@ -1,16 +1,23 @@
 //! REST API of the node
 
+pub mod auth;
 pub mod context;
 pub mod explorer;
+pub mod limits;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+pub mod tls;
 pub mod v0;
 mod v1;
 
+pub use self::auth::AdminAuth;
 pub use self::context::{Context, ContextLock, FullContext};
 
 use jormungandr_lib::interfaces::{Rest, Tls};
 
 use futures::{channel::mpsc, prelude::*};
-use std::{error::Error, net::SocketAddr, time::Duration};
+use std::{error::Error, net::SocketAddr, sync::Arc, time::Duration};
+use tokio_rustls::rustls::ServerConfig;
 use warp::Filter;
 
 #[derive(Clone)]
@@ -22,7 +29,18 @@ impl ServerStopper {
     }
 }
 
-pub async fn start_rest_server(config: Rest, explorer_enabled: bool, context: ContextLock) {
+/// `client_tls` is the TLS configuration verifying the certificates of the
+/// clients, loaded with [`tls::server_config`] from `config.tls`
+///
+/// [`tls::server_config`]: ./tls/fn.server_config.html
+pub async fn start_rest_server(
+    config: Rest,
+    client_tls: Option<Arc<ServerConfig>>,
+    admin_auth: Option<AdminAuth>,
+    explorer_enabled: bool,
+    prometheus_enabled: bool,
+    context: ContextLock,
+) {
     let (stopper_tx, stopper_rx) = mpsc::channel::<()>(0);
     let stopper_rx = stopper_rx.into_future().map(|_| ());
     context
@@ -30,19 +48,40 @@ pub async fn start_rest_server(config: Rest, explorer_enabled: bool, context: Co
         .await
         .set_server_stopper(ServerStopper(stopper_tx));
 
-    let api =
-        warp::path!("api" / ..).and(v0::filter(context.clone()).or(v1::filter(context.clone())));
+    if admin_auth.is_none() && !config.listen.ip().is_loopback() {
+        tracing::warn!(
+            "the admin endpoints of the REST API are reachable without credentials on {}, \
+            they can be restricted in the `admin` section of the REST configuration",
+            config.listen
+        );
+    }
+
+    let limits = config
+        .limits
+        .as_ref()
+        .map(limits::Limits::new)
+        .unwrap_or_default();
+    let api = warp::path!("api" / ..).and(
+        v0::filter(context.clone(), admin_auth, limits.clone())
+            .or(v1::filter(context.clone(), limits)),
+    );
+    // the settings never enable the metrics without the `prometheus` feature
+    #[cfg(feature = "prometheus")]
+    let api = api.or(prometheus::filter(context.clone(), prometheus_enabled));
+    #[cfg(not(feature = "prometheus"))]
+    debug_assert!(!prometheus_enabled);
     if explorer_enabled {
         let explorer = explorer::filter(context);
-        setup_cors(api.or(explorer), config, stopper_rx).await;
+        setup_cors(api.or(explorer), config, client_tls, stopper_rx).await;
     } else {
-        setup_cors(api, config, stopper_rx).await;
+        setup_cors(api, config, client_tls, stopper_rx).await;
     }
 }
 
 async fn setup_cors<App>(
     app: App,
     config: Rest,
+    client_tls: Option<Arc<ServerConfig>>,
     shutdown_signal: impl Future<Output = ()> + Send + 'static,
 ) where
     App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
@@ -61,9 +100,16 @@ async fn setup_cors<App>(
             cors = cors.max_age(Duration::from_secs(max_age));
         }
 
-        run_server_with_app(app.with(cors), config.listen, config.tls, shutdown_signal).await;
+        run_server_with_app(
+            app.with(cors),
+            config.listen,
+            config.tls,
+            client_tls,
+            shutdown_signal,
+        )
+        .await;
     } else {
-        run_server_with_app(app, config.listen, config.tls, shutdown_signal).await;
+        run_server_with_app(app, config.listen, config.tls, client_tls, shutdown_signal).await;
     }
 }
 
@@ -71,11 +117,16 @@ async fn run_server_with_app<App>(
     app: App,
     listen_addr: SocketAddr,
     tls_config: Option<Tls>,
+    client_tls: Option<Arc<ServerConfig>>,
     shutdown_signal: impl Future<Output = ()> + Send + 'static,
 ) where
     App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
     App::Extract: warp::Reply,
 {
+    if let Some(client_tls) = client_tls {
+        return tls::serve(app, listen_addr, client_tls, shutdown_signal).await;
+    }
+
     let server = warp::serve(app);
     if let Some(tls_config) = tls_config {
         let (_, server_fut) = server
@@ -0,0 +1,190 @@
+//! Node metrics in the Prometheus text exposition format
+
+use crate::rest::{
+    context::{Context, Error},
+    ContextLock,
+};
+use jormungandr_lib::interfaces::LeadershipLogStatus;
+
+use std::fmt::{Display, Write};
+use warp::{http::StatusCode, Filter, Rejection, Reply};
+
+const CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+pub fn filter(
+    context: ContextLock,
+    enabled: bool,
+) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let with_context = warp::any().map(move || context.clone());
+
+    warp::path!("metrics")
+        .and(warp::get())
+        .and(warp::any().map(move || enabled))
+        .and(with_context)
+        .and_then(get_metrics)
+}
+
+async fn get_metrics(enabled: bool, context: ContextLock) -> Result<impl Reply, Rejection> {
+    if !enabled {
+        return Err(warp::reject::not_found());
+    }
+    let context = context.read().await;
+    let (body, status) = match collect_metrics(&context).await {
+        Ok(metrics) => (metrics.0, StatusCode::OK),
+        Err(err) => (err.to_string(), StatusCode::SERVICE_UNAVAILABLE),
+    };
+    Ok(warp::reply::with_header(
+        warp::reply::with_status(body, status),
+        "content-type",
+        CONTENT_TYPE,
+    ))
+}
+
+async fn collect_metrics(context: &Context) -> Result<Metrics, Error> {
+    let full_context = context.try_full()?;
+    let tip = context.blockchain_tip()?.get_ref().await;
+    let stats = &full_context.stats_counter;
+    let mut metrics = Metrics(String::new());
+
+    metrics.gauge(
+        "jormungandr_uptime_seconds",
+        "Time since the node started",
+        stats.uptime_sec(),
+    );
+
+    let tip_chain_length = u32::from(tip.chain_length());
+    let tip_date = tip.block_date();
+    metrics.gauge(
+        "jormungandr_tip_chain_length",
+        "Chain length of the tip",
+        tip_chain_length,
+    );
+    metrics.gauge("jormungandr_tip_epoch", "Epoch of the tip", tip_date.epoch);
+    metrics.gauge(
+        "jormungandr_tip_slot",
+        "Slot of the tip within its epoch",
+        tip_date.slot_id,
+    );
+
+    metrics.counter(
+        "jormungandr_blocks_received_total",
+        "Number of blocks received from the network",
+        stats.block_recv_cnt(),
+    );
+    metrics.counter(
+        "jormungandr_fragments_received_total",
+        "Number of fragments accepted in the mempool",
+        stats.tx_recv_cnt(),
+    );
+    metrics.gauge(
+        "jormungandr_mempool_fragments",
+        "Number of fragments in the mempool",
+        stats.mempool_usage(),
+    );
+
+    let nodes_count = full_context.network_state.topology().nodes_count().await;
+    metrics.labeled_gauge(
+        "jormungandr_peers",
+        "Number of peers known by the node, per state",
+        "state",
+        &[
+            ("connected", stats.peer_connected_cnt()),
+            ("available", nodes_count.available_count),
+            ("quarantined", nodes_count.quarantined_count),
+            ("unreachable", nodes_count.not_reachable_count),
+            ("total", nodes_count.all_count),
+        ],
+    );
+
+    let (mut pending, mut rejected, mut block) = (0, 0, 0);
+    for log in full_context.leadership_logs.logs().await {
+        match log.status() {
+            LeadershipLogStatus::Pending => pending += 1,
+            LeadershipLogStatus::Rejected { .. } => rejected += 1,
+            LeadershipLogStatus::Block { .. } => block += 1,
+        }
+    }
+    metrics.labeled_gauge(
+        "jormungandr_leadership_events",
+        "Number of leadership events in the leadership logs, per status",
+        "status",
+        &[
+            ("pending", pending),
+            ("rejected", rejected),
+            ("block", block),
+        ],
+    );
+
+    metrics.counter(
+        "jormungandr_rejected_forks_total",
+        "Number of longer branches rejected for rolling back beyond the epoch stability depth",
+        context.blockchain()?.rejected_forks(),
+    );
+
+    let (processed_blocks, processing_time) = stats.block_processing_time();
+    metrics.header(
+        "jormungandr_block_processing_seconds",
+        "Time spent validating and applying blocks",
+        "summary",
+    );
+    metrics.sample(
+        "jormungandr_block_processing_seconds_sum",
+        processing_time.as_secs_f64(),
+    );
+    metrics.sample(
+        "jormungandr_block_processing_seconds_count",
+        processed_blocks,
+    );
+
+    if let Some(explorer) = &full_context.explorer {
+        let explorer_chain_length = u32::from(explorer.db.tip_chain_length().await);
+        metrics.gauge(
+            "jormungandr_explorer_indexing_lag_blocks",
+            "Number of blocks between the tip and the latest block indexed by the explorer",
+            tip_chain_length.saturating_sub(explorer_chain_length),
+        );
+    }
+
+    Ok(metrics)
+}
+
+struct Metrics(String);
+
+impl Metrics {
+    fn header(&mut self, name: &str, help: &str, kind: &str) {
+        writeln!(self.0, "# HELP {} {}", name, help).unwrap();
+        writeln!(self.0, "# TYPE {} {}", name, kind).unwrap();
+    }
+
+    fn sample(&mut self, name: &str, value: impl Display) {
+        writeln!(self.0, "{} {}", name, value).unwrap();
+    }
+
+    fn gauge(&mut self, name: &str, help: &str, value: impl Display) {
+        self.header(name, help, "gauge");
+        self.sample(name, value);
+    }
+
+    fn counter(&mut self, name: &str, help: &str, value: impl Display) {
+        self.header(name, help, "counter");
+        self.sample(name, value);
+    }
+
+    fn labeled_gauge(
+        &mut self,
+        name: &str,
+        help: &str,
+        label: &str,
+        values: &[(&str, impl Display)],
+    ) {
+        self.header(name, help, "gauge");
+        for (label_value, value) in values {
+            writeln!(
+                self.0,
+                "{}{{{}=\"{}\"}} {}",
+                name, label, label_value, value
+            )
+            .unwrap();
+        }
+    }
+}
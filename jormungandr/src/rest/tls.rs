@@ -0,0 +1,307 @@
+//! TLS serving of the REST API verifying the certificates of the clients.
+//!
+//! warp does not give access to the certificates of the clients, so when the
+//! configuration has a client CA the connections are accepted here and served
+//! by hyper directly. The subject of the client certificate is added to the
+//! extensions of each request of the connection, see [`client_subject`].
+//!
+//! [`client_subject`]: ./fn.client_subject.html
+
+use futures::prelude::*;
+use jormungandr_lib::interfaces::Tls;
+use std::{
+    convert::Infallible,
+    fmt,
+    fs::File,
+    io::{self, BufReader},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio_rustls::{
+    rustls::{
+        internal::pemfile, AllowAnyAuthenticatedClient, PrivateKey, RootCertStore, ServerConfig,
+        ServerSession, Session, TLSError,
+    },
+    server::TlsStream,
+    TlsAcceptor,
+};
+use warp::{
+    hyper::{
+        server::{
+            accept::{self, Accept},
+            conn::{AddrIncoming, AddrStream},
+        },
+        service::{make_service_fn, service_fn, Service},
+        Body, Request, Server,
+    },
+    Filter, Reply,
+};
+
+/// number of TLS handshakes carried on at the same time
+const MAX_PENDING_HANDSHAKES: usize = 64;
+
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("cannot read the TLS file `{}`", .path.display())]
+    File {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    #[error("no PEM-encoded certificate found in `{}`", .0.display())]
+    NoCertificate(PathBuf),
+    #[error("no PEM-encoded PKCS8 or RSA private key found in `{}`", .0.display())]
+    NoPrivateKey(PathBuf),
+    #[error("invalid TLS server certificate or private key")]
+    Tls(#[from] TLSError),
+}
+
+/// the subject of the certificate the client connected with,
+/// e.g. `CN=admin, O=Example`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClientSubject(pub(super) String);
+
+impl ClientSubject {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ClientSubject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// the address of the client, which `warp::addr::remote` cannot give for the
+/// connections accepted here
+#[derive(Debug, Clone, Copy)]
+pub(super) struct RemoteAddr(pub(super) SocketAddr);
+
+/// extract the subject of the client certificate of the request, if the
+/// server verifies the certificates of the clients
+pub fn client_subject(
+) -> impl Filter<Extract = (Option<ClientSubject>,), Error = Infallible> + Clone {
+    warp::ext::optional::<ClientSubject>()
+}
+
+/// load the TLS configuration verifying the certificates of the clients,
+/// `None` if the configuration has no client CA
+pub fn server_config(tls: &Tls) -> Result<Option<Arc<ServerConfig>>, Error> {
+    let client_ca_file = match &tls.client_ca_file {
+        Some(client_ca_file) => Path::new(client_ca_file),
+        None => return Ok(None),
+    };
+
+    let mut roots = RootCertStore::empty();
+    let (added, _) = roots
+        .add_pem_file(&mut open(client_ca_file)?)
+        .map_err(|()| Error::NoCertificate(client_ca_file.to_owned()))?;
+    if added == 0 {
+        return Err(Error::NoCertificate(client_ca_file.to_owned()));
+    }
+
+    let cert_file = Path::new(&tls.cert_file);
+    let certs = pemfile::certs(&mut open(cert_file)?)
+        .ok()
+        .filter(|certs| !certs.is_empty())
+        .ok_or_else(|| Error::NoCertificate(cert_file.to_owned()))?;
+    let key = private_key(Path::new(&tls.priv_key_file))?;
+
+    let mut config = ServerConfig::new(AllowAnyAuthenticatedClient::new(roots));
+    config.set_single_cert(certs, key)?;
+    config.set_protocols(&[b"h2".to_vec(), b"http/1.1".to_vec()]);
+    Ok(Some(Arc::new(config)))
+}
+
+/// the first PKCS8 private key of the file, or else its first RSA private key
+fn private_key(path: &Path) -> Result<PrivateKey, Error> {
+    let first = |keys: Result<Vec<PrivateKey>, ()>| keys.ok()?.into_iter().next();
+    if let Some(key) = first(pemfile::pkcs8_private_keys(&mut open(path)?)) {
+        return Ok(key);
+    }
+    first(pemfile::rsa_private_keys(&mut open(path)?))
+        .ok_or_else(|| Error::NoPrivateKey(path.to_owned()))
+}
+
+fn open(path: &Path) -> Result<BufReader<File>, Error> {
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|source| Error::File {
+            path: path.to_owned(),
+            source,
+        })
+}
+
+fn peer_subject(session: &ServerSession) -> Option<ClientSubject> {
+    let certificates = session.get_peer_certificates()?;
+    let certificate = certificates.first()?;
+    match x509_parser::parse_x509_certificate(&certificate.0) {
+        Ok((_, certificate)) => Some(ClientSubject(certificate.subject().to_string())),
+        Err(error) => {
+            tracing::debug!(%error, "cannot parse the client certificate");
+            None
+        }
+    }
+}
+
+/// serve the app over the connections of the clients with a certificate
+/// signed by the client CA of the configuration
+pub async fn serve<App>(
+    app: App,
+    listen_addr: SocketAddr,
+    config: Arc<ServerConfig>,
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) where
+    App: Filter<Error = warp::Rejection> + Clone + Send + Sync + 'static,
+    App::Extract: Reply,
+{
+    let mut incoming = Box::pin(
+        AddrIncoming::bind(&listen_addr)
+            .unwrap_or_else(|error| panic!("error binding to {}: {}", listen_addr, error)),
+    );
+    let acceptor = TlsAcceptor::from(config);
+
+    let connections = stream::poll_fn(move |cx| incoming.as_mut().poll_accept(cx))
+        .filter_map(|stream| async move {
+            stream
+                .map_err(|error| tracing::debug!(%error, "failed to accept a connection"))
+                .ok()
+        })
+        .map(move |stream| {
+            let remote_addr = stream.remote_addr();
+            tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).map(move |result| {
+                match result {
+                    Ok(Ok(stream)) => Some(Ok::<_, io::Error>(stream)),
+                    Ok(Err(error)) => {
+                        tracing::debug!(%remote_addr, %error, "TLS handshake failed");
+                        None
+                    }
+                    Err(_) => {
+                        tracing::debug!(%remote_addr, "TLS handshake timed out");
+                        None
+                    }
+                }
+            })
+        })
+        .buffer_unordered(MAX_PENDING_HANDSHAKES)
+        .filter_map(future::ready);
+
+    let service = warp::service(app);
+    let make_service = make_service_fn(move |stream: &TlsStream<AddrStream>| {
+        let remote_addr = RemoteAddr(stream.get_ref().0.remote_addr());
+        let subject = peer_subject(stream.get_ref().1);
+        let mut service = service.clone();
+        future::ok::<_, Infallible>(service_fn(move |mut request: Request<Body>| {
+            request.extensions_mut().insert(remote_addr);
+            if let Some(subject) = &subject {
+                request.extensions_mut().insert(subject.clone());
+            }
+            service.call(request)
+        }))
+    });
+
+    let server = Server::builder(accept::from_stream(connections))
+        .serve(make_service)
+        .with_graceful_shutdown(shutdown_signal);
+    if let Err(error) = server.await {
+        tracing::error!(%error, "the REST server failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use tokio_compat_02::FutureExt as _;
+    use tokio_rustls::{
+        rustls::{ClientConfig, ClientSession, StreamOwned},
+        webpki::DNSNameRef,
+    };
+
+    fn resource(name: &str) -> String {
+        format!("{}/resources/tls/{}", env!("CARGO_MANIFEST_DIR"), name)
+    }
+
+    fn tls(priv_key_file: &str) -> Tls {
+        Tls {
+            cert_file: resource("server.crt"),
+            priv_key_file: resource(priv_key_file),
+            client_ca_file: Some(resource("ca.crt")),
+        }
+    }
+
+    fn free_addr() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    /// send a request with the client certificate and return the body of
+    /// the response
+    fn get(addr: SocketAddr) -> String {
+        let mut config = ClientConfig::new();
+        config
+            .root_store
+            .add_pem_file(&mut open(Path::new(&resource("ca.crt"))).unwrap())
+            .unwrap();
+        let certs = pemfile::certs(&mut open(Path::new(&resource("client.crt"))).unwrap()).unwrap();
+        let key = private_key(Path::new(&resource("client.key"))).unwrap();
+        config.set_single_client_cert(certs, key).unwrap();
+        let session = ClientSession::new(
+            &Arc::new(config),
+            DNSNameRef::try_from_ascii_str("localhost").unwrap(),
+        );
+
+        // the server may not be listening yet
+        let socket = (0..50)
+            .find_map(|_| {
+                std::net::TcpStream::connect(addr)
+                    .map_err(|_| std::thread::sleep(Duration::from_millis(100)))
+                    .ok()
+            })
+            .expect("the server does not listen");
+        let mut stream = StreamOwned::new(session, socket);
+        stream
+            .write_all(b"GET / HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (_, body) = response.split_at(response.find("\r\n\r\n").unwrap() + 4);
+        body.to_owned()
+    }
+
+    #[test]
+    fn private_key_is_pkcs8_or_rsa() {
+        assert!(server_config(&tls("server.key")).unwrap().is_some());
+        assert!(server_config(&tls("server-rsa.key")).unwrap().is_some());
+        assert!(matches!(
+            server_config(&tls("server.crt")),
+            Err(Error::NoPrivateKey(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn client_subject_is_given_to_the_app() {
+        let addr = free_addr();
+        let config = server_config(&tls("server.key")).unwrap().unwrap();
+        let app = client_subject().map(|subject: Option<ClientSubject>| {
+            subject.map(|subject| subject.0).unwrap_or_default()
+        });
+        let (stop, stopped) = futures::channel::oneshot::channel::<()>();
+        let server = tokio::spawn(serve(app, addr, config, stopped.map(|_| ())).compat());
+
+        let body = tokio::task::spawn_blocking(move || get(addr))
+            .await
+            .unwrap();
+        assert_eq!(body, "O=Example, CN=admin");
+
+        stop.send(()).unwrap();
+        server.await.unwrap();
+    }
+}
@@ -1,11 +1,21 @@
 use crate::{
-    rest::{v0::logic, ContextLock},
+    rest::{tls::ClientSubject, v0::logic, ContextLock},
     secure::NodeSecret,
 };
+use jormungandr_lib::interfaces::LogTargetLevel;
 use warp::{reject::Reject, Rejection, Reply};
 
 impl Reject for logic::Error {}
 
+/// log the admin requests changing the state of the node, with the subject
+/// of the certificate of the client if the server verifies them
+fn log_admin_request(client: &Option<ClientSubject>, request: &str) {
+    match client {
+        Some(client) => tracing::info!(%client, "{}", request),
+        None => tracing::info!("{}", request),
+    }
+}
+
 pub async fn get_account_state(
     account_id_hex: String,
     context: ContextLock,
@@ -106,7 +116,11 @@ pub async fn get_settings(context: ContextLock) -> Result<impl Reply, Rejection>
         .map_err(warp::reject::custom)
 }
 
-pub async fn shutdown(context: ContextLock) -> Result<impl Reply, Rejection> {
+pub async fn shutdown(
+    client: Option<ClientSubject>,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    log_admin_request(&client, "shutdown requested through the REST API");
     let mut context = context.write().await;
     logic::shutdown(&mut context)
         .await
@@ -114,6 +128,33 @@ pub async fn shutdown(context: ContextLock) -> Result<impl Reply, Rejection> {
         .map_err(warp::reject::custom)
 }
 
+pub async fn get_log_filter(context: ContextLock) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_log_filter(&context)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
+pub async fn post_log_filter(
+    target_level: LogTargetLevel,
+    client: Option<ClientSubject>,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    log_admin_request(
+        &client,
+        &format!(
+            "log level of `{}` set through the REST API",
+            target_level.target
+        ),
+    );
+    let context = context.read().await;
+    logic::set_log_target_level(&context, target_level)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
 pub async fn get_leaders(context: ContextLock) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
     logic::get_leader_ids(&context)
@@ -124,8 +165,10 @@ pub async fn get_leaders(context: ContextLock) -> Result<impl Reply, Rejection>
 
 pub async fn post_leaders(
     secret: NodeSecret,
+    client: Option<ClientSubject>,
     context: ContextLock,
 ) -> Result<impl Reply, Rejection> {
+    log_admin_request(&client, "leader added through the REST API");
     let context = context.read().await;
     logic::post_leaders(&context, secret)
         .await
@@ -133,7 +176,15 @@ pub async fn post_leaders(
         .map_err(warp::reject::custom)
 }
 
-pub async fn delete_leaders(leader_id: u32, context: ContextLock) -> Result<impl Reply, Rejection> {
+pub async fn delete_leaders(
+    leader_id: u32,
+    client: Option<ClientSubject>,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    log_admin_request(
+        &client,
+        &format!("leader {} removed through the REST API", leader_id),
+    );
     let context = context.read().await;
     logic::delete_leaders(&context, leader_id.into())
         .await
@@ -150,6 +201,29 @@ pub async fn get_leaders_logs(context: ContextLock) -> Result<impl Reply, Reject
         .map_err(warp::reject::custom)
 }
 
+pub async fn get_leaders_stats(context: ContextLock) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::get_leaders_stats(&context)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
+pub async fn get_leaders_schedule(context: ContextLock) -> Result<impl Reply, Rejection> {
+    // the context is only locked to get the enclave and the tip, not during
+    // the evaluation of the schedule
+    let schedule = {
+        let context = context.read().await;
+        logic::get_leaders_schedule(&context)
+            .await
+            .map_err(warp::reject::custom)?
+    };
+    schedule
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
 pub async fn get_stake_pools(context: ContextLock) -> Result<impl Reply, Rejection> {
     let context = context.read().await;
     logic::get_stake_pools(&context)
@@ -7,11 +7,15 @@
 //   they are 400 or 500.
 
 use crate::{
-    blockchain::StorageError,
+    blockchain::{new_epoch_leadership_from, EpochLeadership, Ref, StorageError},
     diagnostic::Diagnostic,
     intercom::{self, NetworkMsg, TransactionMsg},
     rest::Context,
-    secure::NodeSecret,
+    secure::{
+        enclave::{Enclave, Error as EnclaveError, Schedule},
+        NodeSecret,
+    },
+    settings::logging::FilterError as LogFilterError,
 };
 use chain_core::property::{
     Block as _, Deserialize, Fragment as fragment_property, FromStr, Serialize,
@@ -29,12 +33,17 @@ use chain_impl_mockchain::{
     transaction::Transaction,
     value::{Value, ValueError},
 };
+use chain_time::{
+    era::{EpochPosition, EpochSlotOffset},
+    Epoch,
+};
 use jormungandr_lib::{
     interfaces::{
-        AccountState, EnclaveLeaderId, EpochRewardsInfo, FragmentLog, FragmentOrigin,
-        LeadershipLog, NodeStats, NodeStatsDto, PeerStats, Rewards as StakePoolRewards,
-        SettingsDto, StakeDistribution, StakeDistributionDto, StakePoolStats, TaxTypeSerde,
-        TransactionOutput, VotePlanStatus,
+        AccountState, EnclaveLeaderId, EpochLeaderSchedule, EpochRewardsInfo, FragmentLog,
+        FragmentOrigin, LeaderSlot, LeaderStats, LeadershipLog, LogFilter, LogTargetLevel,
+        NodeStats, NodeStatsDto, PeerStats, Rewards as StakePoolRewards, SettingsDto,
+        StakeDistribution, StakeDistributionDto, StakePoolStats, TaxTypeSerde, TransactionOutput,
+        VotePlanStatus,
     },
     time::SystemTime,
 };
@@ -76,6 +85,12 @@ pub enum Error {
     InvalidTopic,
     #[error(transparent)]
     Hex(#[from] hex::FromHexError),
+    #[error(transparent)]
+    Enclave(#[from] EnclaveError),
+    #[error(transparent)]
+    LogFilter(#[from] LogFilterError),
+    #[error("the evaluation of the leader schedule failed")]
+    LeadersSchedule(#[from] tokio::task::JoinError),
 }
 
 fn parse_account_id(id_hex: &str) -> Result<Identifier, Error> {
@@ -380,6 +395,7 @@ pub async fn get_settings(context: &Context) -> Result<SettingsDto, Error> {
         slots_per_epoch,
         treasury_tax: current_params.treasury_tax,
         reward_params: current_params.reward_params.clone(),
+        fragment_selection_algorithm: full_context.fragment_selection_algorithm,
     })
 }
 
@@ -389,6 +405,32 @@ pub async fn shutdown(context: &mut Context) -> Result<(), Error> {
     Ok(())
 }
 
+pub async fn get_log_filter(context: &Context) -> Result<LogFilter, Error> {
+    let log_filter = context.log_filter()?;
+    Ok(LogFilter {
+        default_levels: log_filter
+            .default_levels()
+            .iter()
+            .map(ToString::to_string)
+            .collect(),
+        targets: log_filter
+            .target_levels()
+            .into_iter()
+            .map(|(target, level)| (target, level.to_string()))
+            .collect(),
+    })
+}
+
+pub async fn set_log_target_level(
+    context: &Context,
+    target_level: LogTargetLevel,
+) -> Result<LogFilter, Error> {
+    context
+        .log_filter()?
+        .set_target_level(target_level.target, target_level.level.as_deref())?;
+    get_log_filter(context).await
+}
+
 pub async fn get_leader_ids(context: &Context) -> Result<Vec<EnclaveLeaderId>, Error> {
     Ok(context.try_full()?.enclave.get_leader_ids().await)
 }
@@ -398,7 +440,7 @@ pub async fn post_leaders(context: &Context, secret: NodeSecret) -> Result<Encla
         bft_leader: secret.bft(),
         genesis_leader: secret.genesis(),
     };
-    let leader_id = context.try_full()?.enclave.add_leader(leader).await;
+    let leader_id = context.try_full()?.enclave.add_leader(leader).await?;
     Ok(leader_id)
 }
 
@@ -406,7 +448,7 @@ pub async fn delete_leaders(
     context: &Context,
     leader_id: EnclaveLeaderId,
 ) -> Result<Option<()>, Error> {
-    let removed = context.try_full()?.enclave.remove_leader(leader_id).await;
+    let removed = context.try_full()?.enclave.remove_leader(leader_id).await?;
 
     if removed {
         Ok(Some(()))
@@ -419,6 +461,87 @@ pub async fn get_leaders_logs(context: &Context) -> Result<Vec<LeadershipLog>, E
     Ok(context.try_full()?.leadership_logs.logs().await)
 }
 
+pub async fn get_leaders_stats(context: &Context) -> Result<Vec<LeaderStats>, Error> {
+    Ok(context.try_full()?.leadership_tracker.stats().await)
+}
+
+/// compute the slots the leaders of the enclave are elected for, in the
+/// current epoch and in the next one if its schedule is already known.
+///
+/// Only the enclave and the tip are taken from the context, the returned
+/// future evaluates the schedule on a blocking thread once the context is
+/// released.
+pub async fn get_leaders_schedule(
+    context: &Context,
+) -> Result<impl Future<Output = Result<Vec<EpochLeaderSchedule>, Error>>, Error> {
+    let enclave = Arc::new(context.try_full()?.enclave.clone());
+    let tip = context.blockchain_tip()?.get_ref().await;
+    let runtime = tokio::runtime::Handle::current();
+    Ok(async move {
+        let now = SystemTime::now();
+        let schedules = tokio::task::spawn_blocking(move || {
+            runtime.block_on(leaders_schedule(enclave, tip, now))
+        })
+        .await?;
+        Ok(schedules)
+    })
+}
+
+async fn leaders_schedule(
+    enclave: Arc<Enclave>,
+    tip: Arc<Ref>,
+    now: SystemTime,
+) -> Vec<EpochLeaderSchedule> {
+    let tip_epoch = tip.block_date().epoch;
+
+    // no block may have been added yet since the local time entered a new
+    // epoch, the current epoch is then ahead of the tip
+    let era = tip.epoch_leadership_schedule().era();
+    let current_epoch = tip
+        .time_frame()
+        .slot_at(now.as_ref())
+        .and_then(|slot| era.from_slot_to_era(slot))
+        .map_or(tip_epoch, |position| position.epoch.0)
+        .max(tip_epoch);
+
+    // the stake distribution of an epoch is the one at the end of the epoch
+    // before the previous one: the schedule is only known up to the epoch
+    // following the tip
+    let mut schedules = Vec::new();
+    for epoch in current_epoch..=tip_epoch + 1 {
+        let EpochLeadership {
+            leadership,
+            time_frame,
+            ..
+        } = new_epoch_leadership_from(epoch, Arc::clone(&tip), false);
+        let era = leadership.era().clone();
+
+        let mut schedule = Schedule::new(
+            Arc::clone(&enclave),
+            Arc::clone(&leadership),
+            0,
+            era.slots_per_epoch(),
+        );
+        let mut slots = Vec::new();
+        while let Some(event) = schedule.next().await {
+            let slot = era.from_era_to_slot(EpochPosition {
+                epoch: Epoch(event.date.epoch),
+                slot: EpochSlotOffset(event.date.slot_id),
+            });
+            if let Some(scheduled_at_time) = time_frame.slot_to_systemtime(slot) {
+                slots.push(LeaderSlot {
+                    enclave_leader_id: event.id,
+                    scheduled_at_date: event.date.into(),
+                    scheduled_at_time: scheduled_at_time.into(),
+                });
+            }
+        }
+        slots.sort_by_key(|slot| (slot.scheduled_at_date, slot.enclave_leader_id));
+        schedules.push(EpochLeaderSchedule { epoch, slots });
+    }
+    schedules
+}
+
 pub async fn get_stake_pools(context: &Context) -> Result<Vec<String>, Error> {
     Ok(context
         .blockchain_tip()?
@@ -658,3 +781,137 @@ pub async fn get_active_vote_plans(context: &Context) -> Result<Vec<VotePlanStat
         .collect();
     Ok(vp)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockcfg::{Block, BlockDate},
+        blockchain::{Blockchain, LedgerSnapshots, Storage},
+    };
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{
+        chaintypes::ConsensusVersion, fee::LinearFee, key::BftLeaderId, leadership::BftLeader,
+    };
+    use jormungandr_lib::{
+        interfaces::{
+            Block0Configuration, BlockchainConfiguration, ConsensusLeaderId, NumberOfSlotsPerEpoch,
+        },
+        time::SecondsSinceUnixEpoch,
+    };
+    use tracing::Span;
+
+    const SLOTS_PER_EPOCH: u32 = 10;
+    const BLOCK0_TIME: u64 = 1_600_000_000;
+
+    fn block0(leader: &SecretKey<Ed25519>) -> Block {
+        let mut blockchain_configuration = BlockchainConfiguration::new(
+            chain_addr::Discrimination::Test,
+            ConsensusVersion::Bft,
+            LinearFee::new(1, 2, 3),
+        );
+        blockchain_configuration.block0_date = SecondsSinceUnixEpoch::from_secs(BLOCK0_TIME);
+        blockchain_configuration.slots_per_epoch =
+            NumberOfSlotsPerEpoch::new(SLOTS_PER_EPOCH).unwrap();
+        blockchain_configuration.consensus_leader_ids =
+            vec![ConsensusLeaderId(BftLeaderId::from(leader.to_public()))];
+        Block0Configuration {
+            blockchain_configuration,
+            initial: Vec::new(),
+        }
+        .to_block()
+    }
+
+    /// the tip of a chain with only the block0, whose single BFT leader is
+    /// in the enclave and is elected at every slot
+    async fn tip_and_enclave() -> (Arc<Ref>, Arc<Enclave>, EnclaveLeaderId) {
+        let leader = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let block0 = block0(&leader);
+        let blockchain = Blockchain::new(
+            block0.header.hash(),
+            Storage::memory(Span::none()).unwrap(),
+            LedgerSnapshots::new(None, 0),
+            1024,
+            false,
+        );
+        let tip = blockchain
+            .load_from_block0(block0)
+            .await
+            .unwrap()
+            .get_ref()
+            .await;
+
+        let enclave = Enclave::new();
+        let leader_id = enclave
+            .add_leader(Leader {
+                bft_leader: Some(BftLeader { sig_key: leader }),
+                genesis_leader: None,
+            })
+            .await
+            .unwrap();
+        (tip, Arc::new(enclave), leader_id)
+    }
+
+    fn slot_time(tip: &Ref, epoch: u32, slot: u32) -> SystemTime {
+        let era = tip.epoch_leadership_schedule().era();
+        let slot = era.from_era_to_slot(EpochPosition {
+            epoch: Epoch(epoch),
+            slot: EpochSlotOffset(slot),
+        });
+        tip.time_frame().slot_to_systemtime(slot).unwrap().into()
+    }
+
+    #[tokio::test]
+    async fn schedule_of_the_current_and_next_epochs() {
+        let (tip, enclave, leader_id) = tip_and_enclave().await;
+        let now = slot_time(&tip, 0, 0);
+        let schedules = leaders_schedule(enclave, Arc::clone(&tip), now).await;
+
+        assert_eq!(
+            schedules
+                .iter()
+                .map(|schedule| schedule.epoch)
+                .collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        for schedule in schedules {
+            let expected: Vec<LeaderSlot> = (0..SLOTS_PER_EPOCH)
+                .map(|slot_id| LeaderSlot {
+                    enclave_leader_id: leader_id,
+                    scheduled_at_date: BlockDate {
+                        epoch: schedule.epoch,
+                        slot_id,
+                    }
+                    .into(),
+                    scheduled_at_time: slot_time(&tip, schedule.epoch, slot_id),
+                })
+                .collect();
+            assert_eq!(schedule.slots, expected);
+        }
+    }
+
+    #[tokio::test]
+    async fn schedule_follows_the_local_time() {
+        let (tip, enclave, _) = tip_and_enclave().await;
+
+        // the local time is in the epoch following the tip
+        let now = slot_time(&tip, 1, 3);
+        let schedules = leaders_schedule(Arc::clone(&enclave), Arc::clone(&tip), now).await;
+        assert_eq!(schedules.len(), 1);
+        assert_eq!(schedules[0].epoch, 1);
+        assert_eq!(schedules[0].slots.len(), SLOTS_PER_EPOCH as usize);
+
+        // the stake distribution of later epochs is not known yet
+        let now = slot_time(&tip, 2, 0);
+        assert!(leaders_schedule(enclave, tip, now).await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn schedule_without_leaders_is_empty() {
+        let (tip, _, _) = tip_and_enclave().await;
+        let now = slot_time(&tip, 0, 0);
+        let schedules = leaders_schedule(Arc::new(Enclave::new()), tip, now).await;
+        assert_eq!(schedules.len(), 2);
+        assert!(schedules.iter().all(|schedule| schedule.slots.is_empty()));
+    }
+}
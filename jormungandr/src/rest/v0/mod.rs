@@ -1,20 +1,37 @@
 mod handlers;
 pub mod logic;
 
-use crate::rest::{display_internal_server_error, ContextLock};
+use crate::{
+    rest::{
+        auth::{self, AdminAuth, Unauthorized},
+        display_internal_server_error,
+        limits::{self, Limits},
+        tls, ContextLock,
+    },
+    settings::logging::FilterError as LogFilterError,
+};
 
 use warp::{http::StatusCode, Filter, Rejection, Reply};
 
+/// the routes of the API version 0. The routes controlling the node or
+/// exposing its keys and peers are admin ones, requiring the credentials of
+/// `admin_auth` if set. The requests submitting fragments are subject to
+/// `limits`.
 pub fn filter(
     context: ContextLock,
+    admin_auth: Option<AdminAuth>,
+    limits: Limits,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let with_context = warp::any().map(move || context.clone());
+    let admin = auth::admin(admin_auth);
     let root = warp::path!("v0" / ..);
 
     let shutdown = warp::path!("shutdown")
+        .and(admin.clone())
         .and(warp::get().or(warp::post()))
+        .and(tls::client_subject())
         .and(with_context.clone())
-        .and_then(|_, context| handlers::shutdown(context))
+        .and_then(|_, client, context| handlers::shutdown(client, context))
         .boxed();
 
     let account = warp::path!("account" / String)
@@ -66,6 +83,7 @@ pub fn filter(
         let post = warp::path::end()
             .and(warp::post())
             .and(warp::body::json())
+            .and(tls::client_subject())
             .and(with_context.clone())
             .and_then(handlers::post_leaders)
             .boxed();
@@ -76,13 +94,46 @@ pub fn filter(
             .and_then(handlers::get_leaders_logs)
             .boxed();
 
+        let schedule = warp::path!("schedule")
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_leaders_schedule)
+            .boxed();
+
+        let stats = warp::path!("stats")
+            .and(warp::get())
+            .and(with_context.clone())
+            .and_then(handlers::get_leaders_stats)
+            .boxed();
+
         let delete = warp::path!(u32)
             .and(warp::delete())
+            .and(tls::client_subject())
             .and(with_context.clone())
             .and_then(handlers::delete_leaders)
             .boxed();
 
-        root.and(get.or(post).or(logs).or(delete)).boxed()
+        root.and(admin.clone())
+            .and(get.or(post).or(logs).or(schedule).or(stats).or(delete))
+            .boxed()
+    };
+
+    let log = {
+        let root = warp::path!("log" / "filter").boxed();
+
+        let get = warp::get()
+            .and(with_context.clone())
+            .and_then(handlers::get_log_filter)
+            .boxed();
+
+        let post = warp::post()
+            .and(warp::body::json())
+            .and(tls::client_subject())
+            .and(with_context.clone())
+            .and_then(handlers::post_log_filter)
+            .boxed();
+
+        root.and(admin.clone()).and(get.or(post)).boxed()
     };
 
     let p2p = {
@@ -124,7 +175,8 @@ pub fn filter(
             root.and(view.or(view_topic)).boxed()
         };
 
-        root.and(quarantined.or(non_public).or(available).or(view))
+        root.and(admin.clone())
+            .and(quarantined.or(non_public).or(available).or(view))
             .boxed()
     };
 
@@ -178,7 +230,8 @@ pub fn filter(
 
     let message = warp::path!("message")
         .and(warp::post())
-        .and(warp::body::bytes())
+        .and(limits.fragment_submission())
+        .and(limits.fragment_body())
         .and(with_context.clone())
         .and_then(handlers::post_message)
         .boxed();
@@ -220,6 +273,7 @@ pub fn filter(
         .boxed();
 
     let diagnostic = warp::path!("diagnostic")
+        .and(admin)
         .and(warp::get())
         .and(with_context.clone())
         .and_then(handlers::get_diagnostic)
@@ -246,6 +300,7 @@ pub fn filter(
         .or(block)
         .or(fragment)
         .or(leaders)
+        .or(log)
         .or(network)
         .or(settings)
         .or(stake)
@@ -264,10 +319,22 @@ pub fn filter(
 }
 
 /// Convert rejections to actual HTTP errors
-async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
+async fn handle_rejection(err: Rejection) -> Result<warp::reply::Response, Rejection> {
+    if err.find::<Unauthorized>().is_some() {
+        return Ok(auth::unauthorized_reply().into_response());
+    }
+
+    if let Some(reply) = limits::rejection_reply(&err) {
+        return Ok(reply);
+    }
+
     if let Some(err) = err.find::<logic::Error>() {
         let (body, code) = match err {
-            logic::Error::PublicKey(_) | logic::Error::Hash(_) | logic::Error::Hex(_) => {
+            logic::Error::PublicKey(_)
+            | logic::Error::Hash(_)
+            | logic::Error::Hex(_)
+            | logic::Error::LogFilter(LogFilterError::Level(_))
+            | logic::Error::LogFilter(LogFilterError::Target(_)) => {
                 (err.to_string(), StatusCode::BAD_REQUEST)
             }
             err => (
@@ -276,7 +343,7 @@ async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
             ),
         };
 
-        return Ok(warp::reply::with_status(body, code));
+        return Ok(warp::reply::with_status(body, code).into_response());
     }
 
     Err(err)
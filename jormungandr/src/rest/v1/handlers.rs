@@ -1,4 +1,7 @@
 use crate::rest::{v1::logic, ContextLock};
+use futures::StreamExt;
+use jormungandr_lib::interfaces::UnsignedTransaction;
+use std::convert::Infallible;
 use warp::{reject::Reject, Rejection, Reply};
 
 impl Reject for logic::Error {}
@@ -14,6 +17,17 @@ pub async fn post_fragments(
         .map_err(warp::reject::custom)
 }
 
+pub async fn post_validate_fragments(
+    fragments: Vec<String>,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::validate_fragments(&context, fragments)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
 #[derive(Deserialize)]
 pub struct GetMessageStatusesQuery {
     fragment_ids: String,
@@ -38,3 +52,45 @@ pub async fn get_fragment_logs(context: ContextLock) -> Result<impl Reply, Rejec
         .map_err(warp::reject::custom)
         .map(|r| warp::reply::json(&r))
 }
+
+pub async fn post_estimate_fee(
+    transaction: UnsignedTransaction,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    logic::estimate_fee(&context, transaction)
+        .await
+        .map(|r| warp::reply::json(&r))
+        .map_err(warp::reject::custom)
+}
+
+#[derive(Deserialize)]
+pub struct GetEventsQuery {
+    fragment_ids: Option<String>,
+    accounts: Option<String>,
+}
+
+pub async fn get_events(
+    query: GetEventsQuery,
+    context: ContextLock,
+) -> Result<impl Reply, Rejection> {
+    let context = context.read().await;
+    let fragment_ids = query.fragment_ids.as_deref().map(split_list);
+    let accounts = query.accounts.as_deref().map(split_list);
+    logic::subscribe_events(
+        &context,
+        fragment_ids.into_iter().flatten(),
+        accounts.into_iter().flatten(),
+    )
+    .map_err(warp::reject::custom)
+    .map(|events| {
+        let events = events.map(|event| {
+            Ok::<_, Infallible>((warp::sse::event(event.name()), warp::sse::json(event)))
+        });
+        warp::sse::reply(warp::sse::keep_alive().stream(events))
+    })
+}
+
+fn split_list(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').filter(|item| !item.is_empty())
+}
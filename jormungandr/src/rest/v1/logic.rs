@@ -1,17 +1,36 @@
 use crate::{
-    blockchain::StorageError,
+    blockcfg::{BlockDate, Ledger, LedgerParameters},
+    blockchain::{new_epoch_leadership_from, EpochLeadership, Ref, StorageError},
+    fragment::{fragment_fee, selection::error_chain_message},
     intercom::{self, TransactionMsg},
+    notifier::Event,
     rest::Context,
 };
+use chain_addr::Kind;
 use chain_core::property::{Deserialize, Fragment as _};
 use chain_crypto::{digest::Error as DigestError, hash::Error as HashError, PublicKeyFromStrError};
 use chain_impl_mockchain::{
+    account,
     fragment::{Fragment, FragmentId},
+    transaction::{InputEnum, Transaction},
     value::ValueError,
 };
+use chain_time::{TimeEra, TimeFrame};
 use futures::{channel::mpsc::SendError, channel::mpsc::TrySendError, prelude::*};
-use jormungandr_lib::interfaces::{FragmentLog, FragmentOrigin, FragmentStatus};
-use std::{collections::HashMap, str::FromStr};
+use jormungandr_lib::{
+    crypto::hash::Hash,
+    interfaces::{
+        FeeEstimate, FeeEstimationError, FragmentLog, FragmentOrigin, FragmentStatus,
+        UnsignedTransaction, Value,
+    },
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    str::FromStr,
+    sync::Arc,
+    time::SystemTime,
+};
+use tokio::sync::broadcast::error::RecvError;
 use tracing::{span, Level};
 use tracing_futures::Instrument;
 
@@ -40,6 +59,8 @@ pub enum Error {
     Storage(#[from] StorageError),
     #[error(transparent)]
     Hex(#[from] hex::FromHexError),
+    #[error(transparent)]
+    FeeEstimation(#[from] FeeEstimationError),
 }
 
 pub async fn get_fragment_statuses<'a>(
@@ -75,17 +96,21 @@ pub async fn get_fragment_statuses<'a>(
     .await
 }
 
-pub async fn post_fragments(
-    context: &Context,
-    messages: Vec<String>,
-) -> Result<Vec<String>, Error> {
-    let fragments = messages
+fn decode_fragments(messages: Vec<String>) -> Result<Vec<Fragment>, Error> {
+    messages
         .into_iter()
         .map(|message| {
             let message = hex::decode(message)?;
             Fragment::deserialize(message.as_slice()).map_err(Error::Deserialize)
         })
-        .collect::<Result<Vec<_>, _>>()?;
+        .collect()
+}
+
+pub async fn post_fragments(
+    context: &Context,
+    messages: Vec<String>,
+) -> Result<Vec<String>, Error> {
+    let fragments = decode_fragments(messages)?;
     let fragment_ids = fragments
         .iter()
         .map(|fragment| fragment.id().to_string())
@@ -98,6 +123,162 @@ pub async fn post_fragments(
     Ok(fragment_ids)
 }
 
+#[derive(Serialize)]
+pub struct FragmentValidation {
+    fragment_id: String,
+    valid: bool,
+    /// the ledger error if the fragment is not valid
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// the difference between the inputs and the outputs of the fragment
+    fee: Value,
+    /// balances of the accounts involved in the fragment once it is applied,
+    /// empty if the fragment is not valid
+    balances: BTreeMap<String, Value>,
+}
+
+/// Apply the fragments in order on top of the ledger of the current tip,
+/// as they would be in a block, without submitting them to the mempool.
+pub async fn validate_fragments(
+    context: &Context,
+    messages: Vec<String>,
+) -> Result<Vec<FragmentValidation>, Error> {
+    let fragments = decode_fragments(messages)?;
+    let tip = context.blockchain_tip()?.get_ref().await;
+    let block_date = next_block_date(
+        tip.block_date(),
+        tip.epoch_leadership_schedule().era(),
+        tip.time_frame(),
+        SystemTime::now(),
+    );
+    let (mut ledger, ledger_params) = ledger_at(tip, block_date);
+
+    let validations = fragments
+        .iter()
+        .map(|fragment| {
+            let fragment_id = fragment.id().to_string();
+            let fee = fragment_fee(fragment).into();
+            match ledger.apply_fragment(&ledger_params, fragment, block_date) {
+                Ok(new_ledger) => {
+                    ledger = new_ledger;
+                    let balances = fragment_account_ids(fragment)
+                        .into_iter()
+                        .filter_map(|id| {
+                            let state = ledger.accounts().get_state(&id).ok()?;
+                            Some((id.to_string(), state.value().into()))
+                        })
+                        .collect();
+                    FragmentValidation {
+                        fragment_id,
+                        valid: true,
+                        error: None,
+                        fee,
+                        balances,
+                    }
+                }
+                Err(error) => FragmentValidation {
+                    fragment_id,
+                    valid: false,
+                    error: Some(error_chain_message(&error)),
+                    fee,
+                    balances: BTreeMap::new(),
+                },
+            }
+        })
+        .collect();
+    Ok(validations)
+}
+
+/// The date of the next block the fragments could be included in: the slot
+/// following the local time, or the slot following the tip if the local time
+/// is behind it.
+fn next_block_date(
+    tip_date: BlockDate,
+    era: &TimeEra,
+    time_frame: &TimeFrame,
+    now: SystemTime,
+) -> BlockDate {
+    let after_tip = tip_date.next(era);
+    time_frame
+        .slot_at(&now)
+        .and_then(|slot| era.from_slot_to_era(slot))
+        .map(|position| {
+            BlockDate {
+                epoch: position.epoch.0,
+                slot_id: position.slot.0,
+            }
+            .next(era)
+        })
+        .map_or(after_tip, |after_now| after_now.max(after_tip))
+}
+
+/// The ledger and the ledger parameters a block at `block_date` on top of
+/// the tip is applied with, i.e. those of the epoch transition when the date
+/// is in a later epoch than the tip.
+fn ledger_at(tip: Arc<Ref>, block_date: BlockDate) -> (Ledger, Arc<LedgerParameters>) {
+    let EpochLeadership {
+        state,
+        ledger_parameters,
+        ..
+    } = new_epoch_leadership_from(block_date.epoch, tip, false);
+    (state.as_ref().clone(), ledger_parameters)
+}
+
+/// The single accounts spent from or credited by the fragment.
+fn fragment_account_ids(fragment: &Fragment) -> Vec<account::Identifier> {
+    fn account_ids<P>(tx: &Transaction<P>) -> Vec<account::Identifier> {
+        let tx = tx.as_slice();
+        let inputs = tx
+            .inputs()
+            .iter()
+            .filter_map(|input| match input.to_enum() {
+                InputEnum::AccountInput(id, _) => id.to_single_account(),
+                InputEnum::UtxoInput(_) => None,
+            });
+        let outputs = tx
+            .outputs()
+            .iter()
+            .filter_map(|output| match output.address.kind() {
+                Kind::Account(public_key) => Some(public_key.clone().into()),
+                _ => None,
+            });
+        let mut ids = Vec::new();
+        for id in inputs.chain(outputs) {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        ids
+    }
+
+    match fragment {
+        Fragment::Transaction(tx) => account_ids(tx),
+        Fragment::OwnerStakeDelegation(tx) => account_ids(tx),
+        Fragment::StakeDelegation(tx) => account_ids(tx),
+        Fragment::PoolRegistration(tx) => account_ids(tx),
+        Fragment::PoolRetirement(tx) => account_ids(tx),
+        Fragment::PoolUpdate(tx) => account_ids(tx),
+        Fragment::VotePlan(tx) => account_ids(tx),
+        Fragment::VoteCast(tx) => account_ids(tx),
+        Fragment::VoteTally(tx) => account_ids(tx),
+        Fragment::EncryptedVoteTally(tx) => account_ids(tx),
+        Fragment::Initial(_)
+        | Fragment::OldUtxoDeclaration(_)
+        | Fragment::UpdateProposal(_)
+        | Fragment::UpdateVote(_) => Vec::new(),
+    }
+}
+
+/// Compute the fee of the transaction with the fee settings of the current tip.
+pub async fn estimate_fee(
+    context: &Context,
+    transaction: UnsignedTransaction,
+) -> Result<FeeEstimate, Error> {
+    let tip = context.blockchain_tip()?.get_ref().await;
+    let fee = transaction.fee(&tip.epoch_ledger_parameters().fees)?;
+    Ok(FeeEstimate { fee })
+}
+
 pub async fn get_fragment_logs(context: &Context) -> Result<Vec<FragmentLog>, Error> {
     let span =
         span!(parent: context.span()?, Level::TRACE, "fragment_logs", request = "fragment_logs");
@@ -115,3 +296,179 @@ pub async fn get_fragment_logs(context: &Context) -> Result<Vec<FragmentLog>, Er
     .instrument(span)
     .await
 }
+
+/// Selects the fragment events sent to a subscriber, the tip events are
+/// always sent.
+struct EventFilter {
+    fragment_ids: HashSet<Hash>,
+    accounts: HashSet<String>,
+}
+
+impl EventFilter {
+    fn matches(&self, event: &Event) -> bool {
+        match event {
+            Event::FragmentStatus {
+                fragment_id,
+                accounts,
+                ..
+            } => {
+                (self.fragment_ids.is_empty() && self.accounts.is_empty())
+                    || self.fragment_ids.contains(fragment_id)
+                    || accounts
+                        .iter()
+                        .any(|account| self.accounts.contains(account))
+            }
+            Event::Tip { .. } | Event::Rollback { .. } => true,
+        }
+    }
+}
+
+/// Subscribe to the node events, the fragment events are restricted to the
+/// given fragments and to the fragments spending from the given accounts
+/// unless both are empty.
+pub fn subscribe_events<'a>(
+    context: &Context,
+    fragment_ids: impl IntoIterator<Item = &'a str>,
+    accounts: impl IntoIterator<Item = &'a str>,
+) -> Result<impl Stream<Item = Event>, Error> {
+    let fragment_ids = fragment_ids
+        .into_iter()
+        .map(|s| FragmentId::from_str(s).map(Hash::from))
+        .collect::<Result<HashSet<_>, _>>()?;
+    let accounts = accounts
+        .into_iter()
+        .map(|s| hex::decode(s).map(hex::encode))
+        .collect::<Result<HashSet<_>, _>>()?;
+    let filter = EventFilter {
+        fragment_ids,
+        accounts,
+    };
+
+    let receiver = context.try_full()?.notifier.subscribe();
+    let events = stream::unfold(receiver, |mut receiver| async move {
+        loop {
+            match receiver.recv().await {
+                Ok(event) => return Some((event, receiver)),
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::debug!(
+                        missed,
+                        "event subscriber is lagging behind, skipping events"
+                    );
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    });
+    Ok(events.filter(move |event| future::ready(filter.matches(event))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blockcfg::{Block, Leadership},
+        blockchain::Multiverse,
+    };
+    use chain_core::property::ChainLength as _;
+    use chain_crypto::{Ed25519, SecretKey};
+    use chain_impl_mockchain::{chaintypes::ConsensusVersion, fee::LinearFee, key::BftLeaderId};
+    use chain_time::{Epoch, SlotDuration, Timeline};
+    use jormungandr_lib::interfaces::{
+        Block0Configuration, BlockchainConfiguration, ConsensusLeaderId,
+    };
+    use std::time::Duration;
+
+    const SLOTS_PER_EPOCH: u32 = 10;
+
+    fn date(epoch: u32, slot_id: u32) -> BlockDate {
+        BlockDate { epoch, slot_id }
+    }
+
+    /// slots of 10 seconds starting at `start`
+    fn next_date(start: SystemTime, tip_date: BlockDate, now: SystemTime) -> BlockDate {
+        let era = TimeEra::new(0u64.into(), Epoch(0), SLOTS_PER_EPOCH);
+        let time_frame = TimeFrame::new(Timeline::new(start), SlotDuration::from_secs(10));
+        next_block_date(tip_date, &era, &time_frame, now)
+    }
+
+    #[test]
+    fn next_block_date_follows_the_local_time() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let now = start + Duration::from_secs(25);
+
+        assert_eq!(next_date(start, date(0, 1), now), date(0, 3));
+        // the last slot of the epoch is followed by the next epoch
+        let now = start + Duration::from_secs(95);
+        assert_eq!(next_date(start, date(0, 1), now), date(1, 0));
+    }
+
+    #[test]
+    fn next_block_date_follows_the_tip_ahead_of_the_local_time() {
+        let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+
+        let now = start + Duration::from_secs(25);
+        assert_eq!(next_date(start, date(0, 8), now), date(0, 9));
+        // before the start of the blockchain
+        let now = start - Duration::from_secs(25);
+        assert_eq!(next_date(start, BlockDate::first(), now), date(0, 1));
+    }
+
+    fn block0(fees: LinearFee) -> Block {
+        let leader = SecretKey::<Ed25519>::from_binary(&[1; 32]).unwrap();
+        let mut blockchain_configuration = BlockchainConfiguration::new(
+            chain_addr::Discrimination::Test,
+            ConsensusVersion::Bft,
+            fees,
+        );
+        blockchain_configuration.consensus_leader_ids =
+            vec![ConsensusLeaderId(BftLeaderId::from(leader.to_public()))];
+        Block0Configuration {
+            blockchain_configuration,
+            initial: Vec::new(),
+        }
+        .to_block()
+    }
+
+    /// a tip whose ledger settings already have the given fees while the
+    /// parameters of its epoch still have the `epoch_fees`, as after an
+    /// update adopted during the epoch
+    async fn tip(fees: LinearFee, epoch_fees: LinearFee) -> Arc<Ref> {
+        let block0 = block0(fees);
+        let hash = block0.header.hash();
+        let ledger = Ledger::new(hash, block0.contents.iter()).unwrap();
+        let leadership = Leadership::new(0, &ledger);
+        let mut ledger_parameters = leadership.ledger_parameters().clone();
+        ledger_parameters.fees = epoch_fees;
+        let ledger_ref = Multiverse::new()
+            .insert(block0.header.chain_length(), hash, ledger.clone())
+            .await;
+        let time_frame = TimeFrame::new(
+            Timeline::new(SystemTime::UNIX_EPOCH),
+            SlotDuration::from_secs(10),
+        );
+        Arc::new(Ref::new(
+            ledger_ref,
+            Arc::new(time_frame),
+            Arc::new(leadership),
+            Arc::new(ledger),
+            Arc::new(ledger_parameters),
+            None,
+            block0.header,
+            None,
+        ))
+    }
+
+    #[tokio::test]
+    async fn fragments_are_validated_with_the_parameters_of_the_next_epoch() {
+        let fees = LinearFee::new(1, 2, 3);
+        let epoch_fees = LinearFee::new(10, 20, 30);
+        let tip = tip(fees, epoch_fees).await;
+
+        let (_, ledger_params) = ledger_at(Arc::clone(&tip), date(0, 1));
+        assert_eq!(ledger_params.fees.constant, epoch_fees.constant);
+
+        // the next block date is past the epoch boundary
+        let (_, ledger_params) = ledger_at(tip, date(1, 0));
+        assert_eq!(ledger_params.fees.constant, fees.constant);
+    }
+}
@@ -1,12 +1,19 @@
 mod handlers;
 mod logic;
 
-use crate::rest::{display_internal_server_error, ContextLock};
+use crate::rest::{
+    display_internal_server_error,
+    limits::{self, Limits},
+    ContextLock,
+};
 
 use warp::{http::StatusCode, Filter, Rejection, Reply};
 
+/// the routes of the API version 1. The requests submitting fragments are
+/// subject to `limits`.
 pub fn filter(
     context: ContextLock,
+    limits: Limits,
 ) -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
     let with_context = warp::any().map(move || context.clone());
     let root = warp::path!("v1" / ..);
@@ -16,11 +23,20 @@ pub fn filter(
 
         let post = warp::path::end()
             .and(warp::post())
-            .and(warp::body::json())
+            .and(limits.fragment_submission())
+            .and(limits.fragment_batch())
             .and(with_context.clone())
             .and_then(handlers::post_fragments)
             .boxed();
 
+        let validate = warp::path!("validate")
+            .and(warp::post())
+            .and(limits.fragment_submission())
+            .and(limits.fragment_batch())
+            .and(with_context.clone())
+            .and_then(handlers::post_validate_fragments)
+            .boxed();
+
         let status = warp::path!("statuses")
             .and(warp::get())
             .and(warp::query())
@@ -30,32 +46,51 @@ pub fn filter(
 
         let logs = warp::path!("logs")
             .and(warp::get())
-            .and(with_context)
+            .and(with_context.clone())
             .and_then(handlers::get_fragment_logs)
             .boxed();
 
-        root.and(post.or(status).or(logs)).boxed()
+        root.and(post.or(validate).or(status).or(logs)).boxed()
     };
 
-    let routes = fragments;
+    let fees = warp::path!("fees" / "estimate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_context.clone())
+        .and_then(handlers::post_estimate_fee)
+        .boxed();
+
+    let events = warp::path!("events")
+        .and(warp::get())
+        .and(warp::query())
+        .and(with_context)
+        .and_then(handlers::get_events)
+        .boxed();
+
+    let routes = fragments.or(fees).or(events);
 
     root.and(routes).recover(handle_rejection).boxed()
 }
 
 /// Convert rejections to actual HTTP errors
-async fn handle_rejection(err: Rejection) -> Result<impl Reply, Rejection> {
+async fn handle_rejection(err: Rejection) -> Result<warp::reply::Response, Rejection> {
+    if let Some(reply) = limits::rejection_reply(&err) {
+        return Ok(reply);
+    }
+
     if let Some(err) = err.find::<logic::Error>() {
         let (body, code) = match err {
-            logic::Error::PublicKey(_) | logic::Error::Hash(_) | logic::Error::Hex(_) => {
-                (err.to_string(), StatusCode::BAD_REQUEST)
-            }
+            logic::Error::PublicKey(_)
+            | logic::Error::Hash(_)
+            | logic::Error::Hex(_)
+            | logic::Error::FeeEstimation(_) => (err.to_string(), StatusCode::BAD_REQUEST),
             err => (
                 display_internal_server_error(err),
                 StatusCode::INTERNAL_SERVER_ERROR,
             ),
         };
 
-        return Ok(warp::reply::with_status(body, code));
+        return Ok(warp::reply::with_status(body, code).into_response());
     }
 
     Err(err)
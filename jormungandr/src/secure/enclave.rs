@@ -1,22 +1,102 @@
 use crate::blockcfg::{
     BlockDate, HeaderBft, HeaderBftBuilder, HeaderGenesisPraos, HeaderGenesisPraosBuilder,
-    HeaderSetConsensusSignature,
+    HeaderSetConsensusSignature, Leadership,
 };
-use chain_impl_mockchain::leadership::{Leader, LeaderOutput, Leadership};
+use async_trait::async_trait;
+use chain_crypto::{Ed25519, Signature, SumEd25519_12};
+use chain_impl_mockchain::leadership::{Leader, LeaderOutput};
 use jormungandr_lib::interfaces::EnclaveLeaderId as LeaderId;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use thiserror::Error;
 use tokio::sync::RwLock;
 
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("the leaders are managed by the remote signer")]
+    LeadersManagedBySigner,
+    #[error("cannot communicate with the remote signer")]
+    SignerIo(#[from] std::io::Error),
+    #[error("invalid message exchanged with the remote signer")]
+    SignerMessage(#[from] serde_json::Error),
+    #[error("the remote signer refused the request: {0}")]
+    SignerRefused(String),
+    #[error("unexpected response from the remote signer")]
+    SignerUnexpectedResponse,
+    #[error("invalid signature from the remote signer")]
+    SignerSignatureInvalid,
+}
+
+/// A store of leader keys, able to tell which of its leaders are elected at
+/// a given date and to sign the headers of the blocks they create.
+#[async_trait]
+pub trait EnclaveBackend: Send + Sync {
+    async fn get_leader_ids(&self) -> Vec<LeaderId>;
+
+    async fn add_leader(&self, leader: Leader) -> Result<LeaderId, Error>;
+
+    async fn remove_leader(&self, leader_id: LeaderId) -> Result<bool, Error>;
+
+    /// replace the keys of the given leader, `None` if the leader is unknown.
+    ///
+    /// The new keys are registered as a new leader. Without an epoch the old
+    /// leader is removed at once, otherwise the old leader stays elected
+    /// until the given epoch and the new one is only elected from it.
+    async fn replace_leader(
+        &self,
+        leader_id: LeaderId,
+        leader: Leader,
+        at_epoch: Option<u32>,
+    ) -> Result<Option<LeaderId>, Error>;
+
+    /// the leaders elected at the given date, with the proof of their election
+    async fn leaders_at(
+        &self,
+        leadership: &Leadership,
+        date: BlockDate,
+    ) -> Vec<(LeaderId, LeaderOutput)>;
+
+    /// sign the authenticated data of a BFT header, `None` if the leader
+    /// is unknown or is not a BFT leader
+    async fn sign_bft(
+        &self,
+        leader_id: LeaderId,
+        data: &[u8],
+    ) -> Result<Option<Signature<[u8], Ed25519>>, Error>;
+
+    /// sign the authenticated data of a Genesis-Praos header, `None` if the
+    /// leader is unknown or is not a Genesis-Praos leader
+    async fn sign_genesis_praos(
+        &self,
+        leader_id: LeaderId,
+        data: &[u8],
+    ) -> Result<Option<Signature<[u8], SumEd25519_12>>, Error>;
+}
+
+/// a leader of the enclave, only elected within the epochs it is active
+struct EnclaveLeader {
+    leader: Leader,
+    /// the first epoch the leader is active, if it replaces another leader
+    active_from: Option<u32>,
+    /// the first epoch the leader is not active anymore, if it is replaced
+    active_until: Option<u32>,
+}
+
 #[derive(Default)]
 struct EnclaveLeadersWithCache {
-    leaders: BTreeMap<LeaderId, Leader>,
+    leaders: BTreeMap<LeaderId, EnclaveLeader>,
     added_leaders_cache: HashMap<String, LeaderId>,
 }
 
+/// keeps the leader keys in the memory of the node
+#[derive(Default)]
+pub struct MemoryEnclave {
+    leaders_data: RwLock<EnclaveLeadersWithCache>,
+}
+
 #[derive(Clone)]
 pub struct Enclave {
-    leaders_data: Arc<RwLock<EnclaveLeadersWithCache>>,
+    backend: Arc<dyn EnclaveBackend>,
 }
 
 pub struct LeaderEvent {
@@ -33,7 +113,7 @@ pub struct Schedule {
     current_slot_data: Vec<LeaderEvent>,
 }
 
-fn get_maximum_id<A>(leaders: &BTreeMap<LeaderId, A>) -> LeaderId {
+pub(super) fn get_maximum_id<A>(leaders: &BTreeMap<LeaderId, A>) -> LeaderId {
     leaders.keys().last().copied().unwrap_or_default()
 }
 
@@ -58,6 +138,13 @@ fn leader_identifier(leader: &Leader) -> String {
     }
 }
 
+impl EnclaveLeader {
+    fn is_active_at(&self, epoch: u32) -> bool {
+        self.active_from.map_or(true, |from| from <= epoch)
+            && self.active_until.map_or(true, |until| epoch < until)
+    }
+}
+
 impl EnclaveLeadersWithCache {
     fn add(&mut self, leader: Leader) -> LeaderId {
         let identifier = leader_identifier(&leader);
@@ -67,17 +154,66 @@ impl EnclaveLeadersWithCache {
             let leader_id = get_maximum_id(&self.leaders).next();
 
             self.added_leaders_cache.insert(identifier, leader_id);
-            self.leaders.insert(leader_id, leader);
+            self.leaders.insert(
+                leader_id,
+                EnclaveLeader {
+                    leader,
+                    active_from: None,
+                    active_until: None,
+                },
+            );
 
             leader_id
         }
     }
 
+    fn replace(
+        &mut self,
+        leader_id: LeaderId,
+        leader: Leader,
+        at_epoch: Option<u32>,
+    ) -> Option<LeaderId> {
+        if !self.leaders.contains_key(&leader_id) {
+            return None;
+        }
+        // taken before removing the old leader so its identifier is not
+        // reused for the new one
+        let new_leader_id = get_maximum_id(&self.leaders).next();
+
+        match at_epoch {
+            None => {
+                self.remove(leader_id);
+            }
+            Some(epoch) => {
+                if let Some(old_leader) = self.leaders.get_mut(&leader_id) {
+                    old_leader.active_until = Some(epoch);
+                }
+            }
+        }
+
+        // the keys of a rotated Genesis-Praos leader share the same node
+        // id, the identifier now refers to the new leader
+        self.added_leaders_cache
+            .insert(leader_identifier(&leader), new_leader_id);
+        self.leaders.insert(
+            new_leader_id,
+            EnclaveLeader {
+                leader,
+                active_from: at_epoch,
+                active_until: None,
+            },
+        );
+
+        Some(new_leader_id)
+    }
+
     fn remove(&mut self, leader_id: LeaderId) -> bool {
-        if let Some(leader) = self.leaders.remove(&leader_id) {
-            let identifier = leader_identifier(&leader);
+        if let Some(enclave_leader) = self.leaders.remove(&leader_id) {
+            let identifier = leader_identifier(&enclave_leader.leader);
 
-            self.added_leaders_cache.remove(&identifier);
+            if self.added_leaders_cache.get(&identifier) == Some(&leader_id) {
+                self.added_leaders_cache.remove(&identifier);
+            }
 
             true
         } else {
@@ -86,7 +222,83 @@ impl EnclaveLeadersWithCache {
     }
 
     fn get_leader_ids(&self) -> Vec<LeaderId> {
-        self.added_leaders_cache.values().copied().collect()
+        self.leaders.keys().copied().collect()
+    }
+}
+
+impl MemoryEnclave {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl EnclaveBackend for MemoryEnclave {
+    async fn get_leader_ids(&self) -> Vec<LeaderId> {
+        self.leaders_data.read().await.get_leader_ids()
+    }
+
+    async fn add_leader(&self, leader: Leader) -> Result<LeaderId, Error> {
+        Ok(self.leaders_data.write().await.add(leader))
+    }
+
+    async fn remove_leader(&self, leader_id: LeaderId) -> Result<bool, Error> {
+        Ok(self.leaders_data.write().await.remove(leader_id))
+    }
+
+    async fn replace_leader(
+        &self,
+        leader_id: LeaderId,
+        leader: Leader,
+        at_epoch: Option<u32>,
+    ) -> Result<Option<LeaderId>, Error> {
+        Ok(self
+            .leaders_data
+            .write()
+            .await
+            .replace(leader_id, leader, at_epoch))
+    }
+
+    async fn leaders_at(
+        &self,
+        leadership: &Leadership,
+        date: BlockDate,
+    ) -> Vec<(LeaderId, LeaderOutput)> {
+        let leaders = &self.leaders_data.read().await.leaders;
+        leaders
+            .iter()
+            .filter(|(_, enclave_leader)| enclave_leader.is_active_at(date.epoch))
+            .filter_map(|(id, enclave_leader)| {
+                match leadership.is_leader_for_date(&enclave_leader.leader, date) {
+                    LeaderOutput::None => None,
+                    leader_output => Some((*id, leader_output)),
+                }
+            })
+            .collect()
+    }
+
+    async fn sign_bft(
+        &self,
+        leader_id: LeaderId,
+        data: &[u8],
+    ) -> Result<Option<Signature<[u8], Ed25519>>, Error> {
+        let leaders = &self.leaders_data.read().await.leaders;
+        Ok(leaders
+            .get(&leader_id)
+            .and_then(|enclave_leader| enclave_leader.leader.bft_leader.as_ref())
+            .map(|leader| leader.sig_key.sign_slice(data)))
+    }
+
+    async fn sign_genesis_praos(
+        &self,
+        leader_id: LeaderId,
+        data: &[u8],
+    ) -> Result<Option<Signature<[u8], SumEd25519_12>>, Error> {
+        let leaders = &self.leaders_data.read().await.leaders;
+        Ok(leaders
+            .get(&leader_id)
+            .and_then(|enclave_leader| enclave_leader.leader.genesis_leader.as_ref())
+            .map(|leader| leader.sig_key.sign_slice(data)))
     }
 }
 
@@ -97,62 +309,58 @@ impl Default for Enclave {
 }
 
 impl Enclave {
+    /// an enclave keeping the leader keys in memory
     pub fn new() -> Self {
-        Enclave {
-            leaders_data: Arc::new(RwLock::new(EnclaveLeadersWithCache::default())),
-        }
+        Self::with_backend(MemoryEnclave::new())
     }
 
-    pub async fn from_vec(leaders: Vec<Leader>) -> Self {
-        let e = Self::new();
-        for leader in leaders {
-            e.add_leader(leader).await;
+    pub fn with_backend<B: EnclaveBackend + 'static>(backend: B) -> Self {
+        Enclave {
+            backend: Arc::new(backend),
         }
-        e
     }
 
     pub async fn get_leader_ids(&self) -> Vec<LeaderId> {
-        self.leaders_data.read().await.get_leader_ids()
+        self.backend.get_leader_ids().await
+    }
+
+    pub async fn add_leader(&self, leader: Leader) -> Result<LeaderId, Error> {
+        self.backend.add_leader(leader).await
     }
 
-    pub async fn add_leader(&self, leader: Leader) -> LeaderId {
-        self.leaders_data.write().await.add(leader)
+    pub async fn remove_leader(&self, leader_id: LeaderId) -> Result<bool, Error> {
+        self.backend.remove_leader(leader_id).await
     }
 
-    pub async fn remove_leader(&self, leader_id: LeaderId) -> bool {
-        self.leaders_data.write().await.remove(leader_id)
+    pub async fn replace_leader(
+        &self,
+        leader_id: LeaderId,
+        leader: Leader,
+        at_epoch: Option<u32>,
+    ) -> Result<Option<LeaderId>, Error> {
+        self.backend
+            .replace_leader(leader_id, leader, at_epoch)
+            .await
     }
 
     pub async fn create_header_genesis_praos(
         &self,
         header_builder: HeaderGenesisPraosBuilder<HeaderSetConsensusSignature>,
         id: LeaderId,
-    ) -> Option<HeaderGenesisPraos> {
-        let leaders = &self.leaders_data.read().await.leaders;
-        let leader = leaders.get(&id)?;
-        if let Some(genesis_leader) = &leader.genesis_leader {
-            let data = header_builder.get_authenticated_data();
-            let signature = genesis_leader.sig_key.sign_slice(data);
-            Some(header_builder.set_signature(signature.into()))
-        } else {
-            None
-        }
+    ) -> Result<Option<HeaderGenesisPraos>, Error> {
+        let data = header_builder.get_authenticated_data();
+        let signature = self.backend.sign_genesis_praos(id, data).await?;
+        Ok(signature.map(|signature| header_builder.set_signature(signature.into())))
     }
 
     pub async fn create_header_bft(
         &self,
         header_builder: HeaderBftBuilder<HeaderSetConsensusSignature>,
         id: LeaderId,
-    ) -> Option<HeaderBft> {
-        let leaders = &self.leaders_data.read().await.leaders;
-        let leader = leaders.get(&id)?;
-        if let Some(ref leader) = &leader.bft_leader {
-            let data = header_builder.get_authenticated_data();
-            let signature = leader.sig_key.sign_slice(data);
-            Some(header_builder.set_signature(signature.into()))
-        } else {
-            None
-        }
+    ) -> Result<Option<HeaderBft>, Error> {
+        let data = header_builder.get_authenticated_data();
+        let signature = self.backend.sign_bft(id, data).await?;
+        Ok(signature.map(|signature| header_builder.set_signature(signature.into())))
     }
 }
 
@@ -180,17 +388,15 @@ impl Schedule {
         }
 
         while self.current_slot < self.stop_at_slot && self.current_slot_data.is_empty() {
-            let leaders = &self.enclave.leaders_data.read().await.leaders;
             let date = self.leadership.date_at_slot(self.current_slot);
-            for (id, leader) in leaders {
-                match self.leadership.is_leader_for_date(&leader, date) {
-                    LeaderOutput::None => (),
-                    leader_output => self.current_slot_data.push(LeaderEvent {
-                        id: *id,
-                        date,
-                        output: leader_output,
-                    }),
-                }
+            let leaders = self
+                .enclave
+                .backend
+                .leaders_at(&self.leadership, date)
+                .await;
+            for (id, output) in leaders {
+                self.current_slot_data
+                    .push(LeaderEvent { id, date, output });
             }
 
             self.current_slot += 1;
@@ -216,7 +422,7 @@ mod tests {
 
     #[tokio::test]
     async fn enclave_add_different_bft_leaders() {
-        let enclave = Enclave::new();
+        let enclave = MemoryEnclave::new();
         let mut rng = rand_core::OsRng;
 
         let leader1 = Leader {
@@ -237,8 +443,8 @@ mod tests {
         let fst_id = init_leader_id.next();
         let snd_id = fst_id.next();
 
-        assert_eq!(enclave.add_leader(leader1).await, fst_id);
-        assert_eq!(enclave.add_leader(leader2).await, snd_id);
+        assert_eq!(enclave.add_leader(leader1).await.unwrap(), fst_id);
+        assert_eq!(enclave.add_leader(leader2).await.unwrap(), snd_id);
 
         let leaders_data = &enclave.leaders_data.read().await;
         assert_eq!(leaders_data.leaders.len(), 2);
@@ -247,7 +453,7 @@ mod tests {
 
     #[tokio::test]
     async fn enclave_add_duplicated_bft_leaders() {
-        let enclave = Enclave::new();
+        let enclave = MemoryEnclave::new();
         let secret_key = SecretKey::generate(rand_core::OsRng);
 
         let leader1 = Leader {
@@ -266,8 +472,8 @@ mod tests {
 
         // Both leaders are different instances of the same data, adding both of them should return the same id
         assert_eq!(
-            enclave.add_leader(leader1).await,
-            enclave.add_leader(leader2).await
+            enclave.add_leader(leader1).await.unwrap(),
+            enclave.add_leader(leader2).await.unwrap()
         );
 
         // Just one it is really added
@@ -286,7 +492,7 @@ mod tests {
 
     #[tokio::test]
     async fn enclave_add_different_genesis_leaders() {
-        let enclave = Enclave::new();
+        let enclave = MemoryEnclave::new();
         let mut rng = rand_core::OsRng;
 
         let leader1 = Leader {
@@ -311,8 +517,8 @@ mod tests {
         let fst_id = init_leader_id.next();
         let snd_id = fst_id.next();
 
-        assert_eq!(enclave.add_leader(leader1).await, fst_id);
-        assert_eq!(enclave.add_leader(leader2).await, snd_id);
+        assert_eq!(enclave.add_leader(leader1).await.unwrap(), fst_id);
+        assert_eq!(enclave.add_leader(leader2).await.unwrap(), snd_id);
 
         let leaders_data = &enclave.leaders_data.read().await;
         assert_eq!(leaders_data.leaders.len(), 2);
@@ -321,7 +527,7 @@ mod tests {
 
     #[tokio::test]
     async fn enclave_add_duplicated_genesis_leaders() {
-        let enclave = Enclave::new();
+        let enclave = MemoryEnclave::new();
 
         let mut rng = rand_core::OsRng;
         let sig_key_1 = SecretKey::generate(&mut rng);
@@ -348,8 +554,8 @@ mod tests {
 
         // Both leaders are different instances of the same data, adding both of them should return the same id
         assert_eq!(
-            enclave.add_leader(leader1).await,
-            enclave.add_leader(leader2).await
+            enclave.add_leader(leader1).await.unwrap(),
+            enclave.add_leader(leader2).await.unwrap()
         );
 
         let leaders_data = &enclave.leaders_data.read().await;
@@ -358,4 +564,59 @@ mod tests {
         assert_eq!(leaders_data.leaders.len(), 1);
         assert_eq!(leaders_data.added_leaders_cache.len(), 1);
     }
+
+    #[tokio::test]
+    async fn enclave_replace_genesis_leader() {
+        let enclave = MemoryEnclave::new();
+        let mut rng = rand_core::OsRng;
+        let node_id = mk_pool_id(&mut rng);
+        let mut mk_leader = || Leader {
+            bft_leader: None,
+            genesis_leader: Some(GenesisLeader {
+                sig_key: SecretKey::generate(&mut rng),
+                vrf_key: SecretKey::generate(&mut rng),
+                node_id: node_id.clone(),
+            }),
+        };
+        let (leader1, leader2, leader3) = (mk_leader(), mk_leader(), mk_leader());
+
+        let fst_id = enclave.add_leader(leader1).await.unwrap();
+        assert_eq!(
+            enclave
+                .replace_leader(fst_id.next().next(), mk_leader(), None)
+                .await
+                .unwrap(),
+            None
+        );
+
+        // the new keys take over at the epoch 10
+        let snd_id = enclave
+            .replace_leader(fst_id, leader2, Some(10))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(snd_id, fst_id.next());
+        {
+            let leaders_data = enclave.leaders_data.read().await;
+            let (fst, snd) = (
+                &leaders_data.leaders[&fst_id],
+                &leaders_data.leaders[&snd_id],
+            );
+            assert!(fst.is_active_at(9) && !fst.is_active_at(10));
+            assert!(!snd.is_active_at(9) && snd.is_active_at(10));
+        }
+
+        // removing the old leader keeps the new one registered for the node id
+        assert!(enclave.remove_leader(fst_id).await.unwrap());
+        let trd_id = enclave
+            .replace_leader(snd_id, leader3, None)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(enclave.get_leader_ids().await, vec![trd_id]);
+
+        let leaders_data = enclave.leaders_data.read().await;
+        assert_eq!(leaders_data.added_leaders_cache.len(), 1);
+        assert!(leaders_data.leaders[&trd_id].is_active_at(0));
+    }
 }
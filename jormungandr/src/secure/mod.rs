@@ -1,14 +1,20 @@
 use chain_crypto::{Blake2b256, Curve25519_2HashDH, Ed25519, PublicKey, SumEd25519_12};
-use chain_impl_mockchain::leadership::{BftLeader, GenesisLeader};
-use jormungandr_lib::crypto::{
-    hash::Hash,
-    key::{Identifier, SigningKey},
+use chain_impl_mockchain::leadership::{BftLeader, GenesisLeader, Leader};
+use jormungandr_lib::{
+    crypto::{
+        hash::Hash,
+        key::{Identifier, SigningKey},
+        passphrase::{PassphraseError, PassphraseSource},
+    },
+    interfaces::{EncryptedSecretFile, SecretDecryptionError},
 };
 use serde::Deserialize;
-use std::path::Path;
 use thiserror::Error;
 
 pub mod enclave;
+pub mod reload;
+#[cfg(unix)]
+pub mod remote;
 
 /// hold the node's bft secret setting
 #[derive(Clone, Deserialize)]
@@ -48,6 +54,11 @@ pub struct StakePoolInfo {
 pub struct NodeSecret {
     pub bft: Option<Bft>,
     pub genesis: Option<GenesisPraos>,
+    /// when the secret file of a running node is updated, the new keys
+    /// replace the previous ones from the start of this epoch instead of
+    /// immediately
+    #[serde(default)]
+    pub activate_at_epoch: Option<u32>,
 }
 
 /// Node Secret's Public parts
@@ -62,12 +73,59 @@ pub enum NodeSecretFromFileError {
     Io(#[from] std::io::Error),
     #[error("Invalid Node secret file: {0}")]
     Format(#[from] serde_yaml::Error),
+    #[error("Cannot read the passphrase of the node's secrets: {0}")]
+    Passphrase(#[from] PassphraseError),
+    #[error("Cannot decrypt the node's secrets: {0}")]
+    Decryption(#[from] SecretDecryptionError),
+    #[error("Cannot register the node's secrets in the enclave: {0}")]
+    Enclave(#[from] enclave::Error),
+}
+
+/// The passphrase of the encrypted secret files, read once from its source
+/// when the first encrypted file is loaded.
+pub struct Passphrase {
+    source: PassphraseSource,
+    cached: Option<String>,
+}
+
+impl Passphrase {
+    pub fn new(source: PassphraseSource) -> Self {
+        Passphrase {
+            source,
+            cached: None,
+        }
+    }
+
+    fn get(&mut self) -> Result<&str, PassphraseError> {
+        if self.cached.is_none() {
+            self.cached = Some(self.source.read("Passphrase of the node secrets: ")?);
+        }
+        Ok(self.cached.as_deref().unwrap())
+    }
 }
 
 impl NodeSecret {
-    pub fn load_from_file<P: AsRef<Path>>(path: P) -> Result<NodeSecret, NodeSecretFromFileError> {
-        let file = std::fs::File::open(path)?;
-        Ok(serde_yaml::from_reader(file)?)
+    /// parse the content of a secret file, decrypting it with the passphrase
+    /// if it is encrypted
+    pub fn from_slice(
+        content: &[u8],
+        passphrase: &mut Passphrase,
+    ) -> Result<NodeSecret, NodeSecretFromFileError> {
+        let content: serde_yaml::Value = serde_yaml::from_slice(content)?;
+        if content.get(EncryptedSecretFile::KEY).is_none() {
+            return Ok(serde_yaml::from_value(content)?);
+        }
+
+        let file: EncryptedSecretFile = serde_yaml::from_value(content)?;
+        let plaintext = file.encrypted_secret.decrypt(passphrase.get()?)?;
+        Ok(serde_yaml::from_slice(&plaintext)?)
+    }
+
+    pub fn leader(&self) -> Leader {
+        Leader {
+            bft_leader: self.bft(),
+            genesis_leader: self.genesis(),
+        }
     }
 
     pub fn bft(&self) -> Option<BftLeader> {
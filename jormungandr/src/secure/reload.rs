@@ -0,0 +1,230 @@
+//! Reload of the secret files given to the node.
+//!
+//! The secret files are read again when their modification time changes or
+//! when the node receives SIGHUP. The leader of a file whose content changed
+//! is replaced in the enclave, at once or from the epoch set in its
+//! `activate_at_epoch`. In the latter case the previous leader is removed
+//! from the enclave once the tip reaches that epoch.
+
+use super::{enclave::Enclave, NodeSecret, NodeSecretFromFileError, Passphrase};
+use crate::blockchain::Tip;
+use futures::future::{self, Either};
+use jormungandr_lib::interfaces::EnclaveLeaderId as LeaderId;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+/// how often the modification time of the secret files is checked
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+struct SecretFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    content: Vec<u8>,
+    leader_id: LeaderId,
+    /// the previous leader of the file and the epoch it is replaced at
+    retiring: Option<(LeaderId, u32)>,
+}
+
+/// the secret files of the node and the leaders registered from them
+pub struct SecretFiles {
+    files: Vec<SecretFile>,
+    passphrase: Passphrase,
+}
+
+impl SecretFiles {
+    pub fn new(passphrase: Passphrase) -> Self {
+        SecretFiles {
+            files: Vec::new(),
+            passphrase,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// load the given secret file and register its leader in the enclave
+    pub async fn add(
+        &mut self,
+        path: PathBuf,
+        enclave: &Enclave,
+    ) -> Result<LeaderId, NodeSecretFromFileError> {
+        let modified = modified_time(&path);
+        let content = fs::read(&path)?;
+        let secret = NodeSecret::from_slice(&content, &mut self.passphrase)?;
+        let leader_id = enclave.add_leader(secret.leader()).await?;
+        self.files.push(SecretFile {
+            path,
+            modified,
+            content,
+            leader_id,
+            retiring: None,
+        });
+        Ok(leader_id)
+    }
+
+    /// retire the replaced leaders and read again the files which were
+    /// modified, or all of them if `force` is set
+    async fn reload(&mut self, enclave: &Enclave, current_epoch: u32, force: bool) {
+        let passphrase = &mut self.passphrase;
+        for file in self.files.iter_mut() {
+            file.retire(enclave, current_epoch).await;
+
+            let modified = modified_time(&file.path);
+            if !force && modified == file.modified {
+                continue;
+            }
+            file.modified = modified;
+
+            if let Err(error) = file.reload(enclave, passphrase, current_epoch).await {
+                tracing::error!(
+                    path = %file.path.display(),
+                    %error,
+                    "cannot reload the secret file, the previous keys are kept"
+                );
+            }
+        }
+    }
+}
+
+impl SecretFile {
+    async fn retire(&mut self, enclave: &Enclave, current_epoch: u32) {
+        match self.retiring {
+            Some((leader_id, epoch)) if epoch <= current_epoch => {
+                if let Err(error) = enclave.remove_leader(leader_id).await {
+                    tracing::error!(%leader_id, %error, "cannot remove the replaced leader");
+                    return;
+                }
+                tracing::info!(
+                    path = %self.path.display(),
+                    %leader_id,
+                    "the replaced leader was removed from the enclave"
+                );
+                self.retiring = None;
+                // the file may have been updated while the rotation was
+                // pending, read it again
+                self.modified = None;
+            }
+            _ => (),
+        }
+    }
+
+    async fn reload(
+        &mut self,
+        enclave: &Enclave,
+        passphrase: &mut Passphrase,
+        current_epoch: u32,
+    ) -> Result<(), NodeSecretFromFileError> {
+        let content = fs::read(&self.path)?;
+        if content == self.content {
+            return Ok(());
+        }
+        if let Some((_, epoch)) = self.retiring {
+            tracing::warn!(
+                path = %self.path.display(),
+                "the secret file was updated while its keys are already replaced at the epoch {}, it is reloaded from this epoch",
+                epoch
+            );
+            return Ok(());
+        }
+
+        let secret = NodeSecret::from_slice(&content, passphrase)?;
+        let at_epoch = secret
+            .activate_at_epoch
+            .filter(|epoch| *epoch > current_epoch);
+        let leader_id = match enclave
+            .replace_leader(self.leader_id, secret.leader(), at_epoch)
+            .await?
+        {
+            Some(leader_id) => leader_id,
+            // the leader was removed through the REST API in the meantime
+            None => enclave.add_leader(secret.leader()).await?,
+        };
+
+        match at_epoch {
+            Some(epoch) => tracing::info!(
+                path = %self.path.display(),
+                previous_leader_id = %self.leader_id,
+                %leader_id,
+                "the keys of the secret file are replaced at the epoch {}",
+                epoch
+            ),
+            None => tracing::info!(
+                path = %self.path.display(),
+                previous_leader_id = %self.leader_id,
+                %leader_id,
+                "the keys of the secret file were replaced"
+            ),
+        }
+        self.retiring = at_epoch.map(|epoch| (self.leader_id, epoch));
+        self.leader_id = leader_id;
+        self.content = content;
+        Ok(())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+/// reload the secret files when they are modified or when the node receives
+/// SIGHUP
+pub async fn watch(mut files: SecretFiles, enclave: Enclave, tip: Tip) {
+    let mut interval = tokio::time::interval(POLL_INTERVAL);
+    let mut hangup = Hangup::new();
+    loop {
+        let force = match future::select(Box::pin(interval.tick()), Box::pin(hangup.recv())).await {
+            Either::Left(_) => false,
+            Either::Right(_) => {
+                tracing::info!("received SIGHUP, reloading the secret files");
+                true
+            }
+        };
+        let current_epoch = tip.get_ref().await.block_date().epoch;
+        files.reload(&enclave, current_epoch, force).await;
+    }
+}
+
+#[cfg(unix)]
+struct Hangup(Option<tokio::signal::unix::Signal>);
+
+#[cfg(unix)]
+impl Hangup {
+    fn new() -> Self {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::hangup()) {
+            Ok(signal) => Hangup(Some(signal)),
+            Err(error) => {
+                tracing::warn!(reason = %error, "failed to install handler for SIGHUP");
+                Hangup(None)
+            }
+        }
+    }
+
+    async fn recv(&mut self) {
+        match &mut self.0 {
+            Some(signal) if signal.recv().await.is_some() => (),
+            _ => future::pending().await,
+        }
+    }
+}
+
+#[cfg(not(unix))]
+struct Hangup;
+
+#[cfg(not(unix))]
+impl Hangup {
+    fn new() -> Self {
+        Hangup
+    }
+
+    async fn recv(&mut self) {
+        future::pending().await
+    }
+}
@@ -0,0 +1,647 @@
+//! Enclave backend delegating the signatures to a signer process, reached
+//! through a Unix socket.
+//!
+//! The leaders are listed once when connecting to the signer, only their
+//! public keys and pool ids are known to the node. The BFT leader schedule is
+//! evaluated in the node from the public keys, the Genesis-Praos schedule of
+//! an epoch is requested from the signer with the public parameters of the
+//! epoch. The headers are sent to the signer to be signed. The connection is
+//! reopened on the next request after a failure, so the signer can be
+//! restarted without restarting the node.
+
+use super::enclave::{get_maximum_id, EnclaveBackend, Error};
+use crate::blockcfg::{BlockDate, Leadership};
+use async_trait::async_trait;
+use chain_crypto::{Blake2b256, Curve25519_2HashDH, Ed25519, PublicKey, Signature, SumEd25519_12};
+use chain_impl_mockchain::{
+    certificate::PoolId,
+    key::BftLeaderId,
+    leadership::{
+        genesis::{ActiveSlotsCoeff, LeadershipData, Nonce, PercentStake, VrfEvaluator, Witness},
+        Leader, LeaderOutput, LeadershipConsensus,
+    },
+    milli::Milli,
+    stake::Stake,
+};
+use jormungandr_lib::interfaces::{
+    ActiveSlotCoefficient, EnclaveLeaderId as LeaderId, SignerElection, SignerEpoch,
+    SignerPoolStake, SignerRequest, SignerResponse,
+};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::UnixStream,
+    sync::Mutex,
+};
+
+struct RemoteLeader {
+    signer_id: u32,
+    bft: Option<BftLeaderId>,
+    node_id: Option<PoolId>,
+}
+
+struct Connection {
+    path: PathBuf,
+    stream: Option<BufReader<UnixStream>>,
+}
+
+/// the Genesis-Praos elections of an epoch, by slot
+struct EpochSchedule {
+    epoch: SignerEpoch,
+    elections: HashMap<u32, Vec<SignerElection>>,
+}
+
+/// the public parameters of the Genesis-Praos elections of an epoch, read by
+/// the node from its ledger to verify the elections of the signer
+struct ElectionParameters {
+    nonce: Nonce,
+    active_slots_coeff: ActiveSlotsCoeff,
+    /// the VRF public key and the stake of the pools of the leaders
+    pools: HashMap<PoolId, (PublicKey<Curve25519_2HashDH>, Stake)>,
+    total: Stake,
+}
+
+pub struct RemoteEnclave {
+    connection: Mutex<Connection>,
+    leaders: BTreeMap<LeaderId, RemoteLeader>,
+    /// the schedule of the last epoch requested to the signer
+    schedule: Mutex<Option<EpochSchedule>>,
+}
+
+impl Connection {
+    async fn request(&mut self, request: &SignerRequest) -> Result<SignerResponse, Error> {
+        match self.exchange(request).await? {
+            SignerResponse::Error { reason } => Err(Error::SignerRefused(reason)),
+            response => Ok(response),
+        }
+    }
+
+    async fn exchange(&mut self, request: &SignerRequest) -> Result<SignerResponse, Error> {
+        // the stream is only kept once the exchange succeeded, it may be left
+        // in the middle of a message otherwise
+        let mut stream = match self.stream.take() {
+            Some(stream) => stream,
+            None => BufReader::new(UnixStream::connect(&self.path).await?),
+        };
+
+        let mut message = serde_json::to_vec(request)?;
+        message.push(b'\n');
+        stream.get_mut().write_all(&message).await?;
+
+        let mut line = String::new();
+        if stream.read_line(&mut line).await? == 0 {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        let response = serde_json::from_str(&line)?;
+        self.stream = Some(stream);
+        Ok(response)
+    }
+}
+
+impl RemoteEnclave {
+    /// connect to the signer listening on the given socket and retrieve the
+    /// leaders it holds
+    pub async fn connect<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let mut connection = Connection {
+            path: path.as_ref().to_path_buf(),
+            stream: None,
+        };
+        let signer_leaders = match connection.request(&SignerRequest::Leaders).await? {
+            SignerResponse::Leaders { leaders } => leaders,
+            _ => return Err(Error::SignerUnexpectedResponse),
+        };
+
+        let mut leaders = BTreeMap::new();
+        for leader in signer_leaders {
+            let id = get_maximum_id(&leaders).next();
+            let leader = RemoteLeader {
+                signer_id: leader.id,
+                bft: leader
+                    .bft
+                    .map(|key| BftLeaderId::from(key.into_public_key())),
+                node_id: leader
+                    .node_id
+                    .map(|node_id| Blake2b256::from(node_id).into()),
+            };
+            leaders.insert(id, leader);
+        }
+
+        Ok(RemoteEnclave {
+            connection: Mutex::new(connection),
+            leaders,
+            schedule: Mutex::new(None),
+        })
+    }
+
+    async fn sign(&self, request: SignerRequest) -> Result<Vec<u8>, Error> {
+        match self.connection.lock().await.request(&request).await? {
+            SignerResponse::Signature { signature } => {
+                hex::decode(signature).map_err(|_| Error::SignerSignatureInvalid)
+            }
+            _ => Err(Error::SignerUnexpectedResponse),
+        }
+    }
+
+    /// the public parameters of the epoch the signer needs to evaluate the
+    /// election of its leaders
+    fn signer_epoch(
+        &self,
+        genesis_praos: &LeadershipData,
+        epoch: u32,
+        slots_per_epoch: u32,
+    ) -> Option<SignerEpoch> {
+        let distribution = genesis_praos.distribution();
+        let total = distribution.total_stake();
+        let stakes = self
+            .leaders
+            .values()
+            .filter_map(|leader| leader.node_id.as_ref())
+            .filter_map(|node_id| {
+                distribution
+                    .to_pools
+                    .get(node_id)
+                    .map(|pool| SignerPoolStake {
+                        node_id: node_id.clone().into(),
+                        stake: pool.stake.total.into(),
+                        total: total.into(),
+                    })
+            })
+            .collect();
+        Some(SignerEpoch {
+            epoch,
+            slots_per_epoch,
+            nonce: hex::encode(genesis_praos.epoch_nonce().as_ref()),
+            active_slots_coeff: ActiveSlotCoefficient::new(Milli::from(
+                genesis_praos.active_slots_coeff(),
+            ))?,
+            stakes,
+        })
+    }
+
+    fn election_parameters(&self, genesis_praos: &LeadershipData) -> ElectionParameters {
+        let distribution = genesis_praos.distribution();
+        let pools = self
+            .leaders
+            .values()
+            .filter_map(|leader| leader.node_id.as_ref())
+            .filter_map(|node_id| {
+                let pool = distribution.to_pools.get(node_id)?;
+                let registration = pool.registration.as_ref()?;
+                Some((
+                    node_id.clone(),
+                    (registration.keys.vrf_public_key.clone(), pool.stake.total),
+                ))
+            })
+            .collect();
+        ElectionParameters {
+            nonce: genesis_praos.epoch_nonce().clone(),
+            active_slots_coeff: genesis_praos.active_slots_coeff(),
+            pools,
+            total: distribution.total_stake(),
+        }
+    }
+
+    async fn epoch_schedule(&self, epoch: SignerEpoch) -> Result<EpochSchedule, Error> {
+        let request = SignerRequest::Schedule(epoch.clone());
+        let elections = match self.connection.lock().await.request(&request).await? {
+            SignerResponse::Schedule { elections } => elections,
+            _ => return Err(Error::SignerUnexpectedResponse),
+        };
+        let mut schedule = EpochSchedule {
+            epoch,
+            elections: HashMap::new(),
+        };
+        for election in elections {
+            schedule
+                .elections
+                .entry(election.slot)
+                .or_insert_with(Vec::new)
+                .push(election);
+        }
+        Ok(schedule)
+    }
+
+    /// the leaders elected at the given slot of the schedule, with the
+    /// proof of their election given by the signer. The proofs are verified
+    /// with the parameters of the epoch known to the node, the elections of
+    /// the slot are rejected if one of them is invalid.
+    fn elected(
+        &self,
+        schedule: &EpochSchedule,
+        slot: u32,
+        parameters: &ElectionParameters,
+    ) -> Result<Vec<(LeaderId, LeaderOutput)>, Error> {
+        let elections = match schedule.elections.get(&slot) {
+            Some(elections) => elections,
+            None => return Ok(Vec::new()),
+        };
+        let mut elected = Vec::new();
+        for election in elections {
+            let leader = self
+                .leaders
+                .iter()
+                .find(|(_, leader)| leader.signer_id == election.leader);
+            let (id, node_id) = match leader {
+                Some((
+                    id,
+                    RemoteLeader {
+                        node_id: Some(node_id),
+                        ..
+                    },
+                )) => (id, node_id),
+                _ => {
+                    tracing::warn!(
+                        leader = election.leader,
+                        "the remote signer elected an unknown Genesis-Praos leader"
+                    );
+                    continue;
+                }
+            };
+            let proof = hex::decode(&election.proof)
+                .ok()
+                .and_then(|proof| Witness::from_bytes_unverified(&proof))
+                .ok_or(Error::SignerSignatureInvalid)?;
+            parameters.verify(node_id, slot, &proof)?;
+            elected.push((*id, LeaderOutput::GenesisPraos(node_id.clone(), proof)));
+        }
+        Ok(elected)
+    }
+}
+
+impl ElectionParameters {
+    /// check the proof of the election of the pool at the slot, with the
+    /// VRF public key and the stake of the pool in the ledger
+    fn verify(&self, node_id: &PoolId, slot: u32, proof: &Witness) -> Result<(), Error> {
+        let (vrf_public_key, stake) = self
+            .pools
+            .get(node_id)
+            .ok_or(Error::SignerSignatureInvalid)?;
+        let evaluator = VrfEvaluator {
+            stake: PercentStake {
+                stake: *stake,
+                total: self.total,
+            },
+            nonce: &self.nonce,
+            slot_id: slot,
+            active_slots_coeff: self.active_slots_coeff,
+        };
+        evaluator
+            .verify(vrf_public_key, proof)
+            .map(|_| ())
+            .map_err(|_| Error::SignerSignatureInvalid)
+    }
+}
+
+#[async_trait]
+impl EnclaveBackend for RemoteEnclave {
+    async fn get_leader_ids(&self) -> Vec<LeaderId> {
+        self.leaders.keys().copied().collect()
+    }
+
+    async fn add_leader(&self, _leader: Leader) -> Result<LeaderId, Error> {
+        Err(Error::LeadersManagedBySigner)
+    }
+
+    async fn remove_leader(&self, _leader_id: LeaderId) -> Result<bool, Error> {
+        Err(Error::LeadersManagedBySigner)
+    }
+
+    async fn replace_leader(
+        &self,
+        _leader_id: LeaderId,
+        _leader: Leader,
+        _at_epoch: Option<u32>,
+    ) -> Result<Option<LeaderId>, Error> {
+        Err(Error::LeadersManagedBySigner)
+    }
+
+    async fn leaders_at(
+        &self,
+        leadership: &Leadership,
+        date: BlockDate,
+    ) -> Vec<(LeaderId, LeaderOutput)> {
+        let mut elected = Vec::new();
+        match leadership.consensus() {
+            LeadershipConsensus::Bft(bft) => {
+                let bft_leader = match bft.get_leader_at(date) {
+                    Ok(bft_leader) => bft_leader,
+                    Err(error) => {
+                        tracing::warn!(%error, "cannot evaluate the BFT leader at {}", date);
+                        return elected;
+                    }
+                };
+                for (id, leader) in &self.leaders {
+                    if leader.bft.as_ref() == Some(&bft_leader) {
+                        elected.push((*id, LeaderOutput::Bft(bft_leader.clone())));
+                    }
+                }
+            }
+            LeadershipConsensus::GenesisPraos(genesis_praos) => {
+                let slots_per_epoch = leadership.era().slots_per_epoch();
+                let epoch = match self.signer_epoch(genesis_praos, date.epoch, slots_per_epoch) {
+                    Some(epoch) => epoch,
+                    None => {
+                        tracing::warn!(
+                            "invalid active slot coefficient in the epoch {}",
+                            date.epoch
+                        );
+                        return elected;
+                    }
+                };
+
+                // the schedule of an epoch is requested once, the leadership
+                // is evaluated slot by slot
+                let mut schedule = self.schedule.lock().await;
+                if schedule
+                    .as_ref()
+                    .map_or(true, |schedule| schedule.epoch != epoch)
+                {
+                    match self.epoch_schedule(epoch).await {
+                        Ok(epoch_schedule) => *schedule = Some(epoch_schedule),
+                        Err(error) => {
+                            tracing::warn!(%error, "cannot get the leader schedule of the epoch {} from the remote signer", date.epoch);
+                            return elected;
+                        }
+                    }
+                }
+                if let Some(schedule) = schedule.as_ref() {
+                    let parameters = self.election_parameters(genesis_praos);
+                    match self.elected(schedule, date.slot_id, &parameters) {
+                        Ok(leaders) => elected = leaders,
+                        Err(error) => {
+                            tracing::warn!(%error, "rejecting the elections of the remote signer at {}", date)
+                        }
+                    }
+                }
+            }
+        }
+        elected
+    }
+
+    async fn sign_bft(
+        &self,
+        leader_id: LeaderId,
+        data: &[u8],
+    ) -> Result<Option<Signature<[u8], Ed25519>>, Error> {
+        let leader = match self.leaders.get(&leader_id) {
+            Some(leader) if leader.bft.is_some() => leader,
+            _ => return Ok(None),
+        };
+        let signature = self
+            .sign(SignerRequest::SignBft {
+                leader: leader.signer_id,
+                data: hex::encode(data),
+            })
+            .await?;
+        Signature::from_binary(&signature)
+            .map(Some)
+            .map_err(|_| Error::SignerSignatureInvalid)
+    }
+
+    async fn sign_genesis_praos(
+        &self,
+        leader_id: LeaderId,
+        data: &[u8],
+    ) -> Result<Option<Signature<[u8], SumEd25519_12>>, Error> {
+        let leader = match self.leaders.get(&leader_id) {
+            Some(leader) if leader.node_id.is_some() => leader,
+            _ => return Ok(None),
+        };
+        let signature = self
+            .sign(SignerRequest::SignGenesisPraos {
+                leader: leader.signer_id,
+                data: hex::encode(data),
+            })
+            .await?;
+        Signature::from_binary(&signature)
+            .map(Some)
+            .map_err(|_| Error::SignerSignatureInvalid)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chain_crypto::Verification;
+    use jormungandr_lib::{
+        crypto::{hash::Hash, key::SigningKey},
+        interfaces::{serve_signer_request, Bft, GenesisPraos, NodeSecret, Stake},
+    };
+    use std::convert::TryFrom;
+    use std::{
+        io::{BufRead, BufReader, Write},
+        os::unix::net::UnixListener,
+        thread,
+    };
+
+    const NODE_ID: [u8; 32] = [1; 32];
+
+    fn socket_path(test: &str) -> PathBuf {
+        std::env::temp_dir().join(format!(
+            "jormungandr-signer-{}-{}.sock",
+            test,
+            std::process::id()
+        ))
+    }
+
+    fn secrets() -> Vec<NodeSecret> {
+        let mut rng = rand_core::OsRng;
+        vec![
+            NodeSecret {
+                bft: Some(Bft {
+                    signing_key: SigningKey::generate(&mut rng),
+                }),
+                genesis: None,
+            },
+            NodeSecret {
+                bft: None,
+                genesis: Some(GenesisPraos {
+                    node_id: Hash::from(NODE_ID),
+                    sig_key: SigningKey::<SumEd25519_12>::generate(&mut rng),
+                    vrf_key: SigningKey::<Curve25519_2HashDH>::generate(&mut rng),
+                }),
+            },
+        ]
+    }
+
+    /// serve the given number of requests of a single connection, as the
+    /// reference signer does, then close the connection
+    fn spawn_signer(path: &Path, secrets: Vec<NodeSecret>, requests: usize) {
+        let _ = std::fs::remove_file(path);
+        let listener = UnixListener::bind(path).unwrap();
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut writer = stream.try_clone().unwrap();
+            for line in BufReader::new(stream).lines().take(requests) {
+                let request = serde_json::from_str(&line.unwrap()).unwrap();
+                let mut message =
+                    serde_json::to_vec(&serve_signer_request(&secrets, request)).unwrap();
+                message.push(b'\n');
+                writer.write_all(&message).unwrap();
+            }
+        });
+    }
+
+    #[tokio::test]
+    async fn headers_are_signed_by_the_signer() {
+        let path = socket_path("sign");
+        let secrets = secrets();
+        spawn_signer(&path, secrets.clone(), usize::MAX);
+        let enclave = RemoteEnclave::connect(&path).await.unwrap();
+
+        let ids = enclave.get_leader_ids().await;
+        assert_eq!(ids.len(), 2);
+        let (bft_id, genesis_id) = (ids[0], ids[1]);
+        let data = b"authenticated data of a header";
+
+        let signature = enclave.sign_bft(bft_id, data).await.unwrap().unwrap();
+        let public_key = secrets[0]
+            .bft
+            .as_ref()
+            .unwrap()
+            .signing_key
+            .identifier()
+            .into_public_key();
+        assert_eq!(
+            signature.verify_slice(&public_key, data),
+            Verification::Success
+        );
+
+        let signature = enclave
+            .sign_genesis_praos(genesis_id, data)
+            .await
+            .unwrap()
+            .unwrap();
+        let public_key = secrets[1]
+            .genesis
+            .as_ref()
+            .unwrap()
+            .sig_key
+            .identifier()
+            .into_public_key();
+        assert_eq!(
+            signature.verify_slice(&public_key, data),
+            Verification::Success
+        );
+
+        // the leaders only have the kind of key they are listed with
+        assert!(enclave.sign_bft(genesis_id, data).await.unwrap().is_none());
+        assert!(enclave
+            .sign_genesis_praos(bft_id, data)
+            .await
+            .unwrap()
+            .is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    /// the parameters of the epoch seen by the node, with the given VRF
+    /// public key and stake of the pool
+    fn parameters(
+        vrf_public_key: PublicKey<Curve25519_2HashDH>,
+        stake: u64,
+        total: u64,
+        active_slots_coeff: Milli,
+    ) -> ElectionParameters {
+        let mut pools = HashMap::new();
+        pools.insert(
+            PoolId::from(NODE_ID),
+            (vrf_public_key, Stake::from(stake).into()),
+        );
+        ElectionParameters {
+            nonce: Nonce::from([3; 32]),
+            active_slots_coeff: ActiveSlotsCoeff::try_from(active_slots_coeff).unwrap(),
+            pools,
+            total: Stake::from(total).into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn schedule_is_evaluated_by_the_signer() {
+        let path = socket_path("schedule");
+        let secrets = secrets();
+        spawn_signer(&path, secrets.clone(), usize::MAX);
+        let enclave = RemoteEnclave::connect(&path).await.unwrap();
+        let genesis_id = enclave.get_leader_ids().await[1];
+        let vrf_public_key = secrets[1]
+            .genesis
+            .as_ref()
+            .unwrap()
+            .vrf_key
+            .identifier()
+            .into_public_key();
+        let maximum = Milli::from(ActiveSlotCoefficient::MAXIMUM);
+
+        // with the whole stake and an active slot coefficient of 1 the
+        // Genesis-Praos leader is elected at every slot
+        let epoch = SignerEpoch {
+            epoch: 1,
+            slots_per_epoch: 10,
+            nonce: hex::encode([3; 32]),
+            active_slots_coeff: ActiveSlotCoefficient::MAXIMUM,
+            stakes: vec![SignerPoolStake {
+                node_id: Hash::from(NODE_ID),
+                stake: Stake::from(100),
+                total: Stake::from(100),
+            }],
+        };
+        let schedule = enclave.epoch_schedule(epoch).await.unwrap();
+        let honest = parameters(vrf_public_key.clone(), 100, 100, maximum);
+        for slot in 0..10 {
+            let elected = enclave.elected(&schedule, slot, &honest).unwrap();
+            assert_eq!(elected.len(), 1);
+            match &elected[0] {
+                (id, LeaderOutput::GenesisPraos(node_id, _)) => {
+                    assert_eq!(*id, genesis_id);
+                    assert_eq!(*node_id, PoolId::from(NODE_ID));
+                }
+                _ => panic!("the Genesis-Praos leader should be elected at {}", slot),
+            }
+        }
+        assert!(enclave.elected(&schedule, 10, &honest).unwrap().is_empty());
+
+        // the proofs of another VRF key, or for a stake the pool does not
+        // have in the ledger, are rejected
+        let mut rng = rand_core::OsRng;
+        let other_key = SigningKey::<Curve25519_2HashDH>::generate(&mut rng)
+            .identifier()
+            .into_public_key();
+        let other_key = parameters(other_key, 100, 100, maximum);
+        let low_stake = parameters(vrf_public_key, 1, 1_000_000, Milli::from_millis(100));
+        for slot in 0..10 {
+            assert!(matches!(
+                enclave.elected(&schedule, slot, &other_key),
+                Err(Error::SignerSignatureInvalid)
+            ));
+            assert!(matches!(
+                enclave.elected(&schedule, slot, &low_stake),
+                Err(Error::SignerSignatureInvalid)
+            ));
+        }
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_the_signer_restarts() {
+        let path = socket_path("restart");
+        let secrets = secrets();
+        // the first signer stops after listing the leaders and one signature
+        spawn_signer(&path, secrets.clone(), 2);
+        let enclave = RemoteEnclave::connect(&path).await.unwrap();
+        let bft_id = enclave.get_leader_ids().await[0];
+        let data = b"header";
+        assert!(enclave.sign_bft(bft_id, data).await.unwrap().is_some());
+
+        spawn_signer(&path, secrets, usize::MAX);
+        // the connection to the stopped signer fails once, the next request
+        // reaches the new signer
+        assert!(enclave.sign_bft(bft_id, data).await.is_err());
+        assert!(enclave.sign_bft(bft_id, data).await.unwrap().is_some());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
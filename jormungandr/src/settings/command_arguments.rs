@@ -25,6 +25,20 @@ pub struct StartArguments {
     #[structopt(long = "secret", parse(from_os_str))]
     pub secret: Vec<PathBuf>,
 
+    /// Read the passphrase of the encrypted secret files from the given
+    /// environment variable. Without this option or `--secret-passphrase-fd`
+    /// the passphrase is asked on the terminal.
+    #[structopt(
+        long = "secret-passphrase-env",
+        conflicts_with = "secret-passphrase-fd"
+    )]
+    pub secret_passphrase_env: Option<String>,
+
+    /// Read the passphrase of the encrypted secret files from the first line
+    /// of the given file descriptor.
+    #[structopt(long = "secret-passphrase-fd")]
+    pub secret_passphrase_fd: Option<i32>,
+
     /// Path to the genesis block (the block0) of the blockchain
     #[structopt(long = "genesis-block", parse(try_from_str))]
     pub block_0_path: Option<PathBuf>,
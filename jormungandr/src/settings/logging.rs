@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::fmt::{self, Display};
 use std::fs;
 use std::io::{self, Write};
@@ -5,16 +6,15 @@ use std::io::{self, Write};
 use std::net::SocketAddr;
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
 
-use tracing::{level_filters::LevelFilter, Event, Id, Metadata, Subscriber};
+use tracing::level_filters::LevelFilter;
 use tracing_appender::non_blocking::WorkerGuard;
 
-use tracing::span::{Attributes, Record};
 use tracing::subscriber::SetGlobalDefaultError;
-use tracing_subscriber::fmt::SubscriberBuilder;
-#[allow(unused_imports)]
-use tracing_subscriber::layer::SubscriberExt;
-use tracing_subscriber::layer::{Layer, Layered};
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::layer::{Layer, Layered, SubscriberExt};
+use tracing_subscriber::{reload, Registry};
 
 pub struct LogSettings(pub Vec<LogSettingsEntry>, pub LogInfoMsg);
 
@@ -70,6 +70,39 @@ pub enum LogOutput {
         backend: SocketAddr,
         log_id: String,
     },
+    #[cfg(feature = "otlp")]
+    Otlp {
+        /// gRPC endpoint of the OpenTelemetry collector,
+        /// e.g. `http://localhost:4317`
+        endpoint: String,
+        /// name of the service in the exported spans, defaults to
+        /// `jormungandr`
+        #[serde(default)]
+        service_name: Option<String>,
+    },
+}
+
+#[cfg(feature = "otlp")]
+const DEFAULT_OTLP_SERVICE_NAME: &str = "jormungandr";
+
+/// Keeps a log output running until dropped.
+pub enum LogGuard {
+    /// flushes the messages of an output written in the background
+    Writer(WorkerGuard),
+    /// exports the remaining spans and stops the OTLP exporter
+    #[cfg(feature = "otlp")]
+    Otlp(OtlpGuard),
+}
+
+#[cfg(feature = "otlp")]
+pub struct OtlpGuard(tokio::runtime::Runtime);
+
+#[cfg(feature = "otlp")]
+impl Drop for OtlpGuard {
+    fn drop(&mut self) {
+        let _enter = self.0.enter();
+        opentelemetry::global::shutdown_tracer_provider();
+    }
 }
 
 impl FromStr for LogFormat {
@@ -99,112 +132,137 @@ impl FromStr for LogOutput {
     }
 }
 
-struct BoxedSubscriber(Box<dyn Subscriber + Send + Sync>);
+type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
 
-impl Subscriber for BoxedSubscriber {
-    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
-        self.0.enabled(metadata)
-    }
+type FilterReloadHandle = reload::Handle<Targets, Registry>;
 
-    fn new_span(&self, span: &Attributes<'_>) -> Id {
-        self.0.new_span(span)
-    }
+/// Handle to change the log level of the targets at runtime, for all the
+/// configured outputs.
+#[derive(Clone)]
+pub struct LogFilterHandle(Arc<Mutex<LogFilterState>>);
 
-    fn record(&self, span: &Id, values: &Record<'_>) {
-        self.0.record(span, values)
-    }
+struct LogFilterState {
+    /// the level of each output and the handle to reload its filter
+    outputs: Vec<(LevelFilter, FilterReloadHandle)>,
+    targets: BTreeMap<String, LevelFilter>,
+}
 
-    fn record_follows_from(&self, span: &Id, follows: &Id) {
-        self.0.record_follows_from(span, follows)
+impl LogFilterHandle {
+    /// the level of each output, for the targets without a level of their own
+    pub fn default_levels(&self) -> Vec<LevelFilter> {
+        let state = self.0.lock().unwrap();
+        state.outputs.iter().map(|(level, _)| *level).collect()
     }
 
-    fn event(&self, event: &Event<'_>) {
-        self.0.event(event)
+    /// the level set at runtime for each target
+    pub fn target_levels(&self) -> BTreeMap<String, LevelFilter> {
+        self.0.lock().unwrap().targets.clone()
     }
 
-    fn enter(&self, span: &Id) {
-        self.0.enter(span)
-    }
+    /// set the level of the given target in all the outputs, or go back to
+    /// the level of each output if `level` is `None`
+    pub fn set_target_level(&self, target: String, level: Option<&str>) -> Result<(), FilterError> {
+        if target.is_empty() || target.contains(|c: char| c.is_whitespace() || ",=[]{}".contains(c))
+        {
+            return Err(FilterError::Target(target));
+        }
 
-    fn exit(&self, span: &Id) {
-        self.0.exit(span)
+        let mut state = self.0.lock().unwrap();
+        let mut targets = state.targets.clone();
+        match level {
+            Some(level) => {
+                let level = level
+                    .parse()
+                    .map_err(|_| FilterError::Level(level.to_owned()))?;
+                targets.insert(target, level);
+            }
+            None => {
+                targets.remove(&target);
+            }
+        }
+
+        for (level, handle) in state.outputs.iter() {
+            handle
+                .reload(targets_filter(*level, &targets))
+                .map_err(FilterError::Reload)?;
+        }
+        state.targets = targets;
+        Ok(())
     }
 }
 
-impl Layer<BoxedSubscriber> for BoxedSubscriber {}
+fn targets_filter(level: LevelFilter, targets: &BTreeMap<String, LevelFilter>) -> Targets {
+    Targets::new().with_default(level).with_targets(
+        targets
+            .iter()
+            .map(|(target, level)| (target.clone(), *level)),
+    )
+}
+
+/// Put all the outputs on a single registry, each of them behind a filter
+/// of its own so an output only sees the spans and events of its level.
+fn compose(
+    outputs: Vec<(LevelFilter, BoxedLayer)>,
+) -> (Layered<Vec<BoxedLayer>, Registry>, LogFilterHandle) {
+    let mut layers = Vec::with_capacity(outputs.len());
+    let mut handles = Vec::with_capacity(outputs.len());
+    for (level, layer) in outputs {
+        let (filter, handle) = reload::Layer::new(targets_filter(level, &BTreeMap::new()));
+        let layer: BoxedLayer = Box::new(layer.with_filter(filter));
+        layers.push(layer);
+        handles.push((level, handle));
+    }
+
+    let log_filter = LogFilterHandle(Arc::new(Mutex::new(LogFilterState {
+        outputs: handles,
+        targets: BTreeMap::new(),
+    })));
+    (tracing_subscriber::registry().with(layers), log_filter)
+}
 
 impl LogSettings {
-    pub fn init_log(self) -> Result<(Vec<WorkerGuard>, LogInfoMsg), Error> {
-        use tracing_subscriber::prelude::*;
+    pub fn init_log(self) -> Result<(Vec<LogGuard>, LogFilterHandle, LogInfoMsg), Error> {
         let mut guards = Vec::new();
-        let mut layers: Vec<Layered<_, BoxedSubscriber>> = Vec::new();
+        let mut outputs = Vec::new();
         for config in self.0.into_iter() {
-            let (subscriber, guard) = config.to_subscriber()?;
-            let subscriber = BoxedSubscriber(subscriber);
-
-            let layer: Layered<_, _, BoxedSubscriber> =
-                tracing_subscriber::layer::Identity::new().with_subscriber(subscriber);
-
-            layers.push(layer);
+            let (layer, guard) = config.to_layer()?;
+            outputs.push((config.level, layer));
             if let Some(guard) = guard {
                 guards.push(guard);
             }
         }
 
-        let mut layer_iter = layers.into_iter();
-        if let Some(layer) = layer_iter.next() {
-            let mut init_layer: BoxedSubscriber = BoxedSubscriber(Box::new(layer));
-            for layer in layer_iter {
-                init_layer = BoxedSubscriber(Box::new(init_layer.with(layer)));
-            }
-            tracing::subscriber::set_global_default(init_layer)
+        let is_empty = outputs.is_empty();
+        let (subscriber, log_filter) = compose(outputs);
+        if !is_empty {
+            tracing::subscriber::set_global_default(subscriber)
                 .map_err(Error::SetGlobalSubscriberError)?;
         }
 
-        Ok((guards, self.1))
+        Ok((guards, log_filter, self.1))
     }
 }
 
 impl LogSettingsEntry {
-    fn to_subscriber(
-        &self,
-    ) -> Result<(Box<dyn Subscriber + Send + Sync>, Option<WorkerGuard>), Error> {
-        let Self {
-            output,
-            level,
-            format,
-        } = self;
-
-        let builder = SubscriberBuilder::default();
-
-        fn build_writer_subscriber(
-            builder: SubscriberBuilder,
+    fn to_layer(&self) -> Result<(BoxedLayer, Option<LogGuard>), Error> {
+        let Self { output, format, .. } = self;
+
+        fn build_writer_layer(
             writer: impl Write + Send + Sync + 'static,
-            level: LevelFilter,
             format: LogFormat,
-        ) -> (Box<dyn Subscriber + Send + Sync>, Option<WorkerGuard>) {
-            let (subscriber, guard) = tracing_appender::non_blocking(writer);
-            let builder = builder.with_writer(subscriber).with_max_level(level);
-            let subscriber: Box<dyn Subscriber + Send + Sync> = match format {
-                LogFormat::Default | LogFormat::Plain => Box::new(builder.finish()),
-                LogFormat::Json => Box::new(builder.json().finish()),
+        ) -> (BoxedLayer, Option<LogGuard>) {
+            let (writer, guard) = tracing_appender::non_blocking(writer);
+            let layer = tracing_subscriber::fmt::layer().with_writer(writer);
+            let layer: BoxedLayer = match format {
+                LogFormat::Default | LogFormat::Plain => Box::new(layer),
+                LogFormat::Json => Box::new(layer.json()),
             };
-            (subscriber, Some(guard))
+            (layer, Some(LogGuard::Writer(guard)))
         }
 
         match output {
-            LogOutput::Stdout => Ok(build_writer_subscriber(
-                builder,
-                std::io::stdout(),
-                *level,
-                *format,
-            )),
-            LogOutput::Stderr => Ok(build_writer_subscriber(
-                builder,
-                std::io::stderr(),
-                *level,
-                *format,
-            )),
+            LogOutput::Stdout => Ok(build_writer_layer(std::io::stdout(), *format)),
+            LogOutput::Stderr => Ok(build_writer_layer(std::io::stderr(), *format)),
             LogOutput::File(path) => {
                 let file = fs::OpenOptions::new()
                     .create(true)
@@ -215,14 +273,13 @@ impl LogSettingsEntry {
                         path: path.clone(),
                         cause,
                     })?;
-                Ok(build_writer_subscriber(builder, file, *level, *format))
+                Ok(build_writer_layer(file, *format))
             }
             #[cfg(feature = "systemd")]
             LogOutput::Journald => {
                 format.require_default()?;
                 let layer = tracing_journald::layer().map_err(Error::Journald)?;
-                let subscriber = builder.with_max_level(*level).finish().with(layer);
-                Ok((Box::new(subscriber), None))
+                Ok((Box::new(layer), None))
             }
             #[cfg(feature = "gelf")]
             LogOutput::Gelf {
@@ -235,13 +292,62 @@ impl LogSettingsEntry {
                     .connect_tcp(address.clone())
                     .map_err(Error::Gelf)?;
                 tokio::spawn(task);
-                let subscriber = builder.with_max_level(*level).finish().with(layer);
-                Ok((Box::new(subscriber), None))
+                Ok((Box::new(layer), None))
+            }
+            #[cfg(feature = "otlp")]
+            LogOutput::Otlp {
+                endpoint,
+                service_name,
+            } => {
+                format.require_default()?;
+                let (tracer, guard) = otlp_tracer(endpoint, service_name.as_deref())?;
+                let layer = tracing_opentelemetry::layer().with_tracer(tracer);
+                Ok((Box::new(layer), Some(LogGuard::Otlp(guard))))
             }
         }
     }
 }
 
+/// set up the batch exporter of the spans to the OTLP collector
+#[cfg(feature = "otlp")]
+fn otlp_tracer(
+    endpoint: &str,
+    service_name: Option<&str>,
+) -> Result<(opentelemetry::sdk::trace::Tracer, OtlpGuard), Error> {
+    use opentelemetry::{
+        sdk::{trace, Resource},
+        KeyValue,
+    };
+    use opentelemetry_otlp::WithExportConfig;
+
+    // the logs are set up before the runtime of the node is started, the
+    // exporter gets a runtime of its own
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("otlp-exporter")
+        .enable_all()
+        .build()
+        .map_err(Error::OtlpRuntime)?;
+    let service_name = service_name.unwrap_or(DEFAULT_OTLP_SERVICE_NAME).to_owned();
+    let exporter = opentelemetry_otlp::new_exporter()
+        .tonic()
+        .with_endpoint(endpoint);
+    let trace_config = trace::config().with_resource(Resource::new(vec![KeyValue::new(
+        "service.name",
+        service_name,
+    )]));
+    let tracer = {
+        let _enter = runtime.enter();
+        opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(exporter)
+            .with_trace_config(trace_config)
+            .install_batch(opentelemetry::runtime::Tokio)
+            .map_err(Error::Otlp)?
+    };
+    Ok((tracer, OtlpGuard(runtime)))
+}
+
 impl LogFormat {
     #[allow(dead_code)]
     fn require_default(&self) -> Result<(), Error> {
@@ -252,6 +358,16 @@ impl LogFormat {
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum FilterError {
+    #[error("invalid log level `{0}`")]
+    Level(String),
+    #[error("invalid log target `{0}`")]
+    Target(String),
+    #[error("failed to reload the log filter")]
+    Reload(#[source] reload::Error),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error("log format `{specified}` is not supported for this output")]
@@ -268,6 +384,82 @@ pub enum Error {
     #[cfg(feature = "gelf")]
     #[error("GELF connection failed")]
     Gelf(tracing_gelf::BuilderError),
+    #[cfg(feature = "otlp")]
+    #[error("cannot start the runtime of the OTLP exporter")]
+    OtlpRuntime(#[source] io::Error),
+    #[cfg(feature = "otlp")]
+    #[error("cannot set up the OTLP exporter")]
+    Otlp(#[source] opentelemetry::trace::TraceError),
     #[error("failed to set global subscriber")]
     SetGlobalSubscriberError(#[source] SetGlobalDefaultError),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing::span::{Attributes, Id};
+    use tracing::Subscriber;
+    use tracing_subscriber::layer::Context;
+
+    /// an output keeping the names of the spans it receives
+    #[derive(Clone, Default)]
+    struct SpanNames(Arc<Mutex<Vec<&'static str>>>);
+
+    impl SpanNames {
+        fn get(&self) -> Vec<&'static str> {
+            self.0.lock().unwrap().clone()
+        }
+    }
+
+    impl<S: Subscriber> Layer<S> for SpanNames {
+        fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+            self.0.lock().unwrap().push(attrs.metadata().name());
+        }
+    }
+
+    fn emit_spans() {
+        tracing::info_span!("info").in_scope(|| {
+            tracing::debug_span!("debug").in_scope(|| {
+                tracing::debug_span!(target: "jormungandr::logging_test", "targeted")
+                    .in_scope(|| {})
+            })
+        });
+    }
+
+    #[test]
+    fn every_output_receives_the_spans_of_its_level() {
+        let first = SpanNames::default();
+        let second = SpanNames::default();
+        let (subscriber, _log_filter) = compose(vec![
+            (LevelFilter::INFO, Box::new(first.clone())),
+            (LevelFilter::DEBUG, Box::new(second.clone())),
+        ]);
+
+        tracing::subscriber::with_default(subscriber, emit_spans);
+
+        assert_eq!(first.get(), vec!["info"]);
+        assert_eq!(second.get(), vec!["info", "debug", "targeted"]);
+    }
+
+    #[test]
+    fn target_level_applies_to_every_output() {
+        let first = SpanNames::default();
+        let second = SpanNames::default();
+        let (subscriber, log_filter) = compose(vec![
+            (LevelFilter::INFO, Box::new(first.clone())),
+            (LevelFilter::WARN, Box::new(second.clone())),
+        ]);
+        log_filter
+            .set_target_level("jormungandr::logging_test".to_owned(), Some("debug"))
+            .unwrap();
+
+        tracing::subscriber::with_default(subscriber, emit_spans);
+
+        assert_eq!(first.get(), vec!["info", "targeted"]);
+        assert_eq!(second.get(), vec!["targeted"]);
+        assert_eq!(
+            log_filter.default_levels(),
+            vec![LevelFilter::INFO, LevelFilter::WARN]
+        );
+    }
+}
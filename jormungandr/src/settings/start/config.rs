@@ -28,6 +28,9 @@ pub struct Config {
     #[serde(default)]
     pub leadership: Leadership,
 
+    #[serde(default)]
+    pub ledger_snapshot: LedgerSnapshot,
+
     pub rest: Option<Rest>,
 
     #[serde(default)]
@@ -38,6 +41,10 @@ pub struct Config {
 
     pub explorer: Option<Explorer>,
 
+    /// serve the node metrics for Prometheus on the REST server, requires
+    /// the node to be built with the `prometheus` feature
+    pub prometheus: Option<Prometheus>,
+
     /// the time interval with no blockchain updates after which alerts are thrown
     #[serde(default)]
     pub no_blockchain_updates_warning_interval: Option<Duration>,
@@ -174,6 +181,23 @@ pub struct Leadership {
     /// the least recently used log will be erased from the logs for a new one
     /// to be inserted.
     pub logs_capacity: usize,
+
+    /// the Unix socket of a remote signer holding the leader keys, in place
+    /// of the secret files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remote_signer: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct LedgerSnapshot {
+    /// the number of blocks between two snapshots of the ledger state. The
+    /// snapshots are written in the storage directory once they are deeper
+    /// than the epoch stability depth, so the node only has to replay the
+    /// blocks applied since the last snapshot when restarting.
+    ///
+    /// Setting this to `0` disables the ledger snapshots.
+    pub interval: u32,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize)]
@@ -186,6 +210,16 @@ pub struct InterestLevel(pub poldercast::InterestLevel);
 #[serde(deny_unknown_fields)]
 pub struct Explorer {
     pub enabled: bool,
+    /// prefix of the bech32 representation of the addresses, defaults to
+    /// `addr` for the production discrimination and to `ta` for the test one
+    #[serde(default)]
+    pub address_bech32_prefix: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct Prometheus {
+    pub enabled: bool,
 }
 
 pub fn default_interests() -> BTreeMap<Topic, InterestLevel> {
@@ -229,10 +263,17 @@ impl Default for Leadership {
     fn default() -> Self {
         Leadership {
             logs_capacity: 1_024,
+            remote_signer: None,
         }
     }
 }
 
+impl Default for LedgerSnapshot {
+    fn default() -> Self {
+        LedgerSnapshot { interval: 10_000 }
+    }
+}
+
 impl std::str::FromStr for TrustedPeer {
     type Err = String;
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -1,10 +1,11 @@
 pub mod config;
 pub mod network;
 
-use self::config::{Config, Leadership};
+use self::config::{Config, Leadership, LedgerSnapshot};
 use self::network::{Protocol, TrustedPeer};
 use crate::settings::logging::{LogFormat, LogInfoMsg, LogOutput, LogSettings, LogSettingsEntry};
 use crate::settings::{command_arguments::*, Block0Info};
+use jormungandr_lib::crypto::passphrase::PassphraseSource;
 pub use jormungandr_lib::interfaces::{Cors, Mempool, Rest, Tls};
 use std::{fs::File, path::PathBuf};
 use thiserror::Error;
@@ -25,6 +26,8 @@ pub enum Error {
     ExpectedBlock0Info,
     #[error("In the node configuration file, the `p2p.listen_address` value is not a valid address. Use format `/ip4/x.x.x.x/tcp/4920")]
     ListenAddressNotValid,
+    #[error("The secret files cannot be used along with the remote signer set in `leadership.remote_signer`")]
+    SecretsWithRemoteSigner,
 }
 
 /// Overall Settings for node
@@ -33,11 +36,15 @@ pub struct Settings {
     pub storage: Option<PathBuf>,
     pub block_0: Block0Info,
     pub secrets: Vec<PathBuf>,
+    pub secret_passphrase: PassphraseSource,
     pub rest: Option<Rest>,
     pub mempool: Mempool,
     pub rewards_report_all: bool,
     pub leadership: Leadership,
+    pub ledger_snapshot: LedgerSnapshot,
     pub explorer: bool,
+    pub explorer_address_bech32_prefix: Option<String>,
+    pub prometheus: bool,
     pub no_blockchain_updates_warning_interval: std::time::Duration,
 }
 
@@ -128,6 +135,8 @@ impl RawSettings {
                 listen: cmd_listen,
                 tls: None,
                 cors: None,
+                admin: None,
+                limits: None,
             }),
             (None, None) => None,
         }
@@ -161,7 +170,14 @@ impl RawSettings {
             secrets.extend(secret_files);
         }
 
-        if secrets.is_empty() {
+        let leadership = config
+            .as_ref()
+            .map_or(Leadership::default(), |cfg| cfg.leadership.clone());
+        if leadership.remote_signer.is_some() {
+            if !secrets.is_empty() {
+                return Err(Error::SecretsWithRemoteSigner);
+            }
+        } else if secrets.is_empty() {
             tracing::warn!(
                 "Node started without path to the stored secret keys (not a stake pool or a BFT leader)"
             );
@@ -184,20 +200,43 @@ impl RawSettings {
                     .map_or(false, |settings| settings.enabled)
             });
 
+        let explorer_address_bech32_prefix = config
+            .as_ref()
+            .and_then(|cfg| cfg.explorer.as_ref())
+            .and_then(|settings| settings.address_bech32_prefix.clone());
+
+        let prometheus = config.as_ref().map_or(false, |cfg| {
+            cfg.prometheus
+                .as_ref()
+                .map_or(false, |settings| settings.enabled)
+        });
+        if prometheus && !cfg!(feature = "prometheus") {
+            tracing::warn!(
+                "the prometheus metrics are enabled in the configuration but the node was built without the `prometheus` feature"
+            );
+        }
+
         Ok(Settings {
             storage,
             block_0,
             network,
             secrets,
+            secret_passphrase: PassphraseSource::from_options(
+                command_arguments.secret_passphrase_env.clone(),
+                command_arguments.secret_passphrase_fd,
+            ),
             rewards_report_all: command_line.rewards_report_all,
             rest,
             mempool: config
                 .as_ref()
                 .map_or(Mempool::default(), |cfg| cfg.mempool.clone()),
-            leadership: config
+            leadership,
+            ledger_snapshot: config
                 .as_ref()
-                .map_or(Leadership::default(), |cfg| cfg.leadership.clone()),
+                .map_or(LedgerSnapshot::default(), |cfg| cfg.ledger_snapshot.clone()),
             explorer,
+            explorer_address_bech32_prefix,
+            prometheus: prometheus && cfg!(feature = "prometheus"),
             no_blockchain_updates_warning_interval: config
                 .as_ref()
                 .and_then(|config| config.no_blockchain_updates_warning_interval)
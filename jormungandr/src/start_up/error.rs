@@ -2,7 +2,7 @@ use crate::{
     blockcfg, blockchain,
     blockchain::StorageError,
     diagnostic::DiagnosticError,
-    explorer, network, secure,
+    explorer, network, rest, secure,
     settings::{self, logging},
 };
 use std::io;
@@ -51,8 +51,16 @@ pub enum Error {
     NetworkBootstrapError(#[source] network::BootstrapError),
     #[error("Error while loading the node's secrets.")]
     NodeSecrets(#[from] secure::NodeSecretFromFileError),
+    #[error("Error while setting up the enclave of the leader keys")]
+    Enclave(#[source] secure::enclave::Error),
+    #[error("The remote signer is only supported on Unix systems")]
+    RemoteSignerNotSupported,
     #[error("Block 0 is set to start in the future")]
     Block0InFuture,
+    #[error("Error in the admin credentials of the REST API")]
+    RestAdminAuth(#[from] rest::auth::Error),
+    #[error("Error in the TLS configuration of the REST API")]
+    RestTls(#[from] rest::tls::Error),
     #[error("Error while loading the explorer from storage")]
     ExplorerBootstrapError(#[from] explorer::error::ExplorerError),
     #[error("A service has terminated with an error")]
@@ -79,6 +87,8 @@ impl Error {
             Error::Interrupted => 0,
             Error::LoggingInitializationError { .. } => 1,
             Error::ConfigurationError { .. } => 2,
+            Error::RestAdminAuth { .. } => 2,
+            Error::RestTls { .. } => 2,
             Error::IO { .. } => 3,
             Error::ParseError { .. } => 4,
             Error::StorageError { .. } => 5,
@@ -87,6 +97,8 @@ impl Error {
             Error::Block0Mismatch { .. } => 7,
             Error::Block0InFuture => 7,
             Error::NodeSecrets { .. } => 8,
+            Error::Enclave { .. } => 8,
+            Error::RemoteSignerNotSupported => 8,
             Error::FetchBlock0 { .. } => 9,
             Error::NetworkBootstrapError { .. } => 10,
             Error::ExplorerBootstrapError { .. } => 11,
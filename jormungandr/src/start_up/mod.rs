@@ -2,14 +2,20 @@ mod error;
 
 use tracing::{span, Level};
 
+const LEDGER_SNAPSHOTS_DIRECTORY: &str = "ledger_snapshots";
+const MEMPOOL_JOURNAL_DIRECTORY: &str = "mempool";
+const EXPLORER_DIRECTORY: &str = "explorer";
+
 pub use self::error::{Error, ErrorKind};
 use crate::{
     blockcfg::{Block, HeaderId},
-    blockchain::{Blockchain, ErrorKind as BlockchainError, Storage, Tip},
+    blockchain::{Blockchain, ErrorKind as BlockchainError, LedgerSnapshots, Storage, Tip},
     network,
     settings::start::Settings,
 };
 
+use std::path::PathBuf;
+
 /// prepare the block storage from the given settings
 pub fn prepare_storage(setting: &Settings) -> Result<Storage, Error> {
     let span = span!(Level::TRACE, "sub_task", kind = "storage");
@@ -29,6 +35,44 @@ pub fn prepare_storage(setting: &Settings) -> Result<Storage, Error> {
     }
 }
 
+/// prepare the ledger snapshots from the given settings, they are stored
+/// alongside the block storage so they are disabled with an in-memory storage.
+pub fn prepare_ledger_snapshots(setting: &Settings) -> LedgerSnapshots {
+    LedgerSnapshots::new(
+        setting
+            .storage
+            .as_ref()
+            .map(|dir| dir.join(LEDGER_SNAPSHOTS_DIRECTORY)),
+        setting.ledger_snapshot.interval,
+    )
+}
+
+/// get the directory of the mempool journal from the given settings, the
+/// journal is stored alongside the block storage so it is disabled with an
+/// in-memory storage.
+pub fn prepare_mempool_journal(setting: &Settings) -> Option<PathBuf> {
+    if !setting.mempool.persistent {
+        return None;
+    }
+    match &setting.storage {
+        Some(dir) => Some(dir.join(MEMPOOL_JOURNAL_DIRECTORY)),
+        None => {
+            tracing::warn!("the mempool is not persisted as no storage directory is set");
+            None
+        }
+    }
+}
+
+/// get the directory of the database of the explorer from the given settings,
+/// it is stored alongside the block storage so with an in-memory storage it is
+/// a temporary one, the explorer indexing the blocks again on every start.
+pub fn prepare_explorer_store(setting: &Settings) -> Option<PathBuf> {
+    setting
+        .storage
+        .as_ref()
+        .map(|dir| dir.join(EXPLORER_DIRECTORY))
+}
+
 /// Try to fetch the block0_id from the HTTP base URL (services) in the array
 ///
 /// The HTTP url is expecting to be of the form: URL/<hash-id>.block0
@@ -149,12 +193,14 @@ pub async fn prepare_block_0(settings: &Settings, storage: &Storage) -> Result<B
 pub async fn load_blockchain(
     block0: Block,
     storage: Storage,
+    ledger_snapshots: LedgerSnapshots,
     cache_capacity: usize,
     rewards_report_all: bool,
 ) -> Result<(Blockchain, Tip), Error> {
     let blockchain = Blockchain::new(
         block0.header.hash(),
         storage,
+        ledger_snapshots,
         cache_capacity,
         rewards_report_all,
     );
@@ -3,7 +3,7 @@ use chain_impl_mockchain::block::Block;
 use jormungandr_lib::time::SecondsSinceUnixEpoch;
 use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 const SLOT_START_TIME_UNDEFINED: u64 = u64::max_value();
 
@@ -20,6 +20,9 @@ struct StatsCounterImpl {
     slot_start_time: AtomicU64,
     tip_block: ArcSwapOption<Block>,
     peers_connected_cnt: AtomicUsize,
+    mempool_usage: AtomicUsize,
+    block_processing_cnt: AtomicU64,
+    block_processing_time_us: AtomicU64,
 }
 
 impl Default for StatsCounterImpl {
@@ -31,6 +34,9 @@ impl Default for StatsCounterImpl {
             slot_start_time: AtomicU64::new(SLOT_START_TIME_UNDEFINED),
             tip_block: ArcSwapOption::from(None),
             peers_connected_cnt: AtomicUsize::default(),
+            mempool_usage: AtomicUsize::default(),
+            block_processing_cnt: AtomicU64::default(),
+            block_processing_time_us: AtomicU64::default(),
         }
     }
 }
@@ -99,4 +105,32 @@ impl StatsCounter {
     pub fn get_tip_block(&self) -> Option<Arc<Block>> {
         self.stats.tip_block.load_full()
     }
+
+    pub fn set_mempool_usage(&self, count: usize) {
+        self.stats.mempool_usage.store(count, Ordering::Relaxed);
+    }
+
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    pub fn mempool_usage(&self) -> usize {
+        self.stats.mempool_usage.load(Ordering::Relaxed)
+    }
+
+    /// record the time it took to validate and apply a block
+    pub fn add_block_processing_time(&self, time: Duration) {
+        self.stats
+            .block_processing_cnt
+            .fetch_add(1, Ordering::Relaxed);
+        self.stats
+            .block_processing_time_us
+            .fetch_add(time.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// number of processed blocks and total time spent processing them
+    #[cfg_attr(not(feature = "prometheus"), allow(dead_code))]
+    pub fn block_processing_time(&self) -> (u64, Duration) {
+        (
+            self.stats.block_processing_cnt.load(Ordering::Relaxed),
+            Duration::from_micros(self.stats.block_processing_time_us.load(Ordering::Relaxed)),
+        )
+    }
 }
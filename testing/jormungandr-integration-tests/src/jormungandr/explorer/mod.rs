@@ -1,6 +1,12 @@
 use crate::common::{
-    jcli::JCli, jormungandr::ConfigurationBuilder, startup, transaction_utils::TransactionHash,
+    jcli::JCli,
+    jormungandr::{ConfigurationBuilder, Starter},
+    startup,
+    transaction_utils::TransactionHash,
 };
+use assert_fs::TempDir;
+use chain_addr::{AddressReadable, Discrimination, Kind};
+use chain_crypto::{Ed25519, KeyPair};
 use chain_impl_mockchain::fragment::FragmentId;
 use chain_impl_mockchain::key::Hash;
 use jormungandr_lib::interfaces::ActiveSlotCoefficient;
@@ -93,6 +99,62 @@ pub fn explorer_sanity_test() {
     epoch(&explorer);
 }
 
+#[test]
+pub fn explorer_test_discrimination_address_prefix() {
+    explorer_address_prefix(Discrimination::Test, "ta");
+}
+
+#[test]
+pub fn explorer_production_discrimination_address_prefix() {
+    explorer_address_prefix(Discrimination::Production, "addr");
+}
+
+fn explorer_address_prefix(discrimination: Discrimination, prefix: &str) {
+    let temp_dir = TempDir::new().unwrap();
+    let config = ConfigurationBuilder::new()
+        .with_discrimination(discrimination)
+        .with_explorer()
+        .build(&temp_dir);
+
+    let jormungandr = Starter::new()
+        .temp_dir(temp_dir)
+        .config(config)
+        .start()
+        .unwrap();
+    let explorer = jormungandr.explorer();
+
+    let status = explorer.status().unwrap().data.unwrap().status;
+    assert_eq!(
+        status.address_bech32_prefix, prefix,
+        "incorrect address prefix"
+    );
+    let expected_discrimination = match discrimination {
+        Discrimination::Production => "PRODUCTION",
+        Discrimination::Test => "TEST",
+    };
+    assert_eq!(
+        format!("{:?}", status.discrimination),
+        expected_discrimination,
+        "incorrect discrimination"
+    );
+
+    let key_pair = KeyPair::<Ed25519>::generate(&mut rand::thread_rng());
+    let address = chain_addr::Address(discrimination, Kind::Account(key_pair.public_key().clone()));
+    let bech32 = AddressReadable::from_address(prefix, &address).to_string();
+    let response = explorer.address(bech32).unwrap();
+    assert!(
+        response.errors.is_none(),
+        "address with the prefix rejected"
+    );
+
+    let other_prefix = AddressReadable::from_address("other", &address).to_string();
+    let response = explorer.address(other_prefix).unwrap();
+    assert!(
+        response.errors.is_some(),
+        "address with another prefix accepted"
+    );
+}
+
 fn transaction_by_id(explorer: &Explorer, fragment_id: FragmentId) {
     let explorer_transaction = explorer
         .transaction(fragment_id.into())
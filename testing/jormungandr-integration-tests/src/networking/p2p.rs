@@ -131,7 +131,10 @@ pub fn node_whitelist_itself() {
             wallet("delegated1").with(1_000_000).delegated_to(CLIENT),
             wallet("delegated2").with(1_000_000).delegated_to(SERVER),
         ])
-        .custom_config(vec![params(CLIENT).explorer(Explorer { enabled: true })])
+        .custom_config(vec![params(CLIENT).explorer(Explorer {
+            enabled: true,
+            address_bech32_prefix: None,
+        })])
         .build()
         .unwrap();
 
@@ -161,7 +164,10 @@ pub fn node_does_not_quarantine_whitelisted_node() {
             wallet("delegated1").with(1_000_000).delegated_to(CLIENT),
             wallet("delegated2").with(1_000_000).delegated_to(SERVER),
         ])
-        .custom_config(vec![params(CLIENT).explorer(Explorer { enabled: true })])
+        .custom_config(vec![params(CLIENT).explorer(Explorer {
+            enabled: true,
+            address_bech32_prefix: None,
+        })])
         .build()
         .unwrap();
 
@@ -202,7 +208,10 @@ pub fn node_put_in_quarantine_nodes_which_are_not_whitelisted() {
             wallet("delegated1").with(1_000_000).delegated_to(CLIENT),
             wallet("delegated2").with(1_000_000).delegated_to(SERVER),
         ])
-        .custom_config(vec![params(CLIENT).explorer(Explorer { enabled: true })])
+        .custom_config(vec![params(CLIENT).explorer(Explorer {
+            enabled: true,
+            address_bech32_prefix: None,
+        })])
         .build()
         .unwrap();
 
@@ -259,7 +268,10 @@ pub fn node_trust_itself() {
             wallet("delegated1").with(1_000_000).delegated_to(CLIENT),
             wallet("delegated2").with(1_000_000).delegated_to(SERVER),
         ])
-        .custom_config(vec![params(CLIENT).explorer(Explorer { enabled: true })])
+        .custom_config(vec![params(CLIENT).explorer(Explorer {
+            enabled: true,
+            address_bech32_prefix: None,
+        })])
         .build()
         .unwrap();
 
@@ -313,6 +313,8 @@ impl Prepare for Rest {
             listen: context.generate_new_rest_listen_address(),
             tls: None,
             cors: None,
+            admin: None,
+            limits: None,
         }
     }
 }
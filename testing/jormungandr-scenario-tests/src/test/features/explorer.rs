@@ -57,7 +57,10 @@ pub fn passive_node_explorer(mut context: Context<ChaChaRng>) -> Result<Scenario
             .new_spawn_params(PASSIVE)
             .passive()
             .in_memory()
-            .explorer(Explorer { enabled: true }),
+            .explorer(Explorer {
+                enabled: true,
+                address_bech32_prefix: None,
+            }),
     )?;
     passive.wait_for_bootstrap()?;
 
@@ -44,7 +44,10 @@ pub fn retire_stake_pool_explorer(mut context: Context<ChaChaRng>) -> Result<Sce
             .new_spawn_params(LEADER_1)
             .leader()
             .in_memory()
-            .explorer(Explorer { enabled: true }),
+            .explorer(Explorer {
+                enabled: true,
+                address_bech32_prefix: None,
+            }),
     )?;
     leader_1.wait_for_bootstrap()?;
 
@@ -90,6 +90,8 @@ impl LegacyNodeConfigConverter {
                 listen: source.rest.listen,
                 cors: None,
                 tls: None,
+                admin: None,
+                limits: None,
             },
             p2p: P2p {
                 trusted_peers,
@@ -145,6 +147,8 @@ impl LegacyNodeConfigConverter {
                 listen: source.rest.listen,
                 cors: None,
                 tls: None,
+                admin: None,
+                limits: None,
             },
             p2p: P2p {
                 trusted_peers,
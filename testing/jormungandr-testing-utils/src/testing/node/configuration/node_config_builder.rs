@@ -48,6 +48,8 @@ impl NodeConfigBuilder {
                     .unwrap(),
                 tls: None,
                 cors: None,
+                admin: None,
+                limits: None,
             },
             p2p: P2p {
                 trusted_peers: vec![],
@@ -68,7 +70,10 @@ impl NodeConfigBuilder {
                 public_id: None,
             },
             mempool: Some(Mempool::default()),
-            explorer: Explorer { enabled: false },
+            explorer: Explorer {
+                enabled: false,
+                address_bech32_prefix: None,
+            },
         }
     }
 
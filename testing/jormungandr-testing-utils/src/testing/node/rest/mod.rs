@@ -87,6 +87,31 @@ impl JormungandrRest {
         }
     }
 
+    /// connect to a node verifying the certificates of its clients, with the
+    /// given PEM-encoded client certificate and private key
+    pub fn new_with_client_cert<P: AsRef<Path>>(
+        uri: String,
+        cert_file: P,
+        client_cert_file: P,
+        client_key_file: P,
+    ) -> Self {
+        let url = uri
+            .replace("http://", "https://")
+            .replace("127.0.0.1", "localhost");
+
+        let mut identity_pem = std::fs::read(client_cert_file).unwrap();
+        identity_pem.push(b'\n');
+        identity_pem.extend(std::fs::read(client_key_file).unwrap());
+        let settings = RestSettings {
+            certificate: Some(Self::extract_certificate(cert_file.as_ref())),
+            identity_pem: Some(identity_pem),
+            ..Default::default()
+        };
+        Self {
+            inner: legacy::BackwardCompatibleRest::new(url, settings),
+        }
+    }
+
     fn extract_certificate<P: AsRef<Path>>(cert_file: P) -> reqwest::Certificate {
         let mut buf = Vec::new();
         let path = cert_file.as_ref().as_os_str().to_str().unwrap();
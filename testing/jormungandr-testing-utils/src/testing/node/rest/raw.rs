@@ -60,20 +60,23 @@ impl RawRest {
         }
     }
 
+    fn client(&self) -> Result<reqwest::blocking::Client, reqwest::Error> {
+        let mut builder = reqwest::blocking::Client::builder();
+        if let Some(cert) = &self.settings.certificate {
+            builder = builder.use_rustls_tls().add_root_certificate(cert.clone());
+        }
+        if let Some(identity_pem) = &self.settings.identity_pem {
+            builder = builder
+                .use_rustls_tls()
+                .identity(reqwest::Identity::from_pem(identity_pem)?);
+        }
+        builder.build()
+    }
+
     fn get(&self, path: &str) -> Result<reqwest::blocking::Response, reqwest::Error> {
         let request = self.path(path);
         self.print_request_path(&request);
-        match &self.settings.certificate {
-            None => reqwest::blocking::get(&request),
-            Some(cert) => {
-                let client = reqwest::blocking::Client::builder()
-                    .use_rustls_tls()
-                    .add_root_certificate(cert.clone())
-                    .build()
-                    .unwrap();
-                client.get(&request).send()
-            }
-        }
+        self.client()?.get(&request).send()
     }
 
     fn path(&self, path: &str) -> String {
@@ -181,9 +184,7 @@ impl RawRest {
         path: &str,
         body: Vec<u8>,
     ) -> Result<reqwest::blocking::Response, reqwest::Error> {
-        let builder = reqwest::blocking::Client::builder();
-        let client = builder.build()?;
-        client
+        self.client()?
             .post(&self.path_http_or_https(path, ApiVersion::V0))
             .headers(self.construct_headers())
             .body(body)
@@ -206,10 +207,7 @@ impl RawRest {
         &self,
         fragments: Vec<Fragment>,
     ) -> Result<Response, reqwest::Error> {
-        let builder = reqwest::blocking::Client::builder();
-        let client = builder.build()?;
-
-        client
+        self.client()?
             .post(&self.path_http_or_https("fragments", ApiVersion::V1))
             .headers(self.construct_headers())
             .json(
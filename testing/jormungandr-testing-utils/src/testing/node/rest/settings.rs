@@ -5,6 +5,9 @@ pub struct RestSettings {
     pub enable_debug: bool,
     pub use_https_for_post: bool,
     pub certificate: Option<Certificate>,
+    /// PEM-encoded client certificate followed by its private key, for the
+    /// nodes verifying the certificates of their clients
+    pub identity_pem: Option<Vec<u8>>,
 }
 
 impl RestSettings {
@@ -13,6 +16,7 @@ impl RestSettings {
             enable_debug: false,
             use_https_for_post: true,
             certificate: None,
+            identity_pem: None,
         }
     }
 }
@@ -23,6 +27,7 @@ impl Default for RestSettings {
             enable_debug: false,
             use_https_for_post: false,
             certificate: None,
+            identity_pem: None,
         }
     }
 }